@@ -0,0 +1,61 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use qrism::{ECLevel, QRBuilder, Version};
+
+// Times the mask search of versions 1 to 4 on the stack grid fast path against the generic one,
+// which clones the full size QR for each mask
+pub fn benchmark_mask_search(iters: u32) {
+    println!("{:<10} {:>14} {:>14} {:>8}", "Version", "Fast path", "Generic", "Speedup");
+    for v in 1..=4 {
+        let data = "https://ex.io/".repeat(v);
+        let qr = QRBuilder::new(data.as_bytes())
+            .version(Version::Normal(v))
+            .ec_level(ECLevel::L)
+            .build()
+            .unwrap();
+
+        let fast = time_per_iter(iters, || qr.clone().apply_best_mask_small());
+        let generic = time_per_iter(iters, || qr.clone().apply_best_mask_generic());
+
+        let (mut a, mut b) = (qr.clone(), qr.clone());
+        assert_eq!(a.apply_best_mask_small(), b.apply_best_mask_generic(), "Masks differ");
+
+        let speedup = generic.as_secs_f64() / fast.as_secs_f64().max(f64::EPSILON);
+        println!("{:<10} {:>14?} {:>14?} {:>7.2}x", format!("{v}"), fast, generic, speedup);
+    }
+}
+
+fn time_per_iter<R>(iters: u32, f: impl Fn() -> R) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iters {
+        black_box(f());
+    }
+    start.elapsed() / iters
+}
+
+// Times whole builds of small versions, where the mask search is the costliest stage
+pub fn benchmark_build(iters: u32) {
+    for v in 1..=4 {
+        let data = "https://ex.io/".repeat(v);
+        let time = time_per_iter(iters, || {
+            let mut bldr = QRBuilder::new(data.as_bytes());
+            bldr.version(Version::Normal(v)).ec_level(ECLevel::L).build().unwrap()
+        });
+        println!("Build of version {v}: {time:?}");
+    }
+}
+
+fn main() {
+    println!("Running Mask Search Benchmarks...");
+    println!("--------------------------------------------");
+    let start = Instant::now();
+    benchmark_mask_search(2000);
+    println!("Mask search benchmark completed in: {:?}\n", start.elapsed());
+
+    println!("Running Build Benchmarks...");
+    println!("--------------------------------------------");
+    let start = Instant::now();
+    benchmark_build(2000);
+    println!("Build benchmark completed in: {:?}\n", start.elapsed());
+}
@@ -5,7 +5,7 @@ use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use walkdir::WalkDir;
 
-use qrism::reader::detect_qr;
+use qrism::reader::{detect_qr, DecodeStrategy, RobustDecoder, StrategyStats};
 
 #[path = "utils.rs"]
 mod utils;
@@ -36,7 +36,7 @@ pub fn benchmark_decoding(dataset_dir: &Path) {
             });
 
             let start = Instant::now();
-            let mut res = detect_qr(&img);
+            let res = detect_qr(&img);
             let mut _passed = false;
 
             if !res.symbols().is_empty() {
@@ -111,6 +111,61 @@ pub fn benchmark_decoding(dataset_dir: &Path) {
     print_table(&results, &rows, &cols);
 }
 
+// Runs every fallback strategy of the robust decoder on each image at 0deg and records which
+// decoded the expected payload. The merged stats of all datasets give the shipped profile
+pub fn benchmark_strategies(dataset_dir: &Path) -> StrategyStats {
+    let image_paths: Vec<_> = WalkDir::new(dataset_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(is_image_file)
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    let decoder = RobustDecoder::new();
+    let stats = Mutex::new(StrategyStats::new());
+    let results = Mutex::new(HashMap::<String, HashMap<String, u128>>::new());
+
+    image_paths.par_iter().for_each(|img_path| {
+        let parent = get_parent(img_path);
+        let path_str = img_path.to_str().unwrap();
+        let img = image::open(img_path).unwrap();
+        let exp_msg = parse_expected_decode_result(&img_path.with_extension("txt"));
+
+        for strategy in DecodeStrategy::ALL {
+            let passed = decoder
+                .run(&img, strategy)
+                .iter()
+                .any(|(_, msg)| msg.lines().map(String::from).collect::<Vec<_>>() == exp_msg);
+            stats.lock().unwrap().record(path_str, strategy, passed);
+            if passed {
+                let mut results = results.lock().unwrap();
+                *results
+                    .entry(parent.clone())
+                    .or_default()
+                    .entry(strategy.name().to_string())
+                    .or_default() += 1;
+            }
+        }
+    });
+
+    let stats = stats.into_inner().unwrap();
+    let mut results = results.into_inner().unwrap();
+    let total = DecodeStrategy::ALL
+        .iter()
+        .map(|s| (s.name().to_string(), stats.successes(*s) as u128))
+        .collect();
+    results.insert("total".to_string(), total);
+
+    let mut rows = results.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+    rows.sort_unstable();
+    let mut cols = vec!["Strategy"];
+    cols.extend(DecodeStrategy::ALL.iter().map(|s| s.name()));
+
+    println!("\nStrategy successes:");
+    print_table(&results, &rows, &cols);
+    stats
+}
+
 fn main() {
     // Run decoding benchmarks on blackbox dataset
     println!("Running Decoding Benchmarks (Blackbox)...");
@@ -127,4 +182,19 @@ fn main() {
     benchmark_decoding(Path::new("benches/dataset/decoding"));
     let decoding2_time = decoding2_start.elapsed();
     println!("Decoding (decoding) benchmark completed in: {:?}\n", decoding2_time);
+
+    // Derive the fallback strategy order from both datasets
+    println!("Running Strategy Benchmarks...");
+    println!("--------------------------------------------");
+    let mut stats = benchmark_strategies(Path::new("benches/dataset/blackbox"));
+    stats.merge(benchmark_strategies(Path::new("benches/dataset/decoding")));
+    let profile = stats.to_profile();
+    println!("\nStrategy profile over {} images:\n{}", stats.images(), profile.to_text());
+
+    let target = std::env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    let path = Path::new(&target).join("strategy_profile.txt");
+    match std::fs::write(&path, profile.to_text()) {
+        Ok(()) => println!("Strategy profile written to {}", path.display()),
+        Err(e) => eprintln!("Failed to write strategy profile to {}: {e}", path.display()),
+    }
 }
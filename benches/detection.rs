@@ -6,14 +6,14 @@ use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use walkdir::WalkDir;
 
-use qrism::detect_qr;
+use qrism::reader::{detect_qr_with, DetectOptions};
 use qrism::symbol::Symbol;
 
 #[path = "utils.rs"]
 mod utils;
 use utils::{get_parent, is_image_file, parse_expected_bounds_result, print_table};
 
-pub fn benchmark_detection(dataset_dir: &Path) {
+pub fn benchmark_detection(dataset_dir: &Path, opts: &DetectOptions) {
     let image_paths: Vec<_> = WalkDir::new(dataset_dir)
         .into_iter()
         .filter_map(Result::ok)
@@ -34,12 +34,8 @@ pub fn benchmark_detection(dataset_dir: &Path) {
 
         // Filters QRs which can be decoded correctly. Measures time to decode all QRs
         let start = Instant::now();
-        let mut res = detect_qr(&img);
-        let symbols: Vec<&mut Symbol> = res
-            .symbols()
-            .iter_mut()
-            .filter_map(|s| if s.decode().is_ok() { Some(s) } else { None })
-            .collect();
+        let res = detect_qr_with(&img, opts);
+        let symbols: Vec<&Symbol> = res.symbols().iter().filter(|s| s.decode().is_ok()).collect();
         let time = start.elapsed().as_millis();
 
         let symbols = get_corners(&symbols);
@@ -127,7 +123,7 @@ pub fn benchmark_detection(dataset_dir: &Path) {
     print_table(&results, &rows, &cols);
 }
 
-pub fn get_corners(symbols: &[&mut Symbol]) -> Vec<Vec<f64>> {
+pub fn get_corners(symbols: &[&Symbol]) -> Vec<Vec<f64>> {
     let mut symbol_corners = Vec::with_capacity(100);
     for sym in symbols {
         let sz = sym.ver.width() as f64;
@@ -221,7 +217,14 @@ fn main() {
     println!("Running Detection Benchmarks...");
     println!("---------------------------------");
     let detection_start = Instant::now();
-    benchmark_detection(Path::new("benches/dataset/detection"));
+    benchmark_detection(Path::new("benches/dataset/detection"), &DetectOptions::default());
     let detection_time = detection_start.elapsed();
     println!("Detection benchmark completed in: {:?}\n", detection_time);
+
+    // Shadow subset again with illumination flattening, to compare against the row above
+    println!("Running Shadow Benchmarks with Illumination Flattening...");
+    println!("---------------------------------");
+    let mut opts = DetectOptions::default();
+    opts.flatten_illumination(true);
+    benchmark_detection(Path::new("benches/dataset/detection/shadows"), &opts);
 }
@@ -0,0 +1,16 @@
+use std::{error::Error, path::PathBuf};
+
+use qrism::testing::{fuzz_corpus, write_fuzz_corpus};
+
+// Writes the seed corpus for fuzzing the segment reader, by default to fuzz/corpus/decode
+//
+//   cargo run --example gen-corpus --features testing -- [dir]
+fn main() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::args().nth(1).map(PathBuf::from).unwrap_or("fuzz/corpus/decode".into());
+    let n = write_fuzz_corpus(&dir)?;
+    for e in fuzz_corpus() {
+        println!("{:<28} version {:>2} {:?} {} bytes", e.name, *e.ver, e.ecl, e.data.len());
+    }
+    println!("Wrote {n} inputs to {}", dir.display());
+    Ok(())
+}
@@ -22,4 +22,3 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
-
@@ -0,0 +1,31 @@
+use std::error::Error;
+
+use qrism::{
+    reader::{pages::detect_qr_file, DetectOptions},
+    transfer::{TransferReceiver, TransferSender},
+    RenderConfig,
+};
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Send a file as an animation of QR codes, e.g. to show on a screen for a phone to scan
+    let path = std::env::args().nth(1).unwrap_or_else(|| "Cargo.toml".to_string());
+    let data = std::fs::read(&path)?;
+
+    let mut sender = TransferSender::new(&data);
+    sender.chunk_size(200);
+    let mut config = RenderConfig::new();
+    config.module_size(4);
+    sender.save_gif("transfer.gif", &config, 250)?;
+    println!("Saved {} as {} frames in transfer.gif", path, sender.frame_count());
+
+    // Receive the file back by scanning every frame of the animation
+    let res = detect_qr_file("transfer.gif", &DetectOptions::default())?;
+    let mut receiver = TransferReceiver::new();
+    receiver.push_result(&res);
+    let received = receiver.finish()?;
+
+    assert_eq!(received, data);
+    println!("Received {} bytes intact", received.len());
+
+    Ok(())
+}
@@ -8,9 +8,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     let img = image::open(qr_path)?;
 
     // Detect and decode QR codes in the image
-    let mut res = detect_qr(&img);
+    let res = detect_qr(&img);
 
-    if let Some(symbol) = res.symbols().first_mut() {
+    if let Some(symbol) = res.symbols().first() {
         let (metadata, decoded_message) = symbol.decode()?;
         println!("Successfully decoded QR code from: {}", qr_path);
         println!("Decoded message: {}", decoded_message);
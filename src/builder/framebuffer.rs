@@ -0,0 +1,159 @@
+use image::Rgb;
+
+use super::QR;
+use crate::{
+    metadata::Color,
+    utils::{QRError, QRResult},
+};
+
+// Framebuffer
+//------------------------------------------------------------------------------
+
+// Pixel layouts of raw framebuffers, e.g. of embedded displays. Rows are packed left to right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Mono1,    // 1 bit per pixel, msb first, set for light pixels
+    Gray8,    // 0 for black & 255 for white
+    Rgb565,   // Little endian, red in the high bits
+    Rgb888,   // Red, green & blue bytes
+    Rgba8888, // Red, green, blue & opaque alpha bytes
+}
+
+impl PixelFormat {
+    pub fn bits_per_pixel(&self) -> usize {
+        match self {
+            Self::Mono1 => 1,
+            Self::Gray8 => 8,
+            Self::Rgb565 => 16,
+            Self::Rgb888 => 24,
+            Self::Rgba8888 => 32,
+        }
+    }
+
+    // Bytes a row of the given pixels takes, the minimum stride
+    pub fn row_len(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    fn put(&self, buf: &mut [u8], stride: usize, x: usize, y: usize, clr: Color) {
+        let row = &mut buf[y * stride..];
+        let Rgb([r, g, b]) = clr.into();
+        match self {
+            Self::Mono1 => {
+                let bit = 0x80 >> (x % 8);
+                match clr {
+                    Color::White => row[x / 8] |= bit,
+                    _ => row[x / 8] &= !bit,
+                }
+            }
+            Self::Gray8 => {
+                // Luma as per ITU-R BT.601
+                let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+                row[x] = luma as u8;
+            }
+            Self::Rgb565 => {
+                let px = (r as u16 >> 3) << 11 | (g as u16 >> 2) << 5 | b as u16 >> 3;
+                row[x * 2..x * 2 + 2].copy_from_slice(&px.to_le_bytes());
+            }
+            Self::Rgb888 => row[x * 3..x * 3 + 3].copy_from_slice(&[r, g, b]),
+            Self::Rgba8888 => row[x * 4..x * 4 + 4].copy_from_slice(&[r, g, b, 255]),
+        }
+    }
+}
+
+impl QR {
+    // Modules in row major order with their colours, quiet zone excluded
+    pub fn modules(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        let w = self.width();
+        self.grid()[..w * w].iter().enumerate().map(move |(i, m)| (i % w, i / w, **m))
+    }
+
+    // Rasterizes the QR, quiet zone included, into the top left of a framebuffer with rows stride
+    // bytes apart. Bytes past the image in each row are left as is, so the QR can be drawn into a
+    // region of a larger screen by offsetting the buffer. Fails if the buffer can't hold the image
+    pub fn render_into(
+        &self,
+        buf: &mut [u8],
+        stride: usize,
+        format: PixelFormat,
+        module_sz: u32,
+    ) -> QRResult<()> {
+        let module_sz = module_sz.max(1) as usize;
+        let total_sz = self.image_size(module_sz as u32) as usize;
+        let row_len = format.row_len(total_sz);
+        if stride < row_len || buf.len() < stride * (total_sz - 1) + row_len {
+            return Err(QRError::BufferSizeMismatch);
+        }
+
+        // Quiet zone first, then each module painted over it
+        for y in 0..total_sz {
+            for x in 0..total_sz {
+                format.put(buf, stride, x, y, Color::White);
+            }
+        }
+        let qz_sz = (total_sz - self.width() * module_sz) / 2;
+        for (mx, my, clr) in self.modules().filter(|&(_, _, c)| c != Color::White) {
+            let (x0, y0) = (qz_sz + mx * module_sz, qz_sz + my * module_sz);
+            for y in y0..y0 + module_sz {
+                for x in x0..x0 + module_sz {
+                    format.put(buf, stride, x, y, clr);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod framebuffer_tests {
+    use image::DynamicImage;
+
+    use super::PixelFormat;
+    use crate::{builder::QRBuilder, metadata::Color, utils::QRError};
+
+    #[test]
+    fn test_modules() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let modules: Vec<_> = qr.modules().collect();
+        assert_eq!(modules.len(), 21 * 21);
+        assert_eq!(modules[22], (1, 1, Color::White));
+        assert!(modules.iter().all(|&(x, y, c)| *qr.get(x as i32, y as i32) == c));
+    }
+
+    #[test]
+    fn test_render_into() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let sz = qr.image_size(2) as usize;
+        let img = qr.to_image(2);
+
+        let mut buf = vec![0; 3 * sz * sz];
+        qr.render_into(&mut buf, 3 * sz, PixelFormat::Rgb888, 2).unwrap();
+        assert_eq!(buf, img.as_raw()[..]);
+
+        // Padding past each row is left untouched
+        let stride = sz + 3;
+        let mut buf = vec![7; stride * sz];
+        qr.render_into(&mut buf, stride, PixelFormat::Gray8, 2).unwrap();
+        let gray = DynamicImage::ImageRgb8(img.clone()).to_luma8();
+        for (row, exp) in buf.chunks(stride).zip(gray.rows()) {
+            assert!(row[..sz].iter().zip(exp).all(|(a, b)| *a == b.0[0]));
+            assert_eq!(row[sz..], [7, 7, 7]);
+        }
+
+        let stride = PixelFormat::Mono1.row_len(sz);
+        let mut buf = vec![0; stride * sz];
+        qr.render_into(&mut buf, stride, PixelFormat::Mono1, 2).unwrap();
+        let is_set = |x: usize, y: usize| buf[y * stride + x / 8] & 0x80 >> (x % 8) != 0;
+        assert!(is_set(0, 0) && !is_set(8, 8) && is_set(10, 10)); // Quiet zone & top left finder
+
+        let mut buf = vec![0; 2 * sz * sz];
+        qr.render_into(&mut buf, 2 * sz, PixelFormat::Rgb565, 2).unwrap();
+        assert_eq!((buf[0], buf[1]), (0xFF, 0xFF));
+        assert_eq!(buf[(8 * sz + 8) * 2..][..2], [0, 0]);
+
+        let res = qr.render_into(&mut buf[1..], 2 * sz, PixelFormat::Rgb565, 2);
+        assert_eq!(res, Err(QRError::BufferSizeMismatch));
+        let res = qr.render_into(&mut buf, sz, PixelFormat::Rgb565, 2);
+        assert_eq!(res, Err(QRError::BufferSizeMismatch));
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt::Write;
+
+use super::QR;
+use crate::{
+    metadata::Color,
+    utils::{QRError, QRResult},
+};
+
+// G-code config
+//------------------------------------------------------------------------------
+
+// How the tool covers the dark modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolPath {
+    Outline, // Traces the outline of each dark region, e.g. for drag knives & vinyl cutters
+    Raster,  // Fills dark modules with horizontal lines, e.g. for laser marking
+}
+
+// Physical size & machine settings of a G-code export. Without a laser power, the tool is plunged
+// to the cut depth & lifted to the safe height between paths, else it stays put & the laser is
+// switched with M3 & M5
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcodeConfig {
+    size_mm: f64, // Side of the symbol, quiet zone included
+    path: ToolPath,
+    feed: f64, // Cutting feed rate in mm per min
+    safe_z: f64,
+    cut_z: f64,
+    laser_power: Option<u32>,
+    line_gap: f64, // Distance between raster lines in mm
+}
+
+impl Default for GcodeConfig {
+    fn default() -> Self {
+        Self {
+            size_mm: 25.0,
+            path: ToolPath::Outline,
+            feed: 600.0,
+            safe_z: 2.0,
+            cut_z: -0.2,
+            laser_power: None,
+            line_gap: 0.1,
+        }
+    }
+}
+
+impl GcodeConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Side of the symbol in millimetres, quiet zone included
+    pub fn size(&mut self, size_mm: f64) -> &mut Self {
+        self.size_mm = size_mm;
+        self
+    }
+
+    pub fn tool_path(&mut self, path: ToolPath) -> &mut Self {
+        self.path = path;
+        self
+    }
+
+    pub fn feed_rate(&mut self, mm_per_min: f64) -> &mut Self {
+        self.feed = mm_per_min;
+        self
+    }
+
+    // Heights of the tool in millimetres when moving between paths & when cutting
+    pub fn z_heights(&mut self, safe_z: f64, cut_z: f64) -> &mut Self {
+        self.safe_z = safe_z;
+        self.cut_z = cut_z;
+        self
+    }
+
+    // Marks with a laser at the spindle power, in the units of the machine's S word
+    pub fn laser(&mut self, power: u32) -> &mut Self {
+        self.laser_power = Some(power);
+        self
+    }
+
+    pub fn raster_line_gap(&mut self, gap_mm: f64) -> &mut Self {
+        self.line_gap = gap_mm;
+        self
+    }
+
+    // Sizes, feed & line gap must be positive, and heights finite, else the tool paths are
+    // meaningless or never end
+    fn validate(&self) -> QRResult<()> {
+        let positive =
+            [("size", self.size_mm), ("feed rate", self.feed), ("raster line gap", self.line_gap)];
+        if let Some((name, _)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return Err(QRError::InvalidGcodeSetting(name));
+        }
+        if !(self.safe_z.is_finite() && self.cut_z.is_finite()) {
+            return Err(QRError::InvalidGcodeSetting("z height"));
+        }
+        Ok(())
+    }
+
+    fn tool_on(&self) -> String {
+        match self.laser_power {
+            Some(p) => format!("M3 S{p}\n"),
+            None => format!("G1 Z{:.3} F{:.0}\n", self.cut_z, self.feed),
+        }
+    }
+
+    fn tool_off(&self) -> String {
+        match self.laser_power {
+            Some(_) => "M5\n".to_string(),
+            None => format!("G0 Z{:.3}\n", self.safe_z),
+        }
+    }
+}
+
+// G-code export
+//------------------------------------------------------------------------------
+
+impl QR {
+    // G-code in millimetres & absolute coordinates, for cnc marking the QR at the configured size.
+    // Y points up with the origin at the bottom left of the quiet zone, as in the dxf export
+    pub fn to_gcode(&self, config: &GcodeConfig) -> QRResult<String> {
+        config.validate()?;
+        let total = self.image_size(1) as usize;
+        let qz = (total - self.width()) / 2;
+        let module_mm = config.size_mm / total as f64;
+        let pt = |x: f64, y: f64| {
+            ((x + qz as f64) * module_mm, (total - qz) as f64 * module_mm - y * module_mm)
+        };
+
+        let mut paths: Vec<Vec<(f64, f64)>> = Vec::new();
+        match config.path {
+            ToolPath::Outline => {
+                for contour in self.dark_regions().into_iter().flatten() {
+                    let mut path: Vec<_> =
+                        contour.iter().map(|&(x, y)| pt(x as f64, y as f64)).collect();
+                    path.push(path[0]);
+                    paths.push(path);
+                }
+            }
+            ToolPath::Raster => {
+                // Lines snake across each row of modules, reversing direction every line. Gaps
+                // far finer than the modules would take endless lines, so are rejected
+                let w = self.width();
+                let lines = (module_mm / config.line_gap).round();
+                if lines > MAX_RASTER_LINES as f64 {
+                    return Err(QRError::InvalidGcodeSetting("raster line gap"));
+                }
+                let lines = (lines as usize).max(1);
+                for y in 0..w {
+                    let mut runs = Vec::new();
+                    let mut x = 0;
+                    while x < w {
+                        if *self.get(x as i32, y as i32) == Color::White {
+                            x += 1;
+                            continue;
+                        }
+                        let start = x;
+                        while x < w && *self.get(x as i32, y as i32) != Color::White {
+                            x += 1;
+                        }
+                        runs.push((start as f64, x as f64));
+                    }
+                    for i in 0..lines {
+                        let ly = y as f64 + (i as f64 + 0.5) / lines as f64;
+                        let reverse = (y * lines + i) % 2 == 1;
+                        let line = runs.iter().map(|&(x0, x1)| vec![pt(x0, ly), pt(x1, ly)]);
+                        match reverse {
+                            true => paths.extend(line.rev().map(|p| p.into_iter().rev().collect())),
+                            false => paths.extend(line),
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut gcode = String::from("G21\nG90\n");
+        gcode.push_str(&config.tool_off());
+        for path in paths {
+            let (x, y) = path[0];
+            let _ = writeln!(gcode, "G0 X{x:.3} Y{y:.3}");
+            gcode.push_str(&config.tool_on());
+            for (x, y) in &path[1..] {
+                let _ = writeln!(gcode, "G1 X{x:.3} Y{y:.3} F{:.0}", config.feed);
+            }
+            gcode.push_str(&config.tool_off());
+        }
+        gcode.push_str("G0 X0 Y0\nM2\n");
+        Ok(gcode)
+    }
+}
+
+#[cfg(test)]
+mod gcode_tests {
+    use super::{GcodeConfig, ToolPath};
+    use crate::{builder::QRBuilder, metadata::Color, utils::QRError};
+
+    #[test]
+    fn test_gcode_outline() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let mut config = GcodeConfig::new();
+        config.size(29.0).z_heights(1.0, -0.5);
+        let gcode = qr.to_gcode(&config).unwrap();
+
+        let contours: usize = qr.dark_regions().iter().map(|r| r.len()).sum();
+        assert_eq!(gcode.matches("G1 Z-0.500").count(), contours);
+        assert!(gcode.starts_with("G21\nG90\nG0 Z1.000\n"));
+        assert!(gcode.ends_with("M2\n"));
+
+        // Around the top left finder, 1 mm modules with y up from the bottom of the quiet zone
+        let finder = "G0 X4.000 Y25.000\nG1 Z-0.500 F600\nG1 X11.000 Y25.000 F600\n\
+                      G1 X11.000 Y18.000 F600\nG1 X4.000 Y18.000 F600\nG1 X4.000 Y25.000 F600\n";
+        assert!(gcode.contains(finder));
+    }
+
+    #[test]
+    fn test_gcode_raster() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let mut config = GcodeConfig::new();
+        config.size(29.0).tool_path(ToolPath::Raster).laser(800).raster_line_gap(0.5);
+        let gcode = qr.to_gcode(&config).unwrap();
+        assert!(!gcode.contains(" Z"));
+
+        // 2 lines per row of modules, each cutting every run of dark modules
+        let runs: usize = (0..21)
+            .map(|y| {
+                (0..21)
+                    .filter(|&x| {
+                        let dark = |x: i32| *qr.get(x, y) != Color::White;
+                        dark(x) && (x == 0 || !dark(x - 1))
+                    })
+                    .count()
+            })
+            .sum();
+        assert_eq!(gcode.matches("M3 S800\n").count(), 2 * runs);
+        assert_eq!(gcode.matches("M5\n").count(), 2 * runs + 1);
+
+        // First line runs left to right across the top of the finders, the next one back
+        assert!(gcode.contains("G0 X4.000 Y24.750\nM3 S800\nG1 X11.000 Y24.750 F600\nM5\n"));
+        assert!(gcode.contains("G0 X25.000 Y24.250\nM3 S800\nG1 X18.000 Y24.250 F600\nM5\n"));
+    }
+
+    #[test]
+    fn test_gcode_invalid_config() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let err = |config: &GcodeConfig| qr.to_gcode(config).unwrap_err();
+        let gap = QRError::InvalidGcodeSetting("raster line gap");
+        assert_eq!(err(GcodeConfig::new().tool_path(ToolPath::Raster).raster_line_gap(0.0)), gap);
+        assert_eq!(err(GcodeConfig::new().raster_line_gap(f64::NAN)), gap);
+        assert_eq!(err(GcodeConfig::new().size(-1.0)), QRError::InvalidGcodeSetting("size"));
+        let feed = QRError::InvalidGcodeSetting("feed rate");
+        assert_eq!(err(GcodeConfig::new().feed_rate(f64::INFINITY)), feed);
+        let z = QRError::InvalidGcodeSetting("z height");
+        assert_eq!(err(GcodeConfig::new().z_heights(f64::NAN, -0.2)), z);
+        assert!(qr.to_gcode(GcodeConfig::new().z_heights(5.0, -1.0)).is_ok());
+
+        // 1 mm modules take at most 100 lines, so gaps below 0.01 mm are rejected
+        let mut config = GcodeConfig::new();
+        config.size(29.0).tool_path(ToolPath::Raster).raster_line_gap(1e-9);
+        assert_eq!(err(&config), gap);
+        assert!(qr.to_gcode(config.raster_line_gap(0.01)).is_ok());
+        assert!(qr.to_gcode(config.tool_path(ToolPath::Outline).raster_line_gap(1e-9)).is_ok());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Raster lines per row of modules, far past the finest line a laser spot or bit can tell apart
+const MAX_RASTER_LINES: usize = 100;
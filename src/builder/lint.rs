@@ -0,0 +1,211 @@
+use std::{
+    fmt::{Display, Error, Formatter},
+    ops::Range,
+};
+
+use crate::codec::{url_origin_len, Mode};
+
+// Payload lints
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    UppercaseUrl,       // Url fits alphanumeric mode once scheme & host are uppercased
+    LongUrl,            // Url long enough to bloat the symbol, worth shortening
+    TrailingWhitespace, // Usually a copy paste leftover that breaks urls & codes
+    UnescapedWifiChar,  // Special char in a WiFi field value missing its backslash
+}
+
+impl Display for LintKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let msg = match self {
+            Self::UppercaseUrl => "Uppercase scheme & host to encode the url in alphanumeric mode",
+            Self::LongUrl => "Url is long, consider shortening it for a smaller symbol",
+            Self::TrailingWhitespace => "Payload ends with whitespace",
+            Self::UnescapedWifiChar => "WiFi field value has an unescaped special character",
+        };
+        f.write_str(msg)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    kind: LintKind,
+    span: Range<usize>,  // Byte range of the payload at fault
+    fix: Option<String>, // Payload with the issue fixed, when it can be fixed mechanically
+}
+
+impl Lint {
+    fn new(kind: LintKind, span: Range<usize>, fix: Option<String>) -> Self {
+        Self { kind, span, fix }
+    }
+
+    pub fn kind(&self) -> LintKind {
+        self.kind
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn fix(&self) -> Option<&str> {
+        self.fix.as_deref()
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{} at bytes {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+// Flags common mistakes in a payload before it is encoded. Lints are ordered by kind, then by
+// position in the payload
+pub fn lint(payload: &str) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    lint_url(payload, &mut lints);
+    lint_trailing_whitespace(payload, &mut lints);
+    lint_wifi(payload, &mut lints);
+    lints
+}
+
+fn lint_url(payload: &str, lints: &mut Vec<Lint>) {
+    let Some(host_end) = url_origin_len(payload.as_bytes()) else {
+        return;
+    };
+
+    let (origin, rest) = payload.split_at(host_end);
+    let upper = format!("{}{rest}", origin.to_ascii_uppercase());
+    let is_alnum = |s: &str| s.bytes().all(|b| Mode::Alphanumeric.contains(b));
+    if !origin.contains('@') && !is_alnum(payload) && is_alnum(&upper) {
+        lints.push(Lint::new(LintKind::UppercaseUrl, 0..host_end, Some(upper)));
+    }
+
+    if payload.len() > LONG_URL_LEN {
+        lints.push(Lint::new(LintKind::LongUrl, 0..payload.len(), None));
+    }
+}
+
+fn lint_trailing_whitespace(payload: &str, lints: &mut Vec<Lint>) {
+    let trimmed = payload.trim_end();
+    if trimmed.len() < payload.len() {
+        let span = trimmed.len()..payload.len();
+        lints.push(Lint::new(LintKind::TrailingWhitespace, span, Some(trimmed.to_string())));
+    }
+}
+
+// Special chars in WiFi field values must be escaped with a backslash. An unescaped ';' ends the
+// value early, which shows up as a following field without a "KEY:" prefix
+fn lint_wifi(payload: &str, lints: &mut Vec<Lint>) {
+    if !payload.starts_with(WIFI_PREFIX) {
+        return;
+    }
+
+    // Split fields at unescaped ';'
+    let mut fields = Vec::new();
+    let mut start = WIFI_PREFIX.len();
+    let mut esc = false;
+    for (i, c) in payload.char_indices().skip(WIFI_PREFIX.len()) {
+        match c {
+            _ if esc => esc = false,
+            '\\' => esc = true,
+            ';' => {
+                fields.push(start..i);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    fields.push(start..payload.len());
+
+    let mut unescaped = Vec::new();
+    let mut in_value = false;
+    for f in fields.into_iter().filter(|f| !f.is_empty()) {
+        let field = &payload[f.clone()];
+        let key_len = field
+            .find(':')
+            .filter(|&k| k > 0 && field[..k].bytes().all(|b| b.is_ascii_uppercase()));
+        let val_start = match key_len {
+            Some(k) => f.start + k + 1,
+            None if in_value => {
+                unescaped.push(f.start - 1);
+                f.start
+            }
+            None => continue,
+        };
+        in_value = true;
+
+        let mut esc = false;
+        for (i, c) in payload[val_start..f.end].char_indices() {
+            match c {
+                _ if esc => esc = false,
+                '\\' => esc = true,
+                ',' | ':' => unescaped.push(val_start + i),
+                _ => (),
+            }
+        }
+    }
+    unescaped.sort_unstable();
+
+    let mut fix = String::with_capacity(payload.len() + unescaped.len());
+    let mut last = 0;
+    for &i in unescaped.iter() {
+        fix.push_str(&payload[last..i]);
+        fix.push('\\');
+        last = i;
+    }
+    fix.push_str(&payload[last..]);
+
+    for i in unescaped {
+        lints.push(Lint::new(LintKind::UnescapedWifiChar, i..i + 1, Some(fix.clone())));
+    }
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use super::{lint, LintKind};
+
+    #[test]
+    fn test_lint_url() {
+        let lints = lint("https://example.com/ABC-123");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].kind(), LintKind::UppercaseUrl);
+        assert_eq!(lints[0].span(), 0..19);
+        assert_eq!(lints[0].fix(), Some("HTTPS://EXAMPLE.COM/ABC-123"));
+
+        // Lowercase path can't be uppercased
+        assert!(lint("https://example.com/abc").is_empty());
+
+        let long = format!("https://example.com/?q={}", "a".repeat(200));
+        let kinds: Vec<_> = lint(&long).iter().map(|l| l.kind()).collect();
+        assert_eq!(kinds, [LintKind::LongUrl]);
+    }
+
+    #[test]
+    fn test_lint_trailing_whitespace() {
+        let lints = lint("Hello \n");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].kind(), LintKind::TrailingWhitespace);
+        assert_eq!(lints[0].span(), 5..7);
+        assert_eq!(lints[0].fix(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_lint_wifi() {
+        assert!(lint("WIFI:T:WPA;S:my\\;net;P:pass;;").is_empty());
+
+        let lints = lint("WIFI:T:WPA;S:my;net;P:a,b;;");
+        let spans: Vec<_> = lints.iter().map(|l| l.span()).collect();
+        assert_eq!(spans, [15..16, 23..24]);
+        assert!(lints.iter().all(|l| l.kind() == LintKind::UnescapedWifiChar));
+        assert_eq!(lints[0].fix(), Some("WIFI:T:WPA;S:my\\;net;P:a\\,b;;"));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Byte capacity of version 7 at ec level M. Longer urls need denser symbols
+const LONG_URL_LEN: usize = 122;
+
+const WIFI_PREFIX: &str = "WIFI:";
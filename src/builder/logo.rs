@@ -0,0 +1,152 @@
+use image::{imageops, DynamicImage, Rgb, RgbImage, RgbaImage};
+
+use super::{QRBuilder, QR};
+use crate::{
+    metadata::Version,
+    reader::detect_qr,
+    utils::{QRError, QRResult},
+};
+
+// Logo
+//------------------------------------------------------------------------------
+
+impl QR {
+    // Renders the QR with the logo in a light box at the centre. The box covers as many codewords
+    // as a share of what the ec level can correct, & the logo is scaled to fit it keeping its
+    // aspect ratio. Each render is read back, shrinking the box until it decodes
+    pub fn to_image_with_logo(&self, logo: &DynamicImage, module_sz: u32) -> QRResult<RgbImage> {
+        // Micro symbols have no room to spare & can't be read back
+        if let Version::Micro(_) = self.version() {
+            return Err(QRError::InvalidVersion);
+        }
+
+        let max_side = self.logo_box_side();
+        if max_side < 3 {
+            return Err(QRError::InsufficientLogoArea);
+        }
+
+        let module_sz = module_sz.max(1);
+        let logo = logo.to_rgba8();
+        for side in (3..=max_side).rev().step_by(2) {
+            let img = self.render_logo(&logo, side, module_sz);
+            if self.reads_back(&img) {
+                return Ok(img);
+            }
+        }
+        Err(QRError::LogoUnreadable)
+    }
+
+    fn render_logo(&self, logo: &RgbaImage, side: u32, module_sz: u32) -> RgbImage {
+        let mut img = self.to_image(module_sz);
+        let qz_sz = 4 * module_sz;
+        let box_start = qz_sz + (self.width() as u32 - side) / 2 * module_sz;
+        let box_sz = side * module_sz;
+        for y in box_start..box_start + box_sz {
+            for x in box_start..box_start + box_sz {
+                img.put_pixel(x, y, Rgb([255, 255, 255]));
+            }
+        }
+
+        // Logo within a module of padding, blended onto the box by its alpha
+        let inner_sz = box_sz - 2 * module_sz;
+        let (lw, lh) = (logo.width().max(1), logo.height().max(1));
+        let scale = inner_sz as f64 / lw.max(lh) as f64;
+        let (w, h) = (((lw as f64 * scale) as u32).max(1), ((lh as f64 * scale) as u32).max(1));
+        let logo = imageops::resize(logo, w, h, imageops::FilterType::Triangle);
+        let (ox, oy) = (box_start + (box_sz - w) / 2, box_start + (box_sz - h) / 2);
+        for (x, y, px) in logo.enumerate_pixels() {
+            let a = px[3] as u32;
+            let blend = |c: u8| ((c as u32 * a + 255 * (255 - a)) / 255) as u8;
+            img.put_pixel(ox + x, oy + y, Rgb([blend(px[0]), blend(px[1]), blend(px[2])]));
+        }
+        img
+    }
+
+    // Largest odd side in modules of a centred box over a share of the correctable codewords,
+    // clear of the finders & their separators
+    fn logo_box_side(&self) -> u32 {
+        let ec_cap = QRBuilder::ec_capacity(self.version(), self.ec_level()) as f64;
+        let max_modules = ec_cap * LOGO_EC_SHARE * 8.0;
+        let side = (max_modules.sqrt() as u32).min(self.width() as u32 - 16);
+        if side.is_multiple_of(2) {
+            side.saturating_sub(1)
+        } else {
+            side
+        }
+    }
+
+    // Whether the image decodes to this QR module for module
+    fn reads_back(&self, img: &RgbImage) -> bool {
+        let img = DynamicImage::ImageRgb8(img.clone());
+        #[cfg(feature = "poly")]
+        let res = match self.high_capacity() {
+            true => crate::reader::detect_hc_qr(&img),
+            false => detect_qr(&img),
+        };
+        #[cfg(not(feature = "poly"))]
+        let res = detect_qr(&img);
+
+        res.symbols().iter().any(|s| s.reencode().is_ok_and(|qr| qr.grid() == self.grid()))
+    }
+}
+
+#[cfg(test)]
+mod logo_tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    use crate::{
+        builder::QRBuilder,
+        metadata::{ECLevel, Version},
+        reader::detect_qr,
+        utils::QRError,
+    };
+
+    // Red disc on a transparent background
+    fn disc(sz: u32) -> DynamicImage {
+        let r = sz as f64 / 2.0;
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(sz, sz, |x, y| {
+            let (dx, dy) = (x as f64 + 0.5 - r, y as f64 + 0.5 - r);
+            match dx.hypot(dy) <= r {
+                true => Rgba([220, 30, 30, 255]),
+                false => Rgba([0, 0, 0, 0]),
+            }
+        }))
+    }
+
+    #[test]
+    fn test_to_image_with_logo() {
+        let data = b"https://example.com/behind-a-logo";
+        let qr = QRBuilder::new(data).ec_level(ECLevel::H).build().unwrap();
+        let img = qr.to_image_with_logo(&disc(64), 4).unwrap();
+        assert_eq!(img.dimensions(), qr.to_image(4).dimensions());
+
+        let c = img.width() / 2;
+        assert_eq!(img.get_pixel(c, c).0, [220, 30, 30]);
+        let res = detect_qr(&DynamicImage::ImageRgb8(img));
+        assert_eq!(res.symbols()[0].decode().unwrap().1.as_bytes(), data);
+    }
+
+    #[test]
+    fn test_logo_area_follows_ec_level() {
+        let data = b"Hello, world!";
+        let sides = [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H].map(|ecl| {
+            let qr = QRBuilder::new(data).version(Version::Normal(5)).ec_level(ecl).build();
+            qr.unwrap().logo_box_side()
+        });
+        assert!(sides.windows(2).all(|w| w[0] <= w[1]), "{sides:?}");
+
+        let qr = QRBuilder::new(data).version(Version::Normal(1)).ec_level(ECLevel::L).build();
+        let res = qr.unwrap().to_image_with_logo(&disc(16), 4);
+        assert_eq!(res, Err(QRError::InsufficientLogoArea));
+
+        let qr = QRBuilder::new(b"1").version(Version::Micro(2)).ec_level(ECLevel::M).build();
+        assert_eq!(qr.unwrap().to_image_with_logo(&disc(16), 4), Err(QRError::InvalidVersion));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Share of the correctable codewords the logo may cover. The rest is left for smudges, glare &
+// blur, besides codewords the box only partly covers
+const LOGO_EC_SHARE: f64 = 0.5;
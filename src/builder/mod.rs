@@ -1,36 +1,142 @@
+mod framebuffer;
+mod gcode;
+mod lint;
+mod logo;
+mod plan;
 mod qr;
-
+mod render;
+mod report;
+mod sheet;
+mod sink;
+mod small;
+mod vector;
+
+use std::{borrow::Cow, ops::RangeInclusive, time::Instant};
+
+pub use framebuffer::PixelFormat;
+pub use gcode::{GcodeConfig, ToolPath};
+pub use lint::{lint, Lint, LintKind};
+pub use plan::EncodePlan;
 pub(crate) use qr::QR;
+pub use render::{RenderConfig, RenderWarning};
+pub use report::{BuildReport, StageTimings};
+pub use sheet::Sheet;
+pub use sink::QRBuilderDataSink;
+pub use vector::Contour;
 
 use crate::{
     common::{
-        codec::{encode, encode_with_version},
-        ec::Block,
+        codec::{
+            boost_ec_level, compute_optimal_segments, eci_codewords, encode_segments,
+            fold_url_case, plan_fixed_segments, plan_segments, structured_append_codewords, Mode,
+            PaddingPolicy, Segment,
+        },
+        ec::add_ecc,
         mask::{apply_best_mask, MaskPattern},
-        metadata::{ECLevel, Version},
-        utils::{BitStream, QRError, QRResult},
+        metadata::{ECLevel, StructuredAppend, Version, MAX_STRUCTURED_APPEND, MAX_VERSION},
+        utils::{QRError, QRResult},
     },
     debug_println,
 };
 
-#[cfg(test)]
 pub(crate) use qr::Module;
 
+// Builder owning its data, which can be stored in structs & sent across threads freely
+pub type QRBuilderOwned = QRBuilder<'static>;
+
+type Headers = Vec<(Mode, Vec<u8>)>;
+
+#[derive(Debug, Clone)]
 pub struct QRBuilder<'a> {
-    data: &'a [u8],
+    data: Cow<'a, [u8]>,
+    segs: Vec<(Mode, usize)>, // Mode & byte len of each appended segment. Optimal if none
     ver: Option<Version>,
+    min_ver: Option<Version>,
+    max_ver: Option<Version>,
     ecl: ECLevel,
+    boost_ecl: bool,
     hi_cap: bool,
     mask: Option<MaskPattern>,
+    raw_format: Option<u16>,
+    mode: Option<Mode>,
+    eci: Option<u32>,
+    sa: Option<StructuredAppend>, // Set for each symbol of a structured append sequence
+    pad: PaddingPolicy<'a>,
+    fold_url: bool,
 }
 
 impl<'a> QRBuilder<'a> {
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data, ver: None, ecl: ECLevel::M, hi_cap: false, mask: None }
+        Self::with_data(Cow::Borrowed(data))
+    }
+
+    pub(crate) fn with_data(data: Cow<'a, [u8]>) -> Self {
+        Self {
+            data,
+            segs: Vec::new(),
+            ver: None,
+            min_ver: None,
+            max_ver: None,
+            ecl: ECLevel::M,
+            boost_ecl: false,
+            hi_cap: false,
+            mask: None,
+            raw_format: None,
+            mode: None,
+            eci: None,
+            sa: None,
+            pad: PaddingPolicy::Standard,
+            fold_url: false,
+        }
     }
 
     pub fn data(&mut self, data: &'a [u8]) -> &mut Self {
-        self.data = data;
+        self.data = Cow::Borrowed(data);
+        self.segs.clear();
+        self
+    }
+
+    pub fn data_owned(&mut self, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.data = Cow::Owned(data.into());
+        self.segs.clear();
+        self
+    }
+
+    // Appends a segment encoded in the given mode, so pre-classified data skips the optimizer.
+    // The first append replaces data set before, & data set after drops the appended segments.
+    // Forced mode & url case folding don't apply to appended segments
+    pub fn append_segment(&mut self, mode: Mode, data: &[u8]) -> &mut Self {
+        if self.segs.is_empty() {
+            self.data = Cow::Owned(Vec::new());
+        }
+        self.data.to_mut().extend_from_slice(data);
+        self.segs.push((mode, data.len()));
+        self
+    }
+
+    // Appends text in the narrowest of numeric, alphanumeric & byte modes that holds all of it
+    pub fn append_str(&mut self, text: &str) -> &mut Self {
+        let modes = [Mode::Numeric, Mode::Alphanumeric];
+        let mode = modes.into_iter().find(|m| m.validate(text.as_bytes()).is_ok());
+        self.append_segment(mode.unwrap_or(Mode::Byte), text.as_bytes())
+    }
+
+    pub fn append_bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.append_segment(Mode::Byte, data)
+    }
+
+    // Replaces the data with the segments, encoded in order in their modes without running the
+    // optimizer. Same as appending each in turn
+    pub fn segments(&mut self, segs: Vec<Segment>) -> &mut Self {
+        let mut data = Vec::with_capacity(segs.iter().map(|s| s.data.len()).sum());
+        self.segs = segs
+            .iter()
+            .map(|s| {
+                data.extend_from_slice(s.data);
+                (s.mode, s.data.len())
+            })
+            .collect();
+        self.data = Cow::Owned(data);
         self
     }
 
@@ -44,11 +150,44 @@ impl<'a> QRBuilder<'a> {
         self
     }
 
+    // Bounds the version picked automatically, e.g. a floor for a uniform size across a series
+    // of labels or a ceiling for the scanners in use. Ignored if a version is set
+    pub fn version_range(&mut self, min: Version, max: Version) -> &mut Self {
+        self.min_ver = Some(min);
+        self.max_ver = Some(max);
+        self
+    }
+
+    // Bounds only one end of the auto version range, leaving the other as is
+    pub fn min_version(&mut self, min: Version) -> &mut Self {
+        self.min_ver = Some(min);
+        self
+    }
+
+    pub fn max_version(&mut self, max: Version) -> &mut Self {
+        self.max_ver = Some(max);
+        self
+    }
+
+    pub fn unset_version_range(&mut self) -> &mut Self {
+        self.min_ver = None;
+        self.max_ver = None;
+        self
+    }
+
     pub fn ec_level(&mut self, ecl: ECLevel) -> &mut Self {
         self.ecl = ecl;
         self
     }
 
+    // Raises the ec level as far as the spare capacity of the version allows, once the version
+    // is decided. The ec level set is then the minimum
+    pub fn boost_ec(&mut self, enabled: bool) -> &mut Self {
+        self.boost_ecl = enabled;
+        self
+    }
+
+    #[cfg(feature = "poly")]
     pub fn high_capacity(&mut self, enabled: bool) -> &mut Self {
         self.hi_cap = enabled;
         self
@@ -59,20 +198,124 @@ impl<'a> QRBuilder<'a> {
         self
     }
 
+    // Draws these bits in both format info areas in place of the ones for the ec level & mask,
+    // as they would appear on the symbol, i.e. already masked. For experimental symbols with
+    // non-standard format info. Scanners read the ec level & mask off these bits, so the symbol
+    // won't decode unless they match
+    pub fn raw_format_bits(&mut self, bits: u16) -> &mut Self {
+        self.raw_format = Some(bits);
+        self
+    }
+
+    pub fn unset_raw_format_bits(&mut self) -> &mut Self {
+        self.raw_format = None;
+        self
+    }
+
+    // Encodes the whole data in the given mode instead of the optimal mix of modes
+    pub fn force_mode(&mut self, mode: Mode) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn unset_mode(&mut self) -> &mut Self {
+        self.mode = None;
+        self
+    }
+
+    // Puts an ECI header with the designator ahead of the data, which should already be in its
+    // charset, e.g. 3 for iso 8859-1 or 20 for shift jis. Designators go up to 999999
+    pub fn eci(&mut self, designator: u32) -> &mut Self {
+        self.eci = Some(designator);
+        self
+    }
+
+    pub fn unset_eci(&mut self) -> &mut Self {
+        self.eci = None;
+        self
+    }
+
+    // Fills the data capacity left after the terminator with the given content
+    pub fn padding(&mut self, pad: PaddingPolicy<'a>) -> &mut Self {
+        self.pad = pad;
+        self
+    }
+
+    // Uppercases the scheme & host of url payloads, which are case insensitive, so they can be
+    // encoded in alphanumeric mode. Changes the decoded payload, hence opt in
+    pub fn fold_url_case(&mut self, enabled: bool) -> &mut Self {
+        self.fold_url = enabled;
+        self
+    }
+
     pub fn metadata(&self) -> String {
-        match self.ver {
-            Some(v) => format!(
-                "{{ Version: {:?}, Ec level: {:?}, High Capacity: {:?} }}",
-                *v, self.ecl, self.hi_cap
-            ),
-            None => {
-                format!(
-                    "{{ Version: None, Ec level: {:?}, High Capacity: {:?} }}",
-                    self.ecl, self.hi_cap
-                )
-            }
+        let ver = match self.ver {
+            Some(v) => format!("{:?}", *v),
+            None => "None".to_string(),
+        };
+        let eci = match self.eci {
+            Some(d) => format!(", ECI: {d}"),
+            None => String::new(),
+        };
+        let sa = match self.sa {
+            Some(sa) => format!(", Structured append: {} of {}", sa.index + 1, sa.total),
+            None => String::new(),
+        };
+        let fold = match self.url_fold_savings() {
+            Some(bits) => format!(", Url case folding: saved {bits} bits"),
+            None => String::new(),
+        };
+        format!(
+            "{{ Version: {ver}, Ec level: {:?}, High Capacity: {:?}{eci}{sa}{fold} }}",
+            self.ecl, self.hi_cap
+        )
+    }
+
+    // Data to be encoded, with the url case folded if enabled
+    fn payload(&self) -> Cow<'_, [u8]> {
+        let fold = self.fold_url && self.segs.is_empty();
+        match fold.then(|| fold_url_case(&self.data)).flatten() {
+            Some(folded) => Cow::Owned(folded),
+            None => Cow::Borrowed(&self.data),
         }
     }
+
+    // Structured append & ECI modes with their codewords, in the order they precede the data
+    fn headers(sa: Option<StructuredAppend>, eci: Option<u32>) -> QRResult<Headers> {
+        let mut headers = Vec::with_capacity(2);
+        if let Some(sa) = sa {
+            headers.push((Mode::StructuredAppend, structured_append_codewords(sa).to_vec()));
+        }
+        if let Some(d) = eci {
+            headers.push((Mode::Eci, eci_codewords(d)?));
+        }
+        Ok(headers)
+    }
+
+    // Versions that auto selection may pick from
+    fn candidate_versions(&self) -> QRResult<RangeInclusive<usize>> {
+        let bound = |ver: Option<Version>, default| match ver {
+            None => Ok(default),
+            Some(Version::Normal(v)) => Ok(v),
+            Some(_) => Err(QRError::InvalidVersion),
+        };
+        Ok(bound(self.min_ver, 1)?..=bound(self.max_ver, MAX_VERSION)?)
+    }
+
+    // Bits saved by folding the url case, measured at the version picked for the folded payload
+    fn url_fold_savings(&self) -> Option<usize> {
+        if !self.fold_url || !self.segs.is_empty() {
+            return None;
+        }
+
+        let bit_len = |segs: &[Segment]| segs.iter().map(|s| s.bit_len()).sum::<usize>();
+        let payload = self.payload();
+        let vers = self.candidate_versions().ok()?;
+        let (ver, segs) =
+            plan_segments(&payload, &[], self.mode, self.ver, vers, self.ecl, self.hi_cap).ok()?;
+        let orig = compute_optimal_segments(&self.data, ver);
+        Some(bit_len(&orig).saturating_sub(bit_len(&segs)))
+    }
 }
 
 #[cfg(test)]
@@ -86,124 +329,201 @@ mod qrbuilder_util_tests {
         let ver = Version::Normal(1);
         let ecl = ECLevel::L;
         let mut qr_bldr = QRBuilder::new(data);
-        qr_bldr.version(ver).ec_level(ecl).high_capacity(false);
+        qr_bldr.version(ver).ec_level(ecl);
         assert_eq!(qr_bldr.metadata(), "{ Version: 1, Ec level: L, High Capacity: false }");
         qr_bldr.unset_version();
         assert_eq!(qr_bldr.metadata(), "{ Version: None, Ec level: L, High Capacity: false }");
     }
 }
 
+impl QRBuilderOwned {
+    // Builder taking ownership of the data, e.g. a String or Vec<u8>
+    pub fn owned(data: impl Into<Vec<u8>>) -> Self {
+        Self::with_data(Cow::Owned(data.into()))
+    }
+}
+
 impl QRBuilder<'_> {
     pub fn build(&mut self) -> QRResult<QR> {
-        debug_println!("\nConstructing QR {}...", self.metadata());
+        let plan = self.plan()?;
+        let qr = self.realize(&plan)?;
+        if let Some(m) = qr.mask() {
+            self.mask(m);
+        }
+        Ok(qr)
+    }
+
+    // Splits the data across as few symbols as fit, up to max_symbols of at most 16, linked by
+    // structured append headers so scanners can join them back. Data is split evenly, at char
+    // boundaries for utf8 data, and each symbol is built with the builder's settings
+    pub fn structured_append(&self, max_symbols: usize) -> QRResult<Vec<QR>> {
+        if !(1..=MAX_STRUCTURED_APPEND).contains(&max_symbols) {
+            return Err(QRError::InvalidStructuredAppendCount(max_symbols));
+        }
         if self.data.is_empty() {
             return Err(QRError::EmptyData);
         }
 
-        // Encode data optimally
-        debug_println!("Encoding data...");
-        let (enc, ver) = match self.ver {
-            Some(v) => (encode_with_version(self.data, v, self.ecl, self.hi_cap)?, v),
-            None => {
-                debug_println!("Finding best version...");
-                encode(self.data, self.ecl, self.hi_cap)?
+        let payload = self.payload();
+        let parity = StructuredAppend::parity(&payload);
+        for n in 1..=max_symbols.min(payload.len()) {
+            let parts = split_evenly(&payload, n);
+            if parts.iter().any(|p| p.is_empty()) {
+                continue;
             }
+            let plans = parts.iter().enumerate().map(|(i, part)| {
+                let mut bldr = self.clone();
+                bldr.data_owned(*part).fold_url_case(false);
+                bldr.sa = Some(StructuredAppend { index: i as u8, total: n as u8, parity });
+                bldr.plan().map(|plan| (bldr, plan))
+            });
+            match plans.collect::<QRResult<Vec<_>>>() {
+                Ok(plans) => return plans.iter().map(|(b, p)| b.realize(p)).collect(),
+                Err(
+                    QRError::DataTooLong
+                    | QRError::ExceedsVersionRange(..)
+                    | QRError::FitsNoVersion(_),
+                ) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(QRError::DataTooLong)
+    }
+
+    // Decides version, segments & mask without drawing the QR
+    pub fn plan(&self) -> QRResult<EncodePlan> {
+        debug_println!("\nPlanning QR {}...", self.metadata());
+        if self.data.is_empty() {
+            return Err(QRError::EmptyData);
+        }
+        if self.pad == PaddingPolicy::Custom(&[]) {
+            return Err(QRError::EmptyPadding);
+        }
+
+        // Segment data optimally, unless segments were appended
+        debug_println!("Segmenting data...");
+        let payload = self.payload();
+        let vers = self.candidate_versions()?;
+        let headers = Self::headers(self.sa, self.eci)?;
+        let headers: Vec<_> = headers.iter().map(|(m, cwds)| (*m, &cwds[..])).collect();
+        let (ver, segs) = match self.segs.is_empty() {
+            true => {
+                plan_segments(&payload, &headers, self.mode, self.ver, vers, self.ecl, self.hi_cap)
+            }
+            false => {
+                let (ver, ecl, hi_cap) = (self.ver, self.ecl, self.hi_cap);
+                plan_fixed_segments(&payload, &self.segs, &headers, ver, vers, ecl, hi_cap)
+            }
+        }?;
+        let ecl = match self.boost_ecl {
+            true => boost_ec_level(&segs, ver, self.ecl, self.hi_cap),
+            false => self.ecl,
         };
 
-        let _data_len = self.data.len();
-        let _data_cap = ver.data_capacity(self.ecl, self.hi_cap);
-        let _ec_cap = Self::ec_capacity(ver, self.ecl);
-        let tot_cwds = ver.total_codewords(self.hi_cap);
+        Ok(EncodePlan::new(ver, ecl, self.hi_cap, self.mask, self.eci, self.sa, &segs))
+    }
+
+    // Same as build, also reporting the chosen version, segments, sizes, stage timings & dark
+    // module balance
+    pub fn build_with_report(&mut self) -> QRResult<(QR, BuildReport)> {
+        let start = Instant::now();
+        let plan = self.plan()?;
+        let plan_time = start.elapsed();
+
+        let (qr, mut report) = self.realize_with_report(&plan)?;
+        report.timings.plan = plan_time;
+        if let Some(m) = report.mask {
+            self.mask(m);
+        }
+        Ok((qr, report))
+    }
 
-        debug_println!("Constructing payload with ecc & interleaving...");
-        let mut pld = BitStream::new(tot_cwds << 3);
-        let chan_data_cap = ver.channel_data_capacity(self.ecl);
+    // Constructs the QR for the builder data as per the plan
+    pub fn realize(&self, plan: &EncodePlan) -> QRResult<QR> {
+        self.realize_with_report(plan).map(|(qr, _)| qr)
+    }
 
-        debug_assert!(
-            enc.len() % chan_data_cap == 0,
-            "Encoded data length {} is not divisible by channel codewords {chan_data_cap}",
-            enc.len()
-        );
+    fn realize_with_report(&self, plan: &EncodePlan) -> QRResult<(QR, BuildReport)> {
+        debug_println!("\nConstructing QR {}...", self.metadata());
+        if self.data.is_empty() {
+            return Err(QRError::EmptyData);
+        }
+        if self.pad == PaddingPolicy::Custom(&[]) {
+            return Err(QRError::EmptyPadding);
+        }
 
-        enc.data().chunks_exact(chan_data_cap).for_each(|c| {
-            // Splits the data into EC block. The blocks will auto compute ecc
-            let blks = Self::blockify(c, ver, self.ecl);
+        let ver = plan.version();
+        let ecl = plan.ec_level();
+        let hi_cap = plan.high_capacity();
+        if let (Version::Micro(_), Some(m)) = (ver, plan.mask()) {
+            m.micro_number().ok_or(QRError::InvalidMaskingPattern)?;
+        }
+        let mut timings = StageTimings::default();
 
-            // Interleave data & error correction codewords, and write into payload
-            Self::interleave_into(&blks, &mut pld);
-        });
+        debug_println!("Encoding data...");
+        let mut stage = Instant::now();
+        let payload = self.payload();
+        let headers = Self::headers(plan.structured_append(), plan.eci())?;
+        let headers: Vec<_> = headers.iter().map(|(m, cwds)| (*m, &cwds[..])).collect();
+        let segs = plan.segment(&payload, &headers)?;
+        let encoded_bits = segs.iter().map(|s| s.bit_len()).sum();
+        let enc = encode_segments(segs, ver, ecl, hi_cap, self.pad);
+        timings.encode = stage.elapsed();
 
-        // Construct QR
-        debug_println!("Constructing QR...");
-        let mut qr = QR::new(ver, self.ecl, self.hi_cap);
+        debug_println!("Constructing payload with ecc & interleaving...");
+        stage = Instant::now();
+        let pld = add_ecc(enc.data(), ver, ecl, hi_cap);
+        timings.ecc = stage.elapsed();
 
-        debug_println!("Drawing functional patterns...");
-        qr.draw_all_function_patterns();
+        // Construct QR
+        debug_println!("Constructing QR from function pattern template...");
+        stage = Instant::now();
+        let mut qr = QR::from_template(ver, ecl, hi_cap);
 
-        debug_println!("Drawing encoding region...");
-        qr.draw_encoding_region(pld);
+        debug_println!("Drawing data region...");
+        qr.draw_data_region(pld);
+        qr.set_segments(plan.segment_infos());
+        timings.draw = stage.elapsed();
 
-        let mask = match self.mask {
+        stage = Instant::now();
+        match plan.mask() {
             Some(m) => {
                 debug_println!("Apply mask {m:?}...");
                 qr.apply_mask(m);
-                m
+            }
+            None if small::is_small(ver) => {
+                debug_println!("Finding & applying best mask on stack grid...");
+                small::apply_best_mask_small(&mut qr);
             }
             None => {
                 debug_println!("Finding & applying best mask...");
-                apply_best_mask(&mut qr)
+                apply_best_mask(&mut qr);
             }
         };
-        self.mask(mask);
-
-        debug_println!("\x1b[1;32mQR generated successfully!\n \x1b[0m");
-
-        let tot_mods = ver.width() * ver.width();
-        let dark_mods = qr.count_dark_modules();
-        let _lt_mods = tot_mods - dark_mods;
-
-        debug_println!("Report:");
-        debug_println!("{}", qr.metadata());
-        debug_println!("Data capacity: {}, Error Capacity: {}", _data_cap, _ec_cap);
-        debug_println!(
-            "Data size: {}, Encoded size: {}, Compression: {}%",
-            _data_len,
-            enc.len() >> 3,
-            (enc.len() >> 3) * 100 / _data_len
-        );
-        debug_println!(
-            "Dark Cells: {}, Light Cells: {}, Balance: {}\n",
-            dark_mods,
-            _lt_mods,
-            dark_mods * 100 / tot_mods
-        );
-
-        Ok(qr)
-    }
-
-    pub(crate) fn blockify(data: &[u8], ver: Version, ecl: ECLevel) -> Vec<Block> {
-        // b1s = block1_size, b1c = block1_count
-        let (b1s, b1c, b2s, b2c) = ver.data_codewords_per_block(ecl);
-        let ec_len = ver.ecc_per_block(ecl);
 
-        let b1_tot_sz = b1s * b1c;
-        let tot_sz = b1_tot_sz + b2s * b2c;
-
-        debug_assert!(
-            tot_sz == data.len(),
-            "Data len doesn't match total size of blocks: Data len {}, Total block size {}",
-            data.len(),
-            tot_sz
-        );
-
-        let mut blks = Vec::with_capacity(256);
-        data[..b1_tot_sz].chunks(b1s).for_each(|d| blks.push(Block::new(d, b1s + ec_len)));
-
-        if b2s > 0 {
-            data[b1_tot_sz..].chunks(b2s).for_each(|d| blks.push(Block::new(d, b2s + ec_len)));
+        if let Some(bits) = self.raw_format {
+            debug_println!("Overriding format info with {bits:015b}...");
+            qr.draw_raw_format_info(bits);
         }
+        timings.mask = stage.elapsed();
+
+        let chans = if hi_cap { 3 } else { 1 };
+        let report = BuildReport {
+            version: ver,
+            ec_level: ecl,
+            high_capacity: hi_cap,
+            mask: qr.mask(),
+            segments: plan.segment_infos(),
+            data_bytes: self.data.len(),
+            encoded_bits,
+            capacity_bits: ver.data_bit_capacity(ecl, hi_cap),
+            ecc_bits: ver.block_layout(ecl).ec_total() * 8 * chans,
+            dark_modules: qr.count_dark_modules(),
+            total_modules: ver.width() * ver.width(),
+            timings,
+        };
 
-        blks
+        Ok((qr, report))
     }
 
     pub fn ec_capacity(ver: Version, ecl: ECLevel) -> usize {
@@ -215,49 +535,46 @@ impl QRBuilder<'_> {
             _ => 0,
         };
 
-        let ec_bpb = ver.ecc_per_block(ecl);
-        let (_, cnt1, _, cnt2) = ver.data_codewords_per_block(ecl);
-        let ec_bytes = (cnt1 + cnt2) * ec_bpb;
+        let ec_bytes = ver.block_layout(ecl).ec_total();
 
         (ec_bytes - p) / 2
     }
+}
 
-    pub(crate) fn interleave_into(blks: &[Block], out: &mut BitStream) {
-        // Interleaving data codewords
-        let max_len = blks.iter().map(Block::data_len).max().expect("Blocks is empty");
-        for i in 0..max_len {
-            for bl in blks.iter() {
-                if let Some(b) = bl.data().get(i) {
-                    out.push_byte(*b)
-                }
-            }
-        }
-
-        // Interleaving ec codewords
-        let ec_len = blks[0].ec_len();
-        for i in 0..ec_len {
-            for bl in blks.iter() {
-                if let Some(b) = bl.ecc().get(i) {
-                    out.push_byte(*b)
-                }
+// Splits data into n parts of near equal length. Utf8 data is only split at char boundaries
+fn split_evenly(data: &[u8], n: usize) -> Vec<&[u8]> {
+    let text = std::str::from_utf8(data).ok();
+    let mut parts = Vec::with_capacity(n);
+    let mut start = 0;
+    for i in 1..=n {
+        let mut end = data.len() * i / n;
+        if let Some(t) = text {
+            while !t.is_char_boundary(end) {
+                end += 1;
             }
         }
+        parts.push(&data[start..end.max(start)]);
+        start = end.max(start);
     }
+    parts
 }
 
 #[cfg(test)]
 mod builder_tests {
 
-    use super::QRBuilder;
-    use crate::ec::Block;
-    use crate::metadata::{ECLevel, Version};
-    use crate::utils::BitStream;
+    use super::{QRBuilder, QRBuilderOwned};
+    use crate::codec::{Mode, PaddingPolicy, Segment};
+    use crate::detect_qr;
+    use crate::ec::Interleaver;
+    use crate::mask::MaskPattern;
+    use crate::metadata::{ECLevel, StructuredAppend, Version};
+    use crate::utils::QRError;
 
     #[test]
     fn test_add_ec_simple() {
         let msg = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
         let exp_ecc = [b"\xc4\x23\x27\x77\xeb\xd7\xe7\xe2\x5d\x17"];
-        let blks = QRBuilder::blockify(msg, Version::Normal(1), ECLevel::M);
+        let blks = Interleaver::new(Version::Normal(1).block_layout(ECLevel::M)).blockify(msg);
         assert_eq!(blks.len(), exp_ecc.len());
         for (i, b) in blks.iter().enumerate() {
             assert_eq!(b.ecc(), *exp_ecc[i]);
@@ -275,24 +592,13 @@ mod builder_tests {
             b"\x94\x74\xb1\xd4\x4c\x85\x4b\xf2\xee\x4c\xc3\xe6\xbd\x0a\x6c\xf0\xc0\x8d",
             b"\xeb\x9f\x05\xad\x18\x93\x3b\x21\x6a\x28\xff\xac\x52\x02\x83\x20\xb2\xec",
         ];
-        let blks = QRBuilder::blockify(msg, Version::Normal(5), ECLevel::Q);
+        let blks = Interleaver::new(Version::Normal(5).block_layout(ECLevel::Q)).blockify(msg);
         assert_eq!(blks.len(), exp_ecc.len());
         for (i, b) in blks.iter().enumerate() {
             assert_eq!(b.ecc(), *exp_ecc[i]);
         }
     }
 
-    #[test]
-    fn test_interleave() {
-        let data = [vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9, 0]];
-        let mut blks: Vec<Block> = Vec::with_capacity(256);
-        data.iter().for_each(|b| blks.push(Block::new(b, 6)));
-        let mut ilvd = BitStream::new(256);
-        QRBuilder::interleave_into(&blks, &mut ilvd);
-        let exp_ilvd = vec![1, 4, 7, 2, 5, 8, 3, 6, 9, 0];
-        assert_eq!(ilvd.data()[..10], exp_ilvd);
-    }
-
     #[test]
     #[should_panic]
     fn test_builder_data_overflow() {
@@ -304,4 +610,349 @@ mod builder_tests {
             .build()
             .unwrap();
     }
+
+    #[test]
+    fn test_builder_force_mode_invalid_char() {
+        let res = QRBuilder::new(b"12345.6").force_mode(Mode::Numeric).build();
+        assert_eq!(res.err(), Some(QRError::InvalidModeChar(Mode::Numeric, 5, '.')));
+    }
+
+    #[test]
+    fn test_build_with_report() {
+        let data = "1234567890ABCDEFGHIJ hello";
+        let mut bldr = QRBuilder::new(data.as_bytes());
+        bldr.ec_level(ECLevel::Q);
+        let (qr, report) = bldr.build_with_report().unwrap();
+
+        assert_eq!(qr.metadata().version(), Some(report.version));
+        assert_eq!(report.ec_level, ECLevel::Q);
+        assert_eq!(report.mask, qr.mask());
+        assert_eq!(bldr.mask, report.mask);
+        assert_eq!(report.data_bytes, data.len());
+        let seg_len = report.segments.iter().map(|s| s.range.len()).sum::<usize>();
+        assert_eq!(seg_len, data.len());
+
+        let ver = report.version;
+        assert_eq!(report.capacity_bits, ver.data_bit_capacity(ECLevel::Q, false));
+        assert_eq!(report.capacity_bits + report.ecc_bits, ver.total_codewords(false) * 8);
+        assert!(report.encoded_bits <= report.capacity_bits);
+        assert!(report.fill_ratio() > 0.5 && report.fill_ratio() <= 1.0);
+        assert_eq!(report.total_modules, ver.width() * ver.width());
+        assert!((0.3..0.7).contains(&report.dark_ratio()));
+        assert!(report.timings.total() >= report.timings.mask);
+
+        assert_eq!(QRBuilder::new(b"").build_with_report().err(), Some(QRError::EmptyData));
+    }
+
+    #[test]
+    fn test_plan_and_realize() {
+        let mut bldr = QRBuilder::new(b"HELLO 12345678");
+        let mut plan = bldr.plan().unwrap();
+        assert_eq!(plan.version(), Version::Normal(1));
+        assert_eq!(plan.segments(), [(Mode::Alphanumeric, 6), (Mode::Numeric, 8)]);
+
+        plan.set_mask(MaskPattern::new(5));
+        let qr = bldr.realize(&plan).unwrap();
+        assert_eq!(qr.mask(), Some(MaskPattern::new(5)));
+
+        // Plan is reused for payload of same shape
+        bldr.data(b"WORLD 87654321");
+        let qr = bldr.realize(&plan).unwrap();
+        assert_eq!(qr.version(), Version::Normal(1));
+
+        bldr.data(b"world 87654321");
+        let res = bldr.realize(&plan);
+        assert_eq!(res.err(), Some(QRError::InvalidModeChar(Mode::Alphanumeric, 0, 'w')));
+
+        bldr.data(b"WORLD 8765432");
+        assert_eq!(bldr.realize(&plan).err(), Some(QRError::PlanMismatch));
+    }
+
+    #[test]
+    fn test_builder_boost_ec() {
+        let mut bldr = QRBuilder::new(b"HELLO WORLD");
+        bldr.ec_level(ECLevel::L).boost_ec(true);
+        let qr = bldr.build().unwrap();
+        assert_eq!((qr.version(), qr.ec_level()), (Version::Normal(1), ECLevel::Q));
+        assert_eq!(qr.metadata().ec_level(), Some(ECLevel::Q));
+
+        // Spare capacity of a larger version than needed goes to ec too
+        bldr.min_version(Version::Normal(3));
+        assert_eq!(bldr.build().unwrap().ec_level(), ECLevel::H);
+
+        bldr.unset_version_range().boost_ec(false);
+        assert_eq!(bldr.build().unwrap().ec_level(), ECLevel::L);
+    }
+
+    #[cfg(not(any(feature = "max-version-5", feature = "max-version-10")))]
+    #[test]
+    fn test_builder_version_range() {
+        let mut bldr = QRBuilder::new(b"Label 0001");
+        bldr.version_range(Version::Normal(5), Version::Normal(8));
+        assert_eq!(bldr.build().unwrap().version(), Version::Normal(5));
+
+        let data = "Label 0001".repeat(20);
+        bldr.data(data.as_bytes()).version_range(Version::Normal(1), Version::Normal(3));
+        assert_eq!(bldr.build().err(), Some(QRError::ExceedsVersionRange(3, 10)));
+
+        bldr.version(Version::Normal(10));
+        assert_eq!(bldr.build().unwrap().version(), Version::Normal(10));
+
+        bldr.unset_version().version_range(Version::Micro(1), Version::Normal(3));
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidVersion));
+
+        bldr.unset_version_range().min_version(Version::Normal(12));
+        assert_eq!(bldr.build().unwrap().version(), Version::Normal(12));
+
+        bldr.unset_version_range().max_version(Version::Normal(11));
+        assert_eq!(bldr.build().unwrap().version(), Version::Normal(10));
+
+        bldr.max_version(Version::Normal(9));
+        assert_eq!(bldr.build().err(), Some(QRError::ExceedsVersionRange(9, 10)));
+
+        bldr.min_version(Version::Normal(8)).max_version(Version::Normal(7));
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidVersion));
+    }
+
+    #[test]
+    fn test_builder_raw_format_bits() {
+        use crate::metadata::generate_format_info_qr;
+
+        let ecl = ECLevel::Q;
+        let std_bits = generate_format_info_qr(ecl, MaskPattern::new(2)) as u16;
+        let mut bldr = QRBuilder::new(b"Raw format");
+        bldr.ec_level(ecl).mask(MaskPattern::new(2));
+
+        // A flipped bit is corrected, so the symbol still decodes
+        let bits = std_bits ^ 0b100;
+        let qr = bldr.raw_format_bits(bits).build().unwrap();
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(qr.to_image(3)));
+        assert_eq!(res.symbols()[0].raw_format_bits(), (Some(bits), Some(bits)));
+        let (meta, msg) = res.symbols()[0].decode().unwrap();
+        assert_eq!(msg, "Raw format");
+        assert_eq!(meta.raw_format_bits(), Some(bits));
+
+        // Format info claiming a different mask than the one applied
+        let bits = generate_format_info_qr(ECLevel::L, MaskPattern::new(6)) as u16;
+        let qr = bldr.raw_format_bits(bits).build().unwrap();
+        assert_eq!(qr.mask(), Some(MaskPattern::new(2)));
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(qr.to_image(3)));
+        let format = res.symbols()[0].read_format_info().unwrap();
+        assert_eq!(format, (ECLevel::L, MaskPattern::new(6)));
+    }
+
+    #[test]
+    fn test_builder_padding() {
+        let pads = [PaddingPolicy::Standard, PaddingPolicy::Zeros, PaddingPolicy::Custom(b"PAD")];
+        for pad in pads {
+            let qr = QRBuilder::new(b"Padded").padding(pad).build().unwrap();
+            let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+            let res = detect_qr(&img);
+            assert_eq!(res.symbols()[0].decode().unwrap().1, "Padded");
+        }
+
+        let res = QRBuilder::new(b"Padded").padding(PaddingPolicy::Custom(&[])).build();
+        assert_eq!(res.err(), Some(QRError::EmptyPadding));
+    }
+
+    #[test]
+    fn test_builder_owned() {
+        struct Job {
+            bldr: QRBuilderOwned,
+        }
+
+        let msg = String::from("Owned data");
+        let mut job = Job { bldr: QRBuilder::owned(msg.clone()) };
+        job.bldr.ec_level(ECLevel::H);
+        let qr = std::thread::spawn(move || job.bldr.build().unwrap()).join().unwrap();
+        let exp = QRBuilder::new(msg.as_bytes()).ec_level(ECLevel::H).build().unwrap();
+        assert_eq!(qr.grid(), exp.grid());
+
+        let mut bldr = QRBuilder::new(b"Borrowed");
+        bldr.data_owned(msg).ec_level(ECLevel::H);
+        assert_eq!(bldr.build().unwrap().grid(), exp.grid());
+    }
+
+    #[test]
+    fn test_builder_fold_url_case() {
+        let url = b"https://example.com/ABC-123/XYZ-789";
+        let mut bldr = QRBuilder::new(url);
+        assert_eq!(bldr.build().unwrap().version(), Version::Normal(3));
+
+        bldr.fold_url_case(true);
+        let exp =
+            "{ Version: None, Ec level: M, High Capacity: false, Url case folding: saved 59 bits }";
+        assert_eq!(bldr.metadata(), exp);
+        let qr = bldr.build().unwrap();
+        assert_eq!(qr.version(), Version::Normal(2));
+
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+        let res = detect_qr(&img);
+        assert_eq!(res.symbols()[0].decode().unwrap().1, "HTTPS://EXAMPLE.COM/ABC-123/XYZ-789");
+    }
+
+    #[test]
+    fn test_builder_eci() {
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("Crème brûlée");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは");
+        for (eci, data, exp) in [(3, &latin1, "Crème brûlée"), (20, &sjis, "こんにちは")] {
+            let mut bldr = QRBuilder::new(data);
+            bldr.eci(eci);
+            assert_eq!(bldr.plan().unwrap().eci(), Some(eci));
+            let qr = bldr.build().unwrap();
+
+            let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+            let res = detect_qr(&img);
+            let (meta, msg) = res.symbols()[0].decode().unwrap();
+            assert_eq!(msg, exp);
+            assert_eq!(meta.eci(), Some(eci));
+            assert_eq!(meta.segments()[0].mode, Mode::Eci);
+        }
+
+        let res = QRBuilder::new(b"Eci").eci(1_000_000).build();
+        assert_eq!(res.err(), Some(QRError::InvalidEciDesignator(1_000_000)));
+    }
+
+    #[test]
+    fn test_builder_structured_append() {
+        let data = "Ünïcödé sequence split over linked symbols. ".repeat(3);
+        let mut bldr = QRBuilder::new(data.as_bytes());
+        bldr.version(Version::Normal(3)).ec_level(ECLevel::M);
+        assert_eq!(bldr.build().err(), Some(QRError::DataTooLong));
+
+        let qrs = bldr.structured_append(16).unwrap();
+        assert_eq!(qrs.len(), 4);
+
+        let mut joined = String::new();
+        for (i, qr) in qrs.iter().enumerate() {
+            let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+            let res = detect_qr(&img);
+            let (meta, msg) = res.symbols()[0].decode().unwrap();
+            let sa = meta.structured_append().unwrap();
+            assert_eq!((sa.index, sa.total), (i as u8, 4));
+            assert_eq!(sa.parity, StructuredAppend::parity(data.as_bytes()));
+            assert_eq!(meta.segments()[0].mode, Mode::StructuredAppend);
+            joined.push_str(&msg);
+        }
+        assert_eq!(joined, data);
+
+        assert_eq!(bldr.structured_append(3).err(), Some(QRError::DataTooLong));
+        let err = Some(QRError::InvalidStructuredAppendCount(17));
+        assert_eq!(bldr.structured_append(17).err(), err);
+    }
+
+    #[test]
+    fn test_builder_segments() {
+        let qr = QRBuilder::new(b"abcABCDEF1234567890123ABCDEFabc").build().unwrap();
+        let meta = qr.metadata();
+        let segs: Vec<_> = meta.segments().iter().map(|s| (s.mode, s.range.clone())).collect();
+        let exp = [
+            (Mode::Byte, 0..3),
+            (Mode::Alphanumeric, 3..9),
+            (Mode::Numeric, 9..22),
+            (Mode::Alphanumeric, 22..28),
+            (Mode::Byte, 28..31),
+        ];
+        assert_eq!(segs, exp);
+
+        // Decoder reads back the same segments
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+        let res = detect_qr(&img);
+        let (decoded, _) = res.symbols()[0].decode().unwrap();
+        assert_eq!(decoded.segments(), meta.segments());
+    }
+
+    #[test]
+    fn test_builder_append_segments() {
+        // Digits kept in byte mode as appended, where the optimizer would switch to numeric
+        let mut bldr = QRBuilder::new(b"ignored");
+        bldr.append_str("ORDER ").append_str("0012345678").append_bytes(b"42/ok");
+        bldr.append_segment(Mode::Byte, b"2024");
+        let qr = bldr.build().unwrap();
+        let meta = qr.metadata();
+        let segs: Vec<_> = meta.segments().iter().map(|s| (s.mode, s.range.clone())).collect();
+        let exp = [
+            (Mode::Alphanumeric, 0..6),
+            (Mode::Numeric, 6..16),
+            (Mode::Byte, 16..21),
+            (Mode::Byte, 21..25),
+        ];
+        assert_eq!(segs, exp);
+
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+        let res = detect_qr(&img);
+        assert_eq!(res.symbols()[0].decode().unwrap().1, "ORDER 001234567842/ok2024");
+
+        // Segments are checked against their mode, with the offset into the whole data
+        bldr.append_segment(Mode::Numeric, b"12a");
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidModeChar(Mode::Numeric, 27, 'a')));
+        bldr.data(b"12a");
+        assert_eq!(bldr.plan().unwrap().segments(), [(Mode::Byte, 3)]);
+
+        let mut bldr = QRBuilder::new(b"");
+        bldr.append_str(&"12345".repeat(40)).version_range(Version::Normal(1), Version::Normal(2));
+        assert_eq!(bldr.build().err(), Some(QRError::ExceedsVersionRange(2, 5)));
+        bldr.unset_version_range().version(Version::Micro(2)).append_bytes(b"a");
+        assert_eq!(bldr.build().err(), Some(QRError::UnsupportedMicroMode(Mode::Byte)));
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_builder_split_long_segments() {
+        // Runs beyond the char count limit of the version are split across segments
+        let mut bldr = QRBuilder::new(b"");
+        bldr.append_bytes(&[b'a'; 260]).high_capacity(true);
+        bldr.version(Version::Normal(5)).ec_level(ECLevel::L);
+        assert_eq!(bldr.plan().unwrap().segments(), [(Mode::Byte, 255), (Mode::Byte, 5)]);
+        let qr = bldr.build().unwrap();
+        let segs: Vec<_> = qr.metadata().segments().iter().map(|s| s.range.clone()).collect();
+        assert_eq!(segs, [0..255, 255..260]);
+    }
+
+    #[test]
+    fn test_builder_given_segments() {
+        let segs = vec![Segment::new(Mode::Byte, b"id="), Segment::new(Mode::Numeric, b"0042")];
+        let mut bldr = QRBuilder::new(b"replaced");
+        let plan = bldr.segments(segs.clone()).plan().unwrap();
+        assert_eq!(plan.segments(), [(Mode::Byte, 3), (Mode::Numeric, 4)]);
+
+        // Same as appending each in turn
+        let mut appended = QRBuilder::new(b"");
+        appended.append_bytes(b"id=").append_segment(Mode::Numeric, b"0042");
+        assert_eq!(appended.build().unwrap().grid(), bldr.build().unwrap().grid());
+
+        bldr.segments(vec![Segment::new(Mode::Eci, b"\x1a")]);
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidMode(Mode::Eci as u8)));
+        assert_eq!(bldr.segments(vec![]).build().err(), Some(QRError::EmptyData));
+    }
+
+    #[test]
+    fn test_builder_micro() {
+        // Example from ISO/IEC 18004 annex I
+        let mut bldr = QRBuilder::new(b"01234567");
+        bldr.version(Version::Micro(2)).ec_level(ECLevel::L);
+        let qr = bldr.build().unwrap();
+        let exp = b"\x40\x18\xac\xc3\x00\x86\x0d\x22\xae\x30";
+        assert_eq!(qr.read_payload(), exp);
+        assert_eq!(qr.width(), 13);
+
+        for (v, ecl, w) in [(1, ECLevel::L, 11), (3, ECLevel::M, 15), (4, ECLevel::Q, 17)] {
+            let mut bldr = QRBuilder::new(b"12345");
+            let qr = bldr.version(Version::Micro(v)).ec_level(ecl).build().unwrap();
+            assert_eq!(qr.width(), w);
+            assert!(qr.mask().unwrap().micro_number().is_some());
+        }
+
+        let mut bldr = QRBuilder::new(b"ABC");
+        bldr.version(Version::Micro(1)).ec_level(ECLevel::L);
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidModeChar(Mode::Numeric, 0, 'A')));
+        bldr.version(Version::Micro(2)).force_mode(Mode::Byte);
+        assert_eq!(bldr.build().err(), Some(QRError::UnsupportedMicroMode(Mode::Byte)));
+        bldr.version(Version::Micro(4)).ec_level(ECLevel::H);
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidECLevel));
+        bldr.ec_level(ECLevel::L).eci(26);
+        assert_eq!(bldr.build().err(), Some(QRError::UnsupportedMicroMode(Mode::Eci)));
+        bldr.unset_eci().mask(MaskPattern::new(0));
+        assert_eq!(bldr.build().err(), Some(QRError::InvalidMaskingPattern));
+    }
 }
@@ -0,0 +1,128 @@
+use crate::{
+    codec::{Mode, Segment},
+    mask::MaskPattern,
+    metadata::{ECLevel, SegmentInfo, StructuredAppend, Version},
+    utils::{QRError, QRResult},
+};
+
+// Encode plan
+//------------------------------------------------------------------------------
+
+// Decisions made before any module is drawn. A plan only depends on the shape of the payload,
+// i.e. the mode & length of each segment, so it can be reused for payloads of the same shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodePlan {
+    ver: Version,
+    ecl: ECLevel,
+    hi_cap: bool,
+    mask: Option<MaskPattern>, // Best mask is picked during realization if none
+    eci: Option<u32>,
+    sa: Option<StructuredAppend>,
+    segs: Vec<(Mode, usize)>, // Mode & char count of each data segment
+}
+
+impl EncodePlan {
+    pub(crate) fn new(
+        ver: Version,
+        ecl: ECLevel,
+        hi_cap: bool,
+        mask: Option<MaskPattern>,
+        eci: Option<u32>,
+        sa: Option<StructuredAppend>,
+        segs: &[Segment],
+    ) -> Self {
+        let segs = segs.iter().filter(|s| !matches!(s.mode, Mode::Eci | Mode::StructuredAppend));
+        let segs = segs.map(|s| (s.mode, s.data.len())).collect();
+        Self { ver, ecl, hi_cap, mask, eci, sa, segs }
+    }
+
+    pub fn version(&self) -> Version {
+        self.ver
+    }
+
+    pub fn ec_level(&self) -> ECLevel {
+        self.ecl
+    }
+
+    pub fn high_capacity(&self) -> bool {
+        self.hi_cap
+    }
+
+    pub fn mask(&self) -> Option<MaskPattern> {
+        self.mask
+    }
+
+    pub fn eci(&self) -> Option<u32> {
+        self.eci
+    }
+
+    pub fn structured_append(&self) -> Option<StructuredAppend> {
+        self.sa
+    }
+
+    pub fn segments(&self) -> &[(Mode, usize)] {
+        &self.segs
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.segs.iter().map(|(_, l)| l).sum()
+    }
+
+    // Segments with the byte range each covers in the data
+    pub(crate) fn segment_infos(&self) -> Vec<SegmentInfo> {
+        let mut start = 0;
+        let mut infos = Vec::with_capacity(self.segs.len());
+        for &(mode, len) in self.segs.iter() {
+            let char_cnt = if mode == Mode::Kanji { len / 2 } else { len };
+            infos.push(SegmentInfo { mode, char_cnt, range: start..start + len });
+            start += len;
+        }
+        infos
+    }
+
+    pub fn set_mask(&mut self, mask: MaskPattern) -> &mut Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    pub fn unset_mask(&mut self) -> &mut Self {
+        self.mask = None;
+        self
+    }
+
+    // Splits data into segments as per the plan, after a segment for each header, i.e. a
+    // structured append or ECI mode with its codewords. Fails if the data doesn't match the shape
+    pub(crate) fn segment<'a>(
+        &self,
+        data: &'a [u8],
+        headers: &[(Mode, &'a [u8])],
+    ) -> QRResult<Vec<Segment<'a>>> {
+        if data.len() != self.data_len() {
+            return Err(QRError::PlanMismatch);
+        }
+
+        let mode_bits = self.ver.mode_bits();
+        let mut segs = Vec::with_capacity(self.segs.len() + headers.len());
+        for &(mode, cwds) in headers {
+            segs.push(Segment::with_bits(mode, mode_bits, 0, cwds));
+        }
+        let mut start = 0;
+        for &(mode, len) in self.segs.iter() {
+            let chunk = &data[start..start + len];
+            mode.validate(chunk).map_err(|e| match e {
+                QRError::InvalidModeChar(m, i, c) => QRError::InvalidModeChar(m, start + i, c),
+                e => e,
+            })?;
+            let len_bits = self.ver.char_cnt_bits(mode);
+            segs.push(Segment::with_bits(mode, mode_bits, len_bits, chunk));
+            start += len;
+        }
+
+        let sz: usize = segs.iter().map(|s| s.bit_len()).sum();
+        if sz > self.ver.data_bit_capacity(self.ecl, self.hi_cap) {
+            return Err(QRError::DataTooLong);
+        }
+
+        Ok(segs)
+    }
+}
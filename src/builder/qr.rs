@@ -1,9 +1,13 @@
 use core::panic;
 use image::{GrayImage, Luma, Rgb, RgbImage};
-use std::ops::Deref;
+use std::{
+    fmt::{self, Debug, Display, Formatter, Write},
+    ops::{Deref, Range},
+    sync::OnceLock,
+};
 
 use crate::metadata::*;
-use crate::utils::{BitStream, EncRegionIter};
+use crate::utils::{BitStream, EncRegionIter, QRError, QRResult};
 use crate::MaskPattern;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -28,7 +32,7 @@ impl Deref for Module {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct QR {
     grid: Box<[Module; MAX_QR_SIZE]>,
     w: usize,
@@ -36,6 +40,7 @@ pub struct QR {
     ecl: ECLevel,
     hi_cap: bool,
     mask: Option<MaskPattern>,
+    segs: Vec<SegmentInfo>, // Segments the data was encoded in
 }
 
 // QR type for builder
@@ -49,7 +54,15 @@ impl QR {
         );
 
         let w = ver.width();
-        Self { grid: Box::new([Module::Empty; MAX_QR_SIZE]), w, ver, ecl, hi_cap, mask: None }
+        Self {
+            grid: Box::new([Module::Empty; MAX_QR_SIZE]),
+            w,
+            ver,
+            ecl,
+            hi_cap,
+            mask: None,
+            segs: Vec::new(),
+        }
     }
 
     pub fn grid(&self) -> &[Module] {
@@ -77,7 +90,11 @@ impl QR {
     }
 
     pub fn metadata(&self) -> Metadata {
-        Metadata::new(Some(self.ver), Some(self.ecl), self.mask)
+        Metadata::new(Some(self.ver), Some(self.ecl), self.mask).with_segments(self.segs.clone())
+    }
+
+    pub(crate) fn set_segments(&mut self, segs: Vec<SegmentInfo>) {
+        self.segs = segs;
     }
 
     pub fn count_dark_modules(&self) -> usize {
@@ -131,12 +148,54 @@ impl QR {
     pub fn set(&mut self, x: i32, y: i32, module: Module) {
         *self.get_mut(x, y) = module;
     }
+
+    // Modules of other which differ in color from self, for repainting only the changed modules.
+    // If widths differ, every module of other is returned
+    pub fn diff_modules(&self, other: &QR) -> Vec<(usize, usize, Color)> {
+        let w = other.w;
+        let same_size = self.w == w;
+        other.grid[..w * w]
+            .iter()
+            .enumerate()
+            .filter(|&(i, m)| !same_size || **m != *self.grid[i])
+            .map(|(i, m)| (i % w, i / w, **m))
+            .collect()
+    }
+
+    // Splits a high capacity QR into the standard QRs of its red, green & blue channels, i.e. the
+    // symbols before multiplexing. Only the red channel starts with the segment headers, the others
+    // continue its bitstream. Each channel of a standard QR is the QR itself
+    pub fn split_channels(&self) -> [QR; 3] {
+        [0b100, 0b010, 0b001].map(|bit| {
+            let mut chan = self.clone();
+            if !self.hi_cap {
+                return chan;
+            }
+
+            chan.hi_cap = false;
+            chan.segs.clear();
+            for m in chan.grid.iter_mut() {
+                let clr = Color::from(**m as u8 & bit != 0);
+                *m = match *m {
+                    Module::Empty => Module::Empty,
+                    Module::Func(_) => Module::Func(clr),
+                    Module::Version(_) => Module::Version(clr),
+                    Module::Format(_) => Module::Format(clr),
+                    Module::Data(_) => Module::Data(clr),
+                };
+            }
+            chan.set(8, -8, Module::Format(Color::Black));
+            chan
+        })
+    }
 }
 
 #[cfg(test)]
 mod qr_util_tests {
-    use crate::builder::{Module, QR};
-    use crate::common::metadata::{Color, ECLevel, Version};
+    use super::CANONICAL_FORMAT;
+    use crate::builder::{Module, QRBuilder, QR};
+    use crate::common::metadata::{Color, ECLevel, Version, MAX_VERSION};
+    use crate::MaskPattern;
 
     #[test]
     fn test_index_wrap() {
@@ -148,6 +207,70 @@ mod qr_util_tests {
         assert_eq!(qr.get(-w, -w), Module::Func(Color::Black));
     }
 
+    #[test]
+    fn test_diff_modules() {
+        let mut a = QR::new(Version::Normal(1), ECLevel::L, false);
+        let mut b = a.clone();
+        assert!(a.diff_modules(&b).is_empty());
+
+        b.set(3, 5, Module::Data(Color::Black));
+        b.set(-1, 0, Module::Format(Color::Black));
+        a.set(7, 7, Module::Data(Color::White));
+        assert_eq!(a.diff_modules(&b), vec![(20, 0, Color::Black), (3, 5, Color::Black)]);
+
+        let c = QR::new(Version::Normal(2), ECLevel::L, false);
+        assert_eq!(a.diff_modules(&c).len(), 25 * 25);
+    }
+
+    #[test]
+    fn test_remainder_modules() {
+        for v in 1..=MAX_VERSION {
+            let ver = Version::Normal(v);
+            let qr = QRBuilder::new(b"Remainder").version(ver).build().unwrap();
+            let rem = qr.remainder_modules();
+            assert_eq!(rem.len(), ver.remainder_bits(), "Version {v}");
+
+            // Light before masking
+            let mask_fn = qr.mask().unwrap().mask_functions();
+            for (x, y) in rem {
+                let exp = if mask_fn(x, y) { Color::Black } else { Color::White };
+                assert_eq!(qr.get(x, y), Module::Data(exp), "Version {v}, ({x}, {y})");
+            }
+        }
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_split_channels() {
+        use crate::builder::QRBuilder;
+
+        let data = "Hello, world! 🌍".repeat(10);
+        let qr = QRBuilder::new(data.as_bytes()).high_capacity(true).build().unwrap();
+        let chans = qr.split_channels();
+
+        let payload = qr.read_payload();
+        let chan_len = payload.len() / 3;
+        let w = qr.w as i32;
+        for (i, chan) in chans.iter().enumerate() {
+            assert!(!chan.high_capacity());
+            assert_eq!(chan.get(8, -8), Module::Format(Color::Black));
+            assert_eq!(chan.read_payload(), payload[i * chan_len..(i + 1) * chan_len]);
+            assert!(chan.grid[..qr.w * qr.w]
+                .iter()
+                .all(|m| matches!(**m, Color::Black | Color::White)));
+        }
+        for (x, y) in (0..w).flat_map(|x| (0..w).map(move |y| (x, y))) {
+            if (x, y) == (8, w - 8) {
+                continue;
+            }
+            let bits = chans.iter().fold(0, |acc, c| acc << 1 | (*c.get(x, y) as u8 & 1));
+            assert_eq!(bits, *qr.get(x, y) as u8, "Module ({x}, {y}) differs after recombining");
+        }
+
+        let mono = QRBuilder::new(b"Hello").build().unwrap();
+        assert!(mono.split_channels().iter().all(|c| c.grid == mono.grid));
+    }
+
     #[test]
     #[should_panic]
     fn test_row_out_of_bound() {
@@ -179,6 +302,31 @@ mod qr_util_tests {
         let w = qr.w as i32;
         qr.get(0, -(w + 1));
     }
+
+    #[test]
+    fn test_canonical_bytes() {
+        let qr = QRBuilder::new(b"Hello")
+            .version(Version::Normal(1))
+            .ec_level(ECLevel::Q)
+            .mask(MaskPattern::new(2))
+            .build()
+            .unwrap();
+        let bytes = qr.canonical_bytes();
+        assert_eq!(bytes[..7], [CANONICAL_FORMAT, 1, 2, 2, 0, 0, 21]);
+        assert_eq!(bytes.len(), 7 + 56);
+
+        let dark = |i: usize| bytes[7 + i / 8] >> (7 - i % 8) & 1 == 1;
+        assert!((0..21 * 21)
+            .all(|i| dark(i) == (*qr.get(i as i32 % 21, i as i32 / 21) == Color::Black)));
+
+        // Golden symbol. Changes to it break downstream regression tests & need a format bump
+        let hex = bytes[7..].iter().map(|b| format!("{b:02x}")).collect::<String>();
+        let golden = concat!(
+            "fe93fc10106e92bb7455dbab2ec15107faafe005007f3189e87375a6dde03ccde6",
+            "10804ca3faf2d0583ebadc95d5fa2ebac905bdcfe28900"
+        );
+        assert_eq!(hex, golden);
+    }
 }
 
 // Finder pattern
@@ -431,6 +579,7 @@ mod alignment_pattern_tests {
         );
     }
 
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_alignment_pattern_7() {
         let mut qr = QR::new(Version::Normal(7), ECLevel::L, false);
@@ -488,6 +637,73 @@ mod alignment_pattern_tests {
     }
 }
 
+// Function pattern templates
+//------------------------------------------------------------------------------
+
+impl QR {
+    // Function patterns & reserved areas are identical for all QRs of the same version and
+    // palette, so they are drawn once and copied into new QRs
+    pub fn from_template(ver: Version, ecl: ECLevel, hi_cap: bool) -> Self {
+        let mut qr = QR::new(ver, ecl, hi_cap);
+        let tmpl = template(ver, hi_cap);
+        qr.grid[..tmpl.len()].copy_from_slice(tmpl);
+        qr
+    }
+}
+
+// Function patterns & reserved areas of a version, indexed by y * w + x
+fn template(ver: Version, hi_cap: bool) -> &'static [Module] {
+    debug_assert!(
+        matches!(ver, Version::Micro(1..=4) | Version::Normal(1..=MAX_VERSION)),
+        "Invalid version"
+    );
+
+    static TEMPLATES: [[OnceLock<Box<[Module]>>; MAX_VERSION]; 2] =
+        [const { [const { OnceLock::new() }; MAX_VERSION] }; 2];
+    static MICRO_TEMPLATES: [OnceLock<Box<[Module]>>; 4] = [const { OnceLock::new() }; 4];
+
+    let cell = match ver {
+        Version::Micro(v) => &MICRO_TEMPLATES[v - 1],
+        Version::Normal(v) => &TEMPLATES[hi_cap as usize][v - 1],
+    };
+    cell.get_or_init(|| {
+        let mut qr = QR::new(ver, ECLevel::L, hi_cap);
+        qr.draw_all_function_patterns();
+        qr.draw_reserved_areas();
+        let w = ver.width();
+        qr.grid[..w * w].into()
+    })
+}
+
+#[cfg(test)]
+mod template_tests {
+    use crate::builder::QR;
+    use crate::common::metadata::{ECLevel, Version, MAX_VERSION};
+
+    #[test]
+    fn test_template_matches_drawn_patterns() {
+        for v in 1..=MAX_VERSION {
+            let ver = Version::Normal(v);
+            for hi_cap in [false, true] {
+                let mut exp = QR::new(ver, ECLevel::Q, hi_cap);
+                exp.draw_all_function_patterns();
+                exp.draw_reserved_areas();
+
+                let qr = QR::from_template(ver, ECLevel::Q, hi_cap);
+                assert_eq!(qr.grid(), exp.grid(), "Template mismatch for version {v}");
+            }
+        }
+        for v in 1..=4 {
+            let mut exp = QR::new(Version::Micro(v), ECLevel::L, false);
+            exp.draw_all_function_patterns();
+            exp.draw_reserved_areas();
+
+            let qr = QR::from_template(Version::Micro(v), ECLevel::L, false);
+            assert_eq!(qr.grid(), exp.grid(), "Template mismatch for version M{v}");
+        }
+    }
+}
+
 // ALl function patterns
 //------------------------------------------------------------------------------
 
@@ -552,9 +768,23 @@ impl QR {
         self.draw_format_info((1 << FORMAT_INFO_BIT_LEN) - 1);
     }
 
+    // Overwrites both format info copies with bits as they appear on the symbol, i.e. already
+    // masked. Only the low 15 bits are drawn
+    pub(crate) fn draw_raw_format_info(&mut self, bits: u16) {
+        self.draw_format_info(bits as u32 & ((1 << FORMAT_INFO_BIT_LEN) - 1));
+    }
+
     fn draw_format_info(&mut self, format_info: u32) {
         match self.ver {
-            Version::Micro(_) => todo!(),
+            Version::Micro(_) => {
+                self.draw_number(
+                    format_info,
+                    FORMAT_INFO_BIT_LEN,
+                    Module::Format(Color::White),
+                    Module::Format(Color::Black),
+                    &FORMAT_INFO_COORDS_MICRO,
+                );
+            }
             Version::Normal(_) => {
                 self.draw_number(
                     format_info,
@@ -570,7 +800,9 @@ impl QR {
                     Module::Format(Color::Black),
                     &FORMAT_INFO_COORDS_QR_SIDE,
                 );
-                self.set(8, -8, Module::Format(Color::Black));
+                // Dark module doubles as capacity info
+                let clr = if self.hi_cap { Color::White } else { Color::Black };
+                self.set(8, -8, Module::Format(clr));
             }
         }
     }
@@ -655,6 +887,7 @@ mod qr_information_tests {
         );
     }
 
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_version_info_7() {
         let mut qr = QR::new(Version::Normal(7), ECLevel::L, false);
@@ -741,6 +974,7 @@ mod qr_information_tests {
         );
     }
 
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_all_function_patterns_and_qr_info() {
         let mut qr = QR::new(Version::Normal(7), ECLevel::L, false);
@@ -804,14 +1038,25 @@ mod qr_information_tests {
 
 impl QR {
     pub fn draw_encoding_region(&mut self, payload: BitStream) {
+        self.draw_reserved_areas();
+        self.draw_data_region(payload);
+    }
+
+    fn draw_reserved_areas(&mut self) {
         self.reserve_format_area();
         self.draw_version_info();
 
         if self.hi_cap {
             self.set(8, -8, Module::Format(Color::White));
-            self.draw_payload_rgb(payload)
-        } else {
-            self.draw_payload(payload)
+        }
+    }
+
+    // Draws payload assuming function patterns and reserved areas are already drawn
+    pub fn draw_data_region(&mut self, payload: BitStream) {
+        match self.hi_cap {
+            #[cfg(feature = "poly")]
+            true => self.draw_payload_rgb(payload),
+            _ => self.draw_payload(payload),
         }
 
         let w = self.ver.width();
@@ -823,9 +1068,13 @@ impl QR {
         let mut coords = EncRegionIter::new(self.ver);
         let cap = self.ver.channel_codewords();
         let bit_cap = cap << 3;
+        let half = self.half_codeword_bits();
 
-        for (x, y) in coords.by_ref().take(bit_cap) {
+        let bits = (0..bit_cap).filter_map(|i| {
             let bit = payload.take_bit().unwrap();
+            (!half.contains(&i)).then_some(bit)
+        });
+        for (bit, (x, y)) in bits.zip(coords.by_ref()) {
             let module = Module::Data(if bit { Color::Black } else { Color::White });
             debug_assert_eq!(
                 self.get(x, y),
@@ -838,6 +1087,7 @@ impl QR {
         self.fill_remainder_bits(&mut coords);
     }
 
+    #[cfg(feature = "poly")]
     fn draw_payload_rgb(&mut self, mut payload: BitStream) {
         let chan_cap = self.ver.channel_codewords();
         let chan_bit_cap = chan_cap << 3;
@@ -874,6 +1124,8 @@ impl QR {
         }
     }
 
+    // Remainder bits are light before masking, as per ISO/IEC 18004. Readers must ignore them, as
+    // other encoders may set them to anything
     fn fill_remainder_bits(&mut self, coords: impl Iterator<Item = (i32, i32)>) {
         let n = self.ver.remainder_bits();
         for (x, y) in coords.take(n).by_ref() {
@@ -883,6 +1135,103 @@ impl QR {
         }
     }
 
+    // Bits of the payload with no modules, i.e. the low half of the 4 bit last data codeword of
+    // M1 & M3
+    fn half_codeword_bits(&self) -> Range<usize> {
+        let data_bits = self.ver.data_bit_capacity(self.ecl, false);
+        match (self.ver, data_bits & 7) {
+            (Version::Micro(_), 4) => data_bits..data_bits + 4,
+            _ => 0..0,
+        }
+    }
+
+    // Coordinates of the modules left over after the last codeword, see Version::remainder_bits
+    pub fn remainder_modules(&self) -> Vec<(i32, i32)> {
+        let chan_bits = (self.ver.channel_codewords() << 3) - self.half_codeword_bits().len();
+        EncRegionIter::new(self.ver).skip(chan_bits).collect()
+    }
+
+    // Reads back the interleaved codewords of each channel from the data modules
+    pub(crate) fn read_payload(&self) -> Vec<u8> {
+        let chans = if self.hi_cap { 3 } else { 1 };
+        let chan_bits = self.ver.channel_codewords() << 3;
+        let half = self.half_codeword_bits();
+        let mask_fn = self.mask.map(|m| m.mask_functions());
+
+        let mut payload = Vec::with_capacity((chan_bits >> 3) * chans);
+        for chan in (3 - chans..3).rev() {
+            let mut byte = 0u8;
+            let mut coords = EncRegionIter::new(self.ver);
+            for i in 0..chan_bits {
+                let mut bit = false;
+                if !half.contains(&i) {
+                    let (x, y) = coords.next().expect("Data modules run out before the payload");
+                    let clr = *self.get(x, y) as u8;
+                    bit = (clr >> chan) & 1 == 0;
+                    if mask_fn.is_some_and(|f| f(x, y)) {
+                        bit = !bit;
+                    }
+                }
+                byte = (byte << 1) | bit as u8;
+                if i & 7 == 7 {
+                    payload.push(byte);
+                }
+            }
+        }
+        payload
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        let payload = self.read_payload();
+        Fingerprint::compute(self.ver, self.ecl, self.mask, self.hi_cap, &payload)
+    }
+
+    // Stable serialization of the final symbol for regression tests across platforms & releases.
+    // Layout, with CANONICAL_FORMAT bumped on any change to it:
+    //   0     CANONICAL_FORMAT
+    //   1     Version number, with the high bit set for micro QR
+    //   2     EC level, 0 to 3 for L, M, Q & H
+    //   3     Mask pattern, 0xFF if unmasked
+    //   4     Flags, bit 0 for high capacity
+    //   5..7  Width in modules, big endian
+    //   7..   Modules in row major order packed MSB first, 1 bit per module with 1 for dark, or 3
+    //         bits for red, green & blue in high capacity QRs with 1 for an inked channel. The last
+    //         byte is zero padded
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let (ver, micro) = match self.ver {
+            Version::Micro(v) => (v, CANONICAL_MICRO_FLAG),
+            Version::Normal(v) => (v, 0),
+        };
+        let ecl = match self.ecl {
+            ECLevel::L => 0,
+            ECLevel::M => 1,
+            ECLevel::Q => 2,
+            ECLevel::H => 3,
+        };
+        let mask = self.mask.map_or(CANONICAL_NO_MASK, |m| *m);
+
+        let mut out = vec![CANONICAL_FORMAT, ver as u8 | micro, ecl, mask, self.hi_cap as u8];
+        out.extend((self.w as u16).to_be_bytes());
+
+        let chans: &[u8] = if self.hi_cap { &[0b100, 0b010, 0b001] } else { &[0b111] };
+        let inked = self.grid[..self.w * self.w]
+            .iter()
+            .flat_map(|m| chans.iter().map(move |&c| **m as u8 & c == 0));
+        let mut bits = 0;
+        for (i, ink) in inked.enumerate() {
+            bits = (bits << 1) | ink as u8;
+            if i & 7 == 7 {
+                out.push(bits);
+                bits = 0;
+            }
+        }
+        let rem = (self.w * self.w * chans.len()) & 7;
+        if rem != 0 {
+            out.push(bits << (8 - rem));
+        }
+        out
+    }
+
     pub fn apply_mask(&mut self, pattern: MaskPattern) {
         self.mask = Some(pattern);
         let mask_fn = pattern.mask_functions();
@@ -896,7 +1245,10 @@ impl QR {
                 }
             }
         }
-        let format_info = generate_format_info_qr(self.ecl, pattern);
+        let format_info = match self.ver {
+            Version::Micro(_) => generate_format_info_micro(self.ver, self.ecl, pattern),
+            Version::Normal(_) => generate_format_info_qr(self.ecl, pattern),
+        };
         self.draw_format_info(format_info);
     }
 }
@@ -973,6 +1325,104 @@ impl QR {
         canvas
     }
 
+    // Renders Poly codes for mono printers. Each module is split in quadrants, the top left, top
+    // right & bottom left ones light if the red, green & blue channels are set, the bottom right
+    // light only for white modules. Black & white modules stay solid, so finders are unaffected.
+    // Read back with detect_hc_qr_dithered. Modules of 6 pixels or more print reliably
+    pub fn to_dithered_image(&self, module_sz: u32) -> GrayImage {
+        let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 } * module_sz;
+        let qr_sz = self.w as u32 * module_sz;
+        let total_sz = qz_sz + qr_sz + qz_sz;
+
+        GrayImage::from_fn(total_sz, total_sz, |x, y| {
+            // Quiet zone
+            if x < qz_sz || x >= qz_sz + qr_sz || y < qz_sz || y >= qz_sz + qr_sz {
+                return Luma([255]);
+            }
+
+            let (qx, qy) = ((x - qz_sz) / module_sz, (y - qz_sz) / module_sz);
+            let clr = match self.get(qx as i32, qy as i32) {
+                Module::Func(c) | Module::Format(c) | Module::Version(c) | Module::Data(c) => c,
+                Module::Empty => panic!("Empty module found at: {qx} {qy}"),
+            };
+
+            let right = (x - qz_sz) % module_sz * 2 >= module_sz;
+            let bottom = (y - qz_sz) % module_sz * 2 >= module_sz;
+            let is_light = match (right, bottom) {
+                (false, false) => clr as u8 & 0b100 != 0,
+                (true, false) => clr as u8 & 0b010 != 0,
+                (false, true) => clr as u8 & 0b001 != 0,
+                (true, true) => clr == Color::White,
+            };
+            Luma([if is_light { 255 } else { 0 }])
+        })
+    }
+
+    // Side of the rendered image in pixels for the given module size, quiet zone included
+    pub fn image_size(&self, module_sz: u32) -> u32 {
+        let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 } * module_sz;
+        qz_sz * 2 + self.w as u32 * module_sz
+    }
+
+    // Byte length of the rgba buffer for the given module size, quiet zone included
+    pub fn rgba_len(&self, module_sz: u32) -> usize {
+        let total_sz = self.image_size(module_sz) as usize;
+        total_sz * total_sz * 4
+    }
+
+    // Writes the QR into a row major rgba buffer laid out like canvas ImageData, so wasm callers
+    // can render straight into a Uint8ClampedArray. Light modules & the quiet zone take the given
+    // alpha, and colours are scaled by alpha if premultiplied, as WebGL textures expect
+    pub fn write_rgba(
+        &self,
+        module_sz: u32,
+        light_alpha: u8,
+        premultiplied: bool,
+        buf: &mut [u8],
+    ) -> QRResult<()> {
+        if buf.len() != self.rgba_len(module_sz) {
+            return Err(QRError::BufferSizeMismatch);
+        }
+
+        let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 } * module_sz;
+        let qr_sz = self.w as u32 * module_sz;
+        let total_sz = qz_sz + qr_sz + qz_sz;
+
+        let light = match premultiplied {
+            true => [light_alpha; 4],
+            false => [255, 255, 255, light_alpha],
+        };
+
+        for (y, row) in buf.chunks_exact_mut(total_sz as usize * 4).enumerate() {
+            let y = y as u32;
+            for (x, px) in row.chunks_exact_mut(4).enumerate() {
+                let x = x as u32;
+                // Quiet zone
+                if y < qz_sz || y >= qz_sz + qr_sz || x < qz_sz || x >= qz_sz + qr_sz {
+                    px.copy_from_slice(&light);
+                    continue;
+                }
+
+                let qx = (x - qz_sz) / module_sz;
+                let qy = (y - qz_sz) / module_sz;
+                let clr = match self.get(qx as i32, qy as i32) {
+                    Module::Func(c) | Module::Format(c) | Module::Version(c) | Module::Data(c) => c,
+                    Module::Empty => panic!("Empty module found at: {x} {y}"),
+                };
+
+                match clr {
+                    Color::White => px.copy_from_slice(&light),
+                    c => {
+                        let Rgb([r, g, b]) = c.into();
+                        px.copy_from_slice(&[r, g, b, 255]);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[cfg(test)]
     pub fn to_str(&self, module_sz: usize) -> String {
         let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 } * module_sz;
@@ -1012,3 +1462,234 @@ impl QR {
         canvas
     }
 }
+
+// Braille preview
+//------------------------------------------------------------------------------
+
+// Packs 2x4 modules into each braille char for compact previews in logs & tests. Raised dots are
+// light modules, like the block renderer, so the preview reads right on dark terminals
+impl Display for QR {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 };
+        let w = self.w as i32;
+        let total_sz = w + qz_sz * 2;
+
+        // Cells hanging past the quiet zone are left blank
+        let is_light = |x: i32, y: i32| {
+            let (qx, qy) = (x - qz_sz, y - qz_sz);
+            match (x < total_sz && y < total_sz, (0..w).contains(&qx) && (0..w).contains(&qy)) {
+                (false, _) => false,
+                (true, false) => true,
+                (true, true) => *self.get(qx, qy) == Color::White,
+            }
+        };
+
+        for y in (0..total_sz).step_by(4) {
+            for x in (0..total_sz).step_by(2) {
+                let dots = BRAILLE_DOTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &(dx, dy))| is_light(x + dx, y + dy))
+                    .fold(0, |acc, (i, _)| acc | 1 << i);
+                f.write_char(char::from_u32(BRAILLE_BLANK + dots).unwrap())?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for QR {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("QR")
+            .field("ver", &self.ver)
+            .field("ecl", &self.ecl)
+            .field("hi_cap", &self.hi_cap)
+            .field("mask", &self.mask)
+            .finish()?;
+        write!(f, "\n{self}")
+    }
+}
+
+// Half block preview
+//------------------------------------------------------------------------------
+
+// Packs 2 module rows into each char with half blocks, so codes come out square in terminals
+// whose chars are twice as tall as wide. Light modules are filled, like the block renderer
+impl QR {
+    pub fn to_unicode_string(&self) -> String {
+        let (total_sz, _) = self.padded_size();
+        let mut res = String::with_capacity((total_sz * (total_sz + 1) / 2) as usize * 3);
+        for y in (0..total_sz).step_by(2) {
+            for x in 0..total_sz {
+                let is_light = |y| self.padded_color(x, y).is_some_and(|c| c == Color::White);
+                let c = match (is_light(y), is_light(y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                };
+                res.push(c);
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    // Half blocks coloured with ansi escapes, the top module in the foreground & the bottom one in
+    // the background, for previewing Poly codes in colour terminals
+    pub fn to_ansi_string(&self) -> String {
+        let (total_sz, _) = self.padded_size();
+        let mut res = String::new();
+        for y in (0..total_sz).step_by(2) {
+            for x in 0..total_sz {
+                let top = self.padded_color(x, y).map_or(ANSI_DEFAULT_FG, |c| ansi_code(c, 30));
+                let btm = self.padded_color(x, y + 1).map_or(ANSI_DEFAULT_BG, |c| ansi_code(c, 40));
+                write!(res, "\x1b[{top};{btm}m▀").unwrap();
+            }
+            res.push_str(ANSI_RESET);
+            res.push('\n');
+        }
+        res
+    }
+
+    // Side including the quiet zone & the quiet zone width, in modules
+    fn padded_size(&self) -> (i32, i32) {
+        let qz_sz = if let Version::Normal(_) = self.ver { 4 } else { 2 };
+        (self.w as i32 + qz_sz * 2, qz_sz)
+    }
+
+    // Colour of a module in coords including the quiet zone, none past its edge
+    fn padded_color(&self, x: i32, y: i32) -> Option<Color> {
+        let (total_sz, qz_sz) = self.padded_size();
+        let w = self.w as i32;
+        let (qx, qy) = (x - qz_sz, y - qz_sz);
+        match (x < total_sz && y < total_sz, (0..w).contains(&qx) && (0..w).contains(&qy)) {
+            (false, _) => None,
+            (true, false) => Some(Color::White),
+            (true, true) => Some(*self.get(qx, qy)),
+        }
+    }
+}
+
+// Bright ansi colour code from the base, 30 for foreground & 40 for background. Black keeps the
+// normal code, as bright black is grey
+fn ansi_code(clr: Color, base: u8) -> u8 {
+    let idx = match clr {
+        Color::Black => return base,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::White => 7,
+    };
+    base + 60 + idx
+}
+
+#[cfg(test)]
+mod render_tests {
+    use image::DynamicImage;
+
+    use crate::{builder::QRBuilder, metadata::Color, utils::QRError, Version};
+
+    #[test]
+    fn test_write_rgba() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let mut buf = vec![0; qr.rgba_len(3)];
+        qr.write_rgba(3, 255, false, &mut buf).unwrap();
+        assert_eq!(buf, DynamicImage::ImageRgb8(qr.to_image(3)).to_rgba8().into_raw());
+
+        qr.write_rgba(3, 0, true, &mut buf).unwrap();
+        assert_eq!(buf[..4], [0, 0, 0, 0]);
+        assert!(buf.chunks_exact(4).any(|px| px == [0, 0, 0, 255]));
+
+        let res = qr.write_rgba(3, 255, false, &mut buf[4..]);
+        assert_eq!(res, Err(QRError::BufferSizeMismatch));
+    }
+
+    #[test]
+    fn test_braille_display() {
+        let qr = QRBuilder::new(b"Hello, world!").version(Version::Normal(1)).build().unwrap();
+        let preview = qr.to_string();
+        let rows: Vec<_> = preview.lines().collect();
+
+        // 29 modules with quiet zone, 2 per char across & 4 per char down
+        assert_eq!(rows.len(), 8);
+        assert!(rows.iter().all(|r| r.chars().count() == 15));
+
+        // Top row of chars is all quiet zone. Dots past the last module row & column are blank
+        assert!(rows[0].chars().take(14).all(|c| c == '\u{28ff}'));
+        assert_eq!(rows[0].chars().last(), Some('\u{2847}'));
+        assert_eq!(rows[7].chars().next(), Some('\u{2809}'));
+
+        // Outer dark ring of the top left finder starts at module (4, 4)
+        assert_eq!(rows[1].chars().nth(2), Some('\u{28b0}'));
+        assert!(format!("{qr:?}").starts_with("QR { ver: Normal(1), ecl: M, hi_cap: false"));
+    }
+
+    #[test]
+    fn test_unicode_string() {
+        let qr = QRBuilder::new(b"Hello, world!").version(Version::Normal(1)).build().unwrap();
+        let preview = qr.to_unicode_string();
+        let rows: Vec<_> = preview.lines().collect();
+
+        // 29 modules with quiet zone, 2 per char down. Modules past the last row are blank
+        assert_eq!(rows.len(), 15);
+        assert!(rows.iter().all(|r| r.chars().count() == 29));
+        assert!(rows[0].chars().all(|c| c == '█'));
+        assert!(rows[14].chars().all(|c| c == '▀'));
+
+        // Top left finder spans modules 4 to 10, its dark top edge over the light ring inside
+        assert_eq!(rows[2].chars().skip(3).take(9).collect::<String>(), "█ ▄▄▄▄▄ █");
+
+        // Every module reads back
+        let is_light = |x: usize, y: usize| match rows[y / 2].chars().nth(x).unwrap() {
+            '█' => true,
+            '▀' => y.is_multiple_of(2),
+            '▄' => y % 2 == 1,
+            _ => false,
+        };
+        for (y, x) in (0..21).flat_map(|y| (0..21).map(move |x| (y, x))) {
+            let exp = *qr.get(x as i32, y as i32) == Color::White;
+            assert_eq!(is_light(x + 4, y + 4), exp, "Module {x} {y}");
+        }
+    }
+
+    #[test]
+    fn test_ansi_string() {
+        let qr = QRBuilder::new(b"Hello, world!").version(Version::Normal(1)).build().unwrap();
+        let preview = qr.to_ansi_string();
+        let rows: Vec<_> = preview.lines().collect();
+        assert_eq!(rows.len(), 15);
+        assert!(rows[0].starts_with("\x1b[97;107m▀"));
+        assert!(rows[14].ends_with("\x1b[97;49m▀\x1b[0m"));
+        assert!(rows[2].contains("\x1b[30;107m▀"));
+
+        #[cfg(feature = "poly")]
+        {
+            let qr = QRBuilder::new(b"Hello, world!").high_capacity(true).build().unwrap();
+            let preview = qr.to_ansi_string();
+            assert!((91..=96).any(|c| preview.contains(&format!("\x1b[{c};"))));
+        }
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Layout version of QR::canonical_bytes
+const CANONICAL_FORMAT: u8 = 1;
+const CANONICAL_MICRO_FLAG: u8 = 0x80;
+const CANONICAL_NO_MASK: u8 = 0xFF;
+
+const BRAILLE_BLANK: u32 = 0x2800;
+
+const ANSI_DEFAULT_FG: u8 = 39;
+const ANSI_DEFAULT_BG: u8 = 49;
+const ANSI_RESET: &str = "\x1b[0m";
+
+// Offset of the module for each dot bit of a braille char
+const BRAILLE_DOTS: [(i32, i32); 8] =
+    [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
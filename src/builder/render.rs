@@ -0,0 +1,220 @@
+use image::{Rgb, RgbImage};
+
+use super::{Module, QR};
+use crate::metadata::{Color, Version};
+
+// Render config
+//------------------------------------------------------------------------------
+
+// Colours & quiet zone of a rendered symbol, e.g. for branded codes. Only black & white modules
+// take the dark & light colours, so the channel colours of high capacity codes are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderConfig {
+    module_sz: u32,
+    dark: Rgb<u8>,
+    light: Rgb<u8>,
+    quiet_zone: Option<u32>, // Width in modules, the minimum the version allows if none
+    margin: Option<Rgb<u8>>, // Quiet zone colour, the light colour if none
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            module_sz: 4,
+            dark: Rgb([0, 0, 0]),
+            light: Rgb([255, 255, 255]),
+            quiet_zone: None,
+            margin: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Side of a module in pixels
+    pub fn module_size(&mut self, module_sz: u32) -> &mut Self {
+        self.module_sz = module_sz;
+        self
+    }
+
+    pub fn dark_color(&mut self, clr: Rgb<u8>) -> &mut Self {
+        self.dark = clr;
+        self
+    }
+
+    pub fn light_color(&mut self, clr: Rgb<u8>) -> &mut Self {
+        self.light = clr;
+        self
+    }
+
+    pub fn quiet_zone(&mut self, modules: u32) -> &mut Self {
+        self.quiet_zone = Some(modules);
+        self
+    }
+
+    pub fn margin_color(&mut self, clr: Rgb<u8>) -> &mut Self {
+        self.margin = Some(clr);
+        self
+    }
+
+    pub fn quiet_zone_width(&self, ver: Version) -> u32 {
+        self.quiet_zone.unwrap_or_else(|| min_quiet_zone(ver))
+    }
+
+    // WCAG contrast ratio of the dark & light colours, from 1 for equal luminance to 21 for black
+    // on white
+    pub fn contrast_ratio(&self) -> f64 {
+        contrast_ratio(self.dark, self.light)
+    }
+
+    // Settings that risk symbols scanners can't read, in the order of RenderWarning
+    pub fn warnings(&self, ver: Version) -> Vec<RenderWarning> {
+        let mut warnings = Vec::new();
+        let ratio = self.contrast_ratio();
+        if ratio < MIN_CONTRAST_RATIO {
+            warnings.push(RenderWarning::LowContrast(ratio));
+        }
+        if luminance(self.dark) > luminance(self.light) {
+            warnings.push(RenderWarning::Inverted);
+        }
+        if let Some(margin) = self.margin {
+            let ratio = contrast_ratio(self.dark, margin);
+            if ratio < MIN_CONTRAST_RATIO {
+                warnings.push(RenderWarning::LowMarginContrast(ratio));
+            }
+        }
+        if self.quiet_zone_width(ver) < min_quiet_zone(ver) {
+            warnings.push(RenderWarning::NarrowQuietZone(self.quiet_zone_width(ver)));
+        }
+        warnings
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderWarning {
+    LowContrast(f64),       // Contrast ratio of dark & light modules
+    Inverted,               // Dark modules lighter than light ones, which many scanners skip
+    LowMarginContrast(f64), // Contrast ratio of dark modules & the quiet zone
+    NarrowQuietZone(u32),   // Width in modules, below what the version needs
+}
+
+// Rendering
+//------------------------------------------------------------------------------
+
+impl QR {
+    pub fn to_image_with(&self, config: &RenderConfig) -> RgbImage {
+        let module_sz = config.module_sz.max(1);
+        let qz_sz = config.quiet_zone_width(self.version()) * module_sz;
+        let qr_sz = self.width() as u32 * module_sz;
+        let total_sz = qz_sz + qr_sz + qz_sz;
+        let margin = config.margin.unwrap_or(config.light);
+
+        RgbImage::from_fn(total_sz, total_sz, |x, y| {
+            // Quiet zone
+            if x < qz_sz || x >= qz_sz + qr_sz || y < qz_sz || y >= qz_sz + qr_sz {
+                return margin;
+            }
+
+            let (qx, qy) = ((x - qz_sz) / module_sz, (y - qz_sz) / module_sz);
+            let clr = match self.get(qx as i32, qy as i32) {
+                Module::Func(c) | Module::Format(c) | Module::Version(c) | Module::Data(c) => c,
+                Module::Empty => panic!("Empty module found at: {qx} {qy}"),
+            };
+            match clr {
+                Color::Black => config.dark,
+                Color::White => config.light,
+                c => c.into(),
+            }
+        })
+    }
+}
+
+// Luminance helpers
+//------------------------------------------------------------------------------
+
+fn min_quiet_zone(ver: Version) -> u32 {
+    match ver {
+        Version::Micro(_) => 2,
+        Version::Normal(_) => 4,
+    }
+}
+
+// Relative luminance of an sRGB colour as per WCAG 2, from 0 for black to 1 for white
+fn luminance(Rgb(clr): Rgb<u8>) -> f64 {
+    let lin = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * lin(clr[0]) + 0.7152 * lin(clr[1]) + 0.0722 * lin(clr[2])
+}
+
+fn contrast_ratio(a: Rgb<u8>, b: Rgb<u8>) -> f64 {
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+#[cfg(test)]
+mod render_tests {
+    use image::Rgb;
+
+    use super::{RenderConfig, RenderWarning};
+    use crate::{builder::QRBuilder, metadata::Version, reader::detect_qr};
+
+    #[test]
+    fn test_to_image_with_default() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let img = qr.to_image_with(RenderConfig::new().module_size(3));
+        assert_eq!(img, qr.to_image(3));
+    }
+
+    #[test]
+    fn test_to_image_with_colors() {
+        let (navy, cream, teal) = (Rgb([20, 30, 90]), Rgb([250, 240, 210]), Rgb([200, 235, 230]));
+        let qr = QRBuilder::new(b"Branded").build().unwrap();
+        let mut config = RenderConfig::new();
+        config.module_size(2).dark_color(navy).light_color(cream).quiet_zone(6).margin_color(teal);
+        assert!(config.warnings(qr.version()).is_empty());
+
+        let img = qr.to_image_with(&config);
+        assert_eq!(img.width(), (21 + 12) * 2);
+        assert_eq!(*img.get_pixel(0, 0), teal);
+        assert_eq!(*img.get_pixel(12, 12), navy); // Top left finder
+        assert_eq!(*img.get_pixel(14, 14), cream);
+
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+        assert_eq!(res.symbols()[0].decode().unwrap().1, "Branded");
+    }
+
+    #[test]
+    fn test_render_warnings() {
+        let ver = Version::Normal(1);
+        let mut config = RenderConfig::new();
+        config.dark_color(Rgb([160, 160, 160]));
+        assert!(matches!(config.warnings(ver)[..], [RenderWarning::LowContrast(r)] if r < 3.0));
+        assert!((RenderConfig::new().contrast_ratio() - 21.0).abs() < 1e-9);
+
+        let mut config = RenderConfig::new();
+        config.dark_color(Rgb([255, 255, 255])).light_color(Rgb([0, 0, 0]));
+        assert_eq!(config.warnings(ver), [RenderWarning::Inverted]);
+
+        let mut config = RenderConfig::new();
+        config.margin_color(Rgb([40, 40, 40])).quiet_zone(1);
+        let warnings = config.warnings(ver);
+        assert!(matches!(warnings[0], RenderWarning::LowMarginContrast(_)));
+        assert_eq!(warnings[1], RenderWarning::NarrowQuietZone(1));
+        assert!(RenderConfig::new().quiet_zone(2).warnings(Version::Micro(2)).is_empty());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Contrast ratio below which dark & light modules risk blurring together in dim light or glare
+const MIN_CONTRAST_RATIO: f64 = 4.0;
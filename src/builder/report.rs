@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use crate::{
+    mask::MaskPattern,
+    metadata::{ECLevel, SegmentInfo, Version},
+};
+
+// Build report
+//------------------------------------------------------------------------------
+
+// Size & time budget of a build, returned by QRBuilder::build_with_report
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildReport {
+    pub version: Version,
+    pub ec_level: ECLevel,
+    pub high_capacity: bool,
+    pub mask: Option<MaskPattern>,
+    pub segments: Vec<SegmentInfo>,
+    pub data_bytes: usize,    // Input size
+    pub encoded_bits: usize,  // Segment bits, excluding terminator & padding
+    pub capacity_bits: usize, // Data bits the version holds
+    pub ecc_bits: usize,
+    pub dark_modules: usize,
+    pub total_modules: usize,
+    pub timings: StageTimings,
+}
+
+impl BuildReport {
+    // Share of the data capacity used by the encoded segments
+    pub fn fill_ratio(&self) -> f64 {
+        self.encoded_bits as f64 / self.capacity_bits as f64
+    }
+
+    // Share of dark modules, 0.5 being perfectly balanced
+    pub fn dark_ratio(&self) -> f64 {
+        self.dark_modules as f64 / self.total_modules as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    pub plan: Duration,   // Version & segment selection
+    pub encode: Duration, // Segments to padded bit stream
+    pub ecc: Duration,    // Error correction & interleaving
+    pub draw: Duration,   // Function patterns & data region
+    pub mask: Duration,   // Mask selection & format info
+}
+
+impl StageTimings {
+    pub fn total(&self) -> Duration {
+        self.plan + self.encode + self.ecc + self.draw + self.mask
+    }
+}
@@ -0,0 +1,167 @@
+#[cfg(feature = "png")]
+use std::io::{self, Write};
+
+use image::RgbImage;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::QR;
+
+// Symbol sheet
+//------------------------------------------------------------------------------
+
+// Grid layout for printing many symbols on one page. Every cell is as large as the largest symbol
+// with its quiet zone, smaller symbols are centred in their cell. Symbols & rows are rendered in
+// parallel with the rayon feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sheet {
+    cols: usize,
+    module_sz: u32,
+    gap: u32, // Pixels between cells, on top of the quiet zones
+}
+
+impl Sheet {
+    pub fn new(cols: usize) -> Self {
+        Self { cols: cols.max(1), module_sz: DEFAULT_MODULE_SIZE, gap: 0 }
+    }
+
+    pub fn module_size(&mut self, module_sz: u32) -> &mut Self {
+        self.module_sz = module_sz;
+        self
+    }
+
+    pub fn gap(&mut self, gap: u32) -> &mut Self {
+        self.gap = gap;
+        self
+    }
+
+    // Width & height of the composed sheet in pixels
+    pub fn dimensions(&self, qrs: &[QR]) -> (u32, u32) {
+        let cell = self.cell_size(qrs);
+        let cols = self.cols.min(qrs.len()) as u32;
+        let rows = qrs.len().div_ceil(self.cols) as u32;
+        let span = |n: u32| (n * (cell + self.gap)).saturating_sub(self.gap);
+        (span(cols), span(rows))
+    }
+
+    pub fn render(&self, qrs: &[QR]) -> RgbImage {
+        let (w, h) = self.dimensions(qrs);
+        let mut buf = Vec::with_capacity(w as usize * h as usize * 3);
+        self.render_bands(qrs, |band| {
+            buf.extend_from_slice(band);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        RgbImage::from_raw(w, h, buf).unwrap()
+    }
+
+    // Composes the sheet one row of cells at a time, passing the rgb bytes of each band to f, so
+    // the full sheet is never held uncompressed in memory. Bands are ordered top to bottom and
+    // include the gap below them
+    pub fn render_bands<E>(
+        &self,
+        qrs: &[QR],
+        mut f: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let cell = self.cell_size(qrs);
+        let (w, h) = self.dimensions(qrs);
+        let stride = w as usize * 3;
+        let mut band = Vec::new();
+        let mut y = 0;
+
+        for row in qrs.chunks(self.cols) {
+            let band_h = (cell + self.gap).min(h - y);
+            band.clear();
+            band.resize(stride * band_h as usize, 255);
+            self.compose_band(row, cell, stride, &mut band);
+            f(&band)?;
+            y += band_h;
+        }
+
+        Ok(())
+    }
+
+    // Renders the sheet straight into a png stream, band by band
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, qrs: &[QR], w: W) -> io::Result<()> {
+        let (width, height) = self.dimensions(qrs);
+        let mut enc = png::Encoder::new(w, width, height);
+        enc.set_color(png::ColorType::Rgb);
+        enc.set_depth(png::BitDepth::Eight);
+        let mut writer = enc.write_header()?;
+        let mut stream = writer.stream_writer()?;
+        self.render_bands(qrs, |band| stream.write_all(band))?;
+        stream.finish()?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn cell_size(&self, qrs: &[QR]) -> u32 {
+        qrs.iter().map(|qr| qr.image_size(self.module_sz)).max().unwrap_or(0)
+    }
+
+    fn compose_band(&self, row: &[QR], cell: u32, stride: usize, band: &mut [u8]) {
+        let render = |qr: &QR| qr.to_image(self.module_sz);
+        #[cfg(feature = "rayon")]
+        let imgs: Vec<RgbImage> = row.par_iter().map(render).collect();
+        #[cfg(not(feature = "rayon"))]
+        let imgs: Vec<RgbImage> = row.iter().map(render).collect();
+
+        let blit = |(y, line): (usize, &mut [u8])| {
+            for (i, img) in imgs.iter().enumerate() {
+                let off = (cell - img.width()) / 2;
+                let Some(y) = (y as u32).checked_sub(off).filter(|&y| y < img.height()) else {
+                    continue;
+                };
+                let x = (i as u32 * (cell + self.gap) + off) as usize * 3;
+                let src =
+                    &img.as_raw()[(y * img.width() * 3) as usize..][..img.width() as usize * 3];
+                line[x..x + src.len()].copy_from_slice(src);
+            }
+        };
+        #[cfg(feature = "rayon")]
+        band.par_chunks_mut(stride).enumerate().for_each(blit);
+        #[cfg(not(feature = "rayon"))]
+        band.chunks_mut(stride).enumerate().for_each(blit);
+    }
+}
+
+#[cfg(test)]
+mod sheet_tests {
+    use image::DynamicImage;
+
+    use super::Sheet;
+    use crate::{detect_qr, QRBuilder, Version};
+
+    #[test]
+    fn test_sheet() {
+        let msgs = ["Sheet 0", "Sheet 1", "Sheet 2", "A longer payload in a larger symbol"];
+        let qrs: Vec<_> =
+            msgs.iter().map(|m| QRBuilder::new(m.as_bytes()).build().unwrap()).collect();
+        assert_eq!(qrs[3].version(), Version::Normal(3));
+
+        let mut sheet = Sheet::new(3);
+        sheet.module_size(3).gap(5);
+        let img = sheet.render(&qrs);
+
+        // Cells fit version 3 with quiet zone
+        let cell = (29 + 8) * 3;
+        assert_eq!(img.dimensions(), (cell * 3 + 10, cell * 2 + 5));
+
+        let res = detect_qr(&DynamicImage::ImageRgb8(img.clone()));
+        let decoded: Vec<_> = res.symbols().iter().map(|s| s.decode().unwrap().1).collect();
+        assert_eq!(decoded, msgs);
+
+        #[cfg(feature = "png")]
+        {
+            let mut png = Vec::new();
+            sheet.write_png(&qrs, &mut png).unwrap();
+            assert_eq!(image::load_from_memory(&png).unwrap().to_rgb8(), img);
+        }
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const DEFAULT_MODULE_SIZE: u32 = 4;
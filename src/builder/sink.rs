@@ -0,0 +1,103 @@
+use std::fmt::{self, Display, Write};
+
+use super::{QRBuilder, QRBuilderOwned};
+
+// Data sink
+//------------------------------------------------------------------------------
+
+// Collects formatted data for a builder, so structured payloads can be written piece by piece
+// with write! instead of being formatted into an intermediate String
+#[derive(Debug, Clone, Default)]
+pub struct QRBuilderDataSink {
+    buf: String,
+}
+
+impl QRBuilderDataSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { buf: String::with_capacity(capacity) }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    // Builder borrowing the data written so far
+    pub fn builder(&self) -> QRBuilder<'_> {
+        QRBuilder::new(self.buf.as_bytes())
+    }
+
+    // Builder owning the data written so far
+    pub fn into_builder(self) -> QRBuilderOwned {
+        QRBuilder::owned(self.buf)
+    }
+}
+
+impl Write for QRBuilderDataSink {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.buf.push(c);
+        Ok(())
+    }
+}
+
+impl QRBuilderOwned {
+    // Builder for the formatted output of the value
+    pub fn from_display(value: &impl Display) -> Self {
+        let mut sink = QRBuilderDataSink::new();
+        let _ = write!(sink, "{value}");
+        sink.into_builder()
+    }
+}
+
+#[cfg(test)]
+mod sink_tests {
+    use std::fmt::{self, Display, Write};
+
+    use super::QRBuilderDataSink;
+    use crate::{builder::QRBuilder, metadata::ECLevel};
+
+    struct Ticket {
+        id: u32,
+        seat: &'static str,
+    }
+
+    impl Display for Ticket {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "TICKET:{:06};SEAT:{}", self.id, self.seat)
+        }
+    }
+
+    #[test]
+    fn test_from_display() {
+        let ticket = Ticket { id: 42, seat: "B12" };
+        let qr = QRBuilder::from_display(&ticket).ec_level(ECLevel::L).build().unwrap();
+        let exp = QRBuilder::new(b"TICKET:000042;SEAT:B12").ec_level(ECLevel::L).build().unwrap();
+        assert_eq!(qr.grid(), exp.grid());
+    }
+
+    #[test]
+    fn test_sink() {
+        let ssid = "home";
+        let mut sink = QRBuilderDataSink::with_capacity(32);
+        write!(sink, "WIFI:S:{ssid};").unwrap();
+        sink.write_char('T').unwrap();
+        write!(sink, ":WPA;P:{};;", 1234).unwrap();
+        assert_eq!(sink.as_str(), "WIFI:S:home;T:WPA;P:1234;;");
+
+        let exp = QRBuilder::new(b"WIFI:S:home;T:WPA;P:1234;;").build().unwrap();
+        assert_eq!(sink.builder().build().unwrap().grid(), exp.grid());
+        assert_eq!(sink.into_builder().build().unwrap().grid(), exp.grid());
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Module, QR};
+use crate::{
+    mask::{compute_normal_penalty, MaskPattern, ModuleGrid},
+    metadata::{
+        generate_format_info_qr, Color, ECLevel, Version, FORMAT_INFO_BIT_LEN,
+        FORMAT_INFO_COORDS_QR_MAIN, FORMAT_INFO_COORDS_QR_SIDE,
+    },
+};
+
+// Fast path for versions 1 to 4. Masks are evaluated on a stack allocated grid instead of cloning
+// the full size QR for each mask
+//------------------------------------------------------------------------------
+
+pub(crate) fn is_small(ver: Version) -> bool {
+    matches!(ver, Version::Normal(1..=SMALL_VERSION_MAX))
+}
+
+// Stack allocated grid of small versions, indexed by [y][x]
+#[derive(Clone, Copy)]
+pub(crate) struct SmallGrid<const W: usize> {
+    grid: [[Module; W]; W],
+}
+
+impl<const W: usize> SmallGrid<W> {
+    fn from_qr(qr: &QR) -> Self {
+        debug_assert!(qr.width() == W, "Grid width {W} doesn't match QR width {}", qr.width());
+
+        let mut grid = [[Module::Empty; W]; W];
+        for (y, row) in grid.iter_mut().enumerate() {
+            row.copy_from_slice(&qr.grid()[y * W..(y + 1) * W]);
+        }
+        Self { grid }
+    }
+
+    fn set(&mut self, x: i32, y: i32, module: Module) {
+        let (x, y) = Self::wrap(x, y);
+        self.grid[y][x] = module;
+    }
+
+    fn wrap(x: i32, y: i32) -> (usize, usize) {
+        let w = W as i32;
+        let x = if x < 0 { x + w } else { x };
+        let y = if y < 0 { y + w } else { y };
+        (x as usize, y as usize)
+    }
+
+    fn apply_mask(&mut self, ecl: ECLevel, hi_cap: bool, pattern: MaskPattern) {
+        let mask_fn = pattern.mask_functions();
+        for (y, row) in self.grid.iter_mut().enumerate() {
+            for (x, m) in row.iter_mut().enumerate() {
+                if let Module::Data(clr) = m {
+                    if mask_fn(x as i32, y as i32) {
+                        *m = Module::Data(!*clr);
+                    }
+                }
+            }
+        }
+
+        // Format info, mirroring QR::draw_format_info
+        let info = generate_format_info_qr(ecl, pattern);
+        for coords in [&FORMAT_INFO_COORDS_QR_MAIN, &FORMAT_INFO_COORDS_QR_SIDE] {
+            let mut bit = 1 << (FORMAT_INFO_BIT_LEN - 1);
+            for &(x, y) in coords.iter() {
+                let clr = if info & bit == 0 { Color::White } else { Color::Black };
+                self.set(x, y, Module::Format(clr));
+                bit >>= 1;
+            }
+        }
+        let clr = if hi_cap { Color::White } else { Color::Black };
+        self.set(8, -8, Module::Format(clr));
+    }
+}
+
+impl<const W: usize> ModuleGrid for SmallGrid<W> {
+    fn width(&self) -> usize {
+        W
+    }
+
+    fn get(&self, x: i32, y: i32) -> Module {
+        let (x, y) = Self::wrap(x, y);
+        self.grid[y][x]
+    }
+
+    fn count_dark_modules(&self) -> usize {
+        self.grid.iter().flatten().filter(|m| matches!(***m, Color::Black)).count()
+    }
+}
+
+// Evaluates all masks on stack copies of the grid and applies the best one to the QR
+pub(crate) fn apply_best_mask_small(qr: &mut QR) -> MaskPattern {
+    match qr.width() {
+        21 => apply_best_mask_with::<21>(qr),
+        25 => apply_best_mask_with::<25>(qr),
+        29 => apply_best_mask_with::<29>(qr),
+        33 => apply_best_mask_with::<33>(qr),
+        w => unreachable!("Width {w} is not of a small version"),
+    }
+}
+
+fn apply_best_mask_with<const W: usize>(qr: &mut QR) -> MaskPattern {
+    let base = SmallGrid::<W>::from_qr(qr);
+    let (ecl, hi_cap) = (qr.ec_level(), qr.high_capacity());
+    let best_mask = (0..8)
+        .min_by_key(|&m| {
+            let mut grid = base;
+            grid.apply_mask(ecl, hi_cap, MaskPattern::new(m));
+            compute_normal_penalty(&grid)
+        })
+        .expect("Should return atleast 1 mask");
+    let best_mask = MaskPattern::new(best_mask);
+    qr.apply_mask(best_mask);
+    best_mask
+}
+
+// Both mask searches of versions 1 to 4, for the build benchmark to compare
+#[cfg(feature = "benchmark")]
+impl QR {
+    pub fn apply_best_mask_small(&mut self) -> MaskPattern {
+        apply_best_mask_small(self)
+    }
+
+    pub fn apply_best_mask_generic(&mut self) -> MaskPattern {
+        crate::mask::apply_best_mask(self)
+    }
+}
+
+#[cfg(test)]
+mod small_tests {
+    use super::{apply_best_mask_small, is_small};
+    use crate::{
+        builder::QR,
+        mask::apply_best_mask,
+        metadata::{ECLevel, Version},
+        utils::BitStream,
+    };
+
+    fn payload(ver: Version, hi_cap: bool) -> BitStream {
+        let len = ver.total_codewords(hi_cap);
+        let mut bs = BitStream::new(len << 3);
+        for i in 0..len {
+            bs.push_bits((i * 37 % 251) as u8, 8);
+        }
+        bs
+    }
+
+    #[test]
+    fn test_fast_path_matches_generic() {
+        for v in 1..=4 {
+            let ver = Version::Normal(v);
+            assert!(is_small(ver));
+            for (ecl, hi_cap) in [(ECLevel::L, false), (ECLevel::H, false), (ECLevel::M, true)] {
+                let mut exp = QR::new(ver, ecl, hi_cap);
+                exp.draw_all_function_patterns();
+                exp.draw_encoding_region(payload(ver, hi_cap));
+                let exp_mask = apply_best_mask(&mut exp);
+
+                let mut qr = QR::from_template(ver, ecl, hi_cap);
+                qr.draw_data_region(payload(ver, hi_cap));
+                let mask = apply_best_mask_small(&mut qr);
+
+                assert_eq!(mask, exp_mask);
+                assert_eq!(qr.grid(), exp.grid());
+            }
+        }
+        assert!(!is_small(Version::Normal(5)));
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_high_capacity_dark_module() {
+        use crate::{
+            builder::{Module, QRBuilder},
+            metadata::Color,
+        };
+
+        // Built through the stack mask search, which must leave the dark module light
+        for v in 1..=4 {
+            let qr =
+                QRBuilder::new(b"Hello").version(Version::Normal(v)).high_capacity(true).build();
+            assert_eq!(qr.unwrap().get(8, -8), Module::Format(Color::White));
+        }
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const SMALL_VERSION_MAX: usize = 4;
@@ -0,0 +1,285 @@
+use std::fmt::Write;
+
+use super::QR;
+use crate::metadata::Color;
+
+// Contours
+//------------------------------------------------------------------------------
+
+// Closed outline of a dark region as its corners in module coordinates, quiet zone excluded
+pub type Contour = Vec<(usize, usize)>;
+
+impl QR {
+    // Outlines of the dark regions, i.e. modules joined edge to edge, instead of a square per
+    // module, e.g. for engravers & cutters. Each region is its outer contour followed by those of
+    // its holes. With y down, outer contours run clockwise & holes anticlockwise, so regions fill
+    // with the nonzero rule. Regions are ordered by their top left module. Modules of any colour
+    // but white are dark
+    pub fn dark_regions(&self) -> Vec<Vec<Contour>> {
+        let w = self.width();
+        let is_dark = |x: usize, y: usize| *self.grid()[y * w + x] != Color::White;
+
+        // Label regions in reading order of their top left module
+        let mut labels = vec![usize::MAX; w * w];
+        let mut count = 0;
+        for start in 0..w * w {
+            if labels[start] != usize::MAX || !is_dark(start % w, start / w) {
+                continue;
+            }
+            labels[start] = count;
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                let (x, y) = (i % w, i / w);
+                let nbrs = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < w).then(|| i + 1),
+                    (y > 0).then(|| i - w),
+                    (y + 1 < w).then(|| i + w),
+                ];
+                for n in nbrs.into_iter().flatten() {
+                    if labels[n] == usize::MAX && is_dark(n % w, n / w) {
+                        labels[n] = count;
+                        stack.push(n);
+                    }
+                }
+            }
+            count += 1;
+        }
+
+        // Edges between dark & light modules, directed so the dark module is on the right. Each
+        // vertex keeps a bit per direction it has an edge leaving in
+        let vw = w + 1;
+        let mut edges = vec![0u8; vw * vw];
+        for (y, x) in (0..w).flat_map(|y| (0..w).map(move |x| (y, x))) {
+            if !is_dark(x, y) {
+                continue;
+            }
+            if y == 0 || !is_dark(x, y - 1) {
+                edges[y * vw + x] |= 1 << RIGHT;
+            }
+            if x + 1 == w || !is_dark(x + 1, y) {
+                edges[y * vw + x + 1] |= 1 << DOWN;
+            }
+            if y + 1 == w || !is_dark(x, y + 1) {
+                edges[(y + 1) * vw + x + 1] |= 1 << LEFT;
+            }
+            if x == 0 || !is_dark(x - 1, y) {
+                edges[(y + 1) * vw + x] |= 1 << UP;
+            }
+        }
+
+        // Walk each loop of edges. Where 2 regions touch at a corner, turning right keeps to the
+        // same region
+        let mut regions = vec![Vec::new(); count];
+        for v0 in 0..vw * vw {
+            while edges[v0] != 0 {
+                let d0 = edges[v0].trailing_zeros() as usize;
+                let (x0, y0) = (v0 % vw, v0 / vw);
+                let label = match d0 {
+                    RIGHT => labels[y0 * w + x0],
+                    DOWN => labels[y0 * w + x0 - 1],
+                    LEFT => labels[(y0 - 1) * w + x0 - 1],
+                    _ => labels[(y0 - 1) * w + x0],
+                };
+
+                let mut contour = vec![(x0, y0)];
+                let (mut v, mut d) = (v0, d0);
+                edges[v0] &= !(1 << d0);
+                loop {
+                    v = match d {
+                        RIGHT => v + 1,
+                        DOWN => v + vw,
+                        LEFT => v - 1,
+                        _ => v - vw,
+                    };
+                    let open = if v == v0 { edges[v] | 1 << d0 } else { edges[v] };
+                    let next = [(d + 1) % 4, d, (d + 3) % 4]
+                        .into_iter()
+                        .find(|&n| open & 1 << n != 0)
+                        .expect("Edges of a dark region form closed loops");
+                    if v == v0 && next == d0 {
+                        break;
+                    }
+                    edges[v] &= !(1 << next);
+                    if next != d {
+                        contour.push((v % vw, v / vw));
+                    }
+                    d = next;
+                }
+                // The first loop of a region runs through its top left corner, so is the outer one
+                regions[label].push(contour);
+            }
+        }
+        regions
+    }
+}
+
+// Vector export
+//------------------------------------------------------------------------------
+
+impl QR {
+    // Svg with a path per dark region, one unit per module & the quiet zone included. The
+    // document is sized with modules module_mm millimetres wide
+    pub fn to_svg(&self, module_mm: f64) -> String {
+        let total = self.image_size(1) as usize;
+        let qz = (total - self.width()) / 2;
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             width=\"{0}mm\" height=\"{0}mm\" viewBox=\"0 0 {1} {1}\">\n",
+            total as f64 * module_mm,
+            total
+        );
+        for region in self.dark_regions() {
+            svg.push_str("<path d=\"");
+            for contour in region {
+                let (x0, y0) = contour[0];
+                let _ = write!(svg, "M{} {}", x0 + qz, y0 + qz);
+                for pair in contour.windows(2) {
+                    let ((px, _), (x, y)) = (pair[0], pair[1]);
+                    let _ = match x != px {
+                        true => write!(svg, "H{}", x + qz),
+                        false => write!(svg, "V{}", y + qz),
+                    };
+                }
+                svg.push('Z');
+            }
+            svg.push_str("\"/>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Ascii dxf with a closed polyline per contour, in millimetres with modules module_mm wide.
+    // Y points up as in cad, with the origin at the bottom left of the quiet zone. Written as
+    // R12, which cam software widely reads
+    pub fn to_dxf(&self, module_mm: f64) -> String {
+        let total = self.image_size(1) as usize;
+        let qz = (total - self.width()) / 2;
+        let mut dxf = String::from(
+            "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n9\n$INSUNITS\n70\n4\n0\nENDSEC\n\
+             0\nSECTION\n2\nENTITIES\n",
+        );
+        for contour in self.dark_regions().into_iter().flatten() {
+            dxf.push_str("0\nPOLYLINE\n8\nQR\n66\n1\n10\n0.0\n20\n0.0\n30\n0.0\n70\n1\n");
+            for (x, y) in contour {
+                let (x, y) = ((x + qz) as f64 * module_mm, (total - qz - y) as f64 * module_mm);
+                let _ = write!(dxf, "0\nVERTEX\n8\nQR\n10\n{x}\n20\n{y}\n30\n0.0\n");
+            }
+            dxf.push_str("0\nSEQEND\n8\nQR\n");
+        }
+        dxf.push_str("0\nENDSEC\n0\nEOF\n");
+        dxf
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Directions of contour edges, clockwise from right with y down
+const RIGHT: usize = 0;
+const DOWN: usize = 1;
+const LEFT: usize = 2;
+const UP: usize = 3;
+
+#[cfg(test)]
+mod vector_tests {
+    use crate::{builder::QRBuilder, metadata::Color, ECLevel, Version};
+
+    #[test]
+    fn test_dark_regions() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let regions = qr.dark_regions();
+
+        // Top left finder ring & its hole, separated from the rest by the light separator
+        assert_eq!(
+            regions[0],
+            [vec![(0, 0), (7, 0), (7, 7), (0, 7)], vec![(1, 1), (1, 6), (6, 6), (6, 1)]]
+        );
+
+        // Each module centre is dark iff the contours wind around it
+        let contours: Vec<_> = regions.iter().flatten().collect();
+        for (x, y, clr) in qr.modules() {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let mut winding = 0;
+            for c in contours.iter() {
+                for (i, &(ax, ay)) in c.iter().enumerate() {
+                    let (bx, by) = c[(i + 1) % c.len()];
+                    let (ax, ay, bx, by) = (ax as f64, ay as f64, bx as f64, by as f64);
+                    if ax == bx && (ay.min(by)..ay.max(by)).contains(&py) && ax > px {
+                        winding += if by > ay { 1 } else { -1 };
+                    }
+                }
+            }
+            assert_eq!(winding != 0, clr != Color::White, "Module ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn test_region_contours() {
+        let qr = QRBuilder::new(b"12345")
+            .version(Version::Micro(1))
+            .ec_level(ECLevel::L)
+            .build()
+            .unwrap();
+        let regions = qr.dark_regions();
+        let modules = qr.modules().filter(|&(_, _, c)| c != Color::White).count();
+        let area = |c: &Vec<(usize, usize)>| {
+            let n = c.len();
+            let sum: i64 = (0..n)
+                .map(|i| {
+                    let ((ax, ay), (bx, by)) = (c[i], c[(i + 1) % n]);
+                    ax as i64 * by as i64 - bx as i64 * ay as i64
+                })
+                .sum();
+            sum / 2
+        };
+        let total: i64 = regions.iter().flatten().map(area).sum();
+        assert_eq!(total, modules as i64);
+
+        // Outer contours are clockwise, & closed without repeating the start
+        for region in regions.iter() {
+            assert!(area(&region[0]) > 0);
+            assert!(region.iter().all(|c| c.len() >= 4 && c.len() % 2 == 0));
+            assert!(region[1..].iter().all(|c| area(c) < 0));
+        }
+
+        // Modules touching at a corner only are split there rather than traced as a figure 8
+        for c in regions.iter().flatten() {
+            let mut corners = c.clone();
+            corners.sort();
+            corners.dedup();
+            assert_eq!(corners.len(), c.len());
+        }
+    }
+
+    #[test]
+    fn test_to_dxf() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let dxf = qr.to_dxf(0.5);
+        let contours: usize = qr.dark_regions().iter().map(|r| r.len()).sum();
+        assert_eq!(dxf.matches("\nPOLYLINE\n").count(), contours);
+        assert!(dxf.ends_with("0\nENDSEC\n0\nEOF\n"));
+
+        // Top left corner of the tl finder, 4 modules in & 4 down from the top of 29
+        assert!(dxf.contains("0\nVERTEX\n8\nQR\n10\n2\n20\n12.5\n30\n0.0\n"));
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let svg = qr.to_svg(0.5);
+        assert!(svg.contains("width=\"14.5mm\" height=\"14.5mm\" viewBox=\"0 0 29 29\""));
+        assert!(svg.contains("<path d=\"M4 4H11V11H4ZM5 5V10H10V5Z\"/>"));
+        assert_eq!(svg.matches("<path").count(), qr.dark_regions().len());
+
+        #[cfg(feature = "svg-in")]
+        {
+            use crate::reader::{svg::detect_qr_svg_bytes, DetectOptions};
+
+            let mut res = detect_qr_svg_bytes(svg.as_bytes(), &DetectOptions::default()).unwrap();
+            assert_eq!(res.symbols()[0].decode().unwrap().1, "Hello, world!");
+        }
+    }
+}
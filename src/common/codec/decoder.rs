@@ -6,25 +6,86 @@ pub use decode::*;
 mod reader {
     use std::cmp::min;
 
-    use encoding_rs::SHIFT_JIS;
+    use encoding_rs::{
+        Encoding, BIG5, EUC_KR, GB18030, ISO_8859_10, ISO_8859_13, ISO_8859_14, ISO_8859_15,
+        ISO_8859_16, ISO_8859_2, ISO_8859_3, ISO_8859_4, ISO_8859_5, ISO_8859_6, ISO_8859_7,
+        ISO_8859_8, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252,
+        WINDOWS_1254, WINDOWS_1256, WINDOWS_874,
+    };
 
     use crate::codec::Mode;
-    use crate::metadata::Version;
+    use crate::metadata::{Fnc1, SegmentInfo, StructuredAppend, SymbologyId, Version};
     use crate::utils::{BitStream, QRError, QRResult};
 
-    pub fn write_segment(inp: &mut BitStream, ver: Version, out: &mut String) -> QRResult<usize> {
-        let old_len = out.len();
+    // Writes the next segment to out, appends its header & data range to segs and records ECI,
+    // FNC1 & structured append usage in sym. Data bytes as encoded, i.e. before charset decoding
+    // & FNC1 substitution, are xored into parity. Byte segments are read in charset if given, else
+    // in the charset of the ECI designator in effect, else it is detected
+    pub fn write_segment(
+        inp: &mut BitStream,
+        ver: Version,
+        out: &mut String,
+        sym: &mut SymbologyId,
+        segs: &mut Vec<SegmentInfo>,
+        parity: &mut u8,
+        charset: Option<&'static Encoding>,
+    ) -> QRResult<usize> {
         let (mode, char_cnt) = take_header(inp, ver)?;
+        let start = out.len();
 
         let decoded_len = match mode {
-            Mode::Numeric => write_numeric(inp, char_cnt, out)?,
-            Mode::Alphanumeric => write_alphanumeric(inp, char_cnt, out)?,
-            Mode::Byte => write_byte(inp, char_cnt, out)?,
-            Mode::Kanji => write_kanji(inp, char_cnt, out)?,
-            Mode::Eci => write_eci(inp, char_cnt, out)?,
+            Mode::Numeric => {
+                let len = write_numeric(inp, char_cnt, out)?;
+                *parity ^= StructuredAppend::parity(&out.as_bytes()[start..]);
+                len
+            }
+            Mode::Alphanumeric if sym.fnc1.is_some() => {
+                let mut seg = String::with_capacity(char_cnt);
+                let len = write_alphanumeric(inp, char_cnt, &mut seg)?;
+                *parity ^= StructuredAppend::parity(seg.as_bytes());
+                push_fnc1_alphanumeric(&seg, out);
+                len
+            }
+            Mode::Alphanumeric => {
+                let len = write_alphanumeric(inp, char_cnt, out)?;
+                *parity ^= StructuredAppend::parity(&out.as_bytes()[start..]);
+                len
+            }
+            Mode::Byte => {
+                let charset = charset.or(sym.eci.and_then(eci_charset));
+                write_byte(inp, char_cnt, out, parity, charset)?
+            }
+            Mode::Kanji => write_kanji(inp, char_cnt, out, parity)?,
+            Mode::Eci => {
+                let (designator, len) = write_eci(inp)?;
+                sym.eci = Some(designator);
+                len
+            }
+            Mode::StructuredAppend => {
+                sym.structured_append = Some(take_structured_append(inp)?);
+                2
+            }
+            Mode::Fnc1First => {
+                sym.fnc1 = Some(Fnc1::First);
+                0
+            }
+            Mode::Fnc1Second => {
+                let ai = inp.take_bits(8).ok_or(QRError::CorruptDataSegment)? as u8;
+                sym.fnc1 = Some(Fnc1::Second(ai));
+                write_application_indicator(ai, out)?
+            }
             Mode::Terminator => return Ok(0),
         };
 
+        // Ranges index the data as encoded, like those of built symbols, not the decoded text
+        let data_start = segs.last().map_or(0, |s| s.range.end);
+        let data_len = match mode {
+            Mode::Numeric | Mode::Alphanumeric | Mode::Byte => char_cnt,
+            Mode::Kanji => char_cnt * 2,
+            _ => 0,
+        };
+        segs.push(SegmentInfo { mode, char_cnt, range: data_start..data_start + data_len });
+
         let encoded_len = mode.encoded_len(decoded_len);
         let bit_len = ver.mode_bits() + ver.char_cnt_bits(mode) + encoded_len;
 
@@ -38,9 +99,12 @@ mod reader {
             0 => Mode::Terminator,
             1 => Mode::Numeric,
             2 => Mode::Alphanumeric,
+            3 => Mode::StructuredAppend,
             4 => Mode::Byte,
+            5 => Mode::Fnc1First,
             7 => Mode::Eci,
             8 => Mode::Kanji,
+            9 => Mode::Fnc1Second,
             _ => return Err(QRError::InvalidMode(mode_bits as u8)),
         };
 
@@ -92,7 +156,13 @@ mod reader {
         Ok(total_bit_len)
     }
 
-    fn write_byte(inp: &mut BitStream, mut char_cnt: usize, out: &mut String) -> QRResult<usize> {
+    fn write_byte(
+        inp: &mut BitStream,
+        mut char_cnt: usize,
+        out: &mut String,
+        parity: &mut u8,
+        charset: Option<&'static Encoding>,
+    ) -> QRResult<usize> {
         let mut total_bit_len = 0;
         let mut bytes = Vec::with_capacity(char_cnt);
 
@@ -104,29 +174,69 @@ mod reader {
             char_cnt -= 1;
         }
 
-        match String::from_utf8(bytes.clone()) {
-            Ok(utf8) => out.push_str(&utf8),
-            Err(_) => {
-                let (kanji, _, has_err) = SHIFT_JIS.decode(&bytes);
+        out.push_str(&decode_bytes(&bytes, charset)?);
+        *parity ^= StructuredAppend::parity(&bytes);
 
-                if has_err {
-                    return Err(QRError::InvalidCharacterEncoding);
-                }
+        Ok(total_bit_len)
+    }
 
-                out.push_str(&kanji);
+    // Decodes in the given charset, stripping its BOM. Otherwise a BOM picks the charset, then
+    // utf16 is tried if every other byte is mostly 0, then utf8 & finally shift jis
+    fn decode_bytes(bytes: &[u8], charset: Option<&'static Encoding>) -> QRResult<String> {
+        let decode = |enc: &'static Encoding, bytes: &[u8]| {
+            let (text, has_err) = enc.decode_with_bom_removal(bytes);
+            match has_err {
+                true => Err(QRError::InvalidCharacterEncoding),
+                false => Ok(text.into_owned()),
             }
+        };
+
+        if let Some(enc) = charset {
+            return decode(enc, bytes);
+        }
+        if let Some((enc, _)) = Encoding::for_bom(bytes) {
+            return decode(enc, bytes);
+        }
+        if let Some(enc) = sniff_utf16(bytes) {
+            return decode(enc, bytes);
         }
 
-        Ok(total_bit_len)
+        match std::str::from_utf8(bytes) {
+            Ok(utf8) => Ok(utf8.to_string()),
+            Err(_) => decode(SHIFT_JIS, bytes),
+        }
+    }
+
+    // Guesses utf16 without a BOM from text mostly in the latin range, where the high byte of
+    // most code units is 0 & the low byte never is
+    fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+        if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+            return None;
+        }
+
+        let units = bytes.len() / 2;
+        let zeros = |off| bytes.iter().skip(off).step_by(2).filter(|&&b| b == 0).count();
+        let is_high = |z: usize| z * 4 >= units * 3;
+        match (zeros(0), zeros(1)) {
+            (z, 0) if is_high(z) => Some(UTF_16BE),
+            (0, z) if is_high(z) => Some(UTF_16LE),
+            _ => None,
+        }
     }
 
-    fn write_kanji(inp: &mut BitStream, mut char_cnt: usize, out: &mut String) -> QRResult<usize> {
+    fn write_kanji(
+        inp: &mut BitStream,
+        mut char_cnt: usize,
+        out: &mut String,
+        parity: &mut u8,
+    ) -> QRResult<usize> {
         let mut total_bit_len = 0;
 
         while char_cnt > 0 {
             let chunk = inp.take_bits(13).ok_or(QRError::CorruptDataSegment)?;
             let decoded = Mode::Kanji.decode_chunk(chunk, 13);
             total_bit_len += decoded.len();
+            *parity ^= StructuredAppend::parity(&decoded);
             let (decoded_str, _, has_err) = SHIFT_JIS.decode(&decoded);
 
             if has_err {
@@ -140,28 +250,101 @@ mod reader {
         Ok(total_bit_len)
     }
 
-    fn write_eci(inp: &mut BitStream, _char_cnt: usize, _out: &mut str) -> QRResult<usize> {
-        let mut total_bit_len = 8;
-        let mut _eci = inp.take_bits(8).ok_or(QRError::CorruptDataSegment)? as u32;
+    // Reads the designator, returning it with its codeword count
+    fn write_eci(inp: &mut BitStream) -> QRResult<(u32, usize)> {
+        let eci = inp.take_bits(8).ok_or(QRError::CorruptDataSegment)? as u32;
+
+        if eci & 0b1000_0000 == 0 {
+            Ok((eci, 1))
+        } else if eci & 0b1100_0000 == 0b1000_0000 {
+            let lo = inp.take_bits(8).ok_or(QRError::CorruptDataSegment)? as u32;
+            Ok(((eci & 0x3F) << 8 | lo, 2))
+        } else if eci & 0b1110_0000 == 0b1100_0000 {
+            let lo = inp.take_bits(16).ok_or(QRError::CorruptDataSegment)? as u32;
+            Ok(((eci & 0x1F) << 16 | lo, 3))
+        } else {
+            Err(QRError::CorruptDataSegment)
+        }
+    }
 
-        if _eci & 0b1100_0000 == 0b1000_0000 {
-            _eci = (_eci << 8) | inp.take_bits(8).ok_or(QRError::CorruptDataSegment)? as u32;
-            total_bit_len += 8;
-        } else if _eci & 0b1110_0000 == 0b1100_0000 {
-            _eci = (_eci << 16) | inp.take_bits(16).ok_or(QRError::CorruptDataSegment)? as u32;
-            total_bit_len += 16;
+    // Symbol index & count in 4 bits each, followed by the parity byte
+    fn take_structured_append(inp: &mut BitStream) -> QRResult<StructuredAppend> {
+        let bits = inp.take_bits(16).ok_or(QRError::CorruptDataSegment)?;
+        let (index, total) = ((bits >> 12) as u8, (bits >> 8 & 0xF) as u8 + 1);
+        if index >= total {
+            return Err(QRError::CorruptDataSegment);
         }
+        Ok(StructuredAppend { index, total, parity: bits as u8 })
+    }
 
-        Ok(total_bit_len)
+    // Charset of an ECI designator. None for the cp437 designators encoding_rs lacks & the
+    // designators without a charset, in which case the charset is detected
+    fn eci_charset(designator: u32) -> Option<&'static Encoding> {
+        let enc = match designator {
+            1 | 3 | 27 => WINDOWS_1252, // Iso 8859-1 & ascii, as decoded by browsers
+            4 => ISO_8859_2,
+            5 => ISO_8859_3,
+            6 => ISO_8859_4,
+            7 => ISO_8859_5,
+            8 => ISO_8859_6,
+            9 => ISO_8859_7,
+            10 => ISO_8859_8,
+            11 => WINDOWS_1254,
+            12 => ISO_8859_10,
+            13 => WINDOWS_874,
+            15 => ISO_8859_13,
+            16 => ISO_8859_14,
+            17 => ISO_8859_15,
+            18 => ISO_8859_16,
+            20 => SHIFT_JIS,
+            21 => WINDOWS_1250,
+            22 => WINDOWS_1251,
+            23 => WINDOWS_1252,
+            24 => WINDOWS_1256,
+            25 => UTF_16BE,
+            26 => UTF_8,
+            28 => BIG5,
+            29 => GB18030,
+            30 => EUC_KR,
+            _ => return None,
+        };
+        Some(enc)
+    }
+
+    // Application indicator is either 2 digits or a letter offset by 100
+    fn write_application_indicator(ai: u8, out: &mut String) -> QRResult<usize> {
+        match ai {
+            0..=99 => out.push_str(&format!("{ai:02}")),
+            165..=190 | 197..=222 => out.push((ai - 100) as char),
+            _ => return Err(QRError::CorruptDataSegment),
+        }
+        Ok(8)
+    }
+
+    // In FNC1 mode, % in alphanumeric data is the GS separator and %% is a literal %
+    fn push_fnc1_alphanumeric(seg: &str, out: &mut String) {
+        let mut chars = seg.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '%' if chars.peek() == Some(&'%') => {
+                    chars.next();
+                    out.push('%');
+                }
+                '%' => out.push(GS),
+                c => out.push(c),
+            }
+        }
     }
 
     #[cfg(test)]
     mod reader_tests {
         use super::{
-            take_header, write_alphanumeric, write_byte, write_numeric, write_segment, BitStream,
-            Mode,
+            decode_bytes, take_header, write_alphanumeric, write_byte, write_numeric,
+            write_segment, BitStream, Mode, GS,
         };
         use crate::common::codec::encoder::encode_with_version;
+        use crate::metadata::{Fnc1, StructuredAppend, SymbologyId};
+        use crate::utils::QRError;
         use crate::{ECLevel, Version};
 
         #[test]
@@ -279,14 +462,36 @@ mod reader {
 
             take_header(&mut bs, ver).unwrap();
 
-            write_byte(&mut bs, 2, &mut out).unwrap();
+            write_byte(&mut bs, 2, &mut out, &mut 0, None).unwrap();
             assert_eq!(out, "ab");
             out.clear();
 
-            write_byte(&mut bs, 1, &mut out).unwrap();
+            write_byte(&mut bs, 1, &mut out, &mut 0, None).unwrap();
             assert_eq!(out, "c");
         }
 
+        #[test]
+        fn test_decode_bytes() {
+            let utf16 = |s: &str, le: bool| {
+                let units = s.encode_utf16();
+                units.flat_map(|u| if le { u.to_le_bytes() } else { u.to_be_bytes() }).collect()
+            };
+            let bom_le = [[0xFF, 0xFE].to_vec(), utf16("Hi ü", true)].concat();
+            let bom_utf8 = [&[0xEF, 0xBB, 0xBF], "Hi ü".as_bytes()].concat();
+
+            assert_eq!(decode_bytes(&bom_utf8, None).unwrap(), "Hi ü");
+            assert_eq!(decode_bytes(&bom_le, None).unwrap(), "Hi ü");
+            assert_eq!(decode_bytes(&utf16("Hello", false), None).unwrap(), "Hello");
+            assert_eq!(decode_bytes(&utf16("Hello", true), None).unwrap(), "Hello");
+            assert_eq!(decode_bytes("Hi\0\0".as_bytes(), None).unwrap(), "Hi\0\0");
+            assert_eq!(decode_bytes(&[0x83, 0x41], None).unwrap(), "ア");
+
+            let latin1 = [0x63, 0x61, 0x66, 0xE9];
+            assert!(decode_bytes(&latin1, None).is_err());
+            assert_eq!(decode_bytes(&latin1, Some(encoding_rs::WINDOWS_1252)).unwrap(), "café");
+            assert_eq!(decode_bytes(&bom_utf8, Some(encoding_rs::UTF_8)).unwrap(), "Hi ü");
+        }
+
         #[test]
         fn test_write_segment() {
             let data = "abcABCDEF1234567890123ABCDEFabc".as_bytes();
@@ -295,34 +500,110 @@ mod reader {
             let hi_cap = false;
             let mut bs = encode_with_version(data, ver, ecl, hi_cap).unwrap();
             let mut out = String::with_capacity(100);
+            let mut sym = SymbologyId::default();
+            let mut segs = Vec::new();
 
-            write_segment(&mut bs, ver, &mut out).unwrap();
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
             assert_eq!(out, "abc");
             out.clear();
 
-            write_segment(&mut bs, ver, &mut out).unwrap();
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
             assert_eq!(out, "ABCDEF");
             out.clear();
 
-            write_segment(&mut bs, ver, &mut out).unwrap();
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
             assert_eq!(out, "1234567890123");
             out.clear();
 
-            write_segment(&mut bs, ver, &mut out).unwrap();
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
             assert_eq!(out, "ABCDEF");
             out.clear();
 
-            write_segment(&mut bs, ver, &mut out).unwrap();
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
             assert_eq!(out, "abc");
+            assert_eq!(sym, SymbologyId::default());
+        }
+
+        #[test]
+        fn test_write_segment_fnc1() {
+            let ver = Version::Normal(1);
+
+            // FNC1 first, alphanumeric "01%A%%"
+            let mut bs = BitStream::new(128);
+            bs.push_bits(0b0101, 4);
+            bs.push_bits(0b0010, 4);
+            bs.push_bits(6, 9);
+            for (a, b) in [(0, 1), (38, 10), (38, 38)] {
+                bs.push_bits(a * 45 + b, 11);
+            }
+            let mut out = String::new();
+            let mut sym = SymbologyId::default();
+            let mut segs = Vec::new();
+            assert_eq!(
+                write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap(),
+                4
+            );
+            write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
+            assert_eq!(out, format!("01{GS}A%"));
+            assert_eq!(sym.fnc1, Some(Fnc1::First));
+            assert_eq!(sym.to_string(), "]Q3");
+            let modes: Vec<_> = segs.iter().map(|s| (s.mode, s.range.clone())).collect();
+            assert_eq!(modes, [(Mode::Fnc1First, 0..0), (Mode::Alphanumeric, 0..6)]);
+
+            // FNC1 second with application indicator 'a'
+            let mut bs = BitStream::new(64);
+            bs.push_bits(0b1001, 4);
+            bs.push_bits(197, 8);
+            let mut out = String::new();
+            let mut sym = SymbologyId { eci: Some(26), ..Default::default() };
+            assert_eq!(
+                write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap(),
+                12
+            );
+            assert_eq!(out, "a");
+            assert_eq!(sym.to_string(), "]Q6");
+        }
+
+        #[test]
+        fn test_write_segment_structured_append() {
+            let ver = Version::Normal(1);
+            let mut bs = BitStream::new(64);
+            bs.push_bits(0b0011, 4);
+            bs.push_bits(0x23u8, 8);
+            bs.push_bits(0x5Au8, 8);
+            let (mut out, mut sym, mut segs) = (String::new(), SymbologyId::default(), Vec::new());
+            let bit_len =
+                write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None).unwrap();
+            assert_eq!(bit_len, 20);
+            assert_eq!(out, "");
+            let sa = StructuredAppend { index: 2, total: 4, parity: 0x5A };
+            assert_eq!(sym.structured_append, Some(sa));
+            assert_eq!(segs[0].mode, Mode::StructuredAppend);
+
+            // Index past the last symbol
+            let mut bs = BitStream::new(64);
+            bs.push_bits(0b0011, 4);
+            bs.push_bits(0x32u8, 8);
+            bs.push_bits(0u8, 8);
+            let err = write_segment(&mut bs, ver, &mut out, &mut sym, &mut segs, &mut 0, None);
+            assert_eq!(err, Err(QRError::CorruptDataSegment));
         }
     }
+
+    // Global constants
+    //------------------------------------------------------------------------------
+
+    const GS: char = '\x1D';
 }
 
 // Decoder
 //------------------------------------------------------------------------------
 
 pub mod decode {
+    use encoding_rs::Encoding;
+
     use super::reader::write_segment;
+    use crate::metadata::{SegmentInfo, SymbologyId};
     use crate::utils::{BitStream, QRResult};
     use crate::{ECLevel, Version};
 
@@ -332,11 +613,28 @@ pub mod decode {
         ecl: ECLevel,
         hi_cap: bool,
     ) -> QRResult<String> {
+        decode_segments(encoded, ver, ecl, hi_cap, None).map(|(msg, ..)| msg)
+    }
+
+    // Decodes the data along with the AIM symbology identifier derived from ECI & FNC1 usage, the
+    // segments read & the xor of the data bytes as encoded. Byte segments are read in charset if
+    // given
+    pub fn decode_segments(
+        encoded: &mut BitStream,
+        ver: Version,
+        ecl: ECLevel,
+        hi_cap: bool,
+        charset: Option<&'static Encoding>,
+    ) -> QRResult<(String, SymbologyId, Vec<SegmentInfo>, u8)> {
+        let mut sym = SymbologyId::default();
+        let mut segs = Vec::new();
+        let mut parity = 0;
         let bcap = ver.data_bit_capacity(ecl, false);
         let mut res = String::with_capacity(encoded.len());
         let mut bit_len = 0;
         loop {
-            let seg_bit_len = write_segment(encoded, ver, &mut res)?;
+            let seg_bit_len =
+                write_segment(encoded, ver, &mut res, &mut sym, &mut segs, &mut parity, charset)?;
             if seg_bit_len == 0 {
                 break;
             }
@@ -350,14 +648,20 @@ pub mod decode {
                 break;
             }
         }
-        Ok(res)
+        Ok((res, sym, segs, parity))
     }
 
     #[cfg(test)]
     mod decode_tests {
-        use super::decode;
-        use crate::codec::encode_with_version;
+        use test_case::test_case;
+
+        use super::{decode, decode_segments};
+        use crate::codec::encoder::writer::push_segment;
+        use crate::codec::{encode_segments, encode_with_version, Mode, PaddingPolicy, Segment};
+        use crate::metadata::SegmentInfo;
+        use crate::utils::BitStream;
         use crate::{ECLevel, Version};
+        use Mode::*;
 
         #[test]
         fn test_decode() {
@@ -369,5 +673,116 @@ pub mod decode {
             let decoded_data = decode(&mut bs, ver, ecl, hi_cap).unwrap();
             assert_eq!(decoded_data, data);
         }
+
+        // Legal encodings other generators may produce, decoded from hand built streams
+        #[test_case(&[(Byte, b"0123456789")], "0123456789"; "byte mode digits")]
+        #[test_case(&[(Alphanumeric, b"0123")], "0123"; "alphanumeric digits")]
+        #[test_case(&[(Numeric, b"12"), (Numeric, b"345"), (Numeric, b"6")], "123456"; "adjacent numeric")]
+        #[test_case(&[(Byte, b"ab"), (Byte, b"c")], "abc"; "adjacent byte")]
+        #[test_case(&[(Alphanumeric, b"A"), (Alphanumeric, b"B")], "AB"; "adjacent alphanumeric")]
+        #[test_case(&[(Byte, b""), (Numeric, b"1")], "1"; "empty segment")]
+        #[test_case(&[(Numeric, b"1"), (Alphanumeric, b"2"), (Byte, b"3")], "123"; "single char segments")]
+        fn test_decode_non_optimal(segs: &[(Mode, &[u8])], exp: &str) {
+            let (ver, ecl) = (Version::Normal(1), ECLevel::L);
+            for pad in [PaddingPolicy::Standard, PaddingPolicy::Zeros] {
+                let segs = segs
+                    .iter()
+                    .map(|&(m, d)| Segment::with_bits(m, ver.mode_bits(), ver.char_cnt_bits(m), d))
+                    .collect();
+                let mut bs = encode_segments(segs, ver, ecl, false, pad);
+                assert_eq!(decode(&mut bs, ver, ecl, false).unwrap(), exp);
+            }
+        }
+
+        #[test]
+        fn test_decode_early_terminator() {
+            let (ver, ecl) = (Version::Normal(1), ECLevel::L);
+
+            // Terminator followed by arbitrary bits instead of standard padding
+            let mut bs = BitStream::new(ver.data_bit_capacity(ecl, false));
+            push_segment(Segment::with_bits(Byte, 4, 8, b"ok"), &mut bs);
+            bs.push_bits(0u8, 4);
+            while bs.len() + 8 <= bs.capacity() {
+                bs.push_byte(0b1010_0101);
+            }
+            assert_eq!(decode(&mut bs, ver, ecl, false).unwrap(), "ok");
+
+            // Terminator cut short or left out at the end of capacity
+            for (mode, n) in [(Alphanumeric, 25), (Numeric, 41), (Byte, 17)] {
+                let data = [b'7'; 41];
+                let mut bs = BitStream::new(ver.data_bit_capacity(ecl, false));
+                push_segment(
+                    Segment::with_bits(mode, 4, ver.char_cnt_bits(mode), &data[..n]),
+                    &mut bs,
+                );
+                assert!(bs.capacity() - bs.len() <= 4);
+                bs.push_bits(0u8, bs.capacity() - bs.len());
+                let mut bs = BitStream::from(bs.data());
+                assert_eq!(decode(&mut bs, ver, ecl, false).unwrap(), "7".repeat(n));
+            }
+
+            // ECI designator ahead of the data it applies to
+            let mut bs = BitStream::new(ver.data_bit_capacity(ecl, false));
+            bs.push_bits(Eci as u8, 4);
+            bs.push_byte(26);
+            push_segment(Segment::with_bits(Byte, 4, 8, "é".as_bytes()), &mut bs);
+            bs.push_bits(0u8, 4);
+            assert_eq!(decode(&mut bs, ver, ecl, false).unwrap(), "é");
+        }
+
+        #[test]
+        fn test_decode_eci() {
+            let (ver, ecl) = (Version::Normal(1), ECLevel::L);
+            let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("café");
+            let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("日本");
+            for (eci, data, exp) in [([3], &latin1, "café"), ([20], &sjis, "日本")] {
+                let segs =
+                    vec![Segment::with_bits(Eci, 4, 0, &eci), Segment::with_bits(Byte, 4, 8, data)];
+                let mut bs = encode_segments(segs, ver, ecl, false, PaddingPolicy::Standard);
+                let (msg, sym, segs, _) = decode_segments(&mut bs, ver, ecl, false, None).unwrap();
+                assert_eq!(msg, exp);
+                assert_eq!(sym.eci, Some(eci[0] as u32));
+                assert_eq!(sym.to_string(), "]Q2");
+                assert_eq!(segs[0], SegmentInfo { mode: Eci, char_cnt: 0, range: 0..0 });
+            }
+
+            // Charset override takes precedence over the designator
+            let segs =
+                vec![Segment::with_bits(Eci, 4, 0, &[26]), Segment::with_bits(Byte, 4, 8, &latin1)];
+            let mut bs = encode_segments(segs, ver, ecl, false, PaddingPolicy::Standard);
+            let charset = Some(encoding_rs::WINDOWS_1252);
+            let (msg, sym, ..) = decode_segments(&mut bs, ver, ecl, false, charset).unwrap();
+            assert_eq!((msg.as_str(), sym.eci), ("café", Some(26)));
+        }
+
+        #[test]
+        fn test_decode_segments() {
+            let ver = Version::Normal(1);
+            let ecl = ECLevel::L;
+            let mut bs = encode_with_version(b"HELLO 12345678", ver, ecl, false).unwrap();
+            let (msg, sym, segs, _) = decode_segments(&mut bs, ver, ecl, false, None).unwrap();
+            assert_eq!(msg, "HELLO 12345678");
+            assert_eq!(sym.to_string(), "]Q1");
+            let exp = [
+                SegmentInfo { mode: Mode::Alphanumeric, char_cnt: 6, range: 0..6 },
+                SegmentInfo { mode: Mode::Numeric, char_cnt: 8, range: 6..14 },
+            ];
+            assert_eq!(segs, exp);
+
+            // Kanji ranges cover 2 bytes of Shift JIS a char, though each decodes to 3 of UTF-8
+            let mut bs = BitStream::new(128);
+            bs.push_bits(0b1000, 4);
+            bs.push_bits(2u8, 8);
+            for sjis in [0x93FAu16, 0x967B] {
+                let v = sjis - 0x8140;
+                bs.push_bits((v >> 8) * 0xC0 + (v & 0xFF), 13);
+            }
+            push_segment(Segment::with_bits(Numeric, 4, 10, b"12"), &mut bs);
+            bs.push_bits(0u8, 4);
+            let (msg, _, segs, _) = decode_segments(&mut bs, ver, ecl, false, None).unwrap();
+            assert_eq!(msg, "日本12");
+            let ranges: Vec<_> = segs.iter().map(|s| s.range.clone()).collect();
+            assert_eq!(ranges, [0..4, 4..6]);
+        }
     }
 }
@@ -4,17 +4,27 @@ pub use encode::*;
 //------------------------------------------------------------------------------
 
 pub mod encode {
-    use std::mem::swap;
+    use std::{mem::swap, ops::RangeInclusive};
 
-    use crate::codec::{Mode, Segment, MODES};
-    use crate::metadata::{ECLevel, Version};
+    use crate::codec::{Mode, PaddingPolicy, Segment, MODES};
+    use crate::metadata::{ECLevel, Version, MAX_VERSION};
     use crate::utils::{BitStream, QRError, QRResult};
 
-    use super::writer::{pad_remaining_capacity, push_segment, push_terminator};
+    use super::writer::{
+        pad_remaining_capacity, push_segment, push_terminator, push_terminator_bits,
+    };
 
-    // TODO: Write testcases
-    pub fn encode(data: &[u8], ecl: ECLevel, hi_cap: bool) -> QRResult<(BitStream, Version)> {
-        let (ver, segs) = find_optimal_version_and_segments(data, ecl, hi_cap)?;
+    // Encodes in the smallest version that fits. If boost is set, the ec level is then raised as
+    // far as the spare capacity allows, hence returned along with the version
+    pub fn encode(
+        data: &[u8],
+        ecl: ECLevel,
+        hi_cap: bool,
+        boost: bool,
+    ) -> QRResult<(BitStream, Version, ECLevel)> {
+        let (ver, segs) = smallest_fit(data, &[], None, ecl, hi_cap, 1..=MAX_VERSION)
+            .ok_or(QRError::DataTooLong)?;
+        let ecl = if boost { boost_ec_level(&segs, ver, ecl, hi_cap) } else { ecl };
         let bcap = ver.data_bit_capacity(ecl, hi_cap);
         let mut bs = BitStream::new(bcap);
 
@@ -23,8 +33,18 @@ pub mod encode {
         }
 
         push_terminator(&mut bs);
-        pad_remaining_capacity(&mut bs);
-        Ok((bs, ver))
+        pad_remaining_capacity(&mut bs, PaddingPolicy::Standard);
+        Ok((bs, ver, ecl))
+    }
+
+    // Highest ec level from ecl up, that the version supports & still holds the segments in
+    pub fn boost_ec_level(segs: &[Segment], ver: Version, ecl: ECLevel, hi_cap: bool) -> ECLevel {
+        let sz: usize = segs.iter().map(|s| s.bit_len()).sum();
+        [ECLevel::H, ECLevel::Q, ECLevel::M]
+            .into_iter()
+            .take_while(|&e| e > ecl)
+            .find(|&e| ver.supports_ec_level(e) && sz <= ver.data_bit_capacity(e, hi_cap))
+            .unwrap_or(ecl)
     }
 
     // TODO: Write testcases
@@ -34,6 +54,9 @@ pub mod encode {
         ecl: ECLevel,
         hi_cap: bool,
     ) -> QRResult<BitStream> {
+        if matches!(ver, Version::Normal(v) if v > MAX_VERSION) {
+            return Err(QRError::InvalidVersion);
+        }
         let bcap = ver.data_bit_capacity(ecl, hi_cap);
         let segs = compute_optimal_segments(data, ver);
         let sz: usize = segs.iter().map(|s| s.bit_len()).sum();
@@ -47,33 +70,238 @@ pub mod encode {
             push_segment(seg, &mut bs);
         }
         push_terminator(&mut bs);
-        pad_remaining_capacity(&mut bs);
+        pad_remaining_capacity(&mut bs, PaddingPolicy::Standard);
         Ok(bs)
     }
 
-    fn find_optimal_version_and_segments(
-        data: &'_ [u8],
+    // Encodes data entirely in the forced mode. Fails with the first byte outside the mode's
+    // charset instead of silently switching modes
+    pub fn encode_with_mode(
+        data: &[u8],
+        mode: Mode,
+        ver: Option<Version>,
+        ecl: ECLevel,
+        hi_cap: bool,
+    ) -> QRResult<(BitStream, Version)> {
+        let (ver, segs) = plan_segments(data, &[], Some(mode), ver, 1..=MAX_VERSION, ecl, hi_cap)?;
+        Ok((encode_segments(segs, ver, ecl, hi_cap, PaddingPolicy::Standard), ver))
+    }
+
+    // Decides version and segments for the data. Mode is picked optimally unless provided, and
+    // version is the smallest one in vers that fits unless provided. If the data only fits above
+    // the range, the error reports the version it needs. Headers, i.e. structured append & ECI
+    // modes with their codewords, are put in segments ahead of the data in the given order
+    pub fn plan_segments<'a>(
+        data: &'a [u8],
+        headers: &[(Mode, &'a [u8])],
+        mode: Option<Mode>,
+        ver: Option<Version>,
+        vers: RangeInclusive<usize>,
+        ecl: ECLevel,
+        hi_cap: bool,
+    ) -> QRResult<(Version, Vec<Segment<'a>>)> {
+        if matches!(ver, Some(Version::Normal(v)) if v > MAX_VERSION) {
+            return Err(QRError::InvalidVersion);
+        }
+
+        if let Some(mode) = mode {
+            mode.validate(data)?;
+        }
+        if let Some(ver @ Version::Micro(_)) = ver {
+            validate_micro(headers.iter().map(|(m, _)| *m).chain(mode), ver, ecl, hi_cap)?;
+            // Data outside the widest mode of the version is reported as in that mode
+            if let Some(widest) = MODES.iter().rev().find(|m| ver.supports_mode(**m)) {
+                widest.validate(data)?;
+            }
+        }
+
+        if let Some(ver) = ver {
+            let segs = segment(data, headers, mode, ver);
+            let sz: usize = segs.iter().map(|s| s.bit_len()).sum();
+            if sz > ver.data_bit_capacity(ecl, hi_cap) {
+                return Err(QRError::DataTooLong);
+            }
+            return Ok((ver, segs));
+        }
+
+        search_versions(vers, |vers| smallest_fit(data, headers, mode, ecl, hi_cap, vers))
+    }
+
+    // Micro QR only takes some modes & ec levels in each version, and has no high capacity
+    // variant
+    fn validate_micro(
+        mut modes: impl Iterator<Item = Mode>,
+        ver: Version,
+        ecl: ECLevel,
+        hi_cap: bool,
+    ) -> QRResult<()> {
+        if !matches!(ver, Version::Micro(1..=4)) || hi_cap {
+            return Err(QRError::InvalidVersion);
+        }
+        if !ver.supports_ec_level(ecl) {
+            return Err(QRError::InvalidECLevel);
+        }
+        match modes.find(|m| !ver.supports_mode(*m)) {
+            Some(m) => Err(QRError::UnsupportedMicroMode(m)),
+            None => Ok(()),
+        }
+    }
+
+    // Smallest fit in the range of normal versions, found by smallest over a range. If the data
+    // only fits above the range, the error reports the version it needs
+    fn search_versions<'a>(
+        vers: RangeInclusive<usize>,
+        smallest: impl Fn(RangeInclusive<usize>) -> Option<(Version, Vec<Segment<'a>>)>,
+    ) -> QRResult<(Version, Vec<Segment<'a>>)> {
+        let (min, max) = (*vers.start(), *vers.end());
+        if min == 0 || min > max || max > MAX_VERSION {
+            return Err(QRError::InvalidVersion);
+        }
+        if let Some(fit) = smallest(vers) {
+            return Ok(fit);
+        }
+        if max == MAX_VERSION {
+            return Err(QRError::DataTooLong);
+        }
+        let needed = smallest(max + 1..=MAX_VERSION);
+        match needed {
+            Some((v, _)) => Err(QRError::ExceedsVersionRange(max, *v)),
+            None => Err(QRError::FitsNoVersion(max)),
+        }
+    }
+
+    // Decides version for data already split into segments of the given modes & byte lengths,
+    // e.g. appended to a builder piece by piece, without running the optimizer over the data.
+    // Otherwise same as plan_segments
+    pub fn plan_fixed_segments<'a>(
+        data: &'a [u8],
+        shape: &[(Mode, usize)],
+        headers: &[(Mode, &'a [u8])],
+        ver: Option<Version>,
+        vers: RangeInclusive<usize>,
+        ecl: ECLevel,
+        hi_cap: bool,
+    ) -> QRResult<(Version, Vec<Segment<'a>>)> {
+        debug_assert_eq!(
+            data.len(),
+            shape.iter().map(|(_, l)| l).sum::<usize>(),
+            "Segment lengths don't add up to the data"
+        );
+        if matches!(ver, Some(Version::Normal(v)) if v > MAX_VERSION) {
+            return Err(QRError::InvalidVersion);
+        }
+
+        let mut start = 0;
+        for &(mode, len) in shape {
+            mode.validate(&data[start..start + len]).map_err(|e| match e {
+                QRError::InvalidModeChar(m, i, c) => QRError::InvalidModeChar(m, start + i, c),
+                e => e,
+            })?;
+            start += len;
+        }
+        if let Some(ver @ Version::Micro(_)) = ver {
+            let modes = headers.iter().map(|(m, _)| *m).chain(shape.iter().map(|(m, _)| *m));
+            validate_micro(modes, ver, ecl, hi_cap)?;
+        }
+
+        let fit = |ver: Version| {
+            let segs = split_segments(data, shape, headers, ver);
+            let sz: usize = segs.iter().map(|s| s.bit_len()).sum();
+            (sz <= ver.data_bit_capacity(ecl, hi_cap)).then_some((ver, segs))
+        };
+        if let Some(ver) = ver {
+            return fit(ver).ok_or(QRError::DataTooLong);
+        }
+
+        search_versions(vers, |vers| vers.map(Version::Normal).find_map(fit))
+    }
+
+    // Writes segments followed by terminator & padding
+    pub fn encode_segments(
+        segs: Vec<Segment>,
+        ver: Version,
+        ecl: ECLevel,
+        hi_cap: bool,
+        pad: PaddingPolicy,
+    ) -> BitStream {
+        let bcap = ver.data_bit_capacity(ecl, hi_cap);
+        let mut bs = BitStream::new(bcap);
+        for seg in segs {
+            push_segment(seg, &mut bs);
+        }
+        push_terminator_bits(&mut bs, ver.terminator_bits());
+        pad_remaining_capacity(&mut bs, pad);
+        bs
+    }
+
+    // Smallest version in vers that fits the data, with its segments
+    fn smallest_fit<'a>(
+        data: &'a [u8],
+        headers: &[(Mode, &'a [u8])],
+        mode: Option<Mode>,
         ecl: ECLevel,
         hi_cap: bool,
-    ) -> QRResult<(Version, Vec<Segment<'_>>)> {
+        vers: RangeInclusive<usize>,
+    ) -> Option<(Version, Vec<Segment<'a>>)> {
         let mut segs = vec![];
         let mut sz = 0;
-        for v in 1..=40 {
+        let start = *vers.start();
+        for v in vers {
             let ver = Version::Normal(v);
             let bcap = ver.data_bit_capacity(ecl, hi_cap);
-            if v == 1 || v == 10 || v == 27 {
-                segs = compute_optimal_segments(data, ver);
+            // Segments only change with the char count lengths, at versions 10 & 27
+            if v == start || v == 10 || v == 27 {
+                segs = segment(data, headers, mode, ver);
                 sz = segs.iter().map(|s| s.bit_len()).sum();
             }
             if sz <= bcap {
-                return Ok((ver, segs));
+                return Some((ver, segs));
             }
         }
-        Err(QRError::DataTooLong)
+        None
+    }
+
+    // Splits the data as per the shape, after the header segments
+    fn split_segments<'a>(
+        data: &'a [u8],
+        shape: &[(Mode, usize)],
+        headers: &[(Mode, &'a [u8])],
+        ver: Version,
+    ) -> Vec<Segment<'a>> {
+        let mode_bits = ver.mode_bits();
+        let mut segs = Vec::with_capacity(headers.len() + shape.len());
+        segs.extend(headers.iter().map(|&(m, cwds)| Segment::with_bits(m, mode_bits, 0, cwds)));
+        let mut start = 0;
+        for &(mode, len) in shape {
+            // Runs longer than the char count field holds continue in another segment
+            let len_bits = ver.char_cnt_bits(mode);
+            let max_len = (1 << len_bits) - 1;
+            for chunk in data[start..start + len].chunks(max_len) {
+                segs.push(Segment::with_bits(mode, mode_bits, len_bits, chunk));
+            }
+            start += len;
+        }
+        segs
+    }
+
+    // Segments the data in the given mode, or optimally, after the header segments
+    fn segment<'a>(
+        data: &'a [u8],
+        headers: &[(Mode, &'a [u8])],
+        mode: Option<Mode>,
+        ver: Version,
+    ) -> Vec<Segment<'a>> {
+        let segs = match mode {
+            Some(m) => build_segments(ver, vec![m; data.len()], data),
+            None => compute_optimal_segments(data, ver),
+        };
+        let headers =
+            headers.iter().map(|&(m, cwds)| Segment::with_bits(m, ver.mode_bits(), 0, cwds));
+        headers.chain(segs).collect()
     }
 
     // Dynamic programming to compute optimum mode segments
-    fn compute_optimal_segments(data: &'_ [u8], ver: Version) -> Vec<Segment<'_>> {
+    pub(crate) fn compute_optimal_segments(data: &'_ [u8], ver: Version) -> Vec<Segment<'_>> {
         debug_assert!(!data.is_empty(), "Empty data");
 
         let len = data.len();
@@ -86,14 +314,14 @@ pub mod encode {
 
         MODES.iter().enumerate().for_each(|(i, &m)| {
             let char_bits = ver.char_cnt_bits(m);
-            meta_bits[i] = (4 + char_bits) * 6;
-            prev_cost[i] = meta_bits[i];
+            meta_bits[i] = (ver.mode_bits() + char_bits) * 6;
+            prev_cost[i] = if ver.supports_mode(m) { meta_bits[i] } else { usize::MAX };
             max_chars[i] = (1 << char_bits) - 1;
         });
 
         for (i, b) in data.iter().enumerate() {
             for (j, to_mode) in MODES.iter().enumerate() {
-                if !to_mode.contains(*b) {
+                if !to_mode.contains(*b) || !ver.supports_mode(*to_mode) {
                     continue;
                 }
 
@@ -102,8 +330,8 @@ pub mod encode {
                     Mode::Alphanumeric => 33,
                     Mode::Byte => 48,
                     Mode::Kanji => todo!(),
-                    Mode::Eci => {
-                        unreachable!("Optimal segments should never have ECI mode")
+                    Mode::Eci | Mode::StructuredAppend | Mode::Fnc1First | Mode::Fnc1Second => {
+                        unreachable!("Optimal segments should never have header or FNC1 mode")
                     }
                     Mode::Terminator => {
                         unreachable!("Optimal segments should never have terminator mode")
@@ -173,7 +401,11 @@ pub mod encode {
     }
 
     // Build segments encode char modes
-    fn build_segments(ver: Version, char_modes: Vec<Mode>, data: &'_ [u8]) -> Vec<Segment<'_>> {
+    pub(crate) fn build_segments(
+        ver: Version,
+        char_modes: Vec<Mode>,
+        data: &'_ [u8],
+    ) -> Vec<Segment<'_>> {
         let len = data.len();
         let mut segs: Vec<Segment> = vec![];
         let mut seg_start = 0;
@@ -185,7 +417,7 @@ pub mod encode {
             if (seg_mode != m) || (i - seg_start) == (1 << char_bits) - 1 {
                 let mode_bits = ver.mode_bits();
                 let len_bits = ver.char_cnt_bits(seg_mode);
-                segs.push(Segment::new(seg_mode, mode_bits, len_bits, &data[seg_start..i]));
+                segs.push(Segment::with_bits(seg_mode, mode_bits, len_bits, &data[seg_start..i]));
 
                 seg_mode = m;
                 seg_start = i;
@@ -194,7 +426,7 @@ pub mod encode {
 
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(seg_mode);
-        segs.push(Segment::new(seg_mode, mode_bits, len_bits, &data[seg_start..len]));
+        segs.push(Segment::with_bits(seg_mode, mode_bits, len_bits, &data[seg_start..len]));
 
         segs
     }
@@ -204,8 +436,9 @@ pub mod encode {
         use test_case::test_case;
 
         use super::{
-            build_segments, compute_optimal_segments, encode_with_version,
-            find_optimal_version_and_segments, ECLevel, Mode, Segment, Version,
+            boost_ec_level, build_segments, compute_optimal_segments, encode, encode_with_mode,
+            encode_with_version, plan_segments, smallest_fit, ECLevel, Mode, QRError, Segment,
+            Version, MAX_VERSION,
         };
 
         #[test]
@@ -217,19 +450,19 @@ pub mod encode {
             char_modes.extend([Mode::Numeric; 5]);
             char_modes.extend([Mode::Byte; 3]);
             let segs = build_segments(ver, char_modes, data.as_bytes());
-            let seg_1 = Segment::new(
+            let seg_1 = Segment::with_bits(
                 Mode::Alphanumeric,
                 mode_bits,
                 ver.char_cnt_bits(Mode::Alphanumeric),
                 &data.as_bytes()[0..5],
             );
-            let seg_2 = Segment::new(
+            let seg_2 = Segment::with_bits(
                 Mode::Numeric,
                 mode_bits,
                 ver.char_cnt_bits(Mode::Numeric),
                 &data.as_bytes()[5..10],
             );
-            let seg_3 = Segment::new(
+            let seg_3 = Segment::with_bits(
                 Mode::Byte,
                 mode_bits,
                 ver.char_cnt_bits(Mode::Byte),
@@ -264,8 +497,12 @@ pub mod encode {
             for (seg, &(mode, start, end)) in segs.iter().zip(chunks.iter()) {
                 let len_bits = ver.char_cnt_bits(mode);
                 let exp_seg = match end {
-                    Some(e) => Segment::new(mode, mode_bits, len_bits, &data.as_bytes()[start..e]),
-                    None => Segment::new(mode, mode_bits, len_bits, &data.as_bytes()[start..]),
+                    Some(e) => {
+                        Segment::with_bits(mode, mode_bits, len_bits, &data.as_bytes()[start..e])
+                    }
+                    None => {
+                        Segment::with_bits(mode, mode_bits, len_bits, &data.as_bytes()[start..])
+                    }
                 };
                 assert_eq!(*seg, exp_seg);
             }
@@ -279,14 +516,14 @@ pub mod encode {
             let segs = compute_optimal_segments(data.as_bytes(), ver);
             assert_eq!(segs.len(), 46);
             for (i, c) in data.as_bytes().chunks(15).enumerate() {
-                let seg_1 = Segment::new(
+                let seg_1 = Segment::with_bits(
                     Mode::Alphanumeric,
                     mode_bits,
                     ver.char_cnt_bits(Mode::Alphanumeric),
                     &c[..1],
                 );
                 assert_eq!(segs[i * 2], seg_1);
-                let seg_2 = Segment::new(
+                let seg_2 = Segment::with_bits(
                     Mode::Numeric,
                     mode_bits,
                     ver.char_cnt_bits(Mode::Numeric),
@@ -308,8 +545,10 @@ pub mod encode {
             ecl: ECLevel,
             hi_cap: bool,
         ) {
-            let (ver, _) = find_optimal_version_and_segments(data.as_bytes(), ecl, hi_cap).unwrap();
-            assert_eq!(ver, exp_ver);
+            // Data needing a version above the cap fits none
+            let fit = smallest_fit(data.as_bytes(), &[], None, ecl, hi_cap, 1..=MAX_VERSION);
+            let exp = matches!(exp_ver, Version::Normal(v) if v <= MAX_VERSION).then_some(exp_ver);
+            assert_eq!(fit.map(|(v, _)| v), exp);
         }
 
         #[test]
@@ -318,7 +557,73 @@ pub mod encode {
             let data = "a".repeat(2954);
             let ecl = ECLevel::L;
             let hi_cap = false;
-            find_optimal_version_and_segments(data.as_bytes(), ecl, hi_cap).unwrap();
+            smallest_fit(data.as_bytes(), &[], None, ecl, hi_cap, 1..=MAX_VERSION).unwrap();
+        }
+
+        #[test]
+        fn test_encode_with_mode() {
+            let data = "0123456789".repeat(5);
+            let (_, ver) =
+                encode_with_mode(data.as_bytes(), Mode::Numeric, None, ECLevel::L, false).unwrap();
+            assert_eq!(ver, Version::Normal(2));
+        }
+
+        #[test]
+        fn test_encode_with_mode_invalid_char() {
+            let data = "0123-456";
+            let err = encode_with_mode(data.as_bytes(), Mode::Numeric, None, ECLevel::L, false);
+            assert_eq!(err.unwrap_err(), QRError::InvalidModeChar(Mode::Numeric, 4, '-'));
+
+            let data = "HELLO wörld";
+            let ver = Some(Version::Normal(2));
+            let err = encode_with_mode(data.as_bytes(), Mode::Alphanumeric, ver, ECLevel::L, false);
+            assert_eq!(err.unwrap_err(), QRError::InvalidModeChar(Mode::Alphanumeric, 6, 'w'));
+        }
+
+        #[test]
+        fn test_plan_version_above_cap() {
+            let ver = Some(Version::Normal(MAX_VERSION + 1));
+            let err = plan_segments(b"Hello", &[], None, ver, 1..=MAX_VERSION, ECLevel::L, false);
+            assert_eq!(err.unwrap_err(), QRError::InvalidVersion);
+        }
+
+        #[cfg(not(any(feature = "max-version-5", feature = "max-version-10")))]
+        #[test]
+        fn test_plan_version_range() {
+            let data = "A".repeat(100);
+            let plan =
+                |vers| plan_segments(data.as_bytes(), &[], None, None, vers, ECLevel::M, false);
+            assert_eq!(plan(1..=MAX_VERSION).unwrap().0, Version::Normal(5));
+            assert_eq!(plan(6..=8).unwrap().0, Version::Normal(6));
+            assert_eq!(plan(1..=4).unwrap_err(), QRError::ExceedsVersionRange(4, 5));
+            let (min, max) = (6, 5);
+            assert_eq!(plan(min..=max).unwrap_err(), QRError::InvalidVersion);
+
+            let data = "A".repeat(5000);
+            let err = plan_segments(data.as_bytes(), &[], None, None, 1..=10, ECLevel::H, false);
+            assert_eq!(err.unwrap_err(), QRError::FitsNoVersion(10));
+        }
+
+        #[test]
+        fn test_plan_headers() {
+            let data = "é".repeat(8) + "!";
+            let plan = |headers: &[(Mode, &'static [u8])]| {
+                plan_segments(data.as_bytes(), headers, None, None, 1..=2, ECLevel::L, false)
+            };
+            let (ver, segs) = plan(&[]).unwrap();
+            assert_eq!((ver, segs.len()), (Version::Normal(1), 1));
+
+            // ECI header pushes the data over the capacity of version 1
+            let (ver, segs) = plan(&[(Mode::Eci, &[3])]).unwrap();
+            assert_eq!(ver, Version::Normal(2));
+            assert_eq!(segs[0], Segment::with_bits(Mode::Eci, 4, 0, &[3]));
+            assert_eq!(segs.iter().map(|s| s.bit_len()).sum::<usize>(), 12 + 148);
+
+            let (_, segs) =
+                plan(&[(Mode::StructuredAppend, &[0x01, 0x42]), (Mode::Eci, &[3])]).unwrap();
+            let modes: Vec<_> = segs.iter().map(|s| s.mode).collect();
+            assert_eq!(modes, [Mode::StructuredAppend, Mode::Eci, Mode::Byte]);
+            assert_eq!(segs[0].bit_len(), 20);
         }
 
         #[test]
@@ -327,7 +632,23 @@ pub mod encode {
             let ver = Version::Normal(9);
             let ecl = ECLevel::L;
             let hi_cap = true;
-            let _ = encode_with_version(data.as_bytes(), ver, ecl, hi_cap).unwrap();
+            let res = encode_with_version(data.as_bytes(), ver, ecl, hi_cap);
+            assert_eq!(res.is_ok(), MAX_VERSION >= 9);
+        }
+
+        #[test]
+        fn test_encode_boost_ec() {
+            // 116 bits fit version 1 up to ec level M
+            let data = b"Hello, world!";
+            let (_, ver, ecl) = encode(data, ECLevel::L, false, true).unwrap();
+            assert_eq!((ver, ecl), (Version::Normal(1), ECLevel::M));
+            let (_, _, ecl) = encode(data, ECLevel::L, false, false).unwrap();
+            assert_eq!(ecl, ECLevel::L);
+
+            let segs = compute_optimal_segments(b"12345", Version::Micro(2));
+            assert_eq!(boost_ec_level(&segs, Version::Micro(2), ECLevel::L, false), ECLevel::M);
+            let segs = compute_optimal_segments(b"1", Version::Normal(1));
+            assert_eq!(boost_ec_level(&segs, Version::Normal(1), ECLevel::H, false), ECLevel::H);
         }
     }
 }
@@ -335,8 +656,8 @@ pub mod encode {
 // Writer for encoded data
 //------------------------------------------------------------------------------
 
-pub(super) mod writer {
-    use crate::codec::{Mode, Segment, PADDING_CODEWORDS};
+pub(crate) mod writer {
+    use crate::codec::{Mode, PaddingPolicy, Segment};
     use crate::utils::BitStream;
 
     pub fn push_segment(seg: Segment, out: &mut BitStream) {
@@ -346,13 +667,17 @@ pub(super) mod writer {
             Mode::Alphanumeric => push_alphanumeric_data(seg.data, out),
             Mode::Byte => push_byte_data(seg.data, out),
             Mode::Kanji => todo!(),
-            Mode::Eci => unreachable!("Cannot push segment in ECI mode"),
+            Mode::Eci | Mode::StructuredAppend => push_byte_data(seg.data, out),
+            Mode::Fnc1First | Mode::Fnc1Second => unreachable!("Cannot push segment in FNC1 mode"),
             Mode::Terminator => unreachable!("Cannot push segment in terminator mode"),
         }
     }
 
     fn push_header(seg: &Segment, out: &mut BitStream) {
-        out.push_bits(seg.mode as u8, seg.mode_bits);
+        out.push_bits(seg.mode.indicator(seg.mode_bits), seg.mode_bits);
+        if matches!(seg.mode, Mode::Eci | Mode::StructuredAppend) {
+            return;
+        }
         let char_cnt = seg.data.len();
         debug_assert!(
             char_cnt < (1 << seg.len_bits),
@@ -386,28 +711,41 @@ pub(super) mod writer {
     }
 
     pub fn push_terminator(out: &mut BitStream) {
+        push_terminator_bits(out, 4);
+    }
+
+    // Terminator of the given length, cut short if the capacity runs out
+    pub fn push_terminator_bits(out: &mut BitStream, len: usize) {
         let bit_len = out.len();
         let bit_capacity = out.capacity();
         if bit_len < bit_capacity {
-            let term_len = std::cmp::min(4, bit_capacity - bit_len);
+            let term_len = std::cmp::min(len, bit_capacity - bit_len);
             out.push_bits(0, term_len);
         }
     }
 
-    pub fn pad_remaining_capacity(out: &mut BitStream) {
+    // Pads to a codeword boundary, then fills whole codewords with the padding. The 4 bit last
+    // codeword of M1 & M3 is left zero
+    pub fn pad_remaining_capacity(out: &mut BitStream, pad: PaddingPolicy) {
         push_padding_bits(out);
-        push_padding_codewords(out);
+        if out.len() & 7 == 0 {
+            push_padding_codewords(out, pad.codewords());
+        }
+        let rem = out.capacity() - out.len();
+        if rem > 0 {
+            out.push_bits(0, rem);
+        }
     }
 
     fn push_padding_bits(out: &mut BitStream) {
         let offset = out.len() & 7;
         if offset > 0 {
-            let padding_bits_len = 8 - offset;
+            let padding_bits_len = std::cmp::min(8 - offset, out.capacity() - out.len());
             out.push_bits(0, padding_bits_len);
         }
     }
 
-    fn push_padding_codewords(out: &mut BitStream) {
+    fn push_padding_codewords(out: &mut BitStream, cwds: &[u8]) {
         let offset = out.len() & 7;
         debug_assert!(
             offset == 0,
@@ -416,18 +754,19 @@ pub(super) mod writer {
         );
 
         let remain_byte_capacity = (out.capacity() - out.len()) >> 3;
-        PADDING_CODEWORDS.iter().copied().cycle().take(remain_byte_capacity).for_each(|pc| {
+        cwds.iter().copied().cycle().take(remain_byte_capacity).for_each(|pc| {
             out.push_bits(pc, 8);
         });
     }
 
     #[cfg(test)]
     mod writer_tests {
-        use super::{Mode, Segment, PADDING_CODEWORDS};
+        use super::{Mode, PaddingPolicy, Segment};
         use crate::codec::writer::{
-            push_alphanumeric_data, push_byte_data, push_header, push_numeric_data,
-            push_padding_bits, push_padding_codewords, push_terminator,
+            pad_remaining_capacity, push_alphanumeric_data, push_byte_data, push_header,
+            push_numeric_data, push_padding_bits, push_padding_codewords, push_terminator,
         };
+        use crate::codec::PADDING_CODEWORDS;
         use crate::metadata::{ECLevel, Version};
         use crate::utils::BitStream;
 
@@ -449,7 +788,7 @@ pub(super) mod writer {
             for ((mode, di), exp_vec) in modes.iter().zip(dummy_idx.iter()).zip(exp_vecs.iter()) {
                 let mut bs = BitStream::new(bit_capacity);
                 let len_bits = ver.char_cnt_bits(*mode);
-                let seg = Segment::new(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
+                let seg = Segment::with_bits(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
                 push_header(&seg, &mut bs);
                 assert_eq!(bs.data(), exp_vec);
             }
@@ -473,7 +812,7 @@ pub(super) mod writer {
             for ((mode, di), exp_vec) in modes.iter().zip(dummy_idx.iter()).zip(exp_vecs.iter()) {
                 let mut bs = BitStream::new(bit_capacity);
                 let len_bits = ver.char_cnt_bits(*mode);
-                let seg = Segment::new(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
+                let seg = Segment::with_bits(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
                 push_header(&seg, &mut bs);
                 assert_eq!(bs.data(), exp_vec);
             }
@@ -497,7 +836,7 @@ pub(super) mod writer {
             for ((mode, di), exp_vec) in modes.iter().zip(dummy_idx.iter()).zip(exp_vecs.iter()) {
                 let mut bs = BitStream::new(bit_capacity);
                 let len_bits = ver.char_cnt_bits(*mode);
-                let seg = Segment::new(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
+                let seg = Segment::with_bits(*mode, mode_bits, len_bits, &dummy_vec[..*di]);
                 push_header(&seg, &mut bs);
                 assert_eq!(bs.data(), exp_vec);
             }
@@ -580,10 +919,24 @@ pub(super) mod writer {
             let mut bs = BitStream::new(bit_capacity);
             bs.push_bits(1, 0b1);
             push_padding_bits(&mut bs);
-            push_padding_codewords(&mut bs);
+            push_padding_codewords(&mut bs, &PADDING_CODEWORDS);
             let mut output = vec![0b10000000];
             output.extend(PADDING_CODEWORDS.iter().cycle().take(18));
             assert_eq!(bs.data(), output);
         }
+
+        #[test]
+        fn test_pad_remaining_capacity_policies() {
+            let bit_capacity = Version::Normal(1).data_bit_capacity(ECLevel::H, false);
+            let pad = |p| {
+                let mut bs = BitStream::new(bit_capacity);
+                bs.push_bits(0b1, 1);
+                pad_remaining_capacity(&mut bs, p);
+                bs.data().to_vec()
+            };
+            assert_eq!(pad(PaddingPolicy::Zeros), [0b10000000, 0, 0, 0, 0, 0, 0, 0, 0]);
+            let exp = [0b10000000, 1, 2, 3, 1, 2, 3, 1, 2];
+            assert_eq!(pad(PaddingPolicy::Custom(&[1, 2, 3])), exp);
+        }
     }
 }
@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use super::{build_segments, compute_optimal_segments, Mode, Segment};
+use crate::{
+    metadata::{ECLevel, Version},
+    utils::{QRError, QRResult},
+};
+
+// Segmentation explainer
+//------------------------------------------------------------------------------
+
+// Bit cost of one segment picked by the optimizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentCost {
+    pub mode: Mode,
+    pub range: Range<usize>, // Bytes of the data covered
+    pub header_bits: usize,  // Mode indicator & char count
+    pub data_bits: usize,
+}
+
+impl SegmentCost {
+    pub fn total_bits(&self) -> usize {
+        self.header_bits + self.data_bits
+    }
+}
+
+// Why the data encodes to the size it does: the optimal segments with their costs, compared to
+// encoding all of the data in a single mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentExplanation {
+    pub version: Version,
+    pub segments: Vec<SegmentCost>,
+    pub total_bits: usize,
+    pub single_mode: Vec<(Mode, Option<usize>)>, // Bits in each mode, None if data doesn't fit it
+}
+
+impl SegmentExplanation {
+    // Bits saved over the cheapest single mode encoding
+    pub fn saved_bits(&self) -> usize {
+        let best = self.single_mode.iter().filter_map(|&(_, b)| b).min().unwrap_or(0);
+        best.saturating_sub(self.total_bits)
+    }
+
+    pub fn fits(&self, ecl: ECLevel) -> bool {
+        self.total_bits <= self.version.data_bit_capacity(ecl, false)
+    }
+}
+
+// Explains how the data is segmented at the version. Char count widths grow at versions 10 & 27,
+// so the optimal segments can differ between versions
+pub fn explain_segments(data: &[u8], ver: Version) -> QRResult<SegmentExplanation> {
+    if data.is_empty() {
+        return Err(QRError::EmptyData);
+    }
+    if !matches!(ver, Version::Normal(1..=40)) {
+        return Err(QRError::InvalidVersion);
+    }
+
+    let mut start = 0;
+    let segments = compute_optimal_segments(data, ver)
+        .iter()
+        .map(|s| {
+            let range = start..start + s.data.len();
+            start = range.end;
+            SegmentCost {
+                mode: s.mode,
+                range,
+                header_bits: s.bit_len() - data_bits(s),
+                data_bits: data_bits(s),
+            }
+        })
+        .collect::<Vec<_>>();
+    let total_bits = segments.iter().map(SegmentCost::total_bits).sum();
+
+    let single_mode = [Mode::Numeric, Mode::Alphanumeric, Mode::Byte]
+        .into_iter()
+        .map(|m| {
+            let bits = data.iter().all(|&b| m.contains(b)).then(|| {
+                build_segments(ver, vec![m; data.len()], data).iter().map(Segment::bit_len).sum()
+            });
+            (m, bits)
+        })
+        .collect();
+
+    Ok(SegmentExplanation { version: ver, segments, total_bits, single_mode })
+}
+
+fn data_bits(seg: &Segment) -> usize {
+    seg.mode.encoded_len(seg.data.len())
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::explain_segments;
+    use crate::{
+        codec::Mode,
+        metadata::{ECLevel, Version},
+        utils::QRError,
+    };
+
+    #[test]
+    fn test_explain_segments() {
+        let exp = explain_segments(b"ABCDEF0123456789012345abc", Version::Normal(1)).unwrap();
+        let modes = exp.segments.iter().map(|s| (s.mode, s.range.clone())).collect::<Vec<_>>();
+        assert_eq!(
+            modes,
+            [(Mode::Alphanumeric, 0..6), (Mode::Numeric, 6..22), (Mode::Byte, 22..25)]
+        );
+        assert_eq!(exp.segments[0].header_bits, 4 + 9);
+        assert_eq!(exp.segments[0].data_bits, 33);
+        assert_eq!(exp.segments[1].data_bits, 54);
+        assert_eq!(exp.total_bits, 46 + 68 + 36);
+
+        assert_eq!(exp.single_mode[0], (Mode::Numeric, None));
+        assert_eq!(exp.single_mode[1], (Mode::Alphanumeric, None));
+        assert_eq!(exp.single_mode[2], (Mode::Byte, Some(4 + 8 + 25 * 8)));
+        assert_eq!(exp.saved_bits(), 212 - 150);
+        assert!(exp.fits(ECLevel::L));
+        assert!(!exp.fits(ECLevel::H));
+
+        // Longer char counts from version 10 on
+        let exp = explain_segments(b"12345", Version::Normal(10)).unwrap();
+        assert_eq!(exp.segments[0].header_bits, 4 + 12);
+        assert_eq!(exp.saved_bits(), 0);
+
+        assert_eq!(explain_segments(b"", Version::Normal(1)), Err(QRError::EmptyData));
+        assert_eq!(explain_segments(b"1", Version::Normal(41)), Err(QRError::InvalidVersion));
+    }
+}
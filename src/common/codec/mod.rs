@@ -1,10 +1,14 @@
 pub mod decoder;
 pub mod encoder;
+pub mod explain;
 pub mod types;
+mod url;
 
 pub use decoder::*;
 pub use encoder::*;
+pub use explain::*;
 pub use types::*;
+pub(crate) use url::{fold_url_case, url_origin_len};
 
 // Codec proptesting
 //------------------------------------------------------------------------------
@@ -15,10 +19,10 @@ mod codec_proptests {
     use proptest::prelude::*;
 
     use super::{decode, encode_with_version};
-    use crate::metadata::{ECLevel, Version};
+    use crate::metadata::{ECLevel, Version, MAX_VERSION};
 
     pub fn version_strategy() -> impl Strategy<Value = Version> {
-        (1usize..=40).prop_map(Version::Normal)
+        (1usize..=MAX_VERSION).prop_map(Version::Normal)
     }
 
     pub fn ec_level_strategy() -> BoxedStrategy<ECLevel> {
@@ -52,7 +56,7 @@ mod codec_proptests {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(any(feature = "max-version-5", feature = "max-version-10"))))]
 mod codec_tests {
     use crate::ECLevel;
 
@@ -64,7 +68,7 @@ mod codec_tests {
         let ecl = ECLevel::H;
         let hi_cap = true;
 
-        let (mut enc, ver) = encode(data.as_bytes(), ecl, hi_cap).unwrap();
+        let (mut enc, ver, ecl) = encode(data.as_bytes(), ecl, hi_cap, false).unwrap();
 
         let decoded = decode(&mut enc, ver, ecl, hi_cap).unwrap();
 
@@ -1,5 +1,10 @@
 use std::cmp::Ordering;
 
+use crate::{
+    metadata::{StructuredAppend, MAX_STRUCTURED_APPEND},
+    utils::{QRError, QRResult},
+};
+
 // Mode
 //------------------------------------------------------------------------------
 
@@ -10,6 +15,9 @@ pub enum Mode {
     Byte = 0b0100,
     Kanji = 0b1000,
     Eci = 0b0111,
+    StructuredAppend = 0b0011,
+    Fnc1First = 0b0101,
+    Fnc1Second = 0b1001,
     Terminator = 0b0000,
 }
 
@@ -80,6 +88,10 @@ impl Mode {
             Self::Byte => mode_digit,
             Self::Kanji => todo!(),
             Self::Eci => unreachable!("ECI mode doesn't have characters"),
+            Self::StructuredAppend => {
+                unreachable!("Structured append mode doesn't have characters")
+            }
+            Self::Fnc1First | Self::Fnc1Second => unreachable!("FNC1 mode doesn't have characters"),
             Self::Terminator => unreachable!("Terminator mode doesn't have characters"),
         }
     }
@@ -101,6 +113,8 @@ impl Mode {
             }
             Self::Kanji => todo!(),
             Self::Eci => unreachable!("Cannot encode in ECI mode"),
+            Self::StructuredAppend => unreachable!("Cannot encode in structured append mode"),
+            Self::Fnc1First | Self::Fnc1Second => unreachable!("Cannot encode in FNC1 mode"),
             Self::Terminator => unreachable!("Cannot encode in terminator mode"),
         }
     }
@@ -116,6 +130,8 @@ impl Mode {
             }
             Self::Kanji => Self::decode_kanji_chunk(data),
             Self::Eci => unreachable!("Cannot decode in ECI mode"),
+            Self::StructuredAppend => unreachable!("Cannot decode in structured append mode"),
+            Self::Fnc1First | Self::Fnc1Second => unreachable!("Cannot decode in FNC1 mode"),
             Self::Terminator => unreachable!("Cannot decode in terminator mode"),
         }
     }
@@ -167,7 +183,38 @@ impl Mode {
             }
             Self::Byte => true,
             Self::Kanji => todo!(),
-            Self::Eci | Self::Terminator => false,
+            Self::Eci | Self::StructuredAppend => false,
+            Self::Fnc1First | Self::Fnc1Second | Self::Terminator => false,
+        }
+    }
+
+    // Checks that every byte can be encoded in the mode. The error cites the index of the first
+    // offending byte and the character starting there
+    pub fn validate(&self, data: &[u8]) -> QRResult<()> {
+        if !matches!(self, Self::Numeric | Self::Alphanumeric | Self::Byte) {
+            return Err(QRError::InvalidMode(*self as u8));
+        }
+
+        match data.iter().position(|b| !self.contains(*b)) {
+            Some(i) => {
+                let end = std::cmp::min(i + 4, data.len());
+                let ch =
+                    String::from_utf8_lossy(&data[i..end]).chars().next().unwrap_or('\u{FFFD}');
+                Err(QRError::InvalidModeChar(*self, i, ch))
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Bits written ahead of a segment. Micro QR numbers the modes it supports in fewer bits
+    pub fn indicator(&self, mode_bits: usize) -> u8 {
+        match (mode_bits, *self) {
+            (4, m) => m as u8,
+            (_, Self::Numeric | Self::Terminator) => 0,
+            (_, Self::Alphanumeric) => 1,
+            (_, Self::Byte) => 2,
+            (_, Self::Kanji) => 3,
+            (_, m) => unreachable!("Micro QR has no {m:?} mode"),
         }
     }
 
@@ -175,9 +222,10 @@ impl Mode {
         match *self {
             Self::Numeric => (len * 10).div_ceil(3),
             Self::Alphanumeric => (len * 11).div_ceil(2),
-            Self::Byte => len * 8,
+            Self::Byte | Self::Eci | Self::StructuredAppend => len * 8,
             Self::Kanji => (len / 2) * 13,
-            Self::Eci => len,
+            Self::Fnc1Second => len,
+            Self::Fnc1First => 0,
             Self::Terminator => unreachable!("Cannot encode in terminator mode"),
         }
     }
@@ -188,6 +236,7 @@ mod mode_tests {
 
     use super::Mode;
     use super::Mode::*;
+    use crate::utils::QRError;
 
     #[test]
     fn test_comparison() {
@@ -337,6 +386,22 @@ mod mode_tests {
         assert!(!Alphanumeric.contains(b'('));
     }
 
+    #[test]
+    fn test_validate() {
+        assert!(Numeric.validate(b"0123456789").is_ok());
+        assert!(Alphanumeric.validate(b"HELLO WORLD $%*+-./:").is_ok());
+        assert_eq!(Numeric.validate(b"12a4"), Err(QRError::InvalidModeChar(Numeric, 2, 'a')));
+        assert_eq!(
+            Alphanumeric.validate("AB€".as_bytes()),
+            Err(QRError::InvalidModeChar(Alphanumeric, 2, '€'))
+        );
+        assert_eq!(
+            Numeric.validate("1٣".as_bytes()),
+            Err(QRError::InvalidModeChar(Numeric, 1, '٣'))
+        );
+        assert_eq!(Kanji.validate(b"1"), Err(QRError::InvalidMode(Kanji as u8)));
+    }
+
     #[test]
     fn test_encoded_len() {
         assert_eq!(Numeric.encoded_len(3), 10);
@@ -345,35 +410,85 @@ mod mode_tests {
         assert_eq!(Alphanumeric.encoded_len(2), 11);
         assert_eq!(Alphanumeric.encoded_len(1), 6);
         assert_eq!(Byte.encoded_len(1), 8);
+        assert_eq!(Eci.encoded_len(2), 16);
+        assert_eq!(StructuredAppend.encoded_len(2), 16);
     }
 }
 
 // Segment
 //------------------------------------------------------------------------------
 
+// Data encoded in a single mode. Segments passed to the builder skip the mode optimizer, for
+// payloads whose structure is known ahead, & their bit lens are set once a version is picked
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct Segment<'a> {
+pub struct Segment<'a> {
     pub mode: Mode,
-    pub mode_bits: usize, // Bit len of mode
-    pub len_bits: usize,  // Bit len of char count
-    pub data: &'a [u8],   // Reference to raw data
+    pub(crate) mode_bits: usize, // Bit len of mode
+    pub(crate) len_bits: usize,  // Bit len of char count
+    pub data: &'a [u8],          // Reference to raw data
 }
 
 impl<'a> Segment<'a> {
-    pub fn new(mode: Mode, mode_bits: usize, len_bits: usize, data: &'a [u8]) -> Self {
+    pub fn new(mode: Mode, data: &'a [u8]) -> Self {
+        Self { mode, mode_bits: 0, len_bits: 0, data }
+    }
+
+    pub(crate) fn with_bits(mode: Mode, mode_bits: usize, len_bits: usize, data: &'a [u8]) -> Self {
         Self { mode, mode_bits, len_bits, data }
     }
 
-    pub fn bit_len(&self) -> usize {
+    pub(crate) fn bit_len(&self) -> usize {
         let encoded_bits = self.mode.encoded_len(self.data.len());
         self.mode_bits + self.len_bits + encoded_bits
     }
 }
 
+// Designator of an ECI segment as 1, 2 or 3 codewords, prefixed with 0, 10 & 110 respectively
+pub(crate) fn eci_codewords(designator: u32) -> QRResult<Vec<u8>> {
+    let d = designator;
+    match d {
+        0..=127 => Ok(vec![d as u8]),
+        128..=16383 => Ok(vec![0x80 | (d >> 8) as u8, d as u8]),
+        16384..=MAX_ECI_DESIGNATOR => Ok(vec![0xC0 | (d >> 16) as u8, (d >> 8) as u8, d as u8]),
+        _ => Err(QRError::InvalidEciDesignator(d)),
+    }
+}
+
+// Structured append header as 2 codewords, the symbol index & last index in 4 bits each
+// followed by the parity of the whole data
+pub(crate) fn structured_append_codewords(sa: StructuredAppend) -> [u8; 2] {
+    debug_assert!(
+        sa.index < sa.total && sa.total as usize <= MAX_STRUCTURED_APPEND,
+        "Invalid structured append {sa:?}"
+    );
+    [sa.index << 4 | (sa.total - 1), sa.parity]
+}
+
 #[cfg(test)]
 mod segment_tests {
-    use super::{Mode, Segment};
-    use crate::Version;
+    use super::{eci_codewords, structured_append_codewords, Mode, Segment};
+    use crate::{metadata::StructuredAppend, utils::QRError, Version};
+
+    #[test]
+    fn test_eci_codewords() {
+        assert_eq!(eci_codewords(3).unwrap(), [3]);
+        assert_eq!(eci_codewords(127).unwrap(), [0x7F]);
+        assert_eq!(eci_codewords(128).unwrap(), [0x80, 0x80]);
+        assert_eq!(eci_codewords(16383).unwrap(), [0xBF, 0xFF]);
+        assert_eq!(eci_codewords(999999).unwrap(), [0xCF, 0x42, 0x3F]);
+        assert_eq!(eci_codewords(1_000_000), Err(QRError::InvalidEciDesignator(1_000_000)));
+
+        let seg = Segment::with_bits(Mode::Eci, 4, 0, &[0x80, 0x80]);
+        assert_eq!(seg.bit_len(), 20);
+    }
+
+    #[test]
+    fn test_structured_append_codewords() {
+        let sa = StructuredAppend { index: 2, total: 4, parity: 0x5A };
+        assert_eq!(structured_append_codewords(sa), [0x23, 0x5A]);
+        let sa = StructuredAppend { index: 15, total: 16, parity: 0 };
+        assert_eq!(structured_append_codewords(sa), [0xFF, 0]);
+    }
 
     #[test]
     fn test_bit_len_numeric_mode_1() {
@@ -381,11 +496,11 @@ mod segment_tests {
         let mode = Mode::Numeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "123".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "123".as_bytes());
         assert_eq!(seg.bit_len(), 24);
-        let seg = Segment::new(mode, mode_bits, len_bits, "45".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "45".as_bytes());
         assert_eq!(seg.bit_len(), 21);
-        let seg = Segment::new(mode, mode_bits, len_bits, "6".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "6".as_bytes());
         assert_eq!(seg.bit_len(), 18);
     }
 
@@ -395,11 +510,11 @@ mod segment_tests {
         let mode = Mode::Numeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "123".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "123".as_bytes());
         assert_eq!(seg.bit_len(), 26);
-        let seg = Segment::new(mode, mode_bits, len_bits, "45".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "45".as_bytes());
         assert_eq!(seg.bit_len(), 23);
-        let seg = Segment::new(mode, mode_bits, len_bits, "6".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "6".as_bytes());
         assert_eq!(seg.bit_len(), 20);
     }
 
@@ -409,11 +524,11 @@ mod segment_tests {
         let mode = Mode::Numeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "123".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "123".as_bytes());
         assert_eq!(seg.bit_len(), 28);
-        let seg = Segment::new(mode, mode_bits, len_bits, "45".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "45".as_bytes());
         assert_eq!(seg.bit_len(), 25);
-        let seg = Segment::new(mode, mode_bits, len_bits, "6".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "6".as_bytes());
         assert_eq!(seg.bit_len(), 22);
     }
 
@@ -423,9 +538,9 @@ mod segment_tests {
         let mode = Mode::Alphanumeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "AZ".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "AZ".as_bytes());
         assert_eq!(seg.bit_len(), 24);
-        let seg = Segment::new(mode, mode_bits, len_bits, "-".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "-".as_bytes());
         assert_eq!(seg.bit_len(), 19);
     }
 
@@ -435,9 +550,9 @@ mod segment_tests {
         let mode = Mode::Alphanumeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "AZ".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "AZ".as_bytes());
         assert_eq!(seg.bit_len(), 26);
-        let seg = Segment::new(mode, mode_bits, len_bits, "-".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "-".as_bytes());
         assert_eq!(seg.bit_len(), 21);
     }
 
@@ -447,9 +562,9 @@ mod segment_tests {
         let mode = Mode::Alphanumeric;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "AZ".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "AZ".as_bytes());
         assert_eq!(seg.bit_len(), 28);
-        let seg = Segment::new(mode, mode_bits, len_bits, "-".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "-".as_bytes());
         assert_eq!(seg.bit_len(), 23);
     }
 
@@ -459,7 +574,7 @@ mod segment_tests {
         let mode = Mode::Byte;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "a".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "a".as_bytes());
         assert_eq!(seg.bit_len(), 20);
     }
 
@@ -469,7 +584,7 @@ mod segment_tests {
         let mode = Mode::Byte;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "ab".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "ab".as_bytes());
         assert_eq!(seg.bit_len(), 36);
     }
 
@@ -479,14 +594,39 @@ mod segment_tests {
         let mode = Mode::Byte;
         let mode_bits = ver.mode_bits();
         let len_bits = ver.char_cnt_bits(mode);
-        let seg = Segment::new(mode, mode_bits, len_bits, "abc".as_bytes());
+        let seg = Segment::with_bits(mode, mode_bits, len_bits, "abc".as_bytes());
         assert_eq!(seg.bit_len(), 44);
     }
 }
 
+// Padding policy
+//------------------------------------------------------------------------------
+
+// Content filling the data capacity left after the terminator. Decoders stop at the terminator,
+// so every policy reads back the same
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingPolicy<'a> {
+    #[default]
+    Standard, // Alternating 0xEC & 0x11 codewords as per the spec
+    Zeros,
+    Custom(&'a [u8]), // Repeated to fill the capacity
+}
+
+impl PaddingPolicy<'_> {
+    pub(crate) fn codewords(&self) -> &[u8] {
+        match self {
+            Self::Standard => &PADDING_CODEWORDS,
+            Self::Zeros => &[0],
+            Self::Custom(cwds) => cwds,
+        }
+    }
+}
+
 // Global constants
 //------------------------------------------------------------------------------
 
 pub static PADDING_CODEWORDS: [u8; 2] = [0b1110_1100, 0b0001_0001];
 
 pub static MODES: [Mode; 3] = [Mode::Numeric, Mode::Alphanumeric, Mode::Byte];
+
+pub(crate) const MAX_ECI_DESIGNATOR: u32 = 999999;
@@ -0,0 +1,49 @@
+// Url case folding
+//------------------------------------------------------------------------------
+
+// Length of the scheme & host of an http(s) url. These are case insensitive, unlike the path,
+// query & fragment that follow
+pub(crate) fn url_origin_len(payload: &[u8]) -> Option<usize> {
+    let host_start = URL_SCHEMES
+        .iter()
+        .find(|s| payload.get(..s.len()).is_some_and(|p| p.eq_ignore_ascii_case(s.as_bytes())))?
+        .len();
+    let host_end = payload[host_start..]
+        .iter()
+        .position(|b| matches!(b, b'/' | b'?' | b'#'))
+        .map_or(payload.len(), |i| i + host_start);
+    Some(host_end)
+}
+
+// Uppercases the scheme & host of a url so they fit alphanumeric mode. Returns none if the payload
+// isn't a url, has user info which is case sensitive, or is already folded
+pub(crate) fn fold_url_case(payload: &[u8]) -> Option<Vec<u8>> {
+    let origin_len = url_origin_len(payload)?;
+    let origin = &payload[..origin_len];
+    if origin.contains(&b'@') || !origin.iter().any(u8::is_ascii_lowercase) {
+        return None;
+    }
+
+    let mut folded = payload.to_vec();
+    folded[..origin_len].make_ascii_uppercase();
+    Some(folded)
+}
+
+#[cfg(test)]
+mod url_tests {
+    use super::fold_url_case;
+
+    #[test]
+    fn test_fold_url_case() {
+        let folded = fold_url_case(b"https://Example.com/Path?q=a").unwrap();
+        assert_eq!(folded, b"HTTPS://EXAMPLE.COM/Path?q=a");
+        assert_eq!(fold_url_case(b"HTTP://EXAMPLE.COM/abc"), None);
+        assert_eq!(fold_url_case(b"https://user@example.com"), None);
+        assert_eq!(fold_url_case(b"mailto:a@example.com"), None);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const URL_SCHEMES: [&str; 2] = ["http://", "https://"];
@@ -39,6 +39,116 @@ impl Block {
         }
     }
 
+    // Rectifies the block & returns the number of codewords that were corrected
+    pub fn rectify_and_count(&mut self) -> QRResult<usize> {
+        let raw = self.data;
+        self.rectify()?;
+        Ok(raw[..self.len].iter().zip(self.full()).filter(|(a, b)| a != b).count())
+    }
+
+    // Rectifies errors & erasures, i.e. codewords at known positions which are unreliable. An
+    // erasure costs half of an unknown error, so up to ec_len erasures can be recovered
+    pub fn rectify_with_erasures(&mut self, erasures: &[usize]) -> QRResult<&[u8]> {
+        if erasures.is_empty() {
+            return self.rectify();
+        }
+
+        let ec_len = self.len - self.dlen;
+        let rho = erasures.len();
+        if rho > ec_len {
+            return Err(QRError::TooManyError);
+        }
+
+        let synd = match self.syndromes() {
+            Ok(()) => return Ok(self.data()),
+            Err(s) => s,
+        };
+
+        // Erasure locator polynomial
+        let mut gamma = [G(0); MAX_EC_SIZE];
+        gamma[0] = G(1);
+        for (n, &pos) in erasures.iter().enumerate() {
+            debug_assert!(pos < self.len, "Erasure {pos} is outside block of len {}", self.len);
+            let x = G::gen_pow(self.len - 1 - pos);
+            for i in (1..=n + 1).rev() {
+                let prev = gamma[i - 1];
+                gamma[i] += prev * x;
+            }
+        }
+
+        // Errors & erasures locator polynomial, by Berlekamp-Massey seeded with erasure locator
+        let mut lam = gamma;
+        let mut bx = gamma;
+        let mut l = rho;
+        for r in rho..ec_len {
+            let mut d = G(0);
+            for j in 0..=r {
+                d += lam[j] * synd[r - j];
+            }
+
+            bx.copy_within(..MAX_EC_SIZE - 1, 1);
+            bx[0] = G(0);
+            if d.0 == 0 {
+                continue;
+            }
+
+            let prev = lam;
+            for (li, &bi) in lam.iter_mut().zip(bx.iter()) {
+                *li += d * bi;
+            }
+            if 2 * l <= r + rho {
+                l = r + 1 + rho - l;
+                let dinv = G(1).div(d)?;
+                bx = prev.map(|c| c * dinv);
+            }
+        }
+
+        // Error locations
+        let mut locs = Vec::with_capacity(l);
+        for k in 0..self.len {
+            let xinv = G::gen_pow(255 - (self.len - 1 - k));
+            if eval_poly(lam.iter().take(l + 1), xinv).0 == 0 {
+                locs.push(k);
+            }
+        }
+        if locs.len() != l {
+            return Err(QRError::TooManyError);
+        }
+
+        // Error evaluator and sigma derivative
+        let mut omg = [G(0); MAX_EC_SIZE];
+        for i in 0..ec_len {
+            for j in 0..=i {
+                omg[i] += synd[j] * lam[i - j];
+            }
+        }
+        let mut dlam = [G(0); MAX_EC_SIZE];
+        for i in (1..MAX_EC_SIZE).step_by(2) {
+            dlam[i - 1] = lam[i];
+        }
+
+        // Error magnitudes by Forney
+        for k in locs {
+            let p = self.len - 1 - k;
+            let xinv = G::gen_pow(255 - p);
+            let num = G::gen_pow(p) * eval_poly(omg.iter().take(ec_len), xinv);
+            let mag = num.div(eval_poly(dlam.iter(), xinv))?;
+            self.data[k] = (G(self.data[k]) + mag).into();
+        }
+
+        match self.syndromes() {
+            Ok(()) => Ok(self.data()),
+            Err(_) => Err(QRError::TooManyError),
+        }
+    }
+
+    // Rectifies the block with erasures & returns the number of codewords that were corrected
+    pub fn rectify_and_count_with_erasures(&mut self, erasures: &[usize]) -> QRResult<usize> {
+        let raw = self.data;
+        self.rectify_with_erasures(erasures)?;
+        Ok(raw[..self.len].iter().zip(self.full()).filter(|(a, b)| a != b).count())
+    }
+
     fn syndromes(&self) -> Result<(), [G; MAX_EC_SIZE]> {
         let ec_len = self.len - self.dlen;
         let mut synd = [G(0); MAX_EC_SIZE];
@@ -180,6 +290,50 @@ mod ec_rectifier_tests {
     }
 }
 
+#[cfg(test)]
+mod ec_erasure_tests {
+    use super::Block;
+
+    fn corrupted(erasures: &[usize], errors: &[usize]) -> (Vec<u8>, Block) {
+        let data = (0..19).map(|i| (i * 53 + 7) as u8).collect::<Vec<_>>();
+        let mut blk = Block::new(&data, 26);
+        for &i in erasures.iter().chain(errors) {
+            blk.full_mut()[i] ^= 0x5A ^ i as u8;
+        }
+        (data, blk)
+    }
+
+    #[test]
+    fn test_erasures_only() {
+        // 7 erasures exceed the 3 errors correctable without position info
+        let erasures = [0, 3, 8, 11, 19, 22, 25];
+        let (data, mut blk) = corrupted(&erasures, &[]);
+        assert!(blk.clone().rectify().is_err());
+        assert_eq!(blk.rectify_with_erasures(&erasures).unwrap(), data);
+    }
+
+    #[test]
+    fn test_erasures_and_errors() {
+        let erasures = [1, 5, 20];
+        let errors = [9, 14];
+        let (data, mut blk) = corrupted(&erasures, &errors);
+        assert_eq!(blk.rectify_and_count_with_erasures(&erasures).unwrap(), 5);
+        assert_eq!(blk.data(), data);
+
+        // Erased positions which are actually correct are fine too
+        let (data, mut blk) = corrupted(&[], &errors);
+        assert_eq!(blk.rectify_with_erasures(&erasures).unwrap(), data);
+    }
+
+    #[test]
+    fn test_too_many_erasures_and_errors() {
+        let erasures = [1, 5, 20, 21, 22];
+        let errors = [9, 14];
+        let (data, mut blk) = corrupted(&erasures, &errors);
+        assert_ne!(blk.rectify_with_erasures(&erasures).ok(), Some(&data[..]));
+    }
+}
+
 // Rectifier for format and version infos
 pub fn rectify_info(info: u32, valid_numbers: &[u32], err_capacity: u32) -> QRResult<u32> {
     let res = *valid_numbers.iter().min_by_key(|&n| (info ^ n).count_ones()).unwrap();
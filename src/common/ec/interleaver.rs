@@ -0,0 +1,169 @@
+use super::Block;
+use crate::{
+    metadata::{BlockLayout, ECLevel, Version, MAX_BLOCK_COUNT},
+    utils::BitStream,
+};
+
+// Interleaver
+//------------------------------------------------------------------------------
+
+// Maps between the codewords of a channel as laid out in the symbol & its ec blocks. The builder
+// & reader share it, so codewords are always placed & read back in the same order
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Interleaver {
+    layout: BlockLayout,
+}
+
+impl Interleaver {
+    pub fn new(layout: BlockLayout) -> Self {
+        Self { layout }
+    }
+
+    // Codewords of a channel, i.e. data & ec of all blocks
+    pub fn len(&self) -> usize {
+        self.layout.data_len() + self.layout.ec_total()
+    }
+
+    // Block & position within the block of an interleaved codeword
+    pub fn block_index(&self, i: usize) -> (usize, usize) {
+        self.layout.deinterleaved_index(i)
+    }
+
+    // Position in the interleaved codewords of a codeword of a block, the inverse of block_index
+    pub fn interleaved_index(&self, b: usize, pos: usize) -> usize {
+        let [g1, g2] = self.layout.groups;
+        let total_blks = self.layout.block_count();
+        let data_sz = self.layout.data_len();
+        let dlen = if b < g1.count { g1.data_len } else { g2.data_len };
+
+        if pos >= dlen {
+            data_sz + (pos - dlen) * total_blks + b
+        } else if pos < g1.data_len {
+            pos * total_blks + b
+        } else {
+            g1.data_len * total_blks + (pos - g1.data_len) * g2.count + b - g1.count
+        }
+    }
+
+    // Splits the data codewords of a channel into blocks, which compute their ecc
+    pub fn blockify(&self, data: &[u8]) -> Vec<Block> {
+        debug_assert!(
+            self.layout.data_len() == data.len(),
+            "Data len doesn't match total size of blocks: Data len {}, Total block size {}",
+            data.len(),
+            self.layout.data_len()
+        );
+
+        let mut blks = Vec::with_capacity(self.layout.block_count());
+        let mut rest = data;
+        for dlen in self.layout.block_data_lens() {
+            let (d, tail) = rest.split_at(dlen);
+            blks.push(Block::new(d, dlen + self.layout.ec_len));
+            rest = tail;
+        }
+        blks
+    }
+
+    pub fn interleave_into(&self, blks: &[Block], out: &mut BitStream) {
+        debug_assert_eq!(blks.len(), self.layout.block_count(), "Block count mismatch");
+
+        for i in 0..self.len() {
+            let (b, pos) = self.block_index(i);
+            out.push_byte(blks[b].full()[pos]);
+        }
+    }
+
+    // Splits interleaved codewords of a channel into the block buffer & returns the used blocks
+    pub fn deinterleave_into<'a>(
+        &self,
+        data: &[u8],
+        out: &'a mut [Block; MAX_BLOCK_COUNT],
+    ) -> &'a mut [Block] {
+        let blocks = &mut out[..self.layout.block_count()];
+        for (b, dlen) in blocks.iter_mut().zip(self.layout.block_data_lens()) {
+            b.dlen = dlen;
+            b.len = dlen + self.layout.ec_len;
+        }
+        for (i, &cw) in data.iter().take(self.len()).enumerate() {
+            let (b, pos) = self.block_index(i);
+            blocks[b].data[pos] = cw;
+        }
+        blocks
+    }
+}
+
+// Splits the encoded data of each channel into blocks & interleaves their data & ecc
+// codewords into the payload drawn on the symbol
+pub(crate) fn add_ecc(enc: &[u8], ver: Version, ecl: ECLevel, hi_cap: bool) -> BitStream {
+    let mut pld = BitStream::new(ver.total_codewords(hi_cap) << 3);
+    let chan_data_cap = ver.channel_data_capacity(ecl);
+
+    debug_assert!(
+        enc.len().is_multiple_of(chan_data_cap),
+        "Encoded data length {} is not divisible by channel codewords {chan_data_cap}",
+        enc.len()
+    );
+
+    let ilvr = Interleaver::new(ver.block_layout(ecl));
+    enc.chunks_exact(chan_data_cap).for_each(|c| {
+        // Splits the data into EC block. The blocks will auto compute ecc
+        let blks = ilvr.blockify(c);
+
+        // Interleave data & error correction codewords, and write into payload
+        ilvr.interleave_into(&blks, &mut pld);
+    });
+    pld
+}
+
+#[cfg(test)]
+mod interleaver_tests {
+    use super::Interleaver;
+    use crate::{
+        ec::Block,
+        metadata::{BlockGroup, BlockLayout, ECLevel, Version, MAX_BLOCK_COUNT, MAX_VERSION},
+        utils::BitStream,
+    };
+
+    #[test]
+    fn test_interleave() {
+        let layout = BlockLayout {
+            groups: [BlockGroup { count: 2, data_len: 3 }, BlockGroup { count: 1, data_len: 4 }],
+            ec_len: 2,
+        };
+        let ilvr = Interleaver::new(layout);
+        let blks = ilvr.blockify(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+        let mut ilvd = BitStream::new(256);
+        ilvr.interleave_into(&blks, &mut ilvd);
+        assert_eq!(ilvd.data()[..10], [1, 4, 7, 2, 5, 8, 3, 6, 9, 0]);
+        let exp_ecc: Vec<_> = (0..6).map(|k| blks[k % 3].ecc()[k / 3]).collect();
+        assert_eq!(ilvd.data()[10..], exp_ecc);
+    }
+
+    #[test]
+    fn test_interleave_round_trip() {
+        let vers = (1..=4).map(Version::Micro).chain((1..=MAX_VERSION).map(Version::Normal));
+        for ver in vers {
+            for ecl in [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H] {
+                let layout = ver.block_layout(ecl);
+                if layout.data_len() == 0 {
+                    continue;
+                }
+                let ilvr = Interleaver::new(layout);
+                assert_eq!(ilvr.len(), ver.channel_codewords(), "{ver:?} {ecl:?}");
+
+                for i in 0..ilvr.len() {
+                    let (b, pos) = ilvr.block_index(i);
+                    assert_eq!(ilvr.interleaved_index(b, pos), i, "{ver:?} {ecl:?} at {i}");
+                }
+
+                let data: Vec<u8> = (0..layout.data_len()).map(|i| (i * 7) as u8).collect();
+                let blks = ilvr.blockify(&data);
+                let mut ilvd = BitStream::new(ilvr.len() << 3);
+                ilvr.interleave_into(&blks, &mut ilvd);
+
+                let mut buf = [Block::with_encoded(&[], 0); MAX_BLOCK_COUNT];
+                assert_eq!(ilvr.deinterleave_into(ilvd.data(), &mut buf), blks);
+            }
+        }
+    }
+}
@@ -3,9 +3,91 @@ mod encoder;
 
 mod block;
 mod galois;
+mod interleaver;
 
 pub(crate) use block::*;
 pub(crate) use decoder::*;
+pub(crate) use interleaver::{add_ecc, Interleaver};
+
+use crate::utils::{QRError, QRResult};
+
+// Standalone block codec
+//------------------------------------------------------------------------------
+
+// Reed-Solomon code of QR, i.e. GF(256) over x^8 + x^4 + x^3 + x^2 + 1 with generator 2, for
+// use outside of symbols such as custom container formats. Returns the data followed by ec_len
+// ec codewords. Blocks take 1 to 63 ec codewords & up to 255 codewords in all
+pub fn encode_block(data: &[u8], ec_len: usize) -> QRResult<Vec<u8>> {
+    let len = data.len() + ec_len;
+    validate_block_len(len, ec_len)?;
+    Ok(Block::new(data, len).full().to_vec())
+}
+
+// Corrects a block from encode_block in place & returns the number of codewords corrected.
+// Erasures are indices of codewords known to be unreliable, which cost half of an unknown error,
+// so up to 2 * errors + erasures <= ec_len can be corrected
+pub fn correct_block(codewords: &mut [u8], ec_len: usize, erasures: &[usize]) -> QRResult<usize> {
+    let len = codewords.len();
+    validate_block_len(len, ec_len)?;
+    if let Some(&e) = erasures.iter().find(|&&e| e >= len) {
+        return Err(QRError::ErasureOutOfBounds(e));
+    }
+
+    let mut erasures = erasures.to_vec();
+    erasures.sort_unstable();
+    erasures.dedup();
+    let mut blk = Block::with_encoded(codewords, len - ec_len);
+    let corrected = blk.rectify_and_count_with_erasures(&erasures)?;
+    codewords.copy_from_slice(blk.full());
+    Ok(corrected)
+}
+
+fn validate_block_len(len: usize, ec_len: usize) -> QRResult<()> {
+    if !(1..MAX_EC_SIZE).contains(&ec_len) || !(ec_len..MAX_BLOCK_SIZE).contains(&len) {
+        return Err(QRError::InvalidBlockLength(len, ec_len));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod block_codec_tests {
+    use super::{correct_block, encode_block};
+    use crate::utils::QRError;
+
+    #[test]
+    fn test_encode_block() {
+        let data = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
+        let blk = encode_block(data, 10).unwrap();
+        assert_eq!(blk[..data.len()], data[..]);
+        assert_eq!(blk[data.len()..], b"\xc4\x23\x27\x77\xeb\xd7\xe7\xe2\x5d\x17"[..]);
+
+        assert_eq!(encode_block(data, 0), Err(QRError::InvalidBlockLength(16, 0)));
+        assert_eq!(encode_block(data, 64), Err(QRError::InvalidBlockLength(80, 64)));
+        assert_eq!(encode_block(&[0; 250], 6), Err(QRError::InvalidBlockLength(256, 6)));
+    }
+
+    #[test]
+    fn test_correct_block() {
+        let exp = encode_block(b"Standalone block", 8).unwrap();
+        let mut blk = exp.clone();
+        blk[1] ^= 0xFF;
+        blk[20] ^= 0x0F;
+        assert_eq!(correct_block(&mut blk, 8, &[]), Ok(2));
+        assert_eq!(blk, exp);
+
+        // 6 erasures & 1 error fit in 8 ec codewords, where the same 7 corrupted codewords as errors
+        // alone don't
+        for i in [0, 2, 4, 6, 8, 10, 12] {
+            blk[i] ^= 0x55;
+        }
+        assert_eq!(correct_block(&mut blk.clone(), 8, &[]), Err(QRError::TooManyError));
+        assert_eq!(correct_block(&mut blk, 8, &[0, 2, 4, 6, 8, 10]), Ok(7));
+        assert_eq!(blk, exp);
+
+        assert_eq!(correct_block(&mut blk, 8, &[24]), Err(QRError::ErasureOutOfBounds(24)));
+        assert_eq!(correct_block(&mut blk[..4], 8, &[]), Err(QRError::InvalidBlockLength(4, 8)));
+    }
+}
 
 // Global constants
 //------------------------------------------------------------------------------
@@ -21,6 +103,7 @@ pub const MAX_EC_SIZE: usize = 64;
 mod error_correction_proptests {
 
     use proptest::prelude::*;
+    use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
 
     use super::Block;
 
@@ -34,13 +117,12 @@ mod error_correction_proptests {
 
     proptest! {
         #[test]
-        fn proptest_ec((data, ec_len) in block_strategy()) {
+        fn proptest_ec((data, ec_len) in block_strategy(), seed in any::<u64>()) {
             let len = data.len() + ec_len;
             let mut blk = Block::new(&data, len);
 
-            use rand::{seq::IteratorRandom, rng};
             let t = ec_len / 2;
-            let mut rng = rng();
+            let mut rng = StdRng::seed_from_u64(seed);
             let corrupt_indices = (0..len).choose_multiple(&mut rng, t);
 
             for i in corrupt_indices {
@@ -51,5 +133,29 @@ mod error_correction_proptests {
             prop_assert!(rectified.is_ok());
             prop_assert_eq!(rectified.unwrap(), data);
         }
+
+        #[test]
+        fn proptest_ec_erasures(
+            (data, ec_len) in block_strategy(),
+            split in 0usize..=100,
+            seed in any::<u64>(),
+        ) {
+            let len = data.len() + ec_len;
+            let mut blk = Block::new(&data, len);
+
+            // Any mix of erasures & errors within 2 * errors + erasures <= ec_len
+            let erasure_cnt = ec_len * split / 100;
+            let error_cnt = (ec_len - erasure_cnt) / 2;
+            let mut rng = StdRng::seed_from_u64(seed);
+            let indices = (0..len).choose_multiple(&mut rng, erasure_cnt + error_cnt);
+
+            for &i in indices.iter() {
+                blk.full_mut()[i] ^= 0xFF;
+            }
+
+            let rectified = blk.rectify_with_erasures(&indices[..erasure_cnt]);
+            prop_assert!(rectified.is_ok());
+            prop_assert_eq!(rectified.unwrap(), data);
+        }
     }
 }
@@ -1,7 +1,7 @@
 use std::ops::Deref;
 
 use super::metadata::{Color, Version};
-use crate::builder::QR;
+use crate::builder::{Module, QR};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord)]
 pub struct MaskPattern(u8);
@@ -13,6 +13,13 @@ impl MaskPattern {
     }
 }
 
+impl MaskPattern {
+    // Number of the pattern in micro QR format info. Micro QR only uses patterns 1, 4, 6 & 7
+    pub fn micro_number(self) -> Option<u8> {
+        MICRO_MASK_PATTERNS.iter().position(|&p| p == self.0).map(|i| i as u8)
+    }
+}
+
 impl Deref for MaskPattern {
     type Target = u8;
     fn deref(&self) -> &Self::Target {
@@ -73,7 +80,13 @@ impl MaskPattern {
 }
 
 pub fn apply_best_mask(qr: &mut QR) -> MaskPattern {
-    let best_mask = (0..8)
+    let patterns = match qr.version() {
+        Version::Micro(_) => &MICRO_MASK_PATTERNS[..],
+        Version::Normal(_) => &NORMAL_MASK_PATTERNS[..],
+    };
+    let best_mask = patterns
+        .iter()
+        .copied()
         .min_by_key(|m| {
             let mut qr = qr.clone();
             qr.apply_mask(MaskPattern(*m));
@@ -87,19 +100,57 @@ pub fn apply_best_mask(qr: &mut QR) -> MaskPattern {
 
 pub fn compute_total_penalty(qr: &QR) -> u32 {
     match qr.version() {
-        Version::Micro(_) => todo!(),
-        Version::Normal(_) => {
-            let adj_pen = compute_adjacent_penalty(qr);
-            let blk_pen = compute_block_penalty(qr);
-            let fp_pen_h = compute_finder_pattern_penalty(qr, true);
-            let fp_pen_v = compute_finder_pattern_penalty(qr, false);
-            let bal_pen = compute_balance_penalty(qr);
-            adj_pen + blk_pen + fp_pen_h + fp_pen_v + bal_pen
-        }
+        Version::Micro(_) => compute_micro_penalty(qr),
+        Version::Normal(_) => compute_normal_penalty(qr),
+    }
+}
+
+// Micro QR scores masks by the dark modules along the right & bottom edges, which outline the
+// symbol in place of the missing finders. The sparser edge weighs most, and the score is negated
+// into a penalty so lower is better as for normal QR
+fn compute_micro_penalty(qr: &QR) -> u32 {
+    let w = qr.width() as i32;
+    let is_dark = |x, y| *qr.get(x, y) == Color::Black;
+    let right = (1..w).filter(|&y| is_dark(w - 1, y)).count() as u32;
+    let bottom = (1..w).filter(|&x| is_dark(x, w - 1)).count() as u32;
+    let score = right.min(bottom) * 16 + right.max(bottom);
+    w as u32 * 17 - score
+}
+
+// Penalty of a normal QR grid, whether it is the full size QR or a small stack grid
+pub(crate) fn compute_normal_penalty<G: ModuleGrid>(qr: &G) -> u32 {
+    let adj_pen = compute_adjacent_penalty(qr);
+    let blk_pen = compute_block_penalty(qr);
+    let fp_pen_h = compute_finder_pattern_penalty(qr, true);
+    let fp_pen_v = compute_finder_pattern_penalty(qr, false);
+    let bal_pen = compute_balance_penalty(qr);
+    adj_pen + blk_pen + fp_pen_h + fp_pen_v + bal_pen
+}
+
+// Read access to modules shared by the full size QR & the small version grids
+pub(crate) trait ModuleGrid {
+    fn width(&self) -> usize;
+
+    fn get(&self, x: i32, y: i32) -> Module;
+
+    fn count_dark_modules(&self) -> usize;
+}
+
+impl ModuleGrid for QR {
+    fn width(&self) -> usize {
+        QR::width(self)
+    }
+
+    fn get(&self, x: i32, y: i32) -> Module {
+        QR::get(self, x, y)
+    }
+
+    fn count_dark_modules(&self) -> usize {
+        QR::count_dark_modules(self)
     }
 }
 
-fn compute_adjacent_penalty(qr: &QR) -> u32 {
+fn compute_adjacent_penalty<G: ModuleGrid>(qr: &G) -> u32 {
     let mut pen = 0;
     let w = qr.width();
     let mut cols = vec![(Color::Black, 0); w];
@@ -129,7 +180,7 @@ fn compute_adjacent_penalty(qr: &QR) -> u32 {
     pen
 }
 
-fn compute_block_penalty(qr: &QR) -> u32 {
+fn compute_block_penalty<G: ModuleGrid>(qr: &G) -> u32 {
     let mut pen = 0;
     let w = qr.width() as i32;
     for r in 0..w - 1 {
@@ -144,7 +195,7 @@ fn compute_block_penalty(qr: &QR) -> u32 {
     pen
 }
 
-fn compute_finder_pattern_penalty(qr: &QR, is_hor: bool) -> u32 {
+fn compute_finder_pattern_penalty<G: ModuleGrid>(qr: &G, is_hor: bool) -> u32 {
     let mut pen = 0;
     let w = qr.width() as i32;
     static PATTERN: [Color; 7] = [
@@ -158,9 +209,8 @@ fn compute_finder_pattern_penalty(qr: &QR, is_hor: bool) -> u32 {
     ];
     for i in 0..w {
         for j in 0..w - 6 {
-            let get: Box<dyn Fn(i32) -> Color> =
-                if is_hor { Box::new(|c| *qr.get(i, c)) } else { Box::new(|r| *qr.get(r, i)) };
-            if !(j..j + 7).map(&*get).ne(PATTERN.iter().copied()) {
+            let get = |k| if is_hor { *qr.get(i, k) } else { *qr.get(k, i) };
+            if !(j..j + 7).map(get).ne(PATTERN.iter().copied()) {
                 let match_qz = |x| x >= 0 && x < w && get(x) == Color::Black;
                 if (j - 4..j).any(&match_qz) || (j + 7..j + 11).any(&match_qz) {
                     pen += 40;
@@ -171,7 +221,7 @@ fn compute_finder_pattern_penalty(qr: &QR, is_hor: bool) -> u32 {
     pen
 }
 
-fn compute_balance_penalty(qr: &QR) -> u32 {
+fn compute_balance_penalty<G: ModuleGrid>(qr: &G) -> u32 {
     let dark_cnt = qr.count_dark_modules();
     let w = qr.width();
     let tot = w * w;
@@ -184,3 +234,11 @@ fn compute_balance_penalty(qr: &QR) -> u32 {
 }
 
 // TODO: Write test cases
+
+// Global constants
+//------------------------------------------------------------------------------
+
+static NORMAL_MASK_PATTERNS: [u8; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+
+// Patterns micro QR numbers 0 to 3
+static MICRO_MASK_PATTERNS: [u8; 4] = [1, 4, 6, 7];
@@ -1,25 +1,264 @@
 use core::panic;
 use std::cmp::PartialOrd;
 use std::fmt::{Debug, Display};
-use std::ops::{Deref, Not};
+use std::ops::{Deref, Not, Range};
 
 use image::{Luma, Rgb};
 
-use super::{codec::Mode, mask::MaskPattern};
+use super::{
+    codec::Mode,
+    mask::MaskPattern,
+    tables::{alignment_positions, block_count, channel_codewords, version_tables},
+};
 
 // Metadata
 //------------------------------------------------------------------------------
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Metadata {
     ver: Option<Version>,
     ecl: Option<ECLevel>,
     mask: Option<MaskPattern>,
+    ec_usage: Option<ECUsage>, // Only available for decoded symbols
+    symbology: Option<SymbologyId>,
+    fingerprint: Option<Fingerprint>,
+    segments: Vec<SegmentInfo>,
+    parity: Option<u8>,      // Only available for decoded symbols
+    raw_format: Option<u16>, // Only available for decoded symbols
 }
 
 impl Metadata {
     pub fn new(ver: Option<Version>, ecl: Option<ECLevel>, mask: Option<MaskPattern>) -> Self {
-        Self { ver, ecl, mask }
+        Self {
+            ver,
+            ecl,
+            mask,
+            ec_usage: None,
+            symbology: None,
+            fingerprint: None,
+            segments: Vec::new(),
+            parity: None,
+            raw_format: None,
+        }
+    }
+
+    pub fn with_ec_usage(mut self, ec_usage: ECUsage) -> Self {
+        self.ec_usage = Some(ec_usage);
+        self
+    }
+
+    pub fn with_symbology(mut self, symbology: SymbologyId) -> Self {
+        self.symbology = Some(symbology);
+        self
+    }
+
+    pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    pub fn with_segments(mut self, segments: Vec<SegmentInfo>) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    pub fn with_parity(mut self, parity: u8) -> Self {
+        self.parity = Some(parity);
+        self
+    }
+
+    pub fn with_raw_format_bits(mut self, bits: u16) -> Self {
+        self.raw_format = Some(bits);
+        self
+    }
+
+    pub fn version(&self) -> Option<Version> {
+        self.ver
+    }
+
+    pub fn ec_level(&self) -> Option<ECLevel> {
+        self.ecl
+    }
+
+    pub fn mask(&self) -> Option<MaskPattern> {
+        self.mask
+    }
+
+    pub fn ec_usage(&self) -> Option<ECUsage> {
+        self.ec_usage
+    }
+
+    pub fn symbology(&self) -> Option<SymbologyId> {
+        self.symbology
+    }
+
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        self.fingerprint
+    }
+
+    // ECI designator the payload was read in, e.g. 3 for iso 8859-1 or 20 for shift jis
+    pub fn eci(&self) -> Option<u32> {
+        self.symbology.and_then(|s| s.eci)
+    }
+
+    // Position of the symbol in a structured append sequence & the parity of the whole data
+    pub fn structured_append(&self) -> Option<StructuredAppend> {
+        self.symbology.and_then(|s| s.structured_append)
+    }
+
+    // Xor of the data bytes as encoded, before charset decoding. Matches the parity in the
+    // structured append header if the symbol is the whole of a sequence
+    pub fn parity(&self) -> Option<u8> {
+        self.parity
+    }
+
+    // Segments the payload was encoded in, as chosen by the builder or read by the decoder
+    pub fn segments(&self) -> &[SegmentInfo] {
+        &self.segments
+    }
+
+    // 15 format bits as sampled from the copy the ec level & mask were read from, before error
+    // correction & unmasking
+    pub fn raw_format_bits(&self) -> Option<u16> {
+        self.raw_format
+    }
+}
+
+// Segment
+//------------------------------------------------------------------------------
+
+// Mode & char count of a segment, with the byte range it covers in the data as encoded, i.e. the
+// bytes given to the builder, whether the symbol was built or decoded. Kanji take 2 bytes a char
+// in Shift JIS, so ranges needn't match offsets into the decoded text. ECI, FNC1 & structured
+// append segments cover no bytes of their own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentInfo {
+    pub mode: Mode,
+    pub char_cnt: usize,
+    pub range: Range<usize>,
+}
+
+impl Display for SegmentInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}({}) {}..{}", self.mode, self.char_cnt, self.range.start, self.range.end)
+    }
+}
+
+// Fingerprint
+//------------------------------------------------------------------------------
+
+// Stable 64 bit FNV-1a hash over version, ec level, mask, palette & payload codewords. The same
+// symbol yields the same fingerprint whether built or decoded, across runs and platforms
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u64);
+
+impl Fingerprint {
+    pub(crate) fn compute(
+        ver: Version,
+        ecl: ECLevel,
+        mask: Option<MaskPattern>,
+        hi_cap: bool,
+        payload: &[u8],
+    ) -> Self {
+        let (kind, ver_num) = match ver {
+            Version::Micro(v) => (0, v),
+            Version::Normal(v) => (1, v),
+        };
+        let mask = mask.map_or(u8::MAX, |m| *m);
+        let header = [kind, ver_num as u8, ecl as u8, mask, hi_cap as u8];
+
+        let hash = header
+            .iter()
+            .chain(payload)
+            .fold(FNV_OFFSET_BASIS, |h, &b| (h ^ b as u64).wrapping_mul(FNV_PRIME));
+        Self(hash)
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+// AIM symbology identifier
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fnc1 {
+    First,      // GS1
+    Second(u8), // Industry specific with application indicator
+}
+
+// Identifies how the payload should be interpreted by downstream systems, derived from ECI,
+// FNC1 & structured append usage in the symbol
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SymbologyId {
+    pub eci: Option<u32>, // Designator of the last ECI segment
+    pub fnc1: Option<Fnc1>,
+    pub structured_append: Option<StructuredAppend>,
+}
+
+impl SymbologyId {
+    // Modifier character as per AIM ISS QR code specification
+    pub fn modifier(&self) -> u8 {
+        let base = match self.fnc1 {
+            None => 1,
+            Some(Fnc1::First) => 3,
+            Some(Fnc1::Second(_)) => 5,
+        };
+        base + self.eci.is_some() as u8
+    }
+}
+
+impl Display for SymbologyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "]Q{}", self.modifier())
+    }
+}
+
+// Structured append
+//------------------------------------------------------------------------------
+
+// Position of a symbol in a sequence of up to 16 symbols that together carry the data. Every
+// symbol of a sequence has the same parity, which is the xor of all bytes of the data
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StructuredAppend {
+    pub index: u8, // Zero based
+    pub total: u8,
+    pub parity: u8,
+}
+
+impl StructuredAppend {
+    pub fn parity(data: &[u8]) -> u8 {
+        data.iter().fold(0, |p, b| p ^ b)
+    }
+}
+
+// Damage found in the worst block of a decoded symbol relative to the number of codewords that
+// block can correct. Ratio close to 1 means the symbol is close to becoming unreadable
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ECUsage {
+    pub errors: usize, // Corrected codewords in the worst block
+    pub budget: usize, // Correctable codewords in that block
+}
+
+impl ECUsage {
+    // Picks the block with highest fraction of its budget used up
+    pub fn worst(blocks: impl IntoIterator<Item = ECUsage>) -> Option<ECUsage> {
+        blocks.into_iter().max_by(|a, b| (a.errors * b.budget).cmp(&(b.errors * a.budget)))
+    }
+
+    pub fn damage_ratio(&self) -> f64 {
+        if self.budget == 0 {
+            return if self.errors == 0 { 0.0 } else { 1.0 };
+        }
+        self.errors as f64 / self.budget as f64
+    }
+
+    // Fraction of the correction budget left in the worst block
+    pub fn headroom(&self) -> f64 {
+        1.0 - self.damage_ratio()
     }
 }
 
@@ -37,7 +276,40 @@ impl Display for Metadata {
             Some(m) => format!("{:?}", m),
             None => "None".to_string(),
         };
-        write!(f, "Metadata: Version: {}, EC Level: {}, Masking Pattern: {} ", ver, ec, mask)
+        write!(f, "Metadata: Version: {}, EC Level: {}, Masking Pattern: {} ", ver, ec, mask)?;
+        if let Some(u) = &self.ec_usage {
+            write!(f, "EC Usage: {}/{} ", u.errors, u.budget)?;
+        }
+        if let Some(fp) = &self.fingerprint {
+            write!(f, "Fingerprint: {fp} ")?;
+        }
+        if let Some(bits) = self.raw_format {
+            write!(f, "Format bits: {bits:015b} ")?;
+        }
+        if !self.segments.is_empty() {
+            let segs: Vec<_> = self.segments.iter().map(|s| s.to_string()).collect();
+            write!(f, "Segments: [{}] ", segs.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ec_usage_tests {
+    use super::ECUsage;
+
+    #[test]
+    fn test_worst_block() {
+        let blocks = [
+            ECUsage { errors: 2, budget: 8 },
+            ECUsage { errors: 3, budget: 4 },
+            ECUsage { errors: 5, budget: 11 },
+        ];
+        let worst = ECUsage::worst(blocks).unwrap();
+        assert_eq!(worst, ECUsage { errors: 3, budget: 4 });
+        assert_eq!(worst.damage_ratio(), 0.75);
+        assert_eq!(worst.headroom(), 0.25);
+        assert_eq!(ECUsage::worst([]), None);
     }
 }
 
@@ -62,7 +334,7 @@ impl Deref for Version {
 
 impl Version {
     pub fn from_grid_size(grid_size: usize) -> Option<Self> {
-        if !(21..=177).contains(&grid_size) {
+        if !(21..=Version::Normal(MAX_VERSION).width()).contains(&grid_size) {
             return None;
         };
         Some(Version::Normal((grid_size - 17) / 4))
@@ -76,11 +348,13 @@ impl Version {
         }
     }
 
+    // Row & column coordinates of alignment pattern centres. Patterns sit at every pair of them,
+    // except where they overlap the finders
     pub fn alignment_pattern(self) -> &'static [i32] {
         debug_assert!(matches!(self, Self::Micro(1..=4) | Self::Normal(1..=40)), "Invalid version");
         match self {
             Self::Micro(_) => &[],
-            Self::Normal(v) => ALIGNMENT_PATTERN_POSITIONS[v - 1],
+            Self::Normal(v) => alignment_positions(v),
         }
     }
 
@@ -103,36 +377,42 @@ impl Version {
                 Mode::Alphanumeric => *v + 1,
                 Mode::Byte => *v + 1,
                 Mode::Kanji => *v,
-                Mode::Eci | Mode::Terminator => 0,
+                Mode::Eci | Mode::StructuredAppend => 0,
+                Mode::Fnc1First | Mode::Fnc1Second | Mode::Terminator => 0,
             },
             Version::Normal(1..=9) => match mode {
                 Mode::Numeric => 10,
                 Mode::Alphanumeric => 9,
                 Mode::Byte => 8,
                 Mode::Kanji => 8,
-                Mode::Eci | Mode::Terminator => 0,
+                Mode::Eci | Mode::StructuredAppend => 0,
+                Mode::Fnc1First | Mode::Fnc1Second | Mode::Terminator => 0,
             },
             Version::Normal(10..=26) => match mode {
                 Mode::Numeric => 12,
                 Mode::Alphanumeric => 11,
                 Mode::Byte => 16,
                 Mode::Kanji => 10,
-                Mode::Eci | Mode::Terminator => 0,
+                Mode::Eci | Mode::StructuredAppend => 0,
+                Mode::Fnc1First | Mode::Fnc1Second | Mode::Terminator => 0,
             },
             Version::Normal(_) => match mode {
                 Mode::Numeric => 14,
                 Mode::Alphanumeric => 13,
                 Mode::Byte => 16,
                 Mode::Kanji => 12,
-                Mode::Eci | Mode::Terminator => 0,
+                Mode::Eci | Mode::StructuredAppend => 0,
+                Mode::Fnc1First | Mode::Fnc1Second | Mode::Terminator => 0,
             },
         }
     }
 
+    // Bits available for segments, terminator & padding. Tripled for high capacity codes, which
+    // carry a channel per colour
     pub fn data_bit_capacity(self, ecl: ECLevel, hi_cap: bool) -> usize {
         let mut bc = match self {
-            Version::Micro(v) => VERSION_DATA_BIT_CAPACITY[39 + v][ecl as usize],
-            Version::Normal(v) => VERSION_DATA_BIT_CAPACITY[v - 1][ecl as usize],
+            Version::Micro(v) => version_tables().data_bit_capacity[39 + v][ecl as usize],
+            Version::Normal(v) => version_tables().data_bit_capacity[v - 1][ecl as usize],
         };
         if hi_cap {
             bc *= 3;
@@ -142,8 +422,8 @@ impl Version {
 
     pub fn data_capacity(self, ecl: ECLevel, hi_cap: bool) -> usize {
         let mut bc = match self {
-            Version::Micro(v) => VERSION_DATA_BIT_CAPACITY[39 + v][ecl as usize],
-            Version::Normal(v) => VERSION_DATA_BIT_CAPACITY[v - 1][ecl as usize],
+            Version::Micro(v) => version_tables().data_bit_capacity[39 + v][ecl as usize],
+            Version::Normal(v) => version_tables().data_bit_capacity[v - 1][ecl as usize],
         };
         if hi_cap {
             bc *= 3;
@@ -151,10 +431,11 @@ impl Version {
         bc >> 3
     }
 
+    // Data & ec codewords across all channels
     pub fn total_codewords(self, hi_cap: bool) -> usize {
         let mut tc = match self {
-            Version::Micro(v) => VERSION_TOTAL_CODEWORDS[39 + v],
-            Version::Normal(v) => VERSION_TOTAL_CODEWORDS[v - 1],
+            Version::Micro(v) => version_tables().total_codewords[39 + v],
+            Version::Normal(v) => version_tables().total_codewords[v - 1],
         };
         if hi_cap {
             tc *= 3;
@@ -162,33 +443,49 @@ impl Version {
         tc
     }
 
+    // Data codewords of a single channel, counting the 4 bit last codeword of M1 & M3
     pub fn channel_data_capacity(self, ecl: ECLevel) -> usize {
         let bc = match self {
-            Version::Micro(v) => VERSION_DATA_BIT_CAPACITY[39 + v][ecl as usize],
-            Version::Normal(v) => VERSION_DATA_BIT_CAPACITY[v - 1][ecl as usize],
+            Version::Micro(v) => version_tables().data_bit_capacity[39 + v][ecl as usize],
+            Version::Normal(v) => version_tables().data_bit_capacity[v - 1][ecl as usize],
         };
-        bc >> 3
+        bc.div_ceil(8)
     }
 
+    // Data & ec codewords of a single channel
     pub fn channel_codewords(self) -> usize {
         match self {
-            Version::Micro(v) => VERSION_TOTAL_CODEWORDS[39 + v],
-            Version::Normal(v) => VERSION_TOTAL_CODEWORDS[v - 1],
+            Version::Micro(v) => version_tables().total_codewords[39 + v],
+            Version::Normal(v) => version_tables().total_codewords[v - 1],
         }
     }
 
-    pub fn data_codewords_per_block(self, ecl: ECLevel) -> (usize, usize, usize, usize) {
-        match self {
-            Version::Micro(v) => DATA_CODEWORDS_PER_BLOCK[39 + v][ecl as usize],
-            Version::Normal(v) => DATA_CODEWORDS_PER_BLOCK[v - 1][ecl as usize],
+    // Reed-Solomon block structure of a channel, including the ec codewords per block
+    pub fn block_layout(self, ecl: ECLevel) -> BlockLayout {
+        let i = match self {
+            Version::Micro(v) => 39 + v,
+            Version::Normal(v) => v - 1,
+        };
+        let (b1s, b1c, b2s, b2c) = version_tables().blocks[i][ecl as usize];
+        BlockLayout {
+            groups: [
+                BlockGroup { count: b1c, data_len: b1s },
+                BlockGroup { count: b2c, data_len: b2s },
+            ],
+            ec_len: version_tables().ecc_per_block[i][ecl as usize],
         }
     }
 
+    // Data codewords & count of the blocks of group 1, then of group 2
+    #[deprecated(note = "use block_layout, which names the size & count of each group")]
+    pub fn data_codewords_per_block(self, ecl: ECLevel) -> (usize, usize, usize, usize) {
+        let [g1, g2] = self.block_layout(ecl).groups;
+        (g1.data_len, g1.count, g2.data_len, g2.count)
+    }
+
+    #[deprecated(note = "use block_layout(ecl).ec_len")]
     pub fn ecc_per_block(self, ecl: ECLevel) -> usize {
-        match self {
-            Version::Micro(v) => ECC_PER_BLOCK[39 + v][ecl as usize],
-            Version::Normal(v) => ECC_PER_BLOCK[v - 1][ecl as usize],
-        }
+        self.block_layout(ecl).ec_len
     }
 
     pub fn remainder_bits(self) -> usize {
@@ -204,6 +501,37 @@ impl Version {
         }
     }
 
+    // Zero bits ending the data, which grow with the version in micro QR
+    pub fn terminator_bits(self) -> usize {
+        match self {
+            Version::Micro(v) => v * 2 + 1,
+            Version::Normal(_) => 4,
+        }
+    }
+
+    // M1 only holds numeric & M2 alphanumeric data on top. Micro QR has no ECI, FNC1 or
+    // structured append
+    pub fn supports_mode(self, mode: Mode) -> bool {
+        use Mode::*;
+        match self {
+            Version::Normal(_) => true,
+            Version::Micro(1) => mode == Numeric,
+            Version::Micro(2) => matches!(mode, Numeric | Alphanumeric),
+            Version::Micro(_) => matches!(mode, Numeric | Alphanumeric | Byte | Kanji),
+        }
+    }
+
+    // M1 only detects errors, which counts as L, and no micro version goes up to H
+    pub fn supports_ec_level(self, ecl: ECLevel) -> bool {
+        match self {
+            Version::Micro(1) => ecl == ECLevel::L,
+            Version::Micro(2 | 3) => matches!(ecl, ECLevel::L | ECLevel::M),
+            Version::Micro(_) => ecl != ECLevel::H,
+            Version::Normal(_) => true,
+        }
+    }
+
+    // 18 bit version info with its BCH ec bits, only drawn for versions 7 & above
     pub fn info(self) -> u32 {
         debug_assert!(matches!(self, Version::Normal(7..=40)), "Invalid version");
         match self {
@@ -213,6 +541,67 @@ impl Version {
     }
 }
 
+// Block layout
+//------------------------------------------------------------------------------
+
+// Reed-Solomon blocks sharing a data length
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockGroup {
+    pub count: usize,
+    pub data_len: usize,
+}
+
+// Reed-Solomon block structure of a channel. Blocks of the second group, if any, hold one more
+// data codeword than those of the first, and all blocks share the ec length
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockLayout {
+    pub groups: [BlockGroup; 2],
+    pub ec_len: usize,
+}
+
+impl BlockLayout {
+    pub fn block_count(&self) -> usize {
+        self.groups[0].count + self.groups[1].count
+    }
+
+    // Data codewords across all blocks
+    pub fn data_len(&self) -> usize {
+        self.groups.iter().map(|g| g.count * g.data_len).sum()
+    }
+
+    // Ec codewords across all blocks
+    pub fn ec_total(&self) -> usize {
+        self.block_count() * self.ec_len
+    }
+
+    // Data length of each block in order
+    pub fn block_data_lens(&self) -> impl Iterator<Item = usize> + '_ {
+        self.groups.iter().flat_map(|g| std::iter::repeat_n(g.data_len, g.count))
+    }
+
+    // Block & position within the block of an interleaved codeword. Data codewords are interleaved
+    // first, with the longer group 2 blocks alone at the tail, followed by the ecc
+    pub fn deinterleaved_index(&self, i: usize) -> (usize, usize) {
+        let [g1, g2] = self.groups;
+
+        let total_blks = self.block_count();
+        let spl = g1.data_len * total_blks;
+        let data_sz = self.data_len();
+
+        if i < spl {
+            (i % total_blks, i / total_blks)
+        } else if i < data_sz {
+            let k = i - spl;
+            (g1.count + k % g2.count, g1.data_len + k / g2.count)
+        } else {
+            let k = i - data_sz;
+            let b = k % total_blks;
+            let dlen = if b < g1.count { g1.data_len } else { g2.data_len };
+            (b, dlen + k / total_blks)
+        }
+    }
+}
+
 #[cfg(test)]
 mod version_tests {
     use super::Mode;
@@ -299,6 +688,35 @@ mod version_tests {
     fn test_char_cnt_bits_invalid_version_max() {
         Normal(usize::MAX).char_cnt_bits(Mode::Alphanumeric);
     }
+
+    #[test]
+    fn test_block_layout() {
+        use super::{BlockGroup, ECLevel};
+
+        let layout = Normal(5).block_layout(ECLevel::Q);
+        let exp_groups =
+            [BlockGroup { count: 2, data_len: 15 }, BlockGroup { count: 2, data_len: 16 }];
+        assert_eq!(layout.groups, exp_groups);
+        assert_eq!(layout.ec_len, 18);
+        assert_eq!(layout.block_count(), 4);
+        assert_eq!(layout.data_len() + layout.ec_total(), Normal(5).channel_codewords());
+        assert_eq!(layout.block_data_lens().collect::<Vec<_>>(), [15, 15, 16, 16]);
+
+        // Tail of the data region holds the extra codeword of group 2 blocks only
+        assert_eq!(layout.deinterleaved_index(0), (0, 0));
+        assert_eq!(layout.deinterleaved_index(60), (2, 15));
+        assert_eq!(layout.deinterleaved_index(62), (0, 15));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_block_info() {
+        use super::ECLevel;
+
+        assert_eq!(Normal(5).data_codewords_per_block(ECLevel::Q), (15, 2, 16, 2));
+        assert_eq!(Normal(5).ecc_per_block(ECLevel::Q), 18);
+        assert_eq!(Normal(1).data_codewords_per_block(ECLevel::L), (19, 1, 0, 0));
+    }
 }
 
 // Error correction level
@@ -324,7 +742,6 @@ impl From<u8> for ECLevel {
     }
 }
 
-
 // Color
 //------------------------------------------------------------------------------
 
@@ -432,6 +849,19 @@ pub fn generate_format_info_qr(ecl: ECLevel, mask: MaskPattern) -> u32 {
     FORMAT_INFOS_QR[format_data]
 }
 
+// Micro QR packs the version & ec level into a symbol number, followed by the 2 bit mask number
+pub fn generate_format_info_micro(ver: Version, ecl: ECLevel, mask: MaskPattern) -> u32 {
+    debug_assert!(ver.supports_ec_level(ecl), "Invalid ec level {ecl:?} for {ver:?}");
+
+    let sym_num = match ver {
+        Version::Micro(1) => 0,
+        Version::Micro(v) => v * 2 - 3 + ecl as usize,
+        Version::Normal(_) => unreachable!("Normal QR has no symbol number"),
+    };
+    let mask = mask.micro_number().expect("Mask pattern isn't available in micro QR");
+    FORMAT_INFOS_MICRO[sym_num << 2 | mask as usize]
+}
+
 pub fn parse_format_info_qr(info: u32) -> (ECLevel, MaskPattern) {
     let ecl = ECLevel::from(((info >> 13) ^ 1) as u8);
     let mask = MaskPattern::new(((info >> 10) & 7) as u8);
@@ -441,215 +871,28 @@ pub fn parse_format_info_qr(info: u32) -> (ECLevel, MaskPattern) {
 // Global constants
 //------------------------------------------------------------------------------
 
-static ALIGNMENT_PATTERN_POSITIONS: [&[i32]; 40] = [
-    &[],
-    &[6, 18],
-    &[6, 22],
-    &[6, 26],
-    &[6, 30],
-    &[6, 34],
-    &[6, 22, 38],
-    &[6, 24, 42],
-    &[6, 26, 46],
-    &[6, 28, 50],
-    &[6, 30, 54],
-    &[6, 32, 58],
-    &[6, 34, 62],
-    &[6, 26, 46, 66],
-    &[6, 26, 48, 70],
-    &[6, 26, 50, 74],
-    &[6, 30, 54, 78],
-    &[6, 30, 56, 82],
-    &[6, 30, 58, 86],
-    &[6, 34, 62, 90],
-    &[6, 28, 50, 72, 94],
-    &[6, 26, 50, 74, 98],
-    &[6, 30, 54, 78, 102],
-    &[6, 28, 54, 80, 106],
-    &[6, 32, 58, 84, 110],
-    &[6, 30, 58, 86, 114],
-    &[6, 34, 62, 90, 118],
-    &[6, 26, 50, 74, 98, 122],
-    &[6, 30, 54, 78, 102, 126],
-    &[6, 26, 52, 78, 104, 130],
-    &[6, 30, 56, 82, 108, 134],
-    &[6, 34, 60, 86, 112, 138],
-    &[6, 30, 58, 86, 114, 142],
-    &[6, 34, 62, 90, 118, 146],
-    &[6, 30, 54, 78, 102, 126, 150],
-    &[6, 24, 50, 76, 102, 128, 154],
-    &[6, 28, 54, 80, 106, 132, 158],
-    &[6, 32, 58, 84, 110, 136, 162],
-    &[6, 26, 54, 82, 110, 138, 166],
-    &[6, 30, 58, 86, 114, 142, 170],
-];
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
 
-// Data bit capacity per error level per version
-static VERSION_DATA_BIT_CAPACITY: [[usize; 4]; 44] = [
-    [152, 128, 104, 72],
-    [272, 224, 176, 128],
-    [440, 352, 272, 208],
-    [640, 512, 384, 288],
-    [864, 688, 496, 368],
-    [1088, 864, 608, 480],
-    [1248, 992, 704, 528],
-    [1552, 1232, 880, 688],
-    [1856, 1456, 1056, 800],
-    [2192, 1728, 1232, 976],
-    [2592, 2032, 1440, 1120],
-    [2960, 2320, 1648, 1264],
-    [3424, 2672, 1952, 1440],
-    [3688, 2920, 2088, 1576],
-    [4184, 3320, 2360, 1784],
-    [4712, 3624, 2600, 2024],
-    [5176, 4056, 2936, 2264],
-    [5768, 4504, 3176, 2504],
-    [6360, 5016, 3560, 2728],
-    [6888, 5352, 3880, 3080],
-    [7456, 5712, 4096, 3248],
-    [8048, 6256, 4544, 3536],
-    [8752, 6880, 4912, 3712],
-    [9392, 7312, 5312, 4112],
-    [10208, 8000, 5744, 4304],
-    [10960, 8496, 6032, 4768],
-    [11744, 9024, 6464, 5024],
-    [12248, 9544, 6968, 5288],
-    [13048, 10136, 7288, 5608],
-    [13880, 10984, 7880, 5960],
-    [14744, 11640, 8264, 6344],
-    [15640, 12328, 8920, 6760],
-    [16568, 13048, 9368, 7208],
-    [17528, 13800, 9848, 7688],
-    [18448, 14496, 10288, 7888],
-    [19472, 15312, 10832, 8432],
-    [20528, 15936, 11408, 8768],
-    [21616, 16816, 12016, 9136],
-    [22496, 17728, 12656, 9776],
-    [23648, 18672, 13328, 10208],
-    // Micro versions
-    [20, 0, 0, 0],
-    [40, 32, 0, 0],
-    [84, 68, 0, 0],
-    [128, 112, 80, 0],
-];
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
 
-static VERSION_TOTAL_CODEWORDS: [usize; 44] = [
-    26, 44, 70, 100, 134, 172, 196, 242, 292, 346, 404, 466, 532, 581, 655, 733, 815, 901, 991,
-    1085, 1156, 1258, 1364, 1474, 1588, 1706, 1828, 1921, 2051, 2185, 2323, 2465, 2611, 2761, 2876,
-    3034, 3196, 3362, 3532, 3706, //Micro versions
-    5, 10, 17, 24,
-];
+pub(crate) static FORMAT_INFO_BIT_LEN: usize = 15;
+pub(crate) static FORMAT_ERROR_CAPACITY: u32 = 3;
 
-static ECC_PER_BLOCK: [[usize; 4]; 44] = [
-    // Normal versions.
-    [7, 10, 13, 17],
-    [10, 16, 22, 28],
-    [15, 26, 18, 22],
-    [20, 18, 26, 16],
-    [26, 24, 18, 22],
-    [18, 16, 24, 28],
-    [20, 18, 18, 26],
-    [24, 22, 22, 26],
-    [30, 22, 20, 24],
-    [18, 26, 24, 28],
-    [20, 30, 28, 24],
-    [24, 22, 26, 28],
-    [26, 22, 24, 22],
-    [30, 24, 20, 24],
-    [22, 24, 30, 24],
-    [24, 28, 24, 30],
-    [28, 28, 28, 28],
-    [30, 26, 28, 28],
-    [28, 26, 26, 26],
-    [28, 26, 30, 28],
-    [28, 26, 28, 30],
-    [28, 28, 30, 24],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [26, 28, 30, 30],
-    [28, 28, 28, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    [30, 28, 30, 30],
-    // Micro versions.
-    [2, 0, 0, 0],
-    [5, 6, 0, 0],
-    [6, 8, 0, 0],
-    [8, 10, 14, 0],
-];
-
-static DATA_CODEWORDS_PER_BLOCK: [[(usize, usize, usize, usize); 4]; 44] = [
-    // Normal versions.
-    [(19, 1, 0, 0), (16, 1, 0, 0), (13, 1, 0, 0), (9, 1, 0, 0)],
-    [(34, 1, 0, 0), (28, 1, 0, 0), (22, 1, 0, 0), (16, 1, 0, 0)],
-    [(55, 1, 0, 0), (44, 1, 0, 0), (17, 2, 0, 0), (13, 2, 0, 0)],
-    [(80, 1, 0, 0), (32, 2, 0, 0), (24, 2, 0, 0), (9, 4, 0, 0)],
-    [(108, 1, 0, 0), (43, 2, 0, 0), (15, 2, 16, 2), (11, 2, 12, 2)],
-    [(68, 2, 0, 0), (27, 4, 0, 0), (19, 4, 0, 0), (15, 4, 0, 0)],
-    [(78, 2, 0, 0), (31, 4, 0, 0), (14, 2, 15, 4), (13, 4, 14, 1)],
-    [(97, 2, 0, 0), (38, 2, 39, 2), (18, 4, 19, 2), (14, 4, 15, 2)],
-    [(116, 2, 0, 0), (36, 3, 37, 2), (16, 4, 17, 4), (12, 4, 13, 4)],
-    [(68, 2, 69, 2), (43, 4, 44, 1), (19, 6, 20, 2), (15, 6, 16, 2)],
-    [(81, 4, 0, 0), (50, 1, 51, 4), (22, 4, 23, 4), (12, 3, 13, 8)],
-    [(92, 2, 93, 2), (36, 6, 37, 2), (20, 4, 21, 6), (14, 7, 15, 4)],
-    [(107, 4, 0, 0), (37, 8, 38, 1), (20, 8, 21, 4), (11, 12, 12, 4)],
-    [(115, 3, 116, 1), (40, 4, 41, 5), (16, 11, 17, 5), (12, 11, 13, 5)],
-    [(87, 5, 88, 1), (41, 5, 42, 5), (24, 5, 25, 7), (12, 11, 13, 7)],
-    [(98, 5, 99, 1), (45, 7, 46, 3), (19, 15, 20, 2), (15, 3, 16, 13)],
-    [(107, 1, 108, 5), (46, 10, 47, 1), (22, 1, 23, 15), (14, 2, 15, 17)],
-    [(120, 5, 121, 1), (43, 9, 44, 4), (22, 17, 23, 1), (14, 2, 15, 19)],
-    [(113, 3, 114, 4), (44, 3, 45, 11), (21, 17, 22, 4), (13, 9, 14, 16)],
-    [(107, 3, 108, 5), (41, 3, 42, 13), (24, 15, 25, 5), (15, 15, 16, 10)],
-    [(116, 4, 117, 4), (42, 17, 0, 0), (22, 17, 23, 6), (16, 19, 17, 6)],
-    [(111, 2, 112, 7), (46, 17, 0, 0), (24, 7, 25, 16), (13, 34, 0, 0)],
-    [(121, 4, 122, 5), (47, 4, 48, 14), (24, 11, 25, 14), (15, 16, 16, 14)],
-    [(117, 6, 118, 4), (45, 6, 46, 14), (24, 11, 25, 16), (16, 30, 17, 2)],
-    [(106, 8, 107, 4), (47, 8, 48, 13), (24, 7, 25, 22), (15, 22, 16, 13)],
-    [(114, 10, 115, 2), (46, 19, 47, 4), (22, 28, 23, 6), (16, 33, 17, 4)],
-    [(122, 8, 123, 4), (45, 22, 46, 3), (23, 8, 24, 26), (15, 12, 16, 28)],
-    [(117, 3, 118, 10), (45, 3, 46, 23), (24, 4, 25, 31), (15, 11, 16, 31)],
-    [(116, 7, 117, 7), (45, 21, 46, 7), (23, 1, 24, 37), (15, 19, 16, 26)],
-    [(115, 5, 116, 10), (47, 19, 48, 10), (24, 15, 25, 25), (15, 23, 16, 25)],
-    [(115, 13, 116, 3), (46, 2, 47, 29), (24, 42, 25, 1), (15, 23, 16, 28)],
-    [(115, 17, 0, 0), (46, 10, 47, 23), (24, 10, 25, 35), (15, 19, 16, 35)],
-    [(115, 17, 116, 1), (46, 14, 47, 21), (24, 29, 25, 19), (15, 11, 16, 46)],
-    [(115, 13, 116, 6), (46, 14, 47, 23), (24, 44, 25, 7), (16, 59, 17, 1)],
-    [(121, 12, 122, 7), (47, 12, 48, 26), (24, 39, 25, 14), (15, 22, 16, 41)],
-    [(121, 6, 122, 14), (47, 6, 48, 34), (24, 46, 25, 10), (15, 2, 16, 64)],
-    [(122, 17, 123, 4), (46, 29, 47, 14), (24, 49, 25, 10), (15, 24, 16, 46)],
-    [(122, 4, 123, 18), (46, 13, 47, 32), (24, 48, 25, 14), (15, 42, 16, 32)],
-    [(117, 20, 118, 4), (47, 40, 48, 7), (24, 43, 25, 22), (15, 10, 16, 67)],
-    [(118, 19, 119, 6), (47, 18, 48, 31), (24, 34, 25, 34), (15, 20, 16, 61)],
-    // Micro versions.
-    [(3, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)], // M1
-    [(5, 1, 0, 0), (4, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)], // M2
-    [(11, 1, 0, 0), (9, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)], // M3
-    [(16, 1, 0, 0), (14, 1, 0, 0), (10, 1, 0, 0), (0, 0, 0, 0)], // M4
-];
+pub(crate) static FORMAT_MASK: u32 = 0b101010000010010;
 
-pub static FORMAT_INFO_BIT_LEN: usize = 15;
-pub static FORMAT_ERROR_CAPACITY: u32 = 3;
-
-pub static FORMAT_MASK: u32 = 0b101010000010010;
-
-pub static FORMAT_INFOS_QR: [u32; 32] = [
+pub(crate) static FORMAT_INFOS_QR: [u32; 32] = [
     0x5412, 0x5125, 0x5e7c, 0x5b4b, 0x45f9, 0x40ce, 0x4f97, 0x4aa0, 0x77c4, 0x72f3, 0x7daa, 0x789d,
     0x662f, 0x6318, 0x6c41, 0x6976, 0x1689, 0x13be, 0x1ce7, 0x19d0, 0x0762, 0x0255, 0x0d0c, 0x083b,
     0x355f, 0x3068, 0x3f31, 0x3a06, 0x24b4, 0x2183, 0x2eda, 0x2bed,
 ];
 
-pub static FORMAT_INFO_COORDS_QR_MAIN: [(i32, i32); 15] = [
+pub(crate) static FORMAT_INFOS_MICRO: [u32; 32] = [
+    0x4445, 0x4172, 0x4e2b, 0x4b1c, 0x55ae, 0x5099, 0x5fc0, 0x5af7, 0x6793, 0x62a4, 0x6dfd, 0x68ca,
+    0x7678, 0x734f, 0x7c16, 0x7921, 0x06de, 0x03e9, 0x0cb0, 0x0987, 0x1735, 0x1202, 0x1d5b, 0x186c,
+    0x2508, 0x203f, 0x2f66, 0x2a51, 0x34e3, 0x31d4, 0x3e8d, 0x3bba,
+];
+
+pub(crate) static FORMAT_INFO_COORDS_QR_MAIN: [(i32, i32); 15] = [
     (0, 8),
     (1, 8),
     (2, 8),
@@ -667,7 +910,7 @@ pub static FORMAT_INFO_COORDS_QR_MAIN: [(i32, i32); 15] = [
     (8, 0),
 ];
 
-pub static FORMAT_INFO_COORDS_QR_SIDE: [(i32, i32); 15] = [
+pub(crate) static FORMAT_INFO_COORDS_QR_SIDE: [(i32, i32); 15] = [
     (8, -1),
     (8, -2),
     (8, -3),
@@ -685,18 +928,37 @@ pub static FORMAT_INFO_COORDS_QR_SIDE: [(i32, i32); 15] = [
     (-1, 8),
 ];
 
-pub static VERSION_INFO_BIT_LEN: usize = 18;
-pub static VERSION_ERROR_BIT_LEN: usize = 12;
-pub static VERSION_ERROR_CAPACITY: u32 = 3;
+// Single copy beside the finder, along row 8 then up column 8
+pub(crate) static FORMAT_INFO_COORDS_MICRO: [(i32, i32); 15] = [
+    (1, 8),
+    (2, 8),
+    (3, 8),
+    (4, 8),
+    (5, 8),
+    (6, 8),
+    (7, 8),
+    (8, 8),
+    (8, 7),
+    (8, 6),
+    (8, 5),
+    (8, 4),
+    (8, 3),
+    (8, 2),
+    (8, 1),
+];
+
+pub(crate) static VERSION_INFO_BIT_LEN: usize = 18;
+pub(crate) static VERSION_ERROR_BIT_LEN: usize = 12;
+pub(crate) static VERSION_ERROR_CAPACITY: u32 = 3;
 
-pub static VERSION_INFOS: [u32; 34] = [
+pub(crate) static VERSION_INFOS: [u32; 34] = [
     0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928, 0x10b78,
     0x1145d, 0x12a17, 0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4, 0x191e1, 0x1afab,
     0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75, 0x1f250, 0x209d5, 0x216f0, 0x228ba, 0x2379f, 0x24b0b,
     0x2542e, 0x26a64, 0x27541, 0x28c69,
 ];
 
-pub static VERSION_INFO_COORDS_BL: [(i32, i32); 18] = [
+pub(crate) static VERSION_INFO_COORDS_BL: [(i32, i32); 18] = [
     (5, -9),
     (5, -10),
     (5, -11),
@@ -717,7 +979,7 @@ pub static VERSION_INFO_COORDS_BL: [(i32, i32); 18] = [
     (0, -11),
 ];
 
-pub static VERSION_INFO_COORDS_TR: [(i32, i32); 18] = [
+pub(crate) static VERSION_INFO_COORDS_TR: [(i32, i32); 18] = [
     (-9, 5),
     (-10, 5),
     (-11, 5),
@@ -738,4 +1000,48 @@ pub static VERSION_INFO_COORDS_TR: [(i32, i32); 18] = [
     (-11, 0),
 ];
 
-pub const MAX_QR_SIZE: usize = 40960;
+// Highest normal version the builder & reader handle. The max-version features lower it to bound
+// the memory of a decode on constrained devices. Ec blocks & erasures live in fixed size arrays
+// & the bit streams in buffers sized by it, but decoding isn't allocation free: the binarized
+// image, the boxed bit streams, the segments & the decoded string are still heap allocated
+#[cfg(feature = "max-version-5")]
+pub const MAX_VERSION: usize = 5;
+#[cfg(all(feature = "max-version-10", not(feature = "max-version-5")))]
+pub const MAX_VERSION: usize = 10;
+#[cfg(all(
+    feature = "max-version-20",
+    not(any(feature = "max-version-5", feature = "max-version-10"))
+))]
+pub const MAX_VERSION: usize = 20;
+#[cfg(not(any(
+    feature = "max-version-5",
+    feature = "max-version-10",
+    feature = "max-version-20"
+)))]
+pub const MAX_VERSION: usize = 40;
+
+// Module count of the largest grid
+pub const MAX_QR_SIZE: usize = Version::Normal(MAX_VERSION).width().pow(2);
+
+// Codewords of a single channel in the largest symbol
+pub const MAX_CHANNEL_CODEWORDS: usize = channel_codewords(MAX_VERSION - 1);
+
+// Most ec blocks in a channel of any symbol up to the max version
+pub const MAX_BLOCK_COUNT: usize = {
+    let mut max = 0;
+    let mut v = 0;
+    while v < MAX_VERSION {
+        let mut e = 0;
+        while e < 4 {
+            if block_count(v, e) > max {
+                max = block_count(v, e);
+            }
+            e += 1;
+        }
+        v += 1;
+    }
+    max
+};
+
+// Symbols in the longest structured append sequence, as the index takes 4 bits
+pub const MAX_STRUCTURED_APPEND: usize = 16;
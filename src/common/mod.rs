@@ -2,4 +2,5 @@ pub mod codec;
 pub mod ec;
 pub mod mask;
 pub mod metadata;
+pub(crate) mod tables;
 pub mod utils;
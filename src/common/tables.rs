@@ -0,0 +1,445 @@
+#[cfg(not(feature = "unpacked-tables"))]
+use std::sync::LazyLock;
+
+// Version tables
+//------------------------------------------------------------------------------
+
+// Tables are indexed by version - 1, with the 4 micro versions after the 40 normal ones, then by
+// ec level. Only the block structure is stored, bit packed. Capacities, codeword counts & alignment
+// positions follow from it and are unpacked into a cache on first use. The unpacked-tables feature
+// stores every table in full instead, trading binary size for skipping the unpacking
+pub(crate) struct VersionTables {
+    pub data_bit_capacity: [[usize; 4]; 44],
+    pub total_codewords: [usize; 44], // Data & ec codewords of a channel
+    pub ecc_per_block: [[usize; 4]; 44],
+    pub blocks: [[(usize, usize, usize, usize); 4]; 44], // Size & count of both block groups
+    pub alignment: [[i32; 7]; 40],                       // Zero padded for lower versions
+}
+
+#[cfg(not(feature = "unpacked-tables"))]
+pub(crate) fn version_tables() -> &'static VersionTables {
+    static TABLES: LazyLock<VersionTables> = LazyLock::new(VersionTables::unpack);
+    &TABLES
+}
+
+#[cfg(feature = "unpacked-tables")]
+pub(crate) fn version_tables() -> &'static VersionTables {
+    &UNPACKED_TABLES
+}
+
+// Alignment pattern positions of a normal version
+pub(crate) fn alignment_positions(v: usize) -> &'static [i32] {
+    let n = if v == 1 { 0 } else { v / 7 + 2 };
+    &version_tables().alignment[v - 1][..n]
+}
+
+// Blocks per channel. Const, so fixed size buffers can be sized by it
+#[cfg(not(feature = "unpacked-tables"))]
+pub(crate) const fn block_count(i: usize, ecl: usize) -> usize {
+    let [_, b1c, b2c] = PACKED_BLOCKS[i][ecl];
+    b1c as usize + b2c as usize
+}
+
+#[cfg(feature = "unpacked-tables")]
+pub(crate) const fn block_count(i: usize, ecl: usize) -> usize {
+    let (_, b1c, _, b2c) = UNPACKED_TABLES.blocks[i][ecl];
+    b1c + b2c
+}
+
+// Data & ec codewords of a channel, the same for every ec level
+#[cfg(not(feature = "unpacked-tables"))]
+pub(crate) const fn channel_codewords(i: usize) -> usize {
+    let [b1s, b1c, b2c] = PACKED_BLOCKS[i][0];
+    let (b1s, b1c, b2c) = (b1s as usize, b1c as usize, b2c as usize);
+    b1s * b1c + (b1s + 1) * b2c + (b1c + b2c) * PACKED_ECC[i][0] as usize
+}
+
+#[cfg(feature = "unpacked-tables")]
+pub(crate) const fn channel_codewords(i: usize) -> usize {
+    UNPACKED_TABLES.total_codewords[i]
+}
+
+#[cfg(any(test, not(feature = "unpacked-tables")))]
+impl VersionTables {
+    fn unpack() -> Self {
+        let mut t = Self {
+            data_bit_capacity: [[0; 4]; 44],
+            total_codewords: [0; 44],
+            ecc_per_block: [[0; 4]; 44],
+            blocks: [[(0, 0, 0, 0); 4]; 44],
+            alignment: [[0; 7]; 40],
+        };
+
+        for i in 0..44 {
+            for ecl in 0..4 {
+                let [b1s, b1c, b2c] = PACKED_BLOCKS[i][ecl].map(usize::from);
+                let b2s = if b2c > 0 { b1s + 1 } else { 0 };
+                t.blocks[i][ecl] = (b1s, b1c, b2s, b2c);
+                t.ecc_per_block[i][ecl] = PACKED_ECC[i][ecl] as usize;
+
+                // Last data codeword of M1 & M3 is only 4 bits
+                let data_len = b1s * b1c + b2s * b2c;
+                let half = matches!(i, 40 | 42) && data_len > 0;
+                t.data_bit_capacity[i][ecl] = data_len * 8 - if half { 4 } else { 0 };
+            }
+
+            // Same for every ec level, so taken from L, which every version has
+            let (b1s, b1c, b2s, b2c) = t.blocks[i][0];
+            t.total_codewords[i] = b1s * b1c + b2s * b2c + (b1c + b2c) * t.ecc_per_block[i][0];
+        }
+
+        // Evenly spaced from the bottom right, except for the first at 6
+        for v in 2..=40 {
+            let n = v / 7 + 2;
+            let step = if v == 32 { 26 } else { (v * 4 + n * 2 + 1) / (n * 2 - 2) * 2 };
+            let last = v * 4 + 10;
+            t.alignment[v - 1][0] = 6;
+            for k in 1..n {
+                t.alignment[v - 1][k] = (last - step * (n - 1 - k)) as i32;
+            }
+        }
+
+        t
+    }
+}
+
+#[cfg(test)]
+mod tables_tests {
+    use super::{
+        alignment_positions, block_count, channel_codewords, version_tables, VersionTables,
+        UNPACKED_TABLES,
+    };
+
+    #[test]
+    fn test_version_tables() {
+        let t = version_tables();
+        assert_eq!(t.data_bit_capacity[0], [152, 128, 104, 72]);
+        assert_eq!(t.data_bit_capacity[42], [84, 68, 0, 0]);
+        assert_eq!(t.total_codewords[39], 3706);
+        assert_eq!(t.blocks[39][3], (15, 20, 16, 61));
+        assert_eq!(alignment_positions(1), []);
+        assert_eq!(alignment_positions(32), [6, 34, 60, 86, 112, 138]);
+        assert_eq!(alignment_positions(40), [6, 30, 58, 86, 114, 142, 170]);
+    }
+
+    #[test]
+    fn test_packed_matches_unpacked() {
+        let (p, u) = (VersionTables::unpack(), &UNPACKED_TABLES);
+        for i in 0..44 {
+            assert_eq!(p.data_bit_capacity[i], u.data_bit_capacity[i], "Data bits of {i}");
+            assert_eq!(p.total_codewords[i], u.total_codewords[i], "Codewords of {i}");
+            assert_eq!(p.ecc_per_block[i], u.ecc_per_block[i], "Ecc per block of {i}");
+            assert_eq!(p.blocks[i], u.blocks[i], "Blocks of {i}");
+            assert_eq!(channel_codewords(i), u.total_codewords[i], "Const codewords of {i}");
+            for ecl in 0..4 {
+                let (_, b1c, _, b2c) = u.blocks[i][ecl];
+                assert_eq!(block_count(i, ecl), b1c + b2c, "Block count of {i} at {ecl}");
+            }
+        }
+        for v in 0..40 {
+            assert_eq!(p.alignment[v], u.alignment[v], "Alignment of {}", v + 1);
+        }
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Size & count of blocks in the first group & count of blocks in the second, whose blocks hold
+// one more codeword
+#[cfg(any(test, not(feature = "unpacked-tables")))]
+static PACKED_BLOCKS: [[[u8; 3]; 4]; 44] = [
+    // Normal versions
+    [[19, 1, 0], [16, 1, 0], [13, 1, 0], [9, 1, 0]],
+    [[34, 1, 0], [28, 1, 0], [22, 1, 0], [16, 1, 0]],
+    [[55, 1, 0], [44, 1, 0], [17, 2, 0], [13, 2, 0]],
+    [[80, 1, 0], [32, 2, 0], [24, 2, 0], [9, 4, 0]],
+    [[108, 1, 0], [43, 2, 0], [15, 2, 2], [11, 2, 2]],
+    [[68, 2, 0], [27, 4, 0], [19, 4, 0], [15, 4, 0]],
+    [[78, 2, 0], [31, 4, 0], [14, 2, 4], [13, 4, 1]],
+    [[97, 2, 0], [38, 2, 2], [18, 4, 2], [14, 4, 2]],
+    [[116, 2, 0], [36, 3, 2], [16, 4, 4], [12, 4, 4]],
+    [[68, 2, 2], [43, 4, 1], [19, 6, 2], [15, 6, 2]],
+    [[81, 4, 0], [50, 1, 4], [22, 4, 4], [12, 3, 8]],
+    [[92, 2, 2], [36, 6, 2], [20, 4, 6], [14, 7, 4]],
+    [[107, 4, 0], [37, 8, 1], [20, 8, 4], [11, 12, 4]],
+    [[115, 3, 1], [40, 4, 5], [16, 11, 5], [12, 11, 5]],
+    [[87, 5, 1], [41, 5, 5], [24, 5, 7], [12, 11, 7]],
+    [[98, 5, 1], [45, 7, 3], [19, 15, 2], [15, 3, 13]],
+    [[107, 1, 5], [46, 10, 1], [22, 1, 15], [14, 2, 17]],
+    [[120, 5, 1], [43, 9, 4], [22, 17, 1], [14, 2, 19]],
+    [[113, 3, 4], [44, 3, 11], [21, 17, 4], [13, 9, 16]],
+    [[107, 3, 5], [41, 3, 13], [24, 15, 5], [15, 15, 10]],
+    [[116, 4, 4], [42, 17, 0], [22, 17, 6], [16, 19, 6]],
+    [[111, 2, 7], [46, 17, 0], [24, 7, 16], [13, 34, 0]],
+    [[121, 4, 5], [47, 4, 14], [24, 11, 14], [15, 16, 14]],
+    [[117, 6, 4], [45, 6, 14], [24, 11, 16], [16, 30, 2]],
+    [[106, 8, 4], [47, 8, 13], [24, 7, 22], [15, 22, 13]],
+    [[114, 10, 2], [46, 19, 4], [22, 28, 6], [16, 33, 4]],
+    [[122, 8, 4], [45, 22, 3], [23, 8, 26], [15, 12, 28]],
+    [[117, 3, 10], [45, 3, 23], [24, 4, 31], [15, 11, 31]],
+    [[116, 7, 7], [45, 21, 7], [23, 1, 37], [15, 19, 26]],
+    [[115, 5, 10], [47, 19, 10], [24, 15, 25], [15, 23, 25]],
+    [[115, 13, 3], [46, 2, 29], [24, 42, 1], [15, 23, 28]],
+    [[115, 17, 0], [46, 10, 23], [24, 10, 35], [15, 19, 35]],
+    [[115, 17, 1], [46, 14, 21], [24, 29, 19], [15, 11, 46]],
+    [[115, 13, 6], [46, 14, 23], [24, 44, 7], [16, 59, 1]],
+    [[121, 12, 7], [47, 12, 26], [24, 39, 14], [15, 22, 41]],
+    [[121, 6, 14], [47, 6, 34], [24, 46, 10], [15, 2, 64]],
+    [[122, 17, 4], [46, 29, 14], [24, 49, 10], [15, 24, 46]],
+    [[122, 4, 18], [46, 13, 32], [24, 48, 14], [15, 42, 32]],
+    [[117, 20, 4], [47, 40, 7], [24, 43, 22], [15, 10, 67]],
+    [[118, 19, 6], [47, 18, 31], [24, 34, 34], [15, 20, 61]],
+    // Micro versions
+    [[3, 1, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]],
+    [[5, 1, 0], [4, 1, 0], [0, 0, 0], [0, 0, 0]],
+    [[11, 1, 0], [9, 1, 0], [0, 0, 0], [0, 0, 0]],
+    [[16, 1, 0], [14, 1, 0], [10, 1, 0], [0, 0, 0]],
+];
+
+#[cfg(any(test, not(feature = "unpacked-tables")))]
+static PACKED_ECC: [[u8; 4]; 44] = [
+    // Normal versions
+    [7, 10, 13, 17],
+    [10, 16, 22, 28],
+    [15, 26, 18, 22],
+    [20, 18, 26, 16],
+    [26, 24, 18, 22],
+    [18, 16, 24, 28],
+    [20, 18, 18, 26],
+    [24, 22, 22, 26],
+    [30, 22, 20, 24],
+    [18, 26, 24, 28],
+    [20, 30, 28, 24],
+    [24, 22, 26, 28],
+    [26, 22, 24, 22],
+    [30, 24, 20, 24],
+    [22, 24, 30, 24],
+    [24, 28, 24, 30],
+    [28, 28, 28, 28],
+    [30, 26, 28, 28],
+    [28, 26, 26, 26],
+    [28, 26, 30, 28],
+    [28, 26, 28, 30],
+    [28, 28, 30, 24],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [26, 28, 30, 30],
+    [28, 28, 28, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    // Micro versions
+    [2, 0, 0, 0],
+    [5, 6, 0, 0],
+    [6, 8, 0, 0],
+    [8, 10, 14, 0],
+];
+
+#[cfg(any(test, feature = "unpacked-tables"))]
+const UNPACKED_TABLES: VersionTables = VersionTables {
+    data_bit_capacity: [
+        // Normal versions
+        [152, 128, 104, 72],
+        [272, 224, 176, 128],
+        [440, 352, 272, 208],
+        [640, 512, 384, 288],
+        [864, 688, 496, 368],
+        [1088, 864, 608, 480],
+        [1248, 992, 704, 528],
+        [1552, 1232, 880, 688],
+        [1856, 1456, 1056, 800],
+        [2192, 1728, 1232, 976],
+        [2592, 2032, 1440, 1120],
+        [2960, 2320, 1648, 1264],
+        [3424, 2672, 1952, 1440],
+        [3688, 2920, 2088, 1576],
+        [4184, 3320, 2360, 1784],
+        [4712, 3624, 2600, 2024],
+        [5176, 4056, 2936, 2264],
+        [5768, 4504, 3176, 2504],
+        [6360, 5016, 3560, 2728],
+        [6888, 5352, 3880, 3080],
+        [7456, 5712, 4096, 3248],
+        [8048, 6256, 4544, 3536],
+        [8752, 6880, 4912, 3712],
+        [9392, 7312, 5312, 4112],
+        [10208, 8000, 5744, 4304],
+        [10960, 8496, 6032, 4768],
+        [11744, 9024, 6464, 5024],
+        [12248, 9544, 6968, 5288],
+        [13048, 10136, 7288, 5608],
+        [13880, 10984, 7880, 5960],
+        [14744, 11640, 8264, 6344],
+        [15640, 12328, 8920, 6760],
+        [16568, 13048, 9368, 7208],
+        [17528, 13800, 9848, 7688],
+        [18448, 14496, 10288, 7888],
+        [19472, 15312, 10832, 8432],
+        [20528, 15936, 11408, 8768],
+        [21616, 16816, 12016, 9136],
+        [22496, 17728, 12656, 9776],
+        [23648, 18672, 13328, 10208],
+        // Micro versions
+        [20, 0, 0, 0],
+        [40, 32, 0, 0],
+        [84, 68, 0, 0],
+        [128, 112, 80, 0],
+    ],
+    total_codewords: [
+        // Normal versions
+        26, 44, 70, 100, 134, 172, 196, 242, 292, 346, 404, 466, 532, 581, 655, 733, 815, 901, 991,
+        1085, 1156, 1258, 1364, 1474, 1588, 1706, 1828, 1921, 2051, 2185, 2323, 2465, 2611, 2761,
+        2876, 3034, 3196, 3362, 3532, 3706, // Micro versions
+        5, 10, 17, 24,
+    ],
+    ecc_per_block: [
+        // Normal versions
+        [7, 10, 13, 17],
+        [10, 16, 22, 28],
+        [15, 26, 18, 22],
+        [20, 18, 26, 16],
+        [26, 24, 18, 22],
+        [18, 16, 24, 28],
+        [20, 18, 18, 26],
+        [24, 22, 22, 26],
+        [30, 22, 20, 24],
+        [18, 26, 24, 28],
+        [20, 30, 28, 24],
+        [24, 22, 26, 28],
+        [26, 22, 24, 22],
+        [30, 24, 20, 24],
+        [22, 24, 30, 24],
+        [24, 28, 24, 30],
+        [28, 28, 28, 28],
+        [30, 26, 28, 28],
+        [28, 26, 26, 26],
+        [28, 26, 30, 28],
+        [28, 26, 28, 30],
+        [28, 28, 30, 24],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [26, 28, 30, 30],
+        [28, 28, 28, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        [30, 28, 30, 30],
+        // Micro versions
+        [2, 0, 0, 0],
+        [5, 6, 0, 0],
+        [6, 8, 0, 0],
+        [8, 10, 14, 0],
+    ],
+    blocks: [
+        // Normal versions
+        [(19, 1, 0, 0), (16, 1, 0, 0), (13, 1, 0, 0), (9, 1, 0, 0)],
+        [(34, 1, 0, 0), (28, 1, 0, 0), (22, 1, 0, 0), (16, 1, 0, 0)],
+        [(55, 1, 0, 0), (44, 1, 0, 0), (17, 2, 0, 0), (13, 2, 0, 0)],
+        [(80, 1, 0, 0), (32, 2, 0, 0), (24, 2, 0, 0), (9, 4, 0, 0)],
+        [(108, 1, 0, 0), (43, 2, 0, 0), (15, 2, 16, 2), (11, 2, 12, 2)],
+        [(68, 2, 0, 0), (27, 4, 0, 0), (19, 4, 0, 0), (15, 4, 0, 0)],
+        [(78, 2, 0, 0), (31, 4, 0, 0), (14, 2, 15, 4), (13, 4, 14, 1)],
+        [(97, 2, 0, 0), (38, 2, 39, 2), (18, 4, 19, 2), (14, 4, 15, 2)],
+        [(116, 2, 0, 0), (36, 3, 37, 2), (16, 4, 17, 4), (12, 4, 13, 4)],
+        [(68, 2, 69, 2), (43, 4, 44, 1), (19, 6, 20, 2), (15, 6, 16, 2)],
+        [(81, 4, 0, 0), (50, 1, 51, 4), (22, 4, 23, 4), (12, 3, 13, 8)],
+        [(92, 2, 93, 2), (36, 6, 37, 2), (20, 4, 21, 6), (14, 7, 15, 4)],
+        [(107, 4, 0, 0), (37, 8, 38, 1), (20, 8, 21, 4), (11, 12, 12, 4)],
+        [(115, 3, 116, 1), (40, 4, 41, 5), (16, 11, 17, 5), (12, 11, 13, 5)],
+        [(87, 5, 88, 1), (41, 5, 42, 5), (24, 5, 25, 7), (12, 11, 13, 7)],
+        [(98, 5, 99, 1), (45, 7, 46, 3), (19, 15, 20, 2), (15, 3, 16, 13)],
+        [(107, 1, 108, 5), (46, 10, 47, 1), (22, 1, 23, 15), (14, 2, 15, 17)],
+        [(120, 5, 121, 1), (43, 9, 44, 4), (22, 17, 23, 1), (14, 2, 15, 19)],
+        [(113, 3, 114, 4), (44, 3, 45, 11), (21, 17, 22, 4), (13, 9, 14, 16)],
+        [(107, 3, 108, 5), (41, 3, 42, 13), (24, 15, 25, 5), (15, 15, 16, 10)],
+        [(116, 4, 117, 4), (42, 17, 0, 0), (22, 17, 23, 6), (16, 19, 17, 6)],
+        [(111, 2, 112, 7), (46, 17, 0, 0), (24, 7, 25, 16), (13, 34, 0, 0)],
+        [(121, 4, 122, 5), (47, 4, 48, 14), (24, 11, 25, 14), (15, 16, 16, 14)],
+        [(117, 6, 118, 4), (45, 6, 46, 14), (24, 11, 25, 16), (16, 30, 17, 2)],
+        [(106, 8, 107, 4), (47, 8, 48, 13), (24, 7, 25, 22), (15, 22, 16, 13)],
+        [(114, 10, 115, 2), (46, 19, 47, 4), (22, 28, 23, 6), (16, 33, 17, 4)],
+        [(122, 8, 123, 4), (45, 22, 46, 3), (23, 8, 24, 26), (15, 12, 16, 28)],
+        [(117, 3, 118, 10), (45, 3, 46, 23), (24, 4, 25, 31), (15, 11, 16, 31)],
+        [(116, 7, 117, 7), (45, 21, 46, 7), (23, 1, 24, 37), (15, 19, 16, 26)],
+        [(115, 5, 116, 10), (47, 19, 48, 10), (24, 15, 25, 25), (15, 23, 16, 25)],
+        [(115, 13, 116, 3), (46, 2, 47, 29), (24, 42, 25, 1), (15, 23, 16, 28)],
+        [(115, 17, 0, 0), (46, 10, 47, 23), (24, 10, 25, 35), (15, 19, 16, 35)],
+        [(115, 17, 116, 1), (46, 14, 47, 21), (24, 29, 25, 19), (15, 11, 16, 46)],
+        [(115, 13, 116, 6), (46, 14, 47, 23), (24, 44, 25, 7), (16, 59, 17, 1)],
+        [(121, 12, 122, 7), (47, 12, 48, 26), (24, 39, 25, 14), (15, 22, 16, 41)],
+        [(121, 6, 122, 14), (47, 6, 48, 34), (24, 46, 25, 10), (15, 2, 16, 64)],
+        [(122, 17, 123, 4), (46, 29, 47, 14), (24, 49, 25, 10), (15, 24, 16, 46)],
+        [(122, 4, 123, 18), (46, 13, 47, 32), (24, 48, 25, 14), (15, 42, 16, 32)],
+        [(117, 20, 118, 4), (47, 40, 48, 7), (24, 43, 25, 22), (15, 10, 16, 67)],
+        [(118, 19, 119, 6), (47, 18, 48, 31), (24, 34, 25, 34), (15, 20, 16, 61)],
+        // Micro versions
+        [(3, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)],
+        [(5, 1, 0, 0), (4, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)],
+        [(11, 1, 0, 0), (9, 1, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)],
+        [(16, 1, 0, 0), (14, 1, 0, 0), (10, 1, 0, 0), (0, 0, 0, 0)],
+    ],
+    alignment: [
+        [0, 0, 0, 0, 0, 0, 0],
+        [6, 18, 0, 0, 0, 0, 0],
+        [6, 22, 0, 0, 0, 0, 0],
+        [6, 26, 0, 0, 0, 0, 0],
+        [6, 30, 0, 0, 0, 0, 0],
+        [6, 34, 0, 0, 0, 0, 0],
+        [6, 22, 38, 0, 0, 0, 0],
+        [6, 24, 42, 0, 0, 0, 0],
+        [6, 26, 46, 0, 0, 0, 0],
+        [6, 28, 50, 0, 0, 0, 0],
+        [6, 30, 54, 0, 0, 0, 0],
+        [6, 32, 58, 0, 0, 0, 0],
+        [6, 34, 62, 0, 0, 0, 0],
+        [6, 26, 46, 66, 0, 0, 0],
+        [6, 26, 48, 70, 0, 0, 0],
+        [6, 26, 50, 74, 0, 0, 0],
+        [6, 30, 54, 78, 0, 0, 0],
+        [6, 30, 56, 82, 0, 0, 0],
+        [6, 30, 58, 86, 0, 0, 0],
+        [6, 34, 62, 90, 0, 0, 0],
+        [6, 28, 50, 72, 94, 0, 0],
+        [6, 26, 50, 74, 98, 0, 0],
+        [6, 30, 54, 78, 102, 0, 0],
+        [6, 28, 54, 80, 106, 0, 0],
+        [6, 32, 58, 84, 110, 0, 0],
+        [6, 30, 58, 86, 114, 0, 0],
+        [6, 34, 62, 90, 118, 0, 0],
+        [6, 26, 50, 74, 98, 122, 0],
+        [6, 30, 54, 78, 102, 126, 0],
+        [6, 26, 52, 78, 104, 130, 0],
+        [6, 30, 56, 82, 108, 134, 0],
+        [6, 34, 60, 86, 112, 138, 0],
+        [6, 30, 58, 86, 114, 142, 0],
+        [6, 34, 62, 90, 118, 146, 0],
+        [6, 30, 54, 78, 102, 126, 150],
+        [6, 24, 50, 76, 102, 128, 154],
+        [6, 28, 54, 80, 106, 132, 158],
+        [6, 32, 58, 84, 110, 136, 162],
+        [6, 26, 54, 82, 110, 138, 166],
+        [6, 30, 58, 86, 114, 142, 170],
+    ],
+};
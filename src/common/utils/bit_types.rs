@@ -3,6 +3,8 @@ use std::{fmt::Display, mem};
 
 use num_traits::PrimInt;
 
+use crate::metadata::MAX_CHANNEL_CODEWORDS;
+
 // Bit stream
 //------------------------------------------------------------------------------
 
@@ -377,4 +379,5 @@ impl BitArray {
 // Global constants
 //------------------------------------------------------------------------------
 
-pub const MAX_PAYLOAD_SIZE: usize = 16384;
+// Bytes of a high capacity payload at the max version
+pub const MAX_PAYLOAD_SIZE: usize = 3 * MAX_CHANNEL_CODEWORDS;
@@ -1,5 +1,8 @@
 use std::fmt::{Debug, Display, Error, Formatter};
 
+use super::messages::English;
+use crate::codec::Mode;
+
 // Error
 //------------------------------------------------------------------------------
 
@@ -14,6 +17,20 @@ pub enum QRError {
     InvalidColor,
     InvalidChar,
     InvalidMaskingPattern,
+    InvalidModeChar(Mode, usize, char), // Forced mode, byte index, offending char
+    PlanMismatch,
+    BufferSizeMismatch,
+    EmptyPadding,
+    ExceedsVersionRange(usize, usize), // Max version of the range, smallest that fits
+    FitsNoVersion(usize),              // Max version of the range
+    InvalidEciDesignator(u32),
+    InvalidStructuredAppendCount(usize),
+    UnsupportedMicroMode(Mode),
+    InvalidBlockLength(usize, usize), // Codewords in the block, ec codewords
+    InsufficientLogoArea,
+    LogoUnreadable,
+    InvalidGcodeSetting(&'static str), // Name of the setting
+    TooManyTransferFrames(usize),      // Frames the file needs
 
     // QR reader
     SingularMatrix,
@@ -30,51 +47,254 @@ pub enum QRError {
     TimingMismatch,
     AlignmentMismatch,
     DivisionByZero,
+    NonFiniteGeometry,
+    DegenerateQuad,
+    IllConditionedHomography,
     InvalidMode(u8),
     CorruptDataSegment,
     EndOfStream,
     InvalidUTF8Encoding,
     InvalidCharacterEncoding,
+    UnexpectedPayload,
+    MissingStructuredAppendSymbol(u8, u8), // Index of the first missing symbol, symbol count
+    StructuredAppendParityMismatch,
+    MixedStructuredAppend,
+    MalformedIntent(&'static str), // Scheme of the payload
+    ErasureOutOfBounds(usize),
+    MalformedTransferFrame,
+    TransferChecksumMismatch,
+    MixedTransfer,
+    IncompleteTransfer(usize, usize), // Frames received, frame count
+    MalformedBinaryImage,
+    BinarizerSizeMismatch(u32, u32), // Width & height of the binarized image
 }
 
-impl Display for QRError {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        let msg = match *self {
+impl QRError {
+    // Stable identifiers that logs & API responses can reference regardless of the message
+    // wording. Codes are never reused or renumbered, new variants take the next free number of
+    // their group: 1xx for the builder & 2xx for the reader. Variants whose messages differ in
+    // shape take a code each, so every code has a single message template
+    pub fn code(&self) -> u16 {
+        match self {
+            // QR builder
+            Self::EmptyData => 101,
+            Self::DataTooLong => 102,
+            Self::CapacityOverflow => 103,
+            Self::InvalidVersion => 104,
+            Self::InvalidECLevel => 105,
+            Self::InvalidColor => 106,
+            Self::InvalidChar => 107,
+            Self::InvalidMaskingPattern => 108,
+            Self::InvalidModeChar(..) => 109,
+            Self::PlanMismatch => 110,
+            Self::BufferSizeMismatch => 111,
+            Self::EmptyPadding => 112,
+            Self::ExceedsVersionRange(..) => 113,
+            Self::FitsNoVersion(_) => 114,
+            Self::InvalidEciDesignator(_) => 115,
+            Self::InvalidStructuredAppendCount(_) => 116,
+            Self::UnsupportedMicroMode(_) => 117,
+            Self::InvalidBlockLength(..) => 118,
+            Self::InsufficientLogoArea => 119,
+            Self::LogoUnreadable => 120,
+            Self::InvalidGcodeSetting(_) => 121,
+            Self::TooManyTransferFrames(_) => 122,
+
+            // QR reader
+            Self::SingularMatrix => 201,
+            Self::PointAtInfinity => 202,
+            Self::SymbolNotFound => 203,
+            Self::CastingFailed => 204,
+            Self::PixelOutOfBounds => 205,
+            Self::TooManyError => 206,
+            Self::InvalidInfo => 207,
+            Self::InvalidFormatInfo => 208,
+            Self::InvalidVersionInfo => 209,
+            Self::InvalidCapacityInfo => 210,
+            Self::FinderMismatch => 211,
+            Self::TimingMismatch => 212,
+            Self::AlignmentMismatch => 213,
+            Self::DivisionByZero => 214,
+            Self::NonFiniteGeometry => 215,
+            Self::DegenerateQuad => 216,
+            Self::IllConditionedHomography => 217,
+            Self::InvalidMode(..) => 218,
+            Self::CorruptDataSegment => 219,
+            Self::EndOfStream => 220,
+            Self::InvalidUTF8Encoding => 221,
+            Self::InvalidCharacterEncoding => 222,
+            Self::UnexpectedPayload => 223,
+            Self::MissingStructuredAppendSymbol(..) => 224,
+            Self::StructuredAppendParityMismatch => 225,
+            Self::MixedStructuredAppend => 226,
+            Self::MalformedIntent(_) => 227,
+            Self::ErasureOutOfBounds(_) => 228,
+            Self::MalformedTransferFrame => 229,
+            Self::TransferChecksumMismatch => 230,
+            Self::MixedTransfer => 231,
+            Self::IncompleteTransfer(..) => 232,
+            Self::MalformedBinaryImage => 233,
+            Self::BinarizerSizeMismatch(..) => 234,
+        }
+    }
+
+    // Symbolic name of the code, e.g. "DATA_TOO_LONG"
+    pub fn code_name(&self) -> &'static str {
+        match self {
             // QR builder
-            Self::EmptyData => "Empty data",
-            Self::DataTooLong => "Data too long",
-            Self::CapacityOverflow => "Capacity overflow",
-            Self::InvalidVersion => "Invalid version",
-            Self::InvalidECLevel => "Invalid error correction level",
-            Self::InvalidColor => "Invalid color",
-            Self::InvalidChar => "Invalid character",
-            Self::InvalidMaskingPattern => "Invalid masking pattern",
+            Self::EmptyData => "EMPTY_DATA",
+            Self::DataTooLong => "DATA_TOO_LONG",
+            Self::CapacityOverflow => "CAPACITY_OVERFLOW",
+            Self::InvalidVersion => "INVALID_VERSION",
+            Self::InvalidECLevel => "INVALID_EC_LEVEL",
+            Self::InvalidColor => "INVALID_COLOR",
+            Self::InvalidChar => "INVALID_CHAR",
+            Self::InvalidMaskingPattern => "INVALID_MASKING_PATTERN",
+            Self::InvalidModeChar(..) => "INVALID_MODE_CHAR",
+            Self::PlanMismatch => "PLAN_MISMATCH",
+            Self::BufferSizeMismatch => "BUFFER_SIZE_MISMATCH",
+            Self::EmptyPadding => "EMPTY_PADDING",
+            Self::ExceedsVersionRange(..) => "EXCEEDS_VERSION_RANGE",
+            Self::FitsNoVersion(_) => "FITS_NO_VERSION",
+            Self::InvalidEciDesignator(_) => "INVALID_ECI_DESIGNATOR",
+            Self::InvalidStructuredAppendCount(_) => "INVALID_STRUCTURED_APPEND_COUNT",
+            Self::UnsupportedMicroMode(_) => "UNSUPPORTED_MICRO_MODE",
+            Self::InvalidBlockLength(..) => "INVALID_BLOCK_LENGTH",
+            Self::InsufficientLogoArea => "INSUFFICIENT_LOGO_AREA",
+            Self::LogoUnreadable => "LOGO_UNREADABLE",
+            Self::InvalidGcodeSetting(_) => "INVALID_GCODE_SETTING",
+            Self::TooManyTransferFrames(_) => "TOO_MANY_TRANSFER_FRAMES",
 
             // QR reader
-            Self::SingularMatrix => "Cannot compute homography",
-            Self::PointAtInfinity => "Projected point is at infinity",
-            Self::SymbolNotFound => "QR not found",
-            Self::CastingFailed => "f64 to i32 casting failed",
-            Self::PixelOutOfBounds => "Pixel is out of bounds",
-            Self::TooManyError => "Too many errors",
-            Self::InvalidInfo => "Invalid info",
-            Self::InvalidFormatInfo => "Invalid format info detected",
-            Self::InvalidVersionInfo => "Invalid version info detected",
-            Self::InvalidCapacityInfo => "Couldn't read capacity",
-            Self::FinderMismatch => "Finder color mismatch",
-            Self::TimingMismatch => "Timing color mismatch",
-            Self::AlignmentMismatch => "Alignment color mismatch",
-            Self::DivisionByZero => "Division by zero in GF(256)",
-            Self::InvalidMode(m) => &format!("Unexpected mode bits: {m}").to_string(),
-            Self::CorruptDataSegment => "Truncated data segment",
-            Self::EndOfStream => "End of stream reached",
-            Self::InvalidUTF8Encoding => "Invalid UTF8 sequence",
-            Self::InvalidCharacterEncoding => "Character sequence is neither utf8 nor shift jis",
-        };
-        f.write_str(msg)
+            Self::SingularMatrix => "SINGULAR_MATRIX",
+            Self::PointAtInfinity => "POINT_AT_INFINITY",
+            Self::SymbolNotFound => "SYMBOL_NOT_FOUND",
+            Self::CastingFailed => "CASTING_FAILED",
+            Self::PixelOutOfBounds => "PIXEL_OUT_OF_BOUNDS",
+            Self::TooManyError => "TOO_MANY_ERROR",
+            Self::InvalidInfo => "INVALID_INFO",
+            Self::InvalidFormatInfo => "INVALID_FORMAT_INFO",
+            Self::InvalidVersionInfo => "INVALID_VERSION_INFO",
+            Self::InvalidCapacityInfo => "INVALID_CAPACITY_INFO",
+            Self::FinderMismatch => "FINDER_MISMATCH",
+            Self::TimingMismatch => "TIMING_MISMATCH",
+            Self::AlignmentMismatch => "ALIGNMENT_MISMATCH",
+            Self::DivisionByZero => "DIVISION_BY_ZERO",
+            Self::NonFiniteGeometry => "NON_FINITE_GEOMETRY",
+            Self::DegenerateQuad => "DEGENERATE_QUAD",
+            Self::IllConditionedHomography => "ILL_CONDITIONED_HOMOGRAPHY",
+            Self::InvalidMode(..) => "INVALID_MODE",
+            Self::CorruptDataSegment => "CORRUPT_DATA_SEGMENT",
+            Self::EndOfStream => "END_OF_STREAM",
+            Self::InvalidUTF8Encoding => "INVALID_UTF8_ENCODING",
+            Self::InvalidCharacterEncoding => "INVALID_CHARACTER_ENCODING",
+            Self::UnexpectedPayload => "UNEXPECTED_PAYLOAD",
+            Self::MissingStructuredAppendSymbol(..) => "MISSING_STRUCTURED_APPEND_SYMBOL",
+            Self::StructuredAppendParityMismatch => "STRUCTURED_APPEND_PARITY_MISMATCH",
+            Self::MixedStructuredAppend => "MIXED_STRUCTURED_APPEND",
+            Self::MalformedIntent(_) => "MALFORMED_INTENT",
+            Self::ErasureOutOfBounds(_) => "ERASURE_OUT_OF_BOUNDS",
+            Self::MalformedTransferFrame => "MALFORMED_TRANSFER_FRAME",
+            Self::TransferChecksumMismatch => "TRANSFER_CHECKSUM_MISMATCH",
+            Self::MixedTransfer => "MIXED_TRANSFER",
+            Self::IncompleteTransfer(..) => "INCOMPLETE_TRANSFER",
+            Self::MalformedBinaryImage => "MALFORMED_BINARY_IMAGE",
+            Self::BinarizerSizeMismatch(..) => "BINARIZER_SIZE_MISMATCH",
+        }
+    }
+}
+
+impl Display for QRError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str(&self.message(&English))
     }
 }
 
 impl std::error::Error for QRError {}
 
+#[cfg(test)]
+mod error_tests {
+    use std::collections::HashSet;
+
+    use super::QRError;
+    use crate::{codec::Mode, utils::english_template};
+
+    #[test]
+    fn test_error_codes_are_unique() {
+        use QRError::*;
+
+        let errs = [
+            EmptyData,
+            DataTooLong,
+            CapacityOverflow,
+            InvalidVersion,
+            InvalidECLevel,
+            InvalidColor,
+            InvalidChar,
+            InvalidMaskingPattern,
+            InvalidModeChar(Mode::Numeric, 0, 'a'),
+            PlanMismatch,
+            BufferSizeMismatch,
+            EmptyPadding,
+            ExceedsVersionRange(5, 7),
+            FitsNoVersion(5),
+            InvalidEciDesignator(1_000_000),
+            InvalidStructuredAppendCount(17),
+            UnsupportedMicroMode(Mode::Byte),
+            InvalidBlockLength(256, 6),
+            InsufficientLogoArea,
+            LogoUnreadable,
+            InvalidGcodeSetting("feed rate"),
+            TooManyTransferFrames(70_000),
+            SingularMatrix,
+            PointAtInfinity,
+            SymbolNotFound,
+            CastingFailed,
+            PixelOutOfBounds,
+            TooManyError,
+            InvalidInfo,
+            InvalidFormatInfo,
+            InvalidVersionInfo,
+            InvalidCapacityInfo,
+            FinderMismatch,
+            TimingMismatch,
+            AlignmentMismatch,
+            DivisionByZero,
+            NonFiniteGeometry,
+            DegenerateQuad,
+            IllConditionedHomography,
+            InvalidMode(7),
+            CorruptDataSegment,
+            EndOfStream,
+            InvalidUTF8Encoding,
+            InvalidCharacterEncoding,
+            UnexpectedPayload,
+            MissingStructuredAppendSymbol(2, 4),
+            StructuredAppendParityMismatch,
+            MixedStructuredAppend,
+            MalformedIntent("geo"),
+            ErasureOutOfBounds(24),
+            MalformedTransferFrame,
+            TransferChecksumMismatch,
+            MixedTransfer,
+            IncompleteTransfer(5, 7),
+            MalformedBinaryImage,
+            BinarizerSizeMismatch(40, 30),
+        ];
+        assert_eq!(errs.iter().map(QRError::code).collect::<HashSet<_>>().len(), errs.len());
+        assert!(errs.iter().all(|e| english_template(e.code()).is_some()));
+        assert_eq!(errs.iter().map(QRError::code_name).collect::<HashSet<_>>().len(), errs.len());
+        assert!(errs.iter().all(|e| (101..300).contains(&e.code())));
+
+        // Codes are part of the public contract & must not change
+        assert_eq!(EmptyData.code(), 101);
+        assert_eq!(ExceedsVersionRange(5, 7).code(), 113);
+        assert_eq!(FitsNoVersion(5).code(), 114);
+        assert_eq!(SingularMatrix.code(), 201);
+        assert_eq!(TooManyError.code(), 206);
+        assert_eq!(TooManyError.code_name(), "TOO_MANY_ERROR");
+        assert_eq!(UnexpectedPayload.code(), 223);
+    }
+}
+
 pub type QRResult<T> = Result<T, QRError>;
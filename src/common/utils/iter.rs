@@ -33,6 +33,11 @@ impl EncRegionIter {
             return true;
         }
 
+        // Micro QR has a single finder, with timing patterns along the top & left edges
+        if let Version::Micro(_) = self.ver {
+            return x == 0 || y == 0;
+        }
+
         // Top right finder & format info check
         if x >= w - 8 && y < 9 {
             return true;
@@ -132,4 +137,14 @@ mod iter_tests {
             assert_eq!(total_codewords, exp_codewords);
         }
     }
+
+    #[test]
+    fn test_enc_region_iter_micro() {
+        // Last data codeword of M1 & M3 is only 4 bits
+        for (v, half) in [(1, 4), (2, 0), (3, 4), (4, 0)] {
+            let ver = Version::Micro(v);
+            let modules = EncRegionIter::new(ver).count();
+            assert_eq!(modules, ver.channel_codewords() * 8 - half);
+        }
+    }
 }
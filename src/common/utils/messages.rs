@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use super::error::QRError;
+
+// Message catalogs
+//------------------------------------------------------------------------------
+
+// User facing error message templates keyed by QRError::code. Placeholders {0}, {1}, .. are
+// filled with QRError::args, in whatever order the translation needs them
+pub trait MessageCatalog {
+    fn template(&self, code: u16) -> Option<&str>;
+}
+
+// Default catalog that Display uses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct English;
+
+impl MessageCatalog for English {
+    fn template(&self, code: u16) -> Option<&str> {
+        english_template(code)
+    }
+}
+
+impl MessageCatalog for HashMap<u16, String> {
+    fn template(&self, code: u16) -> Option<&str> {
+        self.get(&code).map(String::as_str)
+    }
+}
+
+impl QRError {
+    // Values interpolated into the message template, e.g. the offending char, byte index & mode
+    // of InvalidModeChar
+    pub fn args(&self) -> Vec<String> {
+        match *self {
+            Self::InvalidModeChar(m, i, c) => {
+                vec![format!("{c:?}"), i.to_string(), format!("{m:?}")]
+            }
+            Self::ExceedsVersionRange(max, v) => vec![max.to_string(), v.to_string()],
+            Self::FitsNoVersion(max) => vec![max.to_string()],
+            Self::InvalidEciDesignator(d) => vec![d.to_string()],
+            Self::InvalidStructuredAppendCount(n) => vec![n.to_string()],
+            Self::UnsupportedMicroMode(m) => vec![format!("{m:?}")],
+            Self::InvalidBlockLength(len, ec_len) => vec![len.to_string(), ec_len.to_string()],
+            Self::InvalidMode(m) => vec![m.to_string()],
+            Self::MissingStructuredAppendSymbol(i, n) => vec![(i + 1).to_string(), n.to_string()],
+            Self::InvalidGcodeSetting(name) => vec![name.to_string()],
+            Self::TooManyTransferFrames(n) => vec![n.to_string()],
+            Self::MalformedIntent(scheme) => vec![scheme.to_string()],
+            Self::ErasureOutOfBounds(i) => vec![i.to_string()],
+            Self::IncompleteTransfer(got, n) => vec![got.to_string(), n.to_string()],
+            Self::BinarizerSizeMismatch(w, h) => vec![w.to_string(), h.to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    // Message from the catalog, falling back to English for codes it has no template for
+    pub fn message(&self, catalog: &dyn MessageCatalog) -> String {
+        let code = self.code();
+        let template =
+            catalog.template(code).or_else(|| english_template(code)).unwrap_or_default();
+        fill_template(template, &self.args())
+    }
+}
+
+// Replaces {i} with the ith arg. Unknown placeholders are left as they are
+fn fill_template(template: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let arg = rest
+            .find('}')
+            .and_then(|close| Some((close, args.get(rest[1..close].parse::<usize>().ok()?)?)));
+        match arg {
+            Some((close, arg)) => {
+                out.push_str(arg);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn english_template(code: u16) -> Option<&'static str> {
+    let msg = match code {
+        // QR builder
+        101 => "Empty data",
+        102 => "Data too long",
+        103 => "Capacity overflow",
+        104 => "Invalid version",
+        105 => "Invalid error correction level",
+        106 => "Invalid color",
+        107 => "Invalid character",
+        108 => "Invalid masking pattern",
+        109 => "Character {0} at byte {1} cannot be encoded in {2} mode",
+        110 => "Data doesn't match the shape of the encode plan",
+        111 => "Buffer size doesn't match the rendered image",
+        112 => "Custom padding is empty",
+        113 => "Data needs version {1}, above the max version {0} of the range",
+        114 => "Data doesn't fit any version, the range ends at {0}",
+        115 => "ECI designator {0} is above the max of 999999",
+        116 => "Structured append takes 1 to 16 symbols, not {0}",
+        117 => "{0} mode isn't available in this Micro QR version",
+        118 => "EC blocks take 1 to 63 ec codewords & up to 255 in all, not {1} of {0}",
+        119 => "Error correction level leaves no room for a logo, try a higher level",
+        120 => "Symbol doesn't decode with the logo over it",
+        121 => "G-code {0} is out of range",
+        122 => "File needs {0} transfer frames, above the max of 65536 receivers accept",
+
+        // QR reader
+        201 => "Cannot compute homography",
+        202 => "Projected point is at infinity",
+        203 => "QR not found",
+        204 => "f64 to i32 casting failed",
+        205 => "Pixel is out of bounds",
+        206 => "Too many errors",
+        207 => "Invalid info",
+        208 => "Invalid format info detected",
+        209 => "Invalid version info detected",
+        210 => "Couldn't read capacity",
+        211 => "Finder color mismatch",
+        212 => "Timing color mismatch",
+        213 => "Alignment color mismatch",
+        214 => "Division by zero in GF(256)",
+        215 => "Symbol geometry has NaN or infinite coordinates",
+        216 => "Symbol corners are collinear or folded",
+        217 => "Projection is too skewed to sample reliably",
+        218 => "Unexpected mode bits: {0}",
+        219 => "Truncated data segment",
+        220 => "End of stream reached",
+        221 => "Invalid UTF8 sequence",
+        222 => "Byte segment is invalid in the given or any detected charset",
+        223 => "Payload doesn't match the decode hints",
+        224 => "Symbol {0} of {1} of the structured append sequence is missing",
+        225 => "Structured append parity doesn't match the joined data",
+        226 => "Symbols belong to different structured append sequences",
+        227 => "Malformed {0} payload",
+        228 => "Erasure at codeword {0} is outside the block",
+        229 => "Payload is not a file transfer frame",
+        230 => "Transfer data doesn't match its checksum",
+        231 => "Frames belong to different file transfers",
+        232 => "File transfer is missing frames, {0} of {1} received",
+        233 => "Data is not a packed binary image",
+        234 => "Binarizer returned a {0}x{1} image, not the size of its input",
+        _ => return None,
+    };
+    Some(msg)
+}
+
+#[cfg(test)]
+mod messages_tests {
+    use std::collections::HashMap;
+
+    use super::{fill_template, English};
+    use crate::{codec::Mode, utils::QRError};
+
+    #[test]
+    fn test_fill_template() {
+        let args = ["a".to_string(), "b".to_string()];
+        assert_eq!(fill_template("{1}-{0}-{1}", &args), "b-a-b");
+        assert_eq!(fill_template("{2} {x} {", &args), "{2} {x} {");
+    }
+
+    #[test]
+    fn test_translated_messages() {
+        let err = QRError::InvalidModeChar(Mode::Numeric, 3, 'x');
+        assert_eq!(err.to_string(), "Character 'x' at byte 3 cannot be encoded in Numeric mode");
+        assert_eq!(err.message(&English), err.to_string());
+        assert_eq!(
+            QRError::ExceedsVersionRange(5, 7).to_string(),
+            "Data needs version 7, above the max version 5 of the range"
+        );
+
+        let de = HashMap::from([
+            (109, "Zeichen {0} an Byte {1} ist im Modus {2} nicht kodierbar".to_string()),
+            (206, "Zu viele Fehler".to_string()),
+        ]);
+        assert_eq!(err.message(&de), "Zeichen 'x' an Byte 3 ist im Modus Numeric nicht kodierbar");
+        assert_eq!(QRError::TooManyError.message(&de), "Zu viele Fehler");
+        assert_eq!(QRError::EmptyData.message(&de), "Empty data");
+    }
+}
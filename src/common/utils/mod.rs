@@ -3,8 +3,10 @@ pub mod cast;
 pub mod error;
 pub mod iter;
 pub mod macros;
+pub mod messages;
 
 pub use bit_types::*;
 pub use cast::*;
 pub use error::*;
 pub use iter::*;
+pub use messages::*;
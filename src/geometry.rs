@@ -1,13 +1,13 @@
+//! Integer pixel geometry shared by the reader, for drawing overlays along detected edges
+
 use std::{cmp::Ordering, marker::PhantomData};
 
-#[cfg(test)]
 use image::{Rgb, RgbImage};
 
-use crate::reader::binarize::BinaryImage;
-
 // Point
 //------------------------------------------------------------------------------
 
+/// Pixel position in image coordinates, with y pointing down
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Hash)]
 pub struct Point {
     pub x: i32,
@@ -15,14 +15,15 @@ pub struct Point {
 }
 
 impl Point {
+    /// Squared euclidean distance, which avoids the sqrt when comparing distances
     pub fn dist_sq(&self, other: &Point) -> u32 {
         let dx = other.x - self.x;
         let dy = other.y - self.y;
         (dx * dx + dy * dy) as _
     }
 
-    #[cfg(test)]
-    pub fn highlight(&self, img: &mut RgbImage, color: Rgb<u8>) {
+    /// Paints the 3x3 pixels around the point, clamped to the image
+    pub(crate) fn highlight(&self, img: &mut RgbImage, color: Rgb<u8>) {
         let (w, h) = img.dimensions();
         for i in [-1, 0, 1] {
             for j in [-1, 0, 1] {
@@ -37,6 +38,7 @@ impl Point {
 // Slope
 //------------------------------------------------------------------------------
 
+/// Direction from one point to another as integer deltas
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
 pub struct Slope {
     pub dx: i32,
@@ -50,6 +52,8 @@ impl Slope {
         Self { dx, dy }
     }
 
+    /// Z component of the cross product. Positive if other turns clockwise from self in image
+    /// coordinates
     pub fn cross(&self, other: &Self) -> i32 {
         self.dx * other.dy - self.dy * other.dx
     }
@@ -58,8 +62,9 @@ impl Slope {
 // Axis trait to modify functions based on X/Y axis at compile time
 //------------------------------------------------------------------------------
 
+/// Major axis of a line walk, picked at compile time with the [`X`] & [`Y`] markers
 pub trait Axis {
-    fn bound_check(img: &BinaryImage, pt: &Point) -> bool;
+    fn bound_check(w: u32, h: u32, pt: &Point) -> bool; // True if within 0..w or 0..h along axis
     fn shift(pt: &mut Point, dist: &(i32, i32)); // Shifts point along axis
     fn shift_cross(pt: &mut Point, dist: &(i32, i32)); // Steps point along perpendicular axis
     fn delta(m: &Slope) -> i32; // Returns delta from slope along axis
@@ -70,8 +75,8 @@ pub trait Axis {
 pub struct X;
 
 impl Axis for X {
-    fn bound_check(img: &BinaryImage, pt: &Point) -> bool {
-        0 <= pt.x && pt.x < img.w as i32
+    fn bound_check(w: u32, _h: u32, pt: &Point) -> bool {
+        0 <= pt.x && pt.x < w as i32
     }
 
     fn shift(pt: &mut Point, dist: &(i32, i32)) {
@@ -98,8 +103,8 @@ impl Axis for X {
 pub struct Y;
 
 impl Axis for Y {
-    fn bound_check(img: &BinaryImage, pt: &Point) -> bool {
-        0 <= pt.y && pt.y < img.h as i32
+    fn bound_check(_w: u32, h: u32, pt: &Point) -> bool {
+        0 <= pt.y && pt.y < h as i32
     }
 
     fn shift(pt: &mut Point, dist: &(i32, i32)) {
@@ -126,6 +131,18 @@ impl Axis for Y {
 // Bresenham line scan algorithm
 //------------------------------------------------------------------------------
 
+/// Pixels on the line between two points, stepping once per pixel along the axis. The walk stops
+/// once it is level with the end point along the axis, so the end itself isn't yielded. Use [`X`]
+/// for lines closer to horizontal & [`Y`] for lines closer to vertical to hit every pixel
+///
+/// ```
+/// use qrism::geometry::{BresenhamLine, Point, X};
+///
+/// let (from, to) = (Point { x: 0, y: 0 }, Point { x: 4, y: 2 });
+/// let line = BresenhamLine::<X>::new(&from, &to).collect::<Vec<_>>();
+/// assert_eq!(line.len(), 4);
+/// assert_eq!(line[0], from);
+/// ```
 #[derive(Debug, Clone)]
 pub struct BresenhamLine<A: Axis> {
     cur: Point, // Current position
@@ -188,3 +205,37 @@ impl<A: Axis> Iterator for BresenhamLine<A> {
         res
     }
 }
+
+#[cfg(test)]
+mod geometry_tests {
+    use super::{Axis, BresenhamLine, Point, Slope, X, Y};
+
+    #[test]
+    fn test_point_and_slope() {
+        let (a, b) = (Point { x: 1, y: 2 }, Point { x: 4, y: 6 });
+        assert_eq!(a.dist_sq(&b), 25);
+
+        let right = Slope::new(&a, &Point { x: 5, y: 2 });
+        let down = Slope::new(&a, &Point { x: 1, y: 7 });
+        assert!(right.cross(&down) > 0);
+        assert!(down.cross(&right) < 0);
+
+        assert!(X::bound_check(10, 1, &Point { x: 9, y: 50 }));
+        assert!(!Y::bound_check(10, 1, &Point { x: 9, y: 1 }));
+    }
+
+    #[test]
+    fn test_bresenham_line() {
+        let from = Point { x: 0, y: 0 };
+        let line = BresenhamLine::<X>::new(&from, &Point { x: 6, y: 3 }).collect::<Vec<_>>();
+        let xs = line.iter().map(|p| p.x).collect::<Vec<_>>();
+        assert_eq!(xs, [0, 1, 2, 3, 4, 5]);
+        assert!(line.windows(2).all(|w| (0..=1).contains(&(w[1].y - w[0].y))));
+        assert_eq!(line.last(), Some(&Point { x: 5, y: 2 }));
+
+        let line = BresenhamLine::<Y>::new(&Point { x: 3, y: 5 }, &from).collect::<Vec<_>>();
+        assert_eq!(line.iter().map(|p| p.y).collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+
+        assert_eq!(BresenhamLine::<X>::new(&from, &Point { x: 0, y: 9 }).count(), 0);
+    }
+}
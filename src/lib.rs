@@ -14,6 +14,18 @@
 //!
 //! ## Quick Start
 //!
+//! ### One Call Helpers
+//!
+//! ```rust,no_run
+//! use qrism::{read_qr, save_qr, SaveOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! save_qr("Hello, World!", "qr.png", SaveOptions::default())?;
+//! let messages = read_qr("qr.png")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ### Simple QR Code Generation
 //!
 //! ```rust
@@ -25,6 +37,7 @@
 //!     .build()?;
 //!
 //! let img = qr.to_image(4);  // 4x scale factor
+//! # #[cfg(feature = "png")]
 //! img.save("simple_qr.png")?;
 //! # Ok(())
 //! # }
@@ -40,11 +53,11 @@
 //! let qr = QRBuilder::new(data.as_bytes())
 //!     .version(Version::Normal(2))  // QR version (size) - if not provided, finds smallest version to fit data
 //!     .ec_level(ECLevel::M)         // Error correction level - if not provided, defaults to ECLevel::M
-//!     .high_capacity(false)         // Standard capacity mode - if not provided, defaults to false
 //!     .mask(MaskPattern::new(3))    // Mask pattern - if not provided, finds best mask based on penalty score
 //!     .build()?;
 //!
 //! let img = qr.to_image(4);  // 4x scale factor
+//! # #[cfg(feature = "png")]
 //! img.save("configured_qr.png")?;
 //! # Ok(())
 //! # }
@@ -61,7 +74,7 @@
 //!
 //! // Detect and decode QR codes
 //! let mut res = detect_qr(&img);
-//! if let Some(symbol) = res.symbols().first_mut() {
+//! if let Some(symbol) = res.symbols().first() {
 //!     let (metadata, message) = symbol.decode()?;
 //!     println!("Decoded: {}", message);
 //! }
@@ -72,18 +85,21 @@
 //! ### Reading a high capacity multi color QR
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "poly")]
 //! use qrism::reader::detect_hc_qr;
 //!
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # #[cfg(feature = "poly")] {
 //! // Load and prepare image
 //! let img = image::open("qr_code.png")?;
 //!
 //! // Detect and decode QR codes
 //! let mut res = detect_hc_qr(&img);
-//! if let Some(symbol) = res.symbols().first_mut() {
+//! if let Some(symbol) = res.symbols().first() {
 //!     let (metadata, message) = symbol.decode()?;
 //!     println!("Decoded: {}", message);
 //! }
+//! # }
 //! # Ok(())
 //! # }
 //! ```
@@ -91,9 +107,13 @@
 //! ## QR Code Components
 //!
 //! ### Versions
-//! - **Micro QR**: Versions 1-4 for small data (experimental)
+//! - **Micro QR**: Versions M1-M4, 11x11 to 17x17 modules, when set with `version`. Generation only
 //! - **Normal QR**: Versions 1-40, with sizes from 21x21 to 177x177 modules
 //!
+//! The `max-version-5`, `max-version-10` & `max-version-20` features cap the normal versions, for
+//! readers on memory constrained devices. Buffers sized by the highest version shrink with the
+//! cap, so a decode takes bounded memory, though it still makes a few heap allocations per symbol.
+//!
 //! ### Error Correction Levels
 //! - **L (Low)**: ~7% error correction
 //! - **M (Medium)**: ~15% error correction  
@@ -114,23 +134,121 @@
 //! When decoded, the three separate data streams are combined to reconstruct the original data,
 //! effectively tripling the storage capacity compared to traditional QR codes.
 //!
+//! High capacity support is behind the default `poly` feature. Binaries that only need standard
+//! QR codes can drop it with `default-features = false`.
+//!
 //! ### Example Usage
 //!
 //! ```rust
 //! use qrism::QRBuilder;
 //!
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # #[cfg(feature = "poly")] {
 //! // Create a high capacity QR code with 3x storage
-//! let large_data = "Large dataset that would not fit in a standard QR code...".repeat(10);
+//! let large_data = "Large dataset that would not fit in a standard QR code...".repeat(4);
 //! let qr = QRBuilder::new(large_data.as_bytes())
 //!     .high_capacity(true)  // Enable high capacity mode
 //!     .build()?;
 //!
 //! let img = qr.to_image(4);
+//! # #[cfg(feature = "png")]
 //! img.save("high_capacity_qr.png")?;
+//! # }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Manual Segments
+//!
+//! Data is split into numeric, alphanumeric & byte segments by an optimizer. When the structure of
+//! the payload is known ahead, e.g. for millions of codes of the same format, segments can be given
+//! directly & the optimizer is skipped.
+//!
+//! ```rust
+//! use qrism::{Mode, QRBuilder, Segment};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let serial = b"0012345678";
+//! let segs = vec![Segment::new(Mode::Alphanumeric, b"SN "), Segment::new(Mode::Numeric, serial)];
+//! let qr = QRBuilder::new(b"").segments(segs).build()?;
+//! assert_eq!(qr.metadata().segments()[1].mode, Mode::Numeric);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Vector Output
+//!
+//! For engravers, cutters & plotters, QRs can be written as svg or dxf, with a single outline per
+//! connected dark region rather than a square per module. G-code is also written for cnc marking,
+//! tracing the outlines or filling the dark modules line by line.
+//!
+//! ```rust
+//! use qrism::{GcodeConfig, QRBuilder, ToolPath};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let qr = QRBuilder::new(b"Hello, world!").build()?;
+//! let svg = qr.to_svg(0.5); // 0.5 mm modules
+//! assert!(svg.contains("<path d=\"M4 4H11V11H4ZM5 5V10H10V5Z\"/>"));
+//! let dxf = qr.to_dxf(0.5);
+//! assert!(dxf.ends_with("EOF\n"));
+//!
+//! let mut config = GcodeConfig::new();
+//! config.size(20.0).tool_path(ToolPath::Raster).laser(500); // 20 mm wide with the quiet zone
+//! let gcode = qr.to_gcode(&config)?;
+//! assert!(gcode.contains("M3 S500"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Reed-Solomon Blocks
+//!
+//! The error correction of QR codes is usable on its own through `qrism::ec`, e.g. to protect
+//! records of a custom container format.
+//!
+//! ```rust
+//! use qrism::ec::{correct_block, encode_block};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut blk = encode_block(b"record 42", 6)?;
+//! blk[3] ^= 0xFF; // Damaged in transit
+//! blk[5] ^= 0xFF; // Known to be unreliable
+//! assert_eq!(correct_block(&mut blk, 6, &[5])?, 2);
+//! assert_eq!(&blk[..9], b"record 42");
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## File Transfer
+//!
+//! `qrism::transfer` sends files too large for one symbol as a sequence of frames, e.g. a looping
+//! animation on a screen, each frame carrying its sequence number & a checksum. The receiver takes
+//! frames in any order & reassembles the file once all have been seen. In fountain mode, repair
+//! frames let the receiver rebuild the file from any frames slightly more than its chunks, so
+//! frames the camera misses needn't come round again.
+//!
+//! ```rust
+//! use qrism::transfer::{TransferReceiver, TransferSender};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let data = b"A file too large for a single QR code".repeat(20);
+//! let mut sender = TransferSender::new(&data);
+//! sender.chunk_size(300);
+//!
+//! let mut receiver = TransferReceiver::new();
+//! for frame in sender.frames()?.iter().rev() {
+//!     receiver.push_payload(frame)?;
+//! }
+//! assert_eq!(receiver.finish()?, data);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Image Formats
+//!
+//! Reading & writing files goes through the `image` crate, re-exported as `qrism::image`. Its
+//! codecs are opt in through the `png`, `jpeg`, `gif`, `tiff` & `webp` features, all enabled by
+//! default, while `all-formats` enables every codec `image` supports. The rest of the API works on
+//! in memory images & compiles without any codec.
 
 #![allow(
     clippy::items_after_test_module,
@@ -143,12 +261,34 @@
 
 pub mod builder;
 pub(crate) mod common;
+pub mod geometry;
+pub mod quick;
 pub mod reader;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transfer;
+pub mod verify;
+pub mod version_db;
 
-pub use builder::QRBuilder;
+pub use builder::{
+    lint, BuildReport, EncodePlan, GcodeConfig, Lint, LintKind, PixelFormat, QRBuilder,
+    QRBuilderDataSink, QRBuilderOwned, RenderConfig, RenderWarning, Sheet, StageTimings, ToolPath,
+};
+pub use common::codec::{
+    explain_segments, Mode, PaddingPolicy, Segment, SegmentCost, SegmentExplanation,
+};
+pub use common::ec;
 pub use common::mask::MaskPattern;
-pub use common::metadata::{ECLevel, Version};
+pub use common::metadata::{
+    BlockGroup, BlockLayout, Color, ECLevel, ECUsage, Fingerprint, Fnc1, Metadata, SegmentInfo,
+    StructuredAppend, SymbologyId, Version, MAX_VERSION,
+};
+pub use common::utils::{English, MessageCatalog, QRError, QRResult};
 pub(crate) use common::*;
+// Charsets for DecodeHints::charset_override
+pub use encoding_rs;
+pub use image;
+pub use quick::{read_qr, save_qr, SaveOptions};
 pub use reader::*;
 
 #[cfg(test)]
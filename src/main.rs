@@ -1,16 +1,105 @@
 use std::error::Error;
 use std::path::Path;
+use std::process::ExitCode;
 
-use qrism::{detect_hc_qr, detect_qr, ECLevel, Version};
-use qrism::{MaskPattern, QRBuilder};
+#[cfg(feature = "poly")]
+use qrism::detect_hc_qr;
+use qrism::pages::detect_qr_file;
+use qrism::report::{report, to_json, to_json_with_source};
+use qrism::source::SourceInfo;
+use qrism::verify::{verify_labels, EXIT_USAGE};
+use qrism::{detect_qr, ECLevel, Version};
+use qrism::{DetectOptions, MaskPattern, QRBuilder};
 
-fn main() -> Result<(), Box<dyn Error>> {
+const VERIFY_USAGE: &str = "usage: qrism verify --expected <labels.csv> --images <dir>";
+const DECODE_USAGE: &str = "usage: qrism decode [--json [--source]] <image>";
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("verify") => return Ok(verify(&args[1..])),
+        Some("decode") => return Ok(decode(&args[1..])),
+        _ => {}
+    }
+    demo()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+// Checks printed labels against the expected payloads, exiting with the failure bits of
+// VerifyReport::exit_code
+fn verify(args: &[String]) -> ExitCode {
+    let (mut expected, mut images) = (None, None);
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, val] if flag == "--expected" => expected = Some(val),
+            [flag, val] if flag == "--images" => images = Some(val),
+            _ => (expected, images) = (None, None),
+        }
+    }
+    let (Some(expected), Some(images)) = (expected, images) else {
+        eprintln!("{VERIFY_USAGE}");
+        return ExitCode::from(EXIT_USAGE as u8);
+    };
+
+    match verify_labels(expected, images, &DetectOptions::default()) {
+        Ok(report) => {
+            print!("{}", report.summary());
+            ExitCode::from(report.exit_code() as u8)
+        }
+        Err(e) => {
+            eprintln!("qrism verify: {e}");
+            ExitCode::from(EXIT_USAGE as u8)
+        }
+    }
+}
+
+// Prints the messages of the QRs in the image, or with --json the report of every symbol. With
+// --source the json also has the file name, EXIF timestamp & GPS position of the image, for
+// audits of who scanned what where. Fails if no symbol decodes
+fn decode(args: &[String]) -> ExitCode {
+    let (mut json, mut source, mut path) = (false, false, None);
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--source" => source = true,
+            p if path.is_none() && !p.starts_with("--") => path = Some(p),
+            _ => path = None,
+        }
+    }
+    let Some(path) = path.filter(|_| json || !source) else {
+        eprintln!("{DECODE_USAGE}");
+        return ExitCode::from(EXIT_USAGE as u8);
+    };
+
+    let res = detect_qr_file(path, &DetectOptions::default());
+    let src = if source { SourceInfo::from_file(path).map(Some) } else { Ok(None) };
+    let (mut res, src) = match (res, src) {
+        (Ok(res), Ok(src)) => (res, src),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("qrism decode: {e}");
+            return ExitCode::from(EXIT_USAGE as u8);
+        }
+    };
+
+    let reports = report(&mut res);
+    match (json, src) {
+        (true, Some(src)) => println!("{}", to_json_with_source(&reports, &src)),
+        (true, None) => println!("{}", to_json(&reports)),
+        (false, _) => reports.iter().filter_map(|r| r.text.as_ref()).for_each(|t| println!("{t}")),
+    }
+    match reports.iter().any(|r| r.decoded) {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+// Builds a QR, saves it & reads it back
+fn demo() -> Result<(), Box<dyn Error>> {
     // Create a QR code
     let data = "Hello, world! This is a demonstration of QR code generation and reading.";
     let qr = QRBuilder::new(data.as_bytes())
         .version(Version::Normal(5)) // If not provided, finds smallest version to fit the data
         .ec_level(ECLevel::M) // Defaults to ECLevel::M
-        .high_capacity(false) // Defaults to false, use true for high capacity QR
         .mask(MaskPattern::new(1)) // If not provided, finds best mask based on penalty score
         .build()?;
 
@@ -23,9 +112,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Read the QR code back
     let read_path = Path::new("./assets/qr_example.png");
     let img = image::open(read_path)?;
-    let mut res = detect_qr(&img);
+    let res = detect_qr(&img);
 
-    if let Some(symbol) = res.symbols().first_mut() {
+    if let Some(symbol) = res.symbols().first() {
         let (metadata, decoded_message) = symbol.decode()?;
         println!("Decoded message: {}", decoded_message);
         println!("QR metadata: {:?}", metadata);
@@ -34,11 +123,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Read high capacity QR code
+    #[cfg(feature = "poly")]
+    read_hc_qr()?;
+
+    Ok(())
+}
+
+#[cfg(feature = "poly")]
+fn read_hc_qr() -> Result<(), Box<dyn Error>> {
     let read_path = Path::new("./assets/example6.png");
     let img = image::open(read_path)?;
-    let mut res = detect_hc_qr(&img);
+    let res = detect_hc_qr(&img);
 
-    if let Some(symbol) = res.symbols().first_mut() {
+    if let Some(symbol) = res.symbols().first() {
         let (metadata, decoded_message) = symbol.decode()?;
         println!("Decoded message: {}", decoded_message);
         println!("High capacity QR metadata: {:?}", metadata);
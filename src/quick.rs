@@ -0,0 +1,146 @@
+use std::{error::Error, path::Path};
+
+use image::ImageResult;
+
+use crate::{
+    builder::{GcodeConfig, QRBuilder},
+    metadata::{ECLevel, Version},
+    reader::{pages::detect_qr_file, DetectOptions},
+};
+
+// Save options
+//------------------------------------------------------------------------------
+
+// Settings for save_qr. Defaults pick the smallest version at ec level M with 4 pixel modules,
+// or 1 mm modules in vector files
+#[derive(Debug, Clone, Copy)]
+pub struct SaveOptions {
+    ver: Option<Version>,
+    ecl: ECLevel,
+    module_sz: u32,
+    module_mm: f64,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self { ver: None, ecl: ECLevel::M, module_sz: 4, module_mm: 1.0 }
+    }
+}
+
+impl SaveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version(&mut self, ver: Version) -> &mut Self {
+        self.ver = Some(ver);
+        self
+    }
+
+    pub fn ec_level(&mut self, ecl: ECLevel) -> &mut Self {
+        self.ecl = ecl;
+        self
+    }
+
+    // Side of a module in pixels for raster images
+    pub fn module_size(&mut self, module_sz: u32) -> &mut Self {
+        self.module_sz = module_sz;
+        self
+    }
+
+    // Side of a module in millimetres for svg, dxf & g-code files
+    pub fn module_mm(&mut self, module_mm: f64) -> &mut Self {
+        self.module_mm = module_mm;
+        self
+    }
+}
+
+// One call helpers
+//------------------------------------------------------------------------------
+
+// Builds a QR for the data & saves it as an image. The format follows the file extension, and
+// its codec feature must be enabled. Svg & dxf files get vector outlines of the dark regions, and
+// g-code files, i.e. gcode or nc, trace those outlines
+pub fn save_qr(
+    data: impl AsRef<[u8]>,
+    path: impl AsRef<Path>,
+    opts: SaveOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut builder = QRBuilder::new(data.as_ref());
+    builder.ec_level(opts.ecl);
+    if let Some(ver) = opts.ver {
+        builder.version(ver);
+    }
+
+    let qr = builder.build()?;
+    let path = path.as_ref();
+    let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("svg") => std::fs::write(path, qr.to_svg(opts.module_mm))?,
+        Some("dxf") => std::fs::write(path, qr.to_dxf(opts.module_mm))?,
+        Some("gcode" | "nc") => {
+            let size = opts.module_mm * qr.image_size(1) as f64;
+            std::fs::write(path, qr.to_gcode(GcodeConfig::new().size(size))?)?
+        }
+        _ => qr.to_image(opts.module_sz.max(1)).save(path)?,
+    }
+    Ok(())
+}
+
+// Reads the messages of all QRs in the file, in reading order & page by page. Symbols that are
+// located but fail to decode are skipped
+pub fn read_qr(path: impl AsRef<Path>) -> ImageResult<Vec<String>> {
+    let res = detect_qr_file(path, &DetectOptions::default())?;
+    Ok(res.symbols().iter().filter_map(|s| s.decode().ok()).map(|(_, msg)| msg).collect())
+}
+
+#[cfg(all(test, feature = "png"))]
+mod quick_tests {
+    use super::{read_qr, save_qr, SaveOptions};
+    use crate::{metadata::Version, ECLevel};
+
+    #[test]
+    fn test_save_and_read_qr() {
+        let path = std::env::temp_dir().join(format!("qrism_quick_{}.png", std::process::id()));
+
+        let mut opts = SaveOptions::new();
+        opts.version(Version::Normal(3)).ec_level(ECLevel::H).module_size(3);
+        save_qr("Hello, world!", &path, opts).unwrap();
+        let img = image::open(&path).unwrap();
+        assert_eq!(img.width(), (29 + 8) * 3);
+        assert_eq!(read_qr(&path).unwrap(), ["Hello, world!"]);
+
+        opts.version(Version::Normal(1));
+        assert!(save_qr("a".repeat(100), &path, opts).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(read_qr(&path).is_err());
+    }
+
+    #[test]
+    fn test_save_qr_vector() {
+        let path = std::env::temp_dir().join(format!("qrism_quick_{}.dxf", std::process::id()));
+        save_qr("Hello, world!", &path, SaveOptions::new()).unwrap();
+        let dxf = std::fs::read_to_string(&path).unwrap();
+        assert!(dxf.starts_with("0\nSECTION\n") && dxf.contains("\nPOLYLINE\n"));
+        std::fs::remove_file(&path).unwrap();
+
+        let path = path.with_extension("svg");
+        save_qr("Hello, world!", &path, SaveOptions::new()).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains("width=\"29mm\" height=\"29mm\""));
+        std::fs::remove_file(&path).unwrap();
+
+        // Outlines traced at the module size, 29 modules of 4 mm with the quiet zone. The pixel
+        // module size doesn't apply
+        let mut opts = SaveOptions::new();
+        opts.module_mm(4.0).module_size(7);
+        for ext in ["gcode", "nc"] {
+            let path = path.with_extension(ext);
+            save_qr("Hello, world!", &path, opts).unwrap();
+            let gcode = std::fs::read_to_string(&path).unwrap();
+            assert!(gcode.starts_with("G21\nG90\n") && gcode.ends_with("M2\n"));
+            assert!(gcode.contains("G0 X16.000 Y100.000\n"));
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use crate::metadata::Color;
+
+use super::binarize::BinaryImage;
+
+// Linear barcode
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BarcodeFormat {
+    Code128,
+    Ean13,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    format: BarcodeFormat,
+    text: String,
+    y: u32,     // Scanline on which the barcode was read
+    left: u32,  // Left edge of the first bar
+    right: u32, // Right edge of the last bar
+}
+
+impl Barcode {
+    pub fn format(&self) -> BarcodeFormat {
+        self.format
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+
+    pub fn right(&self) -> u32 {
+        self.right
+    }
+}
+
+// Scanline run lengths
+//------------------------------------------------------------------------------
+
+// Run lengths of alternating colors along a row. The first run is always white (possibly empty),
+// so even indices are spaces and odd indices are bars
+fn row_runs(img: &BinaryImage, y: u32) -> Vec<u32> {
+    let mut runs = Vec::with_capacity(128);
+    let mut last = Color::White;
+    let mut len = 0;
+
+    for x in 0..img.w {
+        let color = match img.get(x, y) {
+            Some(px) if px.get_color() == Color::White => Color::White,
+            Some(_) => Color::Black,
+            None => break,
+        };
+        if color != last {
+            runs.push(len);
+            last = color;
+            len = 0;
+        }
+        len += 1;
+    }
+    runs.push(len);
+
+    // Trailing quiet zone for barcodes touching the right edge
+    if last == Color::Black {
+        runs.push(0);
+    }
+
+    runs
+}
+
+// ENTRY POINT FOR LOCATING BARCODES
+// Scans evenly spaced rows of the binary image and decodes any Code128 or EAN-13 barcode found.
+// Each barcode is reported once, on the first row it was read from
+pub fn locate_barcodes(img: &BinaryImage) -> Vec<Barcode> {
+    let mut barcodes: Vec<Barcode> = Vec::new();
+    let step = std::cmp::max(1, img.h / BARCODE_SCAN_LINES);
+
+    for y in (0..img.h).step_by(step as usize) {
+        let runs = row_runs(img, y);
+        let mut rev = runs.clone();
+        rev.reverse();
+
+        let width = img.w;
+        let found = scan_runs(&runs, y).into_iter().chain(
+            // Flip the edges back to image coordinates for upside down barcodes
+            scan_runs(&rev, y).into_iter().map(|mut b| {
+                (b.left, b.right) = (width - 1 - b.right, width - 1 - b.left);
+                b
+            }),
+        );
+
+        for b in found {
+            if !barcodes.iter().any(|o| o.format == b.format && o.text == b.text) {
+                barcodes.push(b);
+            }
+        }
+    }
+
+    barcodes
+}
+
+fn scan_runs(runs: &[u32], y: u32) -> Vec<Barcode> {
+    let mut res = Vec::new();
+    let mut i = 1;
+
+    while i < runs.len() {
+        let left = runs[..i].iter().sum::<u32>();
+        let decoded = decode_ean13(runs, i)
+            .map(|(text, n)| (BarcodeFormat::Ean13, text, n))
+            .or_else(|| decode_code128(runs, i).map(|(text, n)| (BarcodeFormat::Code128, text, n)));
+
+        match decoded {
+            Some((format, text, n)) => {
+                let right = left + runs[i..i + n].iter().sum::<u32>() - 1;
+                res.push(Barcode { format, text, y, left, right });
+                i += n + 1;
+            }
+            None => i += 2,
+        }
+    }
+
+    res
+}
+
+// Ratio of the quiet zone to the module width that a barcode must be preceded by
+fn has_quiet_zone(runs: &[u32], start: usize, unit: f64) -> bool {
+    (start == 1 && runs[0] == 0) || runs[start - 1] as f64 >= unit * QUIET_ZONE_MODULES
+}
+
+// Sum of deviations of runs from the pattern after scaling runs to the pattern's module count
+fn pattern_variance(runs: &[u32], pattern: &[u8]) -> f64 {
+    let total = runs.iter().sum::<u32>() as f64;
+    let modules = pattern.iter().map(|&p| p as u32).sum::<u32>() as f64;
+    let unit = total / modules;
+    runs.iter().zip(pattern).map(|(&r, &p)| (r as f64 / unit - p as f64).abs()).sum()
+}
+
+fn best_match(runs: &[u32], patterns: &[[u8; 6]]) -> Option<usize> {
+    let (idx, var) = patterns
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i, pattern_variance(runs, p)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())?;
+    (var <= MAX_SYMBOL_VARIANCE).then_some(idx)
+}
+
+// EAN-13
+//------------------------------------------------------------------------------
+
+// Returns the decoded digits and the number of runs consumed, if the runs starting at `start`
+// form a valid EAN-13 barcode
+fn decode_ean13(runs: &[u32], start: usize) -> Option<(String, usize)> {
+    if start + EAN13_RUNS > runs.len() {
+        return None;
+    }
+
+    let runs = &runs[start..start + EAN13_RUNS];
+    let unit = runs.iter().sum::<u32>() as f64 / 95.0;
+    let is_guard = |g: &[u32]| g.iter().all(|&r| (r as f64 / unit - 1.0).abs() <= 0.5);
+    if !is_guard(&runs[..3]) || !is_guard(&runs[27..32]) || !is_guard(&runs[56..]) {
+        return None;
+    }
+
+    let mut digits = [0u8; 13];
+    let mut parity = 0u8;
+
+    // Left half is encoded in L or G codes, parity of which encodes the first digit
+    for (k, digit) in runs[3..27].chunks_exact(4).enumerate() {
+        let (d, is_g) = match_ean_digit(digit, true)?;
+        digits[k + 1] = d;
+        parity = (parity << 1) | is_g as u8;
+    }
+
+    // Right half is encoded in R codes
+    for (k, digit) in runs[32..56].chunks_exact(4).enumerate() {
+        let (d, _) = match_ean_digit(digit, false)?;
+        digits[k + 7] = d;
+    }
+
+    digits[0] = EAN13_FIRST_DIGIT_PARITY.iter().position(|&p| p == parity)? as u8;
+
+    let checksum = digits[..12]
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| if i & 1 == 0 { d as u32 } else { d as u32 * 3 })
+        .sum::<u32>();
+    if (10 - checksum % 10) % 10 != digits[12] as u32 {
+        return None;
+    }
+
+    let text = digits.iter().map(|&d| (d + b'0') as char).collect();
+    Some((text, EAN13_RUNS))
+}
+
+// Returns the digit and whether it was G coded. L and R codes share run widths
+fn match_ean_digit(runs: &[u32], allow_g: bool) -> Option<(u8, bool)> {
+    let mut best = (f64::MAX, 0, false);
+    for (d, pattern) in EAN13_DIGIT_WIDTHS.iter().enumerate() {
+        let var = pattern_variance(runs, pattern);
+        if var < best.0 {
+            best = (var, d as u8, false);
+        }
+        if allow_g {
+            let mut g = *pattern;
+            g.reverse();
+            let var = pattern_variance(runs, &g);
+            if var < best.0 {
+                best = (var, d as u8, true);
+            }
+        }
+    }
+    (best.0 <= MAX_SYMBOL_VARIANCE).then_some((best.1, best.2))
+}
+
+// Code 128
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+// Returns the decoded text and the number of runs consumed, if the runs starting at `start`
+// form a valid Code 128 barcode
+fn decode_code128(runs: &[u32], start: usize) -> Option<(String, usize)> {
+    if start + 6 > runs.len() {
+        return None;
+    }
+
+    let start_val = best_match(&runs[start..start + 6], &CODE128_PATTERNS[103..106])? + 103;
+    let unit = runs[start..start + 6].iter().sum::<u32>() as f64 / 11.0;
+    if !has_quiet_zone(runs, start, unit) {
+        return None;
+    }
+
+    // Collect symbol values till the stop pattern
+    let mut vals = vec![start_val];
+    let mut i = start + 6;
+    loop {
+        if i + 7 <= runs.len()
+            && pattern_variance(&runs[i..i + 7], &CODE128_STOP) <= MAX_SYMBOL_VARIANCE
+        {
+            i += 7;
+            break;
+        }
+        if i + 6 > runs.len() {
+            return None;
+        }
+        vals.push(best_match(&runs[i..i + 6], &CODE128_PATTERNS[..103])?);
+        i += 6;
+    }
+
+    // Start, check symbol and atleast one character
+    if vals.len() < 3 {
+        return None;
+    }
+
+    let check = vals.pop()?;
+    let sum = vals.iter().enumerate().skip(1).map(|(i, &v)| i * v).sum::<usize>() + start_val;
+    if sum % 103 != check {
+        return None;
+    }
+
+    let text = code128_text(&vals)?;
+    Some((text, i - start))
+}
+
+fn code128_text(vals: &[usize]) -> Option<String> {
+    let mut set = match vals[0] {
+        103 => CodeSet::A,
+        104 => CodeSet::B,
+        105 => CodeSet::C,
+        _ => return None,
+    };
+    let mut shift = false;
+    let mut bytes = Vec::with_capacity(vals.len() * 2);
+
+    for &v in &vals[1..] {
+        let cur = match (shift, set) {
+            (true, CodeSet::A) => CodeSet::B,
+            (true, CodeSet::B) => CodeSet::A,
+            _ => set,
+        };
+        shift = false;
+
+        match (cur, v) {
+            (CodeSet::C, 0..=99) => {
+                bytes.push(b'0' + (v / 10) as u8);
+                bytes.push(b'0' + (v % 10) as u8);
+            }
+            (CodeSet::A, 0..=63) | (CodeSet::B, 0..=95) => bytes.push(v as u8 + 32),
+            (CodeSet::A, 64..=95) => bytes.push(v as u8 - 64),
+            (CodeSet::A | CodeSet::B, 98) => shift = true,
+            (CodeSet::A | CodeSet::B, 99) => set = CodeSet::C,
+            (CodeSet::A | CodeSet::C, 100) => set = CodeSet::B,
+            (CodeSet::B | CodeSet::C, 101) => set = CodeSet::A,
+            // FNC1-4 carry no text
+            _ => {}
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod barcode_tests {
+    use image::DynamicImage;
+
+    use image::{GrayImage, Luma};
+
+    use super::{
+        BarcodeFormat, CODE128_PATTERNS, CODE128_STOP, EAN13_DIGIT_WIDTHS, EAN13_FIRST_DIGIT_PARITY,
+    };
+    use crate::reader::detect_barcodes;
+
+    fn render_modules(modules: &[u8], module_sz: u32) -> GrayImage {
+        // Modules alternate between bar and space widths, starting with a bar
+        let qz = 10 * module_sz;
+        let w = modules.iter().map(|&m| m as u32).sum::<u32>() * module_sz + 2 * qz;
+        let h = 60;
+        let mut img = GrayImage::from_pixel(w, h, Luma([255]));
+        let mut x = qz;
+        for (i, &m) in modules.iter().enumerate() {
+            let sz = m as u32 * module_sz;
+            if i & 1 == 0 {
+                for px in x..x + sz {
+                    for y in 0..h {
+                        img.put_pixel(px, y, Luma([0]));
+                    }
+                }
+            }
+            x += sz;
+        }
+        img
+    }
+
+    fn code128_modules(data: &str) -> Vec<u8> {
+        let vals: Vec<usize> = data.bytes().map(|b| (b - 32) as usize).collect();
+        let check = vals.iter().enumerate().map(|(i, &v)| (i + 1) * v).sum::<usize>() + 104;
+        let mut modules = CODE128_PATTERNS[104].to_vec();
+        for v in vals.iter().chain([check % 103].iter()) {
+            modules.extend(CODE128_PATTERNS[*v]);
+        }
+        modules.extend(CODE128_STOP);
+        modules
+    }
+
+    fn ean13_modules(digits: &str) -> Vec<u8> {
+        let d: Vec<usize> = digits.bytes().map(|b| (b - b'0') as usize).collect();
+        let parity = EAN13_FIRST_DIGIT_PARITY[d[0]];
+        let mut modules = vec![1, 1, 1];
+        for (k, &v) in d[1..7].iter().enumerate() {
+            let mut w = EAN13_DIGIT_WIDTHS[v];
+            if parity >> (5 - k) & 1 == 1 {
+                w.reverse();
+            }
+            modules.extend(w);
+        }
+        modules.extend([1, 1, 1, 1, 1]);
+        for &v in d[7..].iter() {
+            modules.extend(EAN13_DIGIT_WIDTHS[v]);
+        }
+        modules.extend([1, 1, 1]);
+        modules
+    }
+
+    #[test]
+    fn test_decode_code128() {
+        let data = "qrism-128 Receipt #42";
+        let img = render_modules(&code128_modules(data), 2);
+        let barcodes = detect_barcodes(&DynamicImage::ImageLuma8(img));
+        assert_eq!(barcodes.len(), 1);
+        assert_eq!(barcodes[0].format(), BarcodeFormat::Code128);
+        assert_eq!(barcodes[0].text(), data);
+    }
+
+    #[test]
+    fn test_decode_ean13() {
+        let data = "4006381333931";
+        let img = render_modules(&ean13_modules(data), 2);
+        let barcodes = detect_barcodes(&DynamicImage::ImageLuma8(img));
+        assert_eq!(barcodes.len(), 1);
+        assert_eq!(barcodes[0].format(), BarcodeFormat::Ean13);
+        assert_eq!(barcodes[0].text(), data);
+    }
+
+    #[test]
+    fn test_decode_upside_down_ean13() {
+        let data = "5901234123457";
+        let mut modules = ean13_modules(data);
+        modules.reverse();
+        let img = render_modules(&modules, 2);
+        let barcodes = detect_barcodes(&DynamicImage::ImageLuma8(img));
+        assert_eq!(barcodes.len(), 1);
+        assert_eq!(barcodes[0].text(), data);
+    }
+
+    #[test]
+    fn test_reject_bad_checksum() {
+        let img = render_modules(&ean13_modules("4006381333932"), 2);
+        let barcodes = detect_barcodes(&DynamicImage::ImageLuma8(img));
+        assert!(barcodes.is_empty());
+
+        // Code 128 with the check symbol, after the start & data symbols, swapped for another
+        let data = "qrism-128";
+        let mut modules = code128_modules(data);
+        let img = render_modules(&modules, 2);
+        assert_eq!(detect_barcodes(&DynamicImage::ImageLuma8(img)).len(), 1);
+        let check = 6 * (data.len() + 1);
+        let other = if modules[check..check + 6] == CODE128_PATTERNS[0] { 1 } else { 0 };
+        modules[check..check + 6].copy_from_slice(&CODE128_PATTERNS[other]);
+        let img = render_modules(&modules, 2);
+        let barcodes = detect_barcodes(&DynamicImage::ImageLuma8(img));
+        assert!(barcodes.is_empty());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Number of rows scanned across the height of the image
+const BARCODE_SCAN_LINES: u32 = 64;
+
+// Minimum width of the quiet zone preceding a barcode, in modules. Kept low because adaptive
+// thresholding darkens wide blank margins, leaving only a sliver of white next to the first bar
+const QUIET_ZONE_MODULES: f64 = 1.0;
+
+// Maximum summed deviation in modules for a run group to match a symbol pattern
+const MAX_SYMBOL_VARIANCE: f64 = 1.5;
+
+// Start guard + 6 digits + middle guard + 6 digits + end guard
+const EAN13_RUNS: usize = 3 + 24 + 5 + 24 + 3;
+
+// Run widths of L codes (space, bar, space, bar). R codes share the widths with inverted colors,
+// and G codes are the reversed widths
+static EAN13_DIGIT_WIDTHS: [[u8; 4]; 10] = [
+    [3, 2, 1, 1],
+    [2, 2, 2, 1],
+    [2, 1, 2, 2],
+    [1, 4, 1, 1],
+    [1, 1, 3, 2],
+    [1, 2, 3, 1],
+    [1, 1, 1, 4],
+    [1, 3, 1, 2],
+    [1, 2, 1, 3],
+    [3, 1, 1, 2],
+];
+
+// L/G parity of the left 6 digits for each first digit. Set bits are G codes
+static EAN13_FIRST_DIGIT_PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+
+// Run widths (bar, space, bar, space, bar, space) of Code 128 symbols. 103-105 are start codes
+static CODE128_PATTERNS: [[u8; 6]; 106] = [
+    [2, 1, 2, 2, 2, 2],
+    [2, 2, 2, 1, 2, 2],
+    [2, 2, 2, 2, 2, 1],
+    [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2],
+    [1, 3, 1, 2, 2, 2],
+    [1, 2, 2, 2, 1, 3],
+    [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2],
+    [2, 2, 1, 2, 1, 3],
+    [2, 2, 1, 3, 1, 2],
+    [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2],
+    [1, 2, 2, 1, 3, 2],
+    [1, 2, 2, 2, 3, 1],
+    [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2],
+    [1, 2, 3, 2, 2, 1],
+    [2, 2, 3, 2, 1, 1],
+    [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1],
+    [2, 1, 3, 2, 1, 2],
+    [2, 2, 3, 1, 1, 2],
+    [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2],
+    [3, 2, 1, 1, 2, 2],
+    [3, 2, 1, 2, 2, 1],
+    [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2],
+    [3, 2, 2, 2, 1, 1],
+    [2, 1, 2, 1, 2, 3],
+    [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1],
+    [1, 1, 1, 3, 2, 3],
+    [1, 3, 1, 1, 2, 3],
+    [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3],
+    [1, 3, 2, 1, 1, 3],
+    [1, 3, 2, 3, 1, 1],
+    [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3],
+    [2, 3, 1, 3, 1, 1],
+    [1, 1, 2, 1, 3, 3],
+    [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1],
+    [1, 1, 3, 1, 2, 3],
+    [1, 1, 3, 3, 2, 1],
+    [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1],
+    [2, 1, 1, 3, 3, 1],
+    [2, 3, 1, 1, 3, 1],
+    [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1],
+    [2, 1, 3, 1, 3, 1],
+    [3, 1, 1, 1, 2, 3],
+    [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1],
+    [3, 1, 2, 1, 1, 3],
+    [3, 1, 2, 3, 1, 1],
+    [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1],
+    [2, 2, 1, 4, 1, 1],
+    [4, 3, 1, 1, 1, 1],
+    [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2],
+    [1, 2, 1, 1, 2, 4],
+    [1, 2, 1, 4, 2, 1],
+    [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1],
+    [1, 1, 2, 2, 1, 4],
+    [1, 1, 2, 4, 1, 2],
+    [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1],
+    [1, 4, 2, 1, 1, 2],
+    [1, 4, 2, 2, 1, 1],
+    [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4],
+    [4, 1, 3, 1, 1, 1],
+    [2, 4, 1, 1, 1, 2],
+    [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2],
+    [1, 2, 1, 1, 4, 2],
+    [1, 2, 1, 2, 4, 1],
+    [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2],
+    [1, 2, 4, 2, 1, 1],
+    [4, 1, 1, 2, 1, 2],
+    [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1],
+    [2, 1, 2, 1, 4, 1],
+    [2, 1, 4, 1, 2, 1],
+    [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3],
+    [1, 1, 1, 3, 4, 1],
+    [1, 3, 1, 1, 4, 1],
+    [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1],
+    [4, 1, 1, 1, 1, 3],
+    [4, 1, 1, 3, 1, 1],
+    [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1],
+    [3, 1, 1, 1, 4, 1],
+    [4, 1, 1, 1, 3, 1],
+    [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4],
+    [2, 1, 1, 2, 3, 2],
+];
+
+static CODE128_STOP: [u8; 7] = [2, 3, 3, 1, 1, 1, 2];
@@ -1,8 +1,11 @@
 use std::collections::VecDeque;
 
-use image::{GenericImageView, Luma, Pixel as ImgPixel, Rgb, RgbImage};
+use image::{GenericImageView, GrayImage, Luma, Pixel as ImgPixel, Rgb, RgbImage};
 
-use crate::metadata::Color;
+use crate::{
+    metadata::Color,
+    utils::{QRError, QRResult},
+};
 
 use super::utils::accumulate::AreaAndCentreLocator;
 use super::utils::{
@@ -104,6 +107,42 @@ impl Binarize for Luma<u8> {
     }
 }
 
+// Binarizer plugin trait
+//------------------------------------------------------------------------------
+
+// Converts a grayscale image into the binary image that the rest of the pipeline consumes.
+// Implement it to plug in custom binarization, e.g. neural or OpenCV based, via
+// DetectOptions::binarizer. The output must have the same dimensions as the input
+pub trait Binarizer: Send + Sync {
+    fn binarize(&self, img: &GrayImage) -> BinaryImage;
+}
+
+impl std::fmt::Debug for dyn Binarizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "dyn Binarizer")
+    }
+}
+
+// Adaptive thresholding over a grid of blocks. Default binarizer of the pipeline
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdaptiveBinarizer;
+
+impl Binarizer for AdaptiveBinarizer {
+    fn binarize(&self, img: &GrayImage) -> BinaryImage {
+        BinaryImage::prepare(img)
+    }
+}
+
+// Otsu thresholding over a grid of blocks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OtsuBinarizer;
+
+impl Binarizer for OtsuBinarizer {
+    fn binarize(&self, img: &GrayImage) -> BinaryImage {
+        BinaryImage::otsu(img)
+    }
+}
+
 // Image type for reader
 //------------------------------------------------------------------------------
 
@@ -301,6 +340,18 @@ impl BinaryImage {
         Self { buffer, regions, w, h }
     }
 
+    // Builds binary image from the color of each pixel. Any color other than white is treated
+    // as dark by the finder & symbol locators
+    pub fn from_fn(w: u32, h: u32, mut f: impl FnMut(u32, u32) -> Color) -> Self {
+        let mut buffer = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                buffer.push(Pixel::Unvisited(f(x, y)));
+            }
+        }
+        Self { buffer, regions: Vec::with_capacity(100), w, h }
+    }
+
     /// Performs absolute/naive binarization
     pub fn global_thresholding(img: RgbImage) -> Self {
         let (w, h) = img.dimensions();
@@ -546,6 +597,137 @@ impl BinaryImage {
     }
 }
 
+impl BinaryImage {
+    // Rethresholds the pixels within each quad with per channel Otsu thresholds computed from the
+    // quad alone. Camera gamma & white balance shift the channels of a colour symbol unevenly,
+    // which the block thresholds spanning the background don't follow
+    pub(crate) fn rethreshold_quads(&mut self, img: &RgbImage, quads: &[[Point; 4]]) {
+        for quad in quads {
+            let xs = quad.map(|p| p.x);
+            let ys = quad.map(|p| p.y);
+            let x0 = xs.into_iter().min().unwrap_or(0).max(0) as u32;
+            let y0 = ys.into_iter().min().unwrap_or(0).max(0) as u32;
+            let x1 = (xs.into_iter().max().unwrap_or(0).max(0) as u32).min(self.w);
+            let y1 = (ys.into_iter().max().unwrap_or(0).max(0) as u32).min(self.h);
+            let inside = |x: u32, y: u32| in_quad(quad, x as i32, y as i32);
+
+            let mut hists = [Histogram::new(false); 3];
+            for y in y0..y1 {
+                for x in (x0..x1).filter(|&x| inside(x, y)) {
+                    for (hist, &val) in hists.iter_mut().zip(img.get_pixel(x, y).channels()) {
+                        hist.accumulate(val);
+                    }
+                }
+            }
+            if hists[0].total == 0 {
+                continue;
+            }
+
+            let thresh = hists.map(|h| h.threshold());
+            for y in y0..y1 {
+                for x in (x0..x1).filter(|&x| inside(x, y)) {
+                    let px = img.get_pixel(x, y).0;
+                    let byte = (0..3).fold(0, |b, i| b << 1 | (px[i] > thresh[i]) as u8);
+                    let clr = Color::try_from(byte).unwrap();
+                    self.buffer[(y * self.w + x) as usize] = Pixel::Unvisited(clr);
+                }
+            }
+        }
+    }
+}
+
+// Whether the point lies within the convex quad, given in either winding order
+fn in_quad(quad: &[Point; 4], x: i32, y: i32) -> bool {
+    let mut sides = [false; 2];
+    for i in 0..4 {
+        let (a, b) = (quad[i], quad[(i + 1) % 4]);
+        let cross = (b.x - a.x) as i64 * (y - a.y) as i64 - (b.y - a.y) as i64 * (x - a.x) as i64;
+        if cross != 0 {
+            sides[(cross > 0) as usize] = true;
+        }
+    }
+    !(sides[0] && sides[1])
+}
+
+// Color clustering binarizing
+//------------------------------------------------------------------------------
+
+impl BinaryImage {
+    // Splits pixels into 2 clusters in Lab space using k-means, and maps the cluster with lower
+    // lightness to dark. Recovers colored codes, such as red on yellow, whose modules don't
+    // separate in luma or in individual channels
+    pub fn color_cluster(img: &RgbImage) -> Self {
+        let (w, h) = img.dimensions();
+        let labs = img.pixels().map(to_lab).collect::<Vec<_>>();
+
+        // Centroids are seeded with the darkest & lightest samples
+        let step = std::cmp::max(labs.len() / CLUSTER_SAMPLE_COUNT, 1);
+        let samples = labs.iter().step_by(step).copied().collect::<Vec<_>>();
+        let by_lightness = |a: &&[f64; 3], b: &&[f64; 3]| a[0].total_cmp(&b[0]);
+        let (Some(&dark), Some(&light)) =
+            (samples.iter().min_by(by_lightness), samples.iter().max_by(by_lightness))
+        else {
+            return Self { buffer: Vec::new(), regions: Vec::with_capacity(100), w, h };
+        };
+
+        let mut cents = [dark, light];
+        for _ in 0..CLUSTER_ITERATIONS {
+            let mut sums = [[0.0; 3]; 2];
+            let mut counts = [0usize; 2];
+            for s in samples.iter() {
+                let k = nearest_centroid(&cents, s);
+                sums[k].iter_mut().zip(s).for_each(|(a, v)| *a += v);
+                counts[k] += 1;
+            }
+            for k in 0..2 {
+                if counts[k] > 0 {
+                    cents[k] = sums[k].map(|v| v / counts[k] as f64);
+                }
+            }
+        }
+
+        let dark_idx = if cents[0][0] <= cents[1][0] { 0 } else { 1 };
+        let buffer = labs
+            .iter()
+            .map(|l| {
+                let is_dark = nearest_centroid(&cents, l) == dark_idx;
+                Pixel::Unvisited(if is_dark { Color::Black } else { Color::White })
+            })
+            .collect();
+
+        Self { buffer, regions: Vec::with_capacity(100), w, h }
+    }
+}
+
+fn nearest_centroid(cents: &[[f64; 3]; 2], lab: &[f64; 3]) -> usize {
+    let dist = |c: &[f64; 3]| c.iter().zip(lab).map(|(a, b)| (a - b) * (a - b)).sum::<f64>();
+    if dist(&cents[0]) <= dist(&cents[1]) {
+        0
+    } else {
+        1
+    }
+}
+
+// Converts sRGB to CIE L*a*b* with D65 white point
+fn to_lab(px: &Rgb<u8>) -> [f64; 3] {
+    let lin = px.0.map(|c| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    let [r, g, b] = lin;
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f64| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
 // Util functions
 impl BinaryImage {
     pub fn get(&self, x: u32, y: u32) -> Option<Pixel> {
@@ -579,6 +761,12 @@ impl BinaryImage {
         Some(&self.buffer[idx])
     }
 
+    // Region the pixel at the point was flood filled into, if it was
+    pub(crate) fn region_at(&self, pt: &Point) -> Option<&Region> {
+        let id = self.get_at_point(pt)?.get_id()?;
+        self.regions.get(id)
+    }
+
     pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut Pixel> {
         let w = self.w;
         let h = self.h;
@@ -610,15 +798,82 @@ impl BinaryImage {
 
     #[cfg(test)]
     pub fn save(&self, path: &Path) -> ImageResult<()> {
-        let w = self.w;
-        let mut img = RgbImage::new(w, self.h);
-        for (i, p) in self.buffer.iter().enumerate() {
-            let i = i as u32;
-            let (x, y) = (i % w, i / w);
-            img.put_pixel(x, y, (*p).into());
+        self.to_rgb().save(path)
+    }
+
+    pub(crate) fn to_rgb(&self) -> RgbImage {
+        RgbImage::from_fn(self.w, self.h, |x, y| self.buffer[(y * self.w + x) as usize].into())
+    }
+}
+
+// Bit packed cache
+//------------------------------------------------------------------------------
+
+// Packs the pixel colours, so binarizing large scans can be cached between passes of a pipeline.
+// Layout is the magic, bits per pixel, width & height as little endian u32s, then rows of pixels
+// packed msb first without padding. Black & white images take a bit per pixel, set for white, &
+// others take 3 bits of their colour. Flood fill state isn't kept
+impl BinaryImage {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let is_mono =
+            self.buffer.iter().all(|p| matches!(p.get_color(), Color::Black | Color::White));
+        let bpp = if is_mono { 1 } else { 3 };
+
+        let mut out = Vec::with_capacity(PACKED_HEADER_LEN + (self.buffer.len() * bpp).div_ceil(8));
+        out.extend_from_slice(PACKED_MAGIC);
+        out.push(bpp as u8);
+        out.extend_from_slice(&self.w.to_le_bytes());
+        out.extend_from_slice(&self.h.to_le_bytes());
+
+        let (mut acc, mut len) = (0u32, 0);
+        for px in self.buffer.iter() {
+            let val = match (is_mono, px.get_color()) {
+                (true, c) => (c == Color::White) as u32,
+                (false, c) => c as u32,
+            };
+            acc = acc << bpp | val;
+            len += bpp;
+            while len >= 8 {
+                len -= 8;
+                out.push((acc >> len) as u8);
+            }
         }
-        img.save(path).unwrap();
-        Ok(())
+        if len > 0 {
+            out.push((acc << (8 - len)) as u8);
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> QRResult<Self> {
+        if data.len() < PACKED_HEADER_LEN || !data.starts_with(PACKED_MAGIC) {
+            return Err(QRError::MalformedBinaryImage);
+        }
+        let bpp = data[4] as usize;
+        let w = u32::from_le_bytes(data[5..9].try_into().unwrap());
+        let h = u32::from_le_bytes(data[9..13].try_into().unwrap());
+        let px_count = (w as usize).checked_mul(h as usize).ok_or(QRError::MalformedBinaryImage)?;
+        let body = &data[PACKED_HEADER_LEN..];
+        if !matches!(bpp, 1 | 3) || body.len() != (px_count * bpp).div_ceil(8) {
+            return Err(QRError::MalformedBinaryImage);
+        }
+
+        let mut buffer = Vec::with_capacity(px_count);
+        let mask = (1 << bpp) - 1;
+        let (mut acc, mut len, mut bytes) = (0u32, 0, body.iter());
+        while buffer.len() < px_count {
+            while len < bpp {
+                acc = acc << 8 | *bytes.next().ok_or(QRError::MalformedBinaryImage)? as u32;
+                len += 8;
+            }
+            len -= bpp;
+            let val = (acc >> len & mask) as u8;
+            let clr = match bpp {
+                1 => Color::from(val == 1),
+                _ => Color::try_from(val).map_err(|_| QRError::MalformedBinaryImage)?,
+            };
+            buffer.push(Pixel::Unvisited(clr));
+        }
+        Ok(Self { buffer, regions: Vec::with_capacity(100), w, h })
     }
 }
 
@@ -716,7 +971,17 @@ impl BinaryImage {
 //------------------------------------------------------------------------------
 
 // Number of blocks the shorter dimension of image should be divided into
-const BLOCK_COUNT: f64 = 20.0;
+pub(crate) const BLOCK_COUNT: f64 = 20.0;
 
 // Number of blocks along row/col in a grid
 const BLOCK_GRID_SIZE: usize = 5;
+
+// Max pixels sampled to find color clusters
+const CLUSTER_SAMPLE_COUNT: usize = 1 << 14;
+
+const CLUSTER_ITERATIONS: usize = 8;
+
+const PACKED_MAGIC: &[u8; 4] = b"QRBI";
+
+// Magic, bits per pixel, width & height
+const PACKED_HEADER_LEN: usize = 13;
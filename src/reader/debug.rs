@@ -0,0 +1,165 @@
+use std::{fmt, sync::Mutex};
+#[cfg(feature = "png")]
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use image::{Rgb, RgbImage};
+
+use super::{binarize::BinaryImage, symbol::Symbol, utils::geometry::Point};
+
+// Debug stage
+//------------------------------------------------------------------------------
+
+// Points of the detection pipeline at which intermediate images are captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugStage {
+    Binarized, // Binary image the finders are searched in
+    Finders,   // Binary image with the finder centres marked
+    Symbols,   // Binary image with the outlines of located symbols
+}
+
+impl DebugStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binarized => "binarized",
+            Self::Finders => "finders",
+            Self::Symbols => "symbols",
+        }
+    }
+}
+
+// Debug sink
+//------------------------------------------------------------------------------
+
+// Receives intermediate images of the detection pipeline, set via DetectOptions::debug_sink.
+// Images are only rendered if a sink is set, and nothing touches the file system unless the sink
+// does. Closures taking the stage & image are sinks too
+pub trait DebugSink: Send + Sync {
+    fn capture(&self, stage: DebugStage, img: &RgbImage);
+}
+
+impl fmt::Debug for dyn DebugSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dyn DebugSink")
+    }
+}
+
+impl<F: Fn(DebugStage, &RgbImage) + Send + Sync> DebugSink for F {
+    fn capture(&self, stage: DebugStage, img: &RgbImage) {
+        self(stage, img)
+    }
+}
+
+// Keeps captured images in memory, e.g. to attach them to a server side diagnostic report
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    imgs: Mutex<Vec<(DebugStage, RgbImage)>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Removes & returns the images captured so far, oldest first
+    pub fn take(&self) -> Vec<(DebugStage, RgbImage)> {
+        std::mem::take(&mut *self.imgs.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl DebugSink for MemorySink {
+    fn capture(&self, stage: DebugStage, img: &RgbImage) {
+        self.imgs.lock().unwrap_or_else(|e| e.into_inner()).push((stage, img.clone()));
+    }
+}
+
+// Saves captured images as numbered pngs in a directory, e.g. 000_binarized.png. Failed writes
+// are skipped so debugging never fails detection
+#[cfg(feature = "png")]
+#[derive(Debug)]
+pub struct DirSink {
+    dir: PathBuf,
+    count: AtomicUsize,
+}
+
+#[cfg(feature = "png")]
+impl DirSink {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), count: AtomicUsize::new(0) }
+    }
+}
+
+#[cfg(feature = "png")]
+impl DebugSink for DirSink {
+    fn capture(&self, stage: DebugStage, img: &RgbImage) {
+        let i = self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = img.save(self.dir.join(format!("{i:03}_{}.png", stage.name())));
+    }
+}
+
+// Renders the stages of a detection pass into the sink
+pub(crate) fn capture_stages(
+    sink: &dyn DebugSink,
+    img: &BinaryImage,
+    finders: &[Point],
+    symbols: &[Symbol],
+) {
+    let mut canvas = img.to_rgb();
+    sink.capture(DebugStage::Binarized, &canvas);
+
+    let bin = canvas.clone();
+    for f in finders {
+        f.highlight(&mut canvas, FINDER_COLOR);
+    }
+    sink.capture(DebugStage::Finders, &canvas);
+
+    canvas = bin;
+    for s in symbols {
+        s.draw_outline(&mut canvas, SYMBOL_COLOR);
+    }
+    sink.capture(DebugStage::Symbols, &canvas);
+}
+
+#[cfg(test)]
+mod debug_tests {
+    use std::sync::{Arc, Mutex};
+
+    use image::{DynamicImage, RgbImage};
+
+    use super::{DebugStage, MemorySink};
+    use crate::{builder::QRBuilder, reader::detect_qr_with, DetectOptions};
+
+    #[test]
+    fn test_memory_sink() {
+        let qr = QRBuilder::new(b"Debug").build().unwrap();
+        let img = DynamicImage::ImageRgb8(qr.to_image(4));
+
+        let sink = Arc::new(MemorySink::new());
+        let mut opts = DetectOptions::new();
+        opts.debug_sink(sink.clone());
+        assert_eq!(detect_qr_with(&img, &opts).symbols().len(), 1);
+
+        let imgs = sink.take();
+        let stages = imgs.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+        assert_eq!(stages, [DebugStage::Binarized, DebugStage::Finders, DebugStage::Symbols]);
+        assert!(imgs.iter().all(|(_, i)| i.dimensions() == (img.width(), img.height())));
+        assert_ne!(imgs[0].1, imgs[1].1);
+        assert_ne!(imgs[0].1, imgs[2].1);
+        assert!(sink.take().is_empty());
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_by_sink = seen.clone();
+        opts.debug_sink(Arc::new(move |s, _: &RgbImage| seen_by_sink.lock().unwrap().push(s)));
+        detect_qr_with(&img, &opts);
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const FINDER_COLOR: Rgb<u8> = Rgb([255, 0, 0]);
+
+const SYMBOL_COLOR: Rgb<u8> = Rgb([0, 160, 255]);
@@ -0,0 +1,197 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use super::{symbol::Symbol, DecodeResult};
+use crate::metadata::Metadata;
+
+// Unique payload
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct UniquePayload {
+    hash: u64,
+    meta: Metadata,
+    text: String,
+    centres: Vec<(i32, i32)>, // One centre per distinct copy of the symbol
+    extents: Vec<f64>,        // Edge length of each copy, used as clustering radius
+    detections: usize,
+}
+
+impl UniquePayload {
+    pub fn meta(&self) -> &Metadata {
+        &self.meta
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Number of distinct copies, i.e. position clusters, of the payload
+    pub fn occurrences(&self) -> usize {
+        self.centres.len()
+    }
+
+    // Total number of times the payload was decoded across all symbols and frames
+    pub fn detections(&self) -> usize {
+        self.detections
+    }
+
+    pub fn centres(&self) -> &[(i32, i32)] {
+        &self.centres
+    }
+
+    fn add_position(&mut self, centre: (i32, i32), extent: f64) {
+        self.detections += 1;
+
+        // Copies whose centres lie within half a symbol of each other are the same copy
+        let is_near = |(c, e): (&(i32, i32), &f64)| {
+            let (dx, dy) = ((c.0 - centre.0) as f64, (c.1 - centre.1) as f64);
+            let r = e.max(extent) * CLUSTER_RADIUS_FACTOR;
+            dx * dx + dy * dy <= r * r
+        };
+
+        if !self.centres.iter().zip(self.extents.iter()).any(is_near) {
+            self.centres.push(centre);
+            self.extents.push(extent);
+        }
+    }
+}
+
+// Deduplicates payloads across symbols of a frame and across frames. Payloads are keyed by
+// hash of the decoded text, and copies of a payload are clustered by position
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Default, Clone)]
+pub struct PayloadDeduper {
+    payloads: Vec<UniquePayload>,
+}
+
+impl PayloadDeduper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Decodes all symbols of the frame and merges the payloads. Returns the number of symbols
+    // successfully decoded
+    pub fn add_frame(&mut self, res: &DecodeResult) -> usize {
+        res.symbols().iter().map(|s| self.add_symbol(s)).filter(|&ok| ok).count()
+    }
+
+    pub fn add_symbol(&mut self, sym: &Symbol) -> bool {
+        let Ok((meta, text)) = sym.decode() else { return false };
+        let Ok(centre) = sym.centre() else { return false };
+        let extent = sym.extent().unwrap_or(0.0);
+        self.add_payload(meta, text, (centre.x, centre.y), extent);
+        true
+    }
+
+    fn add_payload(&mut self, meta: Metadata, text: String, centre: (i32, i32), extent: f64) {
+        let hash = payload_hash(&text);
+        let idx = match self.payloads.iter().position(|p| p.hash == hash && p.text == text) {
+            Some(i) => i,
+            None => {
+                let p = UniquePayload {
+                    hash,
+                    meta,
+                    text,
+                    centres: Vec::new(),
+                    extents: Vec::new(),
+                    detections: 0,
+                };
+                self.payloads.push(p);
+                self.payloads.len() - 1
+            }
+        };
+        self.payloads[idx].add_position(centre, extent);
+    }
+
+    // Unique payloads in the order they were first seen
+    pub fn payloads(&self) -> &[UniquePayload] {
+        &self.payloads
+    }
+
+    pub fn into_payloads(self) -> Vec<UniquePayload> {
+        self.payloads
+    }
+}
+
+fn payload_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod dedup_tests {
+    use image::{DynamicImage, GenericImage, RgbImage};
+
+    use super::PayloadDeduper;
+    use crate::{builder::QRBuilder, metadata::Metadata, reader::detect_qr};
+
+    fn sheet(msgs: &[&str]) -> DynamicImage {
+        let qrs: Vec<RgbImage> = msgs
+            .iter()
+            .map(|m| QRBuilder::new(m.as_bytes()).build().unwrap().to_image(3))
+            .collect();
+        let w = qrs.iter().map(|q| q.width()).sum();
+        let h = qrs.iter().map(|q| q.height()).max().unwrap();
+        let mut img = RgbImage::from_pixel(w, h, image::Rgb([255, 255, 255]));
+        let mut x = 0;
+        for q in qrs.iter() {
+            img.copy_from(q, x, 0).unwrap();
+            x += q.width();
+        }
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_dedup_copies_in_frame() {
+        let img = sheet(&["Hello, world!", "Hello, world!", "Another payload"]);
+        let res = detect_qr(&img);
+
+        let mut dedup = PayloadDeduper::new();
+        assert_eq!(dedup.add_frame(&res), 3);
+
+        let payloads = dedup.payloads();
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads.iter().find(|p| p.text() == "Hello, world!").unwrap().occurrences(), 2);
+        assert_eq!(
+            payloads.iter().find(|p| p.text() == "Another payload").unwrap().occurrences(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_dedup_across_frames() {
+        let img = sheet(&["Hello, world!"]);
+        let mut dedup = PayloadDeduper::new();
+        for _ in 0..3 {
+            dedup.add_frame(&detect_qr(&img));
+        }
+
+        let payloads = dedup.payloads();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].occurrences(), 1);
+        assert_eq!(payloads[0].detections(), 3);
+    }
+
+    #[test]
+    fn test_cluster_by_position() {
+        let meta = Metadata::new(None, None, None);
+        let mut dedup = PayloadDeduper::new();
+        dedup.add_payload(meta.clone(), "abc".to_string(), (100, 100), 50.0);
+        dedup.add_payload(meta.clone(), "abc".to_string(), (110, 95), 50.0);
+        dedup.add_payload(meta, "abc".to_string(), (300, 100), 50.0);
+
+        assert_eq!(dedup.payloads()[0].occurrences(), 2);
+        assert_eq!(dedup.payloads()[0].detections(), 3);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Fraction of symbol edge length within which two centres are considered the same copy
+const CLUSTER_RADIUS_FACTOR: f64 = 0.5;
@@ -1,13 +1,75 @@
 use crate::metadata::Color;
 
 use super::{
-    binarize::{BinaryImage, Pixel},
-    utils::{geometry::Point, verify_finder_pattern, FINDER_PATTERN_TOLERANCE},
+    binarize::{BinaryImage, Pixel, Region},
+    options::{DetectOptions, ScanAngles},
+    utils::{
+        accumulate::MidpointLine,
+        geometry::{Axis, Point, X, Y},
+        trim_outer_runs, verify_finder_pattern, FINDER_PATTERN_TOLERANCE,
+    },
+    view::ImageView,
 };
 
 #[cfg(test)]
 use image::RgbImage;
 
+// Scan direction
+//------------------------------------------------------------------------------
+
+// Direction of the lines scanned for finders. Diagonals cross finders of symbols rotated near
+// 45 degrees along their axes, where rows only cut through them on a few lines near the centre
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ScanDir {
+    Row,
+    Diagonal,     // Down & right
+    AntiDiagonal, // Up & right
+}
+
+impl ScanDir {
+    // Pixel step along the line
+    fn step(self) -> (i32, i32) {
+        match self {
+            Self::Row => (1, 0),
+            Self::Diagonal => (1, 1),
+            Self::AntiDiagonal => (1, -1),
+        }
+    }
+
+    // Pixel step perpendicular to the line, along which finders are crosschecked
+    fn cross(self) -> (i32, i32) {
+        match self {
+            Self::Row => (0, 1),
+            Self::Diagonal => (1, -1),
+            Self::AntiDiagonal => (1, 1),
+        }
+    }
+
+    fn line_count(self, w: u32, h: u32) -> u32 {
+        match self {
+            Self::Row => h,
+            Self::Diagonal | Self::AntiDiagonal => w + h - 1,
+        }
+    }
+
+    // First pixel & pixel count of a line. Diagonals start on the left edge, then the top edge
+    // for down & right lines or the bottom edge for up & right lines
+    fn line(self, i: u32, w: u32, h: u32) -> (Point, u32) {
+        let (x, y) = match self {
+            Self::Row => return (Point { x: 0, y: i as i32 }, w),
+            Self::Diagonal if i < h => (0, h - 1 - i),
+            Self::Diagonal => (i + 1 - h, 0),
+            Self::AntiDiagonal if i < h => (0, i),
+            Self::AntiDiagonal => (i + 1 - h, h - 1),
+        };
+        let len = match self {
+            Self::AntiDiagonal => (w - x).min(y + 1),
+            _ => (w - x).min(h - y),
+        };
+        (Point { x: x as i32, y: y as i32 }, len)
+    }
+}
+
 // Finder line
 //------------------------------------------------------------------------------
 
@@ -15,12 +77,14 @@ use image::RgbImage;
 // ^    ^        ^
 // left |        right
 //      stone
+// Positions are pixel counts along the scan line from its origin
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 struct DatumLine {
     left: u32,
     stone: u32,
     right: u32,
-    y: u32,
+    origin: Point,
+    dir: ScanDir,
 }
 
 // Line scanner to detect finder line
@@ -32,20 +96,21 @@ struct LineScanner {
     prev: Option<Color>,  // Last observed color
     flips: u32,           // Count of color changes
     pos: u32,             // Current position
-    y: u32,
+    origin: Point,        // First pixel of the line
+    dir: ScanDir,
 }
 
 impl LineScanner {
-    pub fn new() -> Self {
-        Self { buffer: [0; 6], prev: None, flips: 0, pos: 0, y: 0 }
+    pub fn new(dir: ScanDir) -> Self {
+        Self { buffer: [0; 6], prev: None, flips: 0, pos: 0, origin: Point::default(), dir }
     }
 
-    pub fn reset(&mut self, y: u32) {
+    pub fn reset(&mut self, origin: Point) {
         self.buffer[5] = 0;
         self.prev = None;
         self.flips = 0;
         self.pos = 0;
-        self.y = y;
+        self.origin = origin;
     }
 
     pub fn advance(&mut self, color: Color) -> Option<DatumLine> {
@@ -61,39 +126,51 @@ impl LineScanner {
         self.prev = Some(color);
         self.flips += 1;
 
-        if self.is_finder_line() {
-            Some(DatumLine {
-                left: self.pos - 1 - self.buffer[..5].iter().sum::<u32>(),
-                stone: self.pos - 1 - self.buffer[2..5].iter().sum::<u32>(),
-                right: self.pos - 1 - self.buffer[4],
-                y: self.y,
-            })
+        if self.flips >= 5 && is_finder_ratio(&self.buffer[..5]) {
+            Some(DatumLine::from_runs(self.pos, self.origin, self.dir, &self.buffer[..5]))
         } else {
             None
         }
     }
+}
 
-    // Validates whether last 5 run lengths are in the 1:1:3:1:1 ratio
-    fn is_finder_line(&self) -> bool {
-        if self.flips < 5 {
-            return false;
-        }
+impl DatumLine {
+    // Builds the datum from the last 5 runs, which end right before pos - 1. The left run is
+    // trimmed if it merged into artwork
+    fn from_runs(pos: u32, origin: Point, dir: ScanDir, runs: &[u32]) -> Self {
+        let right = pos - 1 - runs[4];
+        let stone = right - runs[2] - runs[3];
+        let mut trimmed = [runs[0], runs[1], runs[2], runs[3], runs[4]];
+        trim_outer_runs(&mut trimmed, &FINDER_PATTERN);
+        Self { left: stone - trimmed[1] - trimmed[0], stone, right, origin, dir }
+    }
 
-        // Verify 1:1:3:1:1 ratio with 95% tolerance. The tolerance is very linient because
-        // the validations in the later stages of the pipeline are more stringent
-        let avg = self.buffer[..5].iter().sum::<u32>() as f64 / 7.0;
-        let tol = avg * FINDER_PATTERN_TOLERANCE;
+    // Pixel at the position along the line
+    fn at(&self, pos: u32) -> Point {
+        let (dx, dy) = self.dir.step();
+        Point { x: self.origin.x + dx * pos as i32, y: self.origin.y + dy * pos as i32 }
+    }
+}
 
-        let ratio: [f64; 5] = [1.0, 1.0, 3.0, 1.0, 1.0];
-        for (i, r) in ratio.iter().enumerate() {
-            let rl = self.buffer[i] as f64;
-            if rl < r * avg - tol || rl > r * avg + tol {
-                return false;
-            }
-        }
+// Validates whether 5 run lengths are in the 1:1:3:1:1 ratio, after trimming outer runs merged
+// into adjacent artwork
+fn is_finder_ratio(runs: &[u32]) -> bool {
+    let mut runs = [runs[0], runs[1], runs[2], runs[3], runs[4]];
+    trim_outer_runs(&mut runs, &FINDER_PATTERN);
+
+    // Verify 1:1:3:1:1 ratio with 95% tolerance. The tolerance is very linient because
+    // the validations in the later stages of the pipeline are more stringent
+    let avg = runs.iter().sum::<u32>() as f64 / 7.0;
+    let tol = avg * FINDER_PATTERN_TOLERANCE;
 
-        true
+    for (i, r) in FINDER_PATTERN.iter().enumerate() {
+        let rl = runs[i] as f64;
+        if rl < r * avg - tol || rl > r * avg + tol {
+            return false;
+        }
     }
+
+    true
 }
 
 // Locate finders
@@ -101,35 +178,86 @@ impl LineScanner {
 
 // ENTRY POINT FOR LOCATING FINDER
 // Returns a list of centres of potential finder
-pub fn locate_finders(img: &mut BinaryImage) -> Vec<Point> {
+pub fn locate_finders(img: &mut BinaryImage, opts: &DetectOptions) -> Vec<Point> {
     let mut finders = Vec::with_capacity(100);
-    let w = img.w;
-    let h = img.h;
-    let mut scanner = LineScanner::new();
+    scan_finders(img, ScanDir::Row, opts, &mut finders);
+    locate_diagonal_finders(img, opts, &mut finders);
+    finders
+}
+
+// Extra passes along both diagonals if enabled. Finders already found by an earlier pass are
+// marked, so each is only reported once
+pub(crate) fn locate_diagonal_finders(
+    img: &mut BinaryImage,
+    opts: &DetectOptions,
+    finders: &mut Vec<Point>,
+) {
+    if opts.scan_angles == ScanAngles::RowsAndDiagonals {
+        scan_finders(img, ScanDir::Diagonal, opts, finders);
+        scan_finders(img, ScanDir::AntiDiagonal, opts, finders);
+    }
+}
 
-    for y in 0..h {
-        for x in 0..w {
+fn scan_finders(img: &mut BinaryImage, dir: ScanDir, opts: &DetectOptions, out: &mut Vec<Point>) {
+    let (w, h) = (img.w, img.h);
+    let mut scanner = LineScanner::new(dir);
+
+    for i in 0..dir.line_count(w, h) {
+        let (origin, len) = dir.line(i, w, h);
+        scanner.reset(origin);
+        let (dx, dy) = dir.step();
+
+        for k in 0..len as i32 {
+            let (x, y) = ((origin.x + dx * k) as u32, (origin.y + dy * k) as u32);
             let color = img.get(x, y).unwrap().get_color();
             let datum = match scanner.advance(color) {
                 Some(d) => d,
                 None => continue,
             };
 
-            if let Some(centre) = verify_and_mark_finder(img, &datum) {
-                finders.push(centre);
+            if let Some(centre) = verify_and_mark_finder(img, &datum, opts) {
+                out.push(centre);
             }
         }
 
-        // Handles an edge case where the QR is located at the right edge of the image
+        // Handles an edge case where the QR is located at the end of the line
         if let Some(datum) = scanner.advance(Color::White) {
-            if let Some(centre) = verify_and_mark_finder(img, &datum) {
-                finders.push(centre);
+            if let Some(centre) = verify_and_mark_finder(img, &datum, opts) {
+                out.push(centre);
             }
         }
-
-        scanner.reset(y + 1);
     }
+}
+
+// Finder line found by a row scan outside the cpu pipeline, e.g. on the gpu. Pos is one past
+// the pixel where the colour flipped after the 5 runs, as in the line scanner
+#[cfg(feature = "gpu")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FinderRun {
+    pub pos: u32,
+    pub y: u32,
+    pub runs: [u32; 5],
+}
 
+// Verifies externally scanned finder lines in the same row major order as locate_finders, so
+// both yield the same finders for the same binary image
+#[cfg(feature = "gpu")]
+pub(crate) fn locate_finders_from(
+    img: &mut BinaryImage,
+    runs: &mut [FinderRun],
+    opts: &DetectOptions,
+) -> Vec<Point> {
+    runs.sort_unstable_by_key(|r| (r.y, r.pos));
+    let mut finders: Vec<_> = runs
+        .iter()
+        .filter(|r| is_finder_ratio(&r.runs))
+        .filter_map(|r| {
+            let origin = Point { x: 0, y: r.y as i32 };
+            let datum = DatumLine::from_runs(r.pos, origin, ScanDir::Row, &r.runs);
+            verify_and_mark_finder(img, &datum, opts)
+        })
+        .collect();
+    locate_diagonal_finders(img, opts, &mut finders);
     finders
 }
 
@@ -138,14 +266,20 @@ pub fn locate_finders(img: &mut BinaryImage) -> Vec<Point> {
 // 2. The region wasn't already marked as candidate
 // 3. Ring and stone regions aren't connected
 // 4. Area of stone region is roughly 37.5% of ring region
-// 5. Crosscheck 1:1:3:1:1 pattern along Y axis
+// 5. Crosscheck 1:1:3:1:1 pattern across the scan line
+// If stylized finders are enabled, 1 & 4 are relaxed to a stone shape check
 // Finally it marks the regions are candidate and returns the centre
-fn verify_and_mark_finder(img: &mut BinaryImage, datum: &DatumLine) -> Option<Point> {
-    let (l, r, s, y) = (datum.left, datum.right, datum.stone, datum.y);
+fn verify_and_mark_finder(
+    img: &mut BinaryImage,
+    datum: &DatumLine,
+    opts: &DetectOptions,
+) -> Option<Point> {
+    let (l, r, s) = (datum.at(datum.left), datum.at(datum.right), datum.at(datum.stone));
+    let coords = |p: Point| (p.x as u32, p.y as u32);
 
     // If pixel has been visited, check if regions is already marked as finder
-    if matches!(img.get(s, y), Some(Pixel::Visited(..))) {
-        let stone = img.get_region((s, y));
+    if matches!(img.get_at_point(&s), Some(Pixel::Visited(..))) {
+        let stone = img.get_region(coords(s));
 
         // Exit if stone is already made a candidate from previous iterations
         if stone.is_finder {
@@ -153,46 +287,239 @@ fn verify_and_mark_finder(img: &mut BinaryImage, datum: &DatumLine) -> Option<Po
         }
     }
 
-    let sx = r - (s - l) * 5 / 4;
-    let seed = Point { x: sx as i32, y: datum.y as i32 };
-    let pattern = [1.0, 1.0, 3.0, 1.0, 1.0];
-    let max_run = (r - l) * 2; // Setting a loose upper limit on the run
+    let seed = datum.at(datum.right - (datum.stone - datum.left) * 5 / 4);
+    let max_run = (datum.right - datum.left) * 2; // Setting a loose upper limit on the run
 
-    // Verify 1:1:3:1:1 pattern along Y axis. Returns the top and bottom pts if valid
-    let (t, b) = verify_finder_pattern(img, &seed, &pattern, max_run)?;
+    // Verify 1:1:3:1:1 pattern across the scan line. Returns the steps to either end if valid
+    let cross = datum.dir.cross();
+    let (up, down) = verify_finder_pattern(img, &seed, cross, &FINDER_PATTERN, max_run)?;
+    let t = Point { x: seed.x - cross.0 * up as i32, y: seed.y - cross.1 * up as i32 };
+    let b = Point { x: seed.x + cross.0 * down as i32, y: seed.y + cross.1 * down as i32 };
 
-    let stone = img.get_region((s, y)).clone();
-    let ring = img.get_region((r, y)).clone();
+    let stone = img.get_region(coords(s)).clone();
+    let ring = img.get_region(coords(r)).clone();
 
-    // Check if left, top and bottom points lie within the ring
-    let lid = img.get(l, y)?.get_id()?;
-    let tid = img.get(sx, t)?.get_id()?;
-    let bid = img.get(sx, b)?.get_id()?;
-    if lid != ring.id || tid != ring.id || bid != ring.id {
+    // False if ring & stone are connected
+    if img.get_at_point(&r) == img.get_at_point(&s) {
         return None;
     }
 
-    // False if ring & stone are connected, or if ring to stone area is not roughly 37,5%
+    // Check if left, top and bottom points lie within the ring, and if ring to stone area is
+    // roughly 37,5%
+    let in_ring = |p: Point| img.get_at_point(&p).and_then(|p| p.get_id()) == Some(ring.id);
     let ratio = stone.area * 100 / ring.area;
-    if img.get(r, y) == img.get(s, y) || ratio <= 10 || 70 <= ratio {
+    let is_strict = in_ring(l) && in_ring(t) && in_ring(b) && 10 < ratio && ratio < 70;
+
+    let is_stylized = || opts.stylized_finders && is_stylized_finder(img, &stone, datum, up, down);
+
+    // A ring merged with adjacent artwork is far larger than expected, so the finder is checked
+    // on its internals, module by module, as for stylized finders
+    let is_framed = || ratio <= 10 && is_stylized_finder(img, &stone, datum, up, down);
+    if !is_strict && !is_stylized() && !is_framed() {
         return None;
     }
 
-    img.get_region((r, y)).is_finder = true;
-    img.get_region((s, y)).is_finder = true;
+    img.get_region(coords(r)).is_finder = true;
+    img.get_region(coords(s)).is_finder = true;
 
     Some(stone.centre)
 }
 
+// Relaxed check for art-styled eyes. The stone, which may be rounded, must be centred within
+// the crosschecks and its area must be close to that of a 3x3 module square. Since the ring may
+// be broken into pieces, module samples along the middle of each side must be dark and those
+// along the inner light ring must be light. Modules are measured in steps along & across the
+// scan line, up & down being the steps from the crosscheck seed to either end
+fn is_stylized_finder(
+    img: &BinaryImage,
+    stone: &Region,
+    datum: &DatumLine,
+    up: u32,
+    down: u32,
+) -> bool {
+    let (l, r) = (datum.left as f64, datum.right as f64);
+    let ((sx, sy), (cx, cy)) = (datum.dir.step(), datum.dir.cross());
+    let (sx, sy, cx, cy) = (sx as f64, sy as f64, cx as f64, cy as f64);
+
+    // Left to right spans 6 modules along the line, up to down spans 7 modules across it
+    let mod_w = (r - l) / 6.0;
+    let mod_h = (up + down) as f64 / 7.0;
+    let along = r - 2.5 * mod_w;
+    let across = (down as f64 - up as f64) / 2.0;
+    let px = datum.origin.x as f64 + along * sx + across * cx;
+    let py = datum.origin.y as f64 + along * sy + across * cy;
+
+    // Offset of the stone centre in steps along & across
+    let (ox, oy) = (stone.centre.x as f64 - px, stone.centre.y as f64 - py);
+    let dx = (ox * sx + oy * sy) / (sx * sx + sy * sy);
+    let dy = (ox * cx + oy * cy) / (cx * cx + cy * cy);
+    if dx.abs() > mod_w || dy.abs() > mod_h {
+        return false;
+    }
+
+    // Pixel area of a module scales with the area spanned by a step along & across
+    let step_area = (sx * cy - sy * cx).abs();
+    let area_ratio = stone.area as f64 / (9.0 * mod_w * mod_h * step_area);
+    if !(STYLIZED_STONE_AREA_MIN..=STYLIZED_STONE_AREA_MAX).contains(&area_ratio) {
+        return false;
+    }
+
+    let is_color = |mx: f64, my: f64, clr: Color| {
+        let (a, c) = (mx * mod_w, my * mod_h);
+        let (x, y) = (px + a * sx + c * cx, py + a * sy + c * cy);
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+        img.get(x as u32, y as u32).is_some_and(|p| p.get_color() == clr)
+    };
+
+    let sides = [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)];
+    sides.iter().all(|&(ux, uy)| {
+        let ring = (-1..=1)
+            .map(|k| k as f64)
+            .all(|k| is_color(3.0 * ux + k * uy, 3.0 * uy + k * ux, Color::Black));
+        let gap = (-2..=2)
+            .map(|k| k as f64)
+            .all(|k| is_color(2.0 * ux + k * uy, 2.0 * uy + k * ux, Color::White));
+        ring && gap
+    })
+}
+
+// Sub-pixel finder centre
+//------------------------------------------------------------------------------
+
+// Refines the integer centroid of a finder to sub-pixel accuracy. Rows across the middle of the
+// stone vote for the line through the midpoints of horizontal chords, and columns for that of
+// vertical chords. Both lines pass through the centre, so it is their intersection. Falls back to
+// the centroid if either line can't be fit
+pub(crate) fn refine_finder_centre(img: &BinaryImage, centre: &Point) -> (f64, f64) {
+    let (cx, cy) = (centre.x as f64, centre.y as f64);
+    let (Some((_, _, stone_w)), Some((_, _, stone_h))) =
+        (scan_finder_line::<X>(img, centre), scan_finder_line::<Y>(img, centre))
+    else {
+        return (cx, cy);
+    };
+
+    let mut rows = MidpointLine::new();
+    for k in -(stone_h as i32 / 3)..=stone_h as i32 / 3 {
+        let seed = Point { x: centre.x, y: centre.y + k };
+        if let Some((off, wt, _)) = scan_finder_line::<X>(img, &seed) {
+            rows.vote(k as f64, off, wt);
+        }
+    }
+    let mut cols = MidpointLine::new();
+    for k in -(stone_w as i32 / 3)..=stone_w as i32 / 3 {
+        let seed = Point { x: centre.x + k, y: centre.y };
+        if let Some((off, wt, _)) = scan_finder_line::<Y>(img, &seed) {
+            cols.vote(k as f64, off, wt);
+        }
+    }
+
+    // Relative to the centroid, x = a1 + b1 * y & y = a2 + b2 * x
+    let (Some((a1, b1)), Some((a2, b2))) =
+        (rows.fit(CENTRE_VOTE_RADIUS), cols.fit(CENTRE_VOTE_RADIUS))
+    else {
+        return (cx, cy);
+    };
+    let det = 1.0 - b1 * b2;
+    if det.abs() < 0.5 {
+        return (cx, cy);
+    }
+    let dx = (a1 + b1 * a2) / det;
+    let dy = a2 + b2 * dx;
+
+    // Shift can't exceed a module, or the lines didn't cross the same stone
+    let max_shift = std::cmp::min(stone_w, stone_h) as f64 / 3.0;
+    if dx.abs() > max_shift || dy.abs() > max_shift {
+        return (cx, cy);
+    }
+    (cx + dx, cy + dy)
+}
+
+// Measures the finder runs along the axis through the seed, which must lie on the stone. Returns
+// the offset of the stone centre from the seed, the fit weight & the stone run length
+fn scan_finder_line<A: Axis>(img: &BinaryImage, seed: &Point) -> Option<(f64, f64, u32)> {
+    let in_bounds =
+        |pt: &Point| X::bound_check(img.w, img.h, pt) && Y::bound_check(img.w, img.h, pt);
+    if !in_bounds(seed) {
+        return None;
+    }
+    let stone_clr = img.get_at_point(seed)?.get_color();
+
+    // Runs outwards from the seed: stone, light ring & dark ring. The dark ring may be cut short
+    // by the image edge
+    let walk = |dir: (i32, i32)| {
+        let mut runs = [0u32; 3];
+        let (mut pos, mut clr, mut k) = (*seed, stone_clr, 0);
+        loop {
+            A::shift(&mut pos, &dir);
+            if !in_bounds(&pos) {
+                break;
+            }
+            let c = img.get_at_point(&pos)?.get_color();
+            if c != clr {
+                k += 1;
+                if k == 3 {
+                    break;
+                }
+                clr = c;
+            }
+            runs[k] += 1;
+            if k > 0 && runs[k] > 2 * runs[0] + 2 {
+                return None;
+            }
+        }
+        (runs[2] > 0).then_some(runs)
+    };
+    let (back, fwd) = (walk((-1, -1))?, walk((1, 1))?);
+
+    let runs = [back[2], back[1], back[0] + fwd[0] + 1, fwd[1], fwd[2]];
+    if !is_finder_ratio(&runs) {
+        return None;
+    }
+
+    // Mean deviation from the ideal runs, squared to favour clean lines
+    let avg = runs.iter().sum::<u32>() as f64 / 7.0;
+    let dev = runs.iter().zip(FINDER_PATTERN).map(|(&r, e)| (r as f64 - e * avg).abs());
+    let weight = (1.0 - dev.sum::<f64>() / (7.0 * avg)).max(0.0).powi(2);
+
+    Some(((fwd[0] as f64 - back[0] as f64) / 2.0, weight, runs[2]))
+}
+
 #[cfg(test)]
 mod finder_tests {
 
+    use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+
     use crate::{
-        reader::{binarize::BinaryImage, utils::geometry::Point},
+        reader::{
+            binarize::BinaryImage, detect_qr_with, utils::geometry::Point, DetectOptions,
+            ScanAngles,
+        },
         ECLevel, MaskPattern, QRBuilder, Version,
     };
 
-    use super::locate_finders;
+    use super::{locate_finders, refine_finder_centre, ScanDir};
+
+    #[test]
+    fn test_refine_finder_centre() {
+        // 29 modules with the quiet zone, at 2.6 pixels per module
+        let qr = QRBuilder::new(b"Hi").version(Version::Normal(1)).build().unwrap();
+        let img = DynamicImage::ImageRgb8(qr.to_image(1));
+        let img = img.resize_exact(75, 75, image::imageops::FilterType::Nearest).to_rgb8();
+        let scale = 75.0 / 29.0;
+
+        let mut bin = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut bin, &DetectOptions::default());
+        assert_eq!(finders.len(), 3);
+        for f in finders {
+            // Finder centres are 7.5 modules from the edges, in pixel index coordinates
+            let near = |v: i32| if v < 37 { 7.5 * scale - 0.5 } else { 21.5 * scale - 0.5 };
+            let (ex, ey) = (near(f.x), near(f.y));
+            let (rx, ry) = refine_finder_centre(&bin, &f);
+            assert!((rx - ex).abs() <= 0.5 && (ry - ey).abs() <= 0.5, "{rx},{ry} vs {ex},{ey}");
+        }
+    }
 
     #[test]
     fn test_locate_finder() {
@@ -200,26 +527,146 @@ mod finder_tests {
         let ver = Version::Normal(4);
         let ecl = ECLevel::L;
         let mask = MaskPattern::new(1);
-        let hi_cap = false;
-
-        let qr = QRBuilder::new(data.as_bytes())
-            .version(ver)
-            .ec_level(ecl)
-            .high_capacity(hi_cap)
-            .mask(mask)
-            .build()
-            .unwrap();
+
+        let qr =
+            QRBuilder::new(data.as_bytes()).version(ver).ec_level(ecl).mask(mask).build().unwrap();
         let img = qr.to_image(10);
 
         let centres = [[75, 75], [335, 75], [75, 335]];
         let mut bin_img = BinaryImage::prepare(&img);
-        let finders = locate_finders(&mut bin_img);
+        let finders = locate_finders(&mut bin_img, &Default::default());
 
         for (i, f) in finders.iter().enumerate() {
             let cent_pt = Point { x: centres[i][0], y: centres[i][1] };
             assert_eq!(*f, cent_pt, "Finder centre doesn't match");
         }
     }
+
+    // Round stones and cut the ring corners of all finders
+    fn stylize_finders(img: &mut RgbImage, qz: u32, w: u32, mod_sz: u32) {
+        for (ox, oy) in [(0, 0), (w - 7, 0), (0, w - 7)] {
+            let (x0, y0) = ((qz + ox) * mod_sz, (qz + oy) * mod_sz);
+            let c = 3.5 * mod_sz as f64;
+            let rad = 1.5 * mod_sz as f64;
+            for dy in 0..7 * mod_sz {
+                for dx in 0..7 * mod_sz {
+                    let (mx, my) = (dx / mod_sz, dy / mod_sz);
+                    let is_corner = (mx == 0 || mx == 6) && (my == 0 || my == 6);
+                    let in_stone = (2..=4).contains(&mx) && (2..=4).contains(&my);
+                    let (px, py) = (dx as f64 + 0.5 - c, dy as f64 + 0.5 - c);
+                    if is_corner || (in_stone && px.hypot(py) > rad) {
+                        img.put_pixel(x0 + dx, y0 + dy, Rgb([255, 255, 255]));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_locate_stylized_finder() {
+        let data = "Hello, world!";
+        let qr = QRBuilder::new(data.as_bytes()).version(Version::Normal(4)).build().unwrap();
+        let mut img = qr.to_image(10);
+        stylize_finders(&mut img, 4, 33, 10);
+
+        let mut bin_img = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut bin_img, &DetectOptions::default());
+        assert!(finders.is_empty(), "Strict check accepted stylized finders: {finders:?}");
+
+        let mut opts = DetectOptions::new();
+        opts.stylized_finders(true);
+        let mut bin_img = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut bin_img, &opts);
+        let centres = [[75, 75], [335, 75], [75, 335]];
+        assert_eq!(finders.len(), 3, "{finders:?}");
+        for (f, c) in finders.iter().zip(centres) {
+            assert!((f.x - c[0]).abs() <= 1 && (f.y - c[1]).abs() <= 1, "{f:?} != {c:?}");
+        }
+
+        let img = DynamicImage::ImageRgb8(img);
+        let (_, msg) = detect_qr_with(&img, &opts).symbols()[0].decode().unwrap();
+        assert_eq!(msg, data);
+    }
+
+    #[test]
+    fn test_scan_dir_lines() {
+        let (w, h) = (7, 4);
+        for dir in [ScanDir::Row, ScanDir::Diagonal, ScanDir::AntiDiagonal] {
+            let (dx, dy) = dir.step();
+            let mut seen = vec![0; (w * h) as usize];
+            for i in 0..dir.line_count(w, h) {
+                let (o, len) = dir.line(i, w, h);
+                for k in 0..len as i32 {
+                    seen[((o.y + dy * k) * w as i32 + o.x + dx * k) as usize] += 1;
+                }
+            }
+            assert!(seen.iter().all(|&n| n == 1), "{dir:?} {seen:?}");
+        }
+    }
+
+    // Symbol rotated 45 degrees about the image centre, in low contrast with uniform noise
+    fn rotated_noisy(qr: &crate::builder::QR, mod_sz: f64, noise: f64, seed: u64) -> GrayImage {
+        let src = DynamicImage::ImageRgb8(qr.to_image(1)).to_luma8();
+        let n = src.width() as f64 * mod_sz;
+        let size = (n * 1.5) as u32;
+        let (c, cos) = (size as f64 / 2.0, std::f64::consts::FRAC_1_SQRT_2);
+        let mut state = seed;
+        GrayImage::from_fn(size, size, |x, y| {
+            let (dx, dy) = (x as f64 + 0.5 - c, y as f64 + 0.5 - c);
+            let sx = ((dx + dy) * cos + n / 2.0) / mod_sz;
+            let sy = ((dy - dx) * cos + n / 2.0) / mod_sz;
+            let inside =
+                (0.0..src.width() as f64).contains(&sx) && (0.0..src.height() as f64).contains(&sy);
+            let base = if inside { src.get_pixel(sx as u32, sy as u32)[0] as f64 } else { 255.0 };
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let r = ((state >> 33) as f64 / (1u64 << 31) as f64 - 0.5) * 2.0 * noise;
+            Luma([(base * 0.6 + 50.0 + r).clamp(0.0, 255.0) as u8])
+        })
+    }
+
+    #[test]
+    fn test_diagonal_scan() {
+        let data = "Rotated noisy code";
+        let qr = QRBuilder::new(data.as_bytes()).version(Version::Normal(3)).build().unwrap();
+        let img = DynamicImage::ImageLuma8(rotated_noisy(&qr, 3.0, 60.0, 2));
+
+        let mut opts = DetectOptions::new();
+        let res = detect_qr_with(&img, &opts);
+        assert!(res.symbols().iter().all(|s| s.decode().is_err()));
+
+        opts.scan_angles(ScanAngles::RowsAndDiagonals);
+        let res = detect_qr_with(&img, &opts);
+        let msgs: Vec<_> = res.symbols().iter().filter_map(|s| s.decode().ok()).collect();
+        assert_eq!(msgs[0].1, data);
+    }
+
+    #[test]
+    fn test_locate_framed_finder() {
+        // Frame drawn in the inner 2 modules of the quiet zone, touching the finders
+        let data = "Hello, world!";
+        let qr = QRBuilder::new(data.as_bytes()).version(Version::Normal(4)).build().unwrap();
+        let mut img = qr.to_image(10);
+        let (inner, outer) = (40..370, 20..390);
+        for (x, y, px) in img.enumerate_pixels_mut() {
+            let in_symbol = inner.contains(&x) && inner.contains(&y);
+            if outer.contains(&x) && outer.contains(&y) && !in_symbol {
+                *px = Rgb([0, 0, 0]);
+            }
+        }
+
+        let mut bin_img = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut bin_img, &DetectOptions::default());
+        let centres = [[75, 75], [335, 75], [75, 335]];
+        assert_eq!(finders.len(), 3, "{finders:?}");
+        for (f, c) in finders.iter().zip(centres) {
+            assert!((f.x - c[0]).abs() <= 1 && (f.y - c[1]).abs() <= 1, "{f:?} != {c:?}");
+        }
+
+        let img = DynamicImage::ImageRgb8(img);
+        let (_, msg) =
+            detect_qr_with(&img, &DetectOptions::default()).symbols()[0].decode().unwrap();
+        assert_eq!(msg, data);
+    }
 }
 
 // Groups finders in 3, which form potential symbols
@@ -228,7 +675,8 @@ mod finder_tests {
 #[derive(Debug, Clone)]
 pub struct FinderGroup {
     pub finders: [Point; 3], // [BL, TL, TR]
-    pub score: f64,          // Timing pattern score + Estimate mod count score
+    pub score: f64,          // Weighed misfit of geometry, pitch & timing. Lower is better
+    pub saliency: f64,       // Size, contrast & centrality. Set by score_saliency
 }
 
 impl FinderGroup {
@@ -243,10 +691,20 @@ impl FinderGroup {
     }
 }
 
-pub fn group_finders(finders: &[Point]) -> Vec<FinderGroup> {
+// Scores every triple of finders on how well it fits a symbol, from 4 terms each relative to its
+// tolerance:
+// 1. Distance of the bl & tr finders from the tl finder is equal
+// 2. Angle at the tl finder is right
+// 3. Module pitch, measured off the stone of each finder, is equal
+// 4. Timing lines between the finders alternate dark & light module by module
+// Terms are weighed into a single score, so a slight miss in one is made up for by the others.
+// Triples past the tolerance of the geometry terms or the combined score are dropped, & the rest
+// are ranked from the best score
+pub fn group_finders(img: &BinaryImage, finders: &[Point]) -> Vec<FinderGroup> {
     // Store all possible combinations of finders
     let mut groups: Vec<FinderGroup> = Vec::new();
     let right_angle = 90f64.to_radians();
+    let pitches: Vec<_> = finders.iter().map(|f| finder_pitch(img, f)).collect();
 
     for (i1, f1) in finders.iter().enumerate() {
         for (i2, f2) in finders.iter().enumerate() {
@@ -275,21 +733,155 @@ pub fn group_finders(finders: &[Point]) -> Vec<FinderGroup> {
                     continue;
                 }
 
-                let score = symmetry_score + angle_score;
-
-                // Create and push group into groups
                 let finders = [*f3, *f1, *f2];
-                let group = FinderGroup { finders, score };
-                groups.push(group);
+                let (pitch_score, timing_score) = match (pitches[i1], pitches[i2], pitches[i3]) {
+                    (Some(p1), Some(p2), Some(p3)) => {
+                        let mean = (p1 + p2 + p3) / 3.0;
+                        let spread = (p1.max(p2).max(p3) - p1.min(p2).min(p3)) / mean;
+
+                        // Modules between the centres of the tl finder & the others
+                        let side = ((d12 as f64).sqrt() + (d13 as f64).sqrt()) / 2.0;
+                        let mod_cnt = side / mean;
+                        if !(MIN_GROUP_MODULES..=MAX_GROUP_MODULES).contains(&mod_cnt) {
+                            continue;
+                        }
+                        let timing = timing_mismatch(img, &finders, [p3, p1, p2]);
+                        (spread / PITCH_TOLERANCE, timing / TIMING_TOLERANCE)
+                    }
+                    // Neutral if any stone wasn't flood filled
+                    _ => (0.5, 0.5),
+                };
+
+                let score = GROUP_SYMMETRY_WEIGHT * symmetry_score / SYMMETRY_THRESHOLD
+                    + GROUP_ANGLE_WEIGHT * angle_score / ANGLE_THRESHOLD
+                    + GROUP_PITCH_WEIGHT * pitch_score
+                    + GROUP_TIMING_WEIGHT * timing_score;
+                if score > GROUP_SCORE_THRESHOLD {
+                    continue;
+                }
+
+                groups.push(FinderGroup { finders, score, saliency: 0.0 });
             }
         }
     }
 
-    groups.sort_unstable_by(|a, b| a.score.partial_cmp(&b.score).unwrap());
+    groups.sort_unstable_by(|a, b| a.score.total_cmp(&b.score));
 
     groups
 }
 
+// Module pitch from the area of the stone, a 3x3 module square
+fn finder_pitch(img: &BinaryImage, finder: &Point) -> Option<f64> {
+    let stone = img.region_at(finder).filter(|r| r.color != Color::White)?;
+    Some((stone.area as f64 / 9.0).sqrt())
+}
+
+// How far the timing lines of the group are from a clean timing pattern, from 0 to 1. Each line
+// runs 3 modules inside the line between the tl finder & another one, from separator to
+// separator, with the module pitch shifting linearly from one finder to the other as under
+// perspective. It's sampled about twice a module. Its transitions are compared to the modules it
+// crosses & its share of dark samples to half, the worse of which counts. Samples outside the
+// image are skipped
+fn timing_mismatch(img: &BinaryImage, finders: &[Point; 3], pitches: [f64; 3]) -> f64 {
+    let [bl, tl, tr] = finders.map(|p| (p.x as f64, p.y as f64));
+    let [bl_p, tl_p, tr_p] = pitches;
+
+    let line = |to: (f64, f64), to_p: f64, side: (f64, f64)| {
+        let len = (to.0 - tl.0).hypot(to.1 - tl.1);
+        let side_len = (side.0 - tl.0).hypot(side.1 - tl.1);
+        let (start, end) = (4.75 * tl_p, len - 4.75 * to_p);
+        let mod_cnt = (end - start) / ((tl_p + to_p) / 2.0);
+        if len <= f64::EPSILON || side_len <= f64::EPSILON || mod_cnt < 1.0 {
+            return 1.0;
+        }
+
+        let steps = (2.0 * mod_cnt).round() as usize;
+        let (mut dark, mut samples, mut transitions) = (0, 0, 0);
+        let mut prev = None;
+        for i in 0..=steps {
+            let d = start + (end - start) * i as f64 / steps as f64;
+            let inset = 3.0 * (tl_p + (to_p - tl_p) * d / len);
+            let (u, v) = (d / len, inset / side_len);
+            let x = tl.0 + u * (to.0 - tl.0) + v * (side.0 - tl.0);
+            let y = tl.1 + u * (to.1 - tl.1) + v * (side.1 - tl.1);
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let Some(px) = img.get(x as u32, y as u32) else { continue };
+            let is_dark = px.get_color() != Color::White;
+            dark += is_dark as usize;
+            samples += 1;
+            transitions += (prev.is_some_and(|p| p != is_dark)) as usize;
+            prev = Some(is_dark);
+        }
+        if samples == 0 {
+            return 1.0;
+        }
+        let transition_miss = (transitions as f64 / mod_cnt - 1.0).abs().min(1.0);
+        let balance_miss = (2.0 * dark as f64 / samples as f64 - 1.0).abs();
+        transition_miss.max(balance_miss)
+    };
+
+    (line(tr, tr_p, bl) + line(bl, bl_p, tr)) / 2.0
+}
+
+// Scores how likely each group is a real, prominent symbol from its size relative to the image,
+// the luma contrast inside it & the distance of its centre from the image centre, scaled down by
+// how loosely the finders fit the corners of a square. Contrast is left neutral without luma
+pub(crate) fn score_saliency(
+    groups: &mut [FinderGroup],
+    w: u32,
+    h: u32,
+    luma: Option<&dyn ImageView>,
+) {
+    let (w, h) = (w as f64, h as f64);
+    let half_diag = (w * w + h * h).sqrt() / 2.0;
+
+    for g in groups.iter_mut() {
+        let [bl, tl, tr] = g.finders;
+        let side = |a: &Point| (tl.dist_sq(a) as f64).sqrt();
+        let size = ((side(&bl) * side(&tr)).sqrt() / w.min(h)).min(1.0);
+
+        let (cx, cy) = ((bl.x + tr.x) as f64 / 2.0, (bl.y + tr.y) as f64 / 2.0);
+        let centrality = 1.0 - ((cx - w / 2.0).hypot(cy - h / 2.0) / half_diag).min(1.0);
+
+        let contrast = luma.map_or(0.5, |l| group_contrast(l, &g.finders));
+
+        let fit = 1.0 - (g.score / GROUP_SCORE_THRESHOLD).min(1.0);
+        g.saliency = fit
+            * (SALIENCY_SIZE_WEIGHT * size
+                + SALIENCY_CONTRAST_WEIGHT * contrast
+                + SALIENCY_CENTRE_WEIGHT * centrality);
+    }
+}
+
+// Spread between the dark & light luma percentiles over a grid of samples in the parallelogram
+// spanned by the finder centres, as a fraction of the full range
+fn group_contrast(luma: &dyn ImageView, finders: &[Point; 3]) -> f64 {
+    let [bl, tl, tr] = finders.map(|p| (p.x as f64, p.y as f64));
+    let (lw, lh) = (luma.width() as f64, luma.height() as f64);
+    let n = CONTRAST_SAMPLES;
+
+    let mut samples = Vec::with_capacity(n * n);
+    for i in 0..n {
+        for j in 0..n {
+            let (u, v) = (i as f64 / (n - 1) as f64, j as f64 / (n - 1) as f64);
+            let x = tl.0 + u * (tr.0 - tl.0) + v * (bl.0 - tl.0);
+            let y = tl.1 + u * (tr.1 - tl.1) + v * (bl.1 - tl.1);
+            if (0.0..lw).contains(&x) && (0.0..lh).contains(&y) {
+                samples.push(luma.get_luma(x as u32, y as u32));
+            }
+        }
+    }
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    samples.sort_unstable();
+    let pct = |p: usize| samples[(samples.len() - 1) * p / 100] as f64;
+    (pct(90) - pct(10)) / 255.0
+}
+
 // Angle between AB & BC in radians
 fn angle(a: &Point, b: &Point, c: &Point) -> f64 {
     let ab = ((a.x - b.x) as f64, (a.y - b.y) as f64);
@@ -311,6 +903,8 @@ fn angle(a: &Point, b: &Point, c: &Point) -> f64 {
 #[cfg(test)]
 mod group_finders_tests {
 
+    use image::{imageops, Rgb, RgbImage};
+
     use crate::{reader::binarize::BinaryImage, ECLevel, MaskPattern, QRBuilder, Version};
 
     use super::{group_finders, locate_finders};
@@ -321,33 +915,102 @@ mod group_finders_tests {
         let ver = Version::Normal(4);
         let ecl = ECLevel::L;
         let mask = MaskPattern::new(1);
-        let hi_cap = false;
-
-        let qr = QRBuilder::new(data.as_bytes())
-            .version(ver)
-            .ec_level(ecl)
-            .high_capacity(hi_cap)
-            .mask(mask)
-            .build()
-            .unwrap();
+
+        let qr =
+            QRBuilder::new(data.as_bytes()).version(ver).ec_level(ecl).mask(mask).build().unwrap();
         let img = qr.to_image(10);
 
         let centres = [(75, 75), (335, 75), (75, 335)];
 
         let mut img = BinaryImage::prepare(&img);
-        let finders = locate_finders(&mut img);
-        let group = group_finders(&finders);
+        let finders = locate_finders(&mut img, &Default::default());
+        let group = group_finders(&img, &finders);
         assert!(!group.is_empty(), "No group found");
         for f in group[0].finders.iter() {
             let c = (f.x, f.y);
             assert!(centres.contains(&c))
         }
     }
+
+    #[test]
+    fn test_group_finders_among_square_logos() {
+        let mut bldr = QRBuilder::new(b"Scene full of square logos");
+        let qr = bldr.version(Version::Normal(2)).build().unwrap().to_image(4);
+        let mut img = RgbImage::from_pixel(700, 360, Rgb([255, 255, 255]));
+        imageops::replace(&mut img, &qr, 0, 0);
+
+        // Finder shaped logos at the pitch of the QR, in right angled triples on a plain background
+        let logo = RgbImage::from_fn(28, 28, |x, y| {
+            let ring = (x / 4).min(y / 4).min(6 - x / 4).min(6 - y / 4);
+            if ring == 1 {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        for (x, y) in [(370, 16), (370, 134), (370, 252), (488, 16), (488, 134), (606, 16)] {
+            imageops::replace(&mut img, &logo, x, y);
+        }
+
+        let mut img = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut img, &Default::default());
+        assert_eq!(finders.len(), 9);
+        let groups = group_finders(&img, &finders);
+        assert!(!groups.is_empty(), "No group found");
+
+        let in_qr = |x: i32| x < qr.width() as i32;
+        assert!(groups[0].finders.iter().all(|f| in_qr(f.x)), "{:?}", groups[0]);
+        assert!(groups.iter().all(|g| g.finders.iter().any(|f| in_qr(f.x))));
+        assert!(groups.windows(2).all(|w| w[0].score <= w[1].score));
+    }
 }
 
 // Global constants
 //------------------------------------------------------------------------------
 
+const FINDER_PATTERN: [f64; 5] = [1.0, 1.0, 3.0, 1.0, 1.0];
+
+// Weights of the saliency terms of a finder group. They sum to 1
+const SALIENCY_SIZE_WEIGHT: f64 = 0.4;
+
+const SALIENCY_CONTRAST_WEIGHT: f64 = 0.3;
+
+const SALIENCY_CENTRE_WEIGHT: f64 = 0.3;
+
+// Samples per side of the grid over which the contrast of a group is measured
+const CONTRAST_SAMPLES: usize = 16;
+
 pub const SYMMETRY_THRESHOLD: f64 = 0.75;
 
 pub const ANGLE_THRESHOLD: f64 = 0.5;
+
+// Relative spread of the module pitch of the finders in a group at which its term scores 1
+const PITCH_TOLERANCE: f64 = 0.5;
+
+// Timing mismatch at which its term scores 1
+const TIMING_TOLERANCE: f64 = 0.5;
+
+// Weights of the terms of a group score. They sum to 1
+const GROUP_SYMMETRY_WEIGHT: f64 = 0.15;
+
+const GROUP_ANGLE_WEIGHT: f64 = 0.15;
+
+const GROUP_PITCH_WEIGHT: f64 = 0.2;
+
+const GROUP_TIMING_WEIGHT: f64 = 0.5;
+
+// Max weighed score of a group, where each term scores 1 at its tolerance
+const GROUP_SCORE_THRESHOLD: f64 = 0.8;
+
+// Bounds on the modules between finder centres, 14 for version 1 & 170 for version 40
+const MIN_GROUP_MODULES: f64 = 10.0;
+
+const MAX_GROUP_MODULES: f64 = 200.0;
+
+// Bounds on stone area relative to a 3x3 module square. A circle inscribed in it is ~0.785
+pub const STYLIZED_STONE_AREA_MIN: f64 = 0.6;
+
+pub const STYLIZED_STONE_AREA_MAX: f64 = 1.3;
+
+// Max distance in pixels of a chord midpoint from the first fit line to be kept for the refit
+const CENTRE_VOTE_RADIUS: f64 = 1.0;
@@ -0,0 +1,172 @@
+use image::GrayImage;
+
+use super::utils::geometry::Point;
+
+// Glare mask
+//------------------------------------------------------------------------------
+
+// Pixels washed out by specular highlights, e.g. from glossy lamination. Modules sampled within
+// the mask are unreliable, so their codewords are treated as erasures by the ec decoder
+#[derive(Debug, Clone)]
+pub(crate) struct GlareMask {
+    w: u32,
+    h: u32,
+    mask: Vec<bool>,
+}
+
+impl GlareMask {
+    // Flags pixels noticeably brighter than the paper and close to saturation. Returns none if
+    // the image has no glare
+    pub fn detect(img: &GrayImage) -> Option<Self> {
+        let (w, h) = img.dimensions();
+        let px_cnt = (w * h) as usize;
+        if px_cnt == 0 {
+            return None;
+        }
+
+        // Paper level is the median of pixels brighter than the mean
+        let mut hist = [0usize; 256];
+        img.pixels().for_each(|p| hist[p.0[0] as usize] += 1);
+        let sum = hist.iter().enumerate().map(|(v, &c)| v * c).sum::<usize>();
+        let mean = sum / px_cnt;
+        let light_cnt = hist[mean + 1..].iter().sum::<usize>();
+        let mut acc = 0;
+        let paper = (mean + 1..256)
+            .find(|&v| {
+                acc += hist[v];
+                acc * 2 >= light_cnt
+            })
+            .unwrap_or(255);
+
+        let thresh = std::cmp::max(GLARE_LUMA_MIN, paper + GLARE_MARGIN);
+        if thresh > 255 {
+            return None;
+        }
+
+        let mask = img.pixels().map(|p| p.0[0] as usize >= thresh).collect::<Vec<_>>();
+        if mask.iter().filter(|&&g| g).count() < GLARE_MIN_AREA {
+            return None;
+        }
+
+        let mut glare = Self { w, h, mask };
+        glare.dilate(GLARE_DILATION);
+        Some(glare)
+    }
+
+    pub fn contains(&self, pt: &Point) -> bool {
+        if pt.x < 0 || pt.y < 0 || pt.x as u32 >= self.w || pt.y as u32 >= self.h {
+            return false;
+        }
+        self.mask[(pt.y as u32 * self.w + pt.x as u32) as usize]
+    }
+
+    // Grows the mask to cover the blooming halo around highlights
+    fn dilate(&mut self, r: usize) {
+        let (w, h) = (self.w as usize, self.h as usize);
+        let mut tmp = vec![false; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let (l, rr) = (x.saturating_sub(r), std::cmp::min(x + r, w - 1));
+                tmp[y * w + x] = self.mask[y * w + l..=y * w + rr].contains(&true);
+            }
+        }
+        for y in 0..h {
+            for x in 0..w {
+                let (t, b) = (y.saturating_sub(r), std::cmp::min(y + r, h - 1));
+                self.mask[y * w + x] = (t..=b).any(|ny| tmp[ny * w + x]);
+            }
+        }
+    }
+
+    // Fills masked pixels by interpolating between the nearest unmasked pixels along the row &
+    // column, so the highlight doesn't skew the thresholds of surrounding blocks
+    pub fn inpaint(&self, img: &mut GrayImage) {
+        let (w, h) = (self.w as usize, self.h as usize);
+        let src = img.as_raw().clone();
+        let mut sum = vec![0u32; w * h];
+        let mut cnt = vec![0u32; w * h];
+
+        let mut fill = |idx: &dyn Fn(usize) -> usize, len: usize| {
+            let mut i = 0;
+            while i < len {
+                if !self.mask[idx(i)] {
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < len && self.mask[idx(i)] {
+                    i += 1;
+                }
+                let before = start.checked_sub(1).map(|j| src[idx(j)] as f64);
+                let after = (i < len).then(|| src[idx(i)] as f64);
+                let (a, b) = match (before, after) {
+                    (Some(a), Some(b)) => (a, b),
+                    (Some(a), None) => (a, a),
+                    (None, Some(b)) => (b, b),
+                    (None, None) => continue,
+                };
+                let span = (i - start + 1) as f64;
+                for j in start..i {
+                    let t = (j - start + 1) as f64 / span;
+                    sum[idx(j)] += (a + (b - a) * t).round() as u32;
+                    cnt[idx(j)] += 1;
+                }
+            }
+        };
+
+        for y in 0..h {
+            fill(&|x| y * w + x, w);
+        }
+        for x in 0..w {
+            fill(&|y| y * w + x, h);
+        }
+
+        for (i, p) in img.iter_mut().enumerate() {
+            if let Some(v) = sum[i].checked_div(cnt[i]) {
+                *p = v as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod glare_tests {
+    use image::{GrayImage, Luma};
+
+    use super::GlareMask;
+    use crate::reader::utils::geometry::Point;
+
+    #[test]
+    fn test_detect_and_inpaint() {
+        let mut img = GrayImage::from_fn(60, 60, |x, _| Luma([if x % 10 < 5 { 30 } else { 190 }]));
+        assert!(GlareMask::detect(&img).is_none());
+
+        for y in 20..30 {
+            for x in 20..30 {
+                img.put_pixel(x, y, Luma([255]));
+            }
+        }
+        let glare = GlareMask::detect(&img).unwrap();
+        assert!(glare.contains(&Point { x: 25, y: 25 }));
+        assert!(glare.contains(&Point { x: 19, y: 25 }));
+        assert!(!glare.contains(&Point { x: 5, y: 5 }));
+
+        glare.inpaint(&mut img);
+        assert!(img.pixels().all(|p| p.0[0] < 255));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Min luma of glare pixels
+const GLARE_LUMA_MIN: usize = 235;
+
+// Min luma above the paper level for glare pixels
+const GLARE_MARGIN: usize = 25;
+
+// Min pixel count of glare in an image
+const GLARE_MIN_AREA: usize = 16;
+
+// Pixels by which the glare mask is grown
+const GLARE_DILATION: usize = 2;
@@ -0,0 +1,297 @@
+use std::sync::{mpsc, Arc, OnceLock};
+
+use image::GrayImage;
+use wgpu::util::DeviceExt;
+
+use super::{
+    binarize::{BinaryImage, BLOCK_COUNT},
+    finder::FinderRun,
+};
+use crate::metadata::Color;
+
+// Gpu backend
+//------------------------------------------------------------------------------
+
+// Compute pipelines for adaptive thresholding & the 1:1:3:1:1 row scan. Thresholds match
+// BinaryImage::prepare bit for bit, and the scanned finder lines are verified on the cpu
+#[derive(Debug)]
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    block_means: wgpu::ComputePipeline,
+    block_thresholds: wgpu::ComputePipeline,
+    binarize: wgpu::ComputePipeline,
+    scan_rows: wgpu::ComputePipeline,
+}
+
+impl GpuBackend {
+    // Returns none if no adapter is present
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .ok()?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("qrism"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("qrism"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let mut entries = vec![wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }];
+        entries.push(storage(1, true));
+        entries.extend((2..7).map(|b| storage(b, false)));
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("qrism"),
+            entries: &entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("qrism"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let block_means = pipeline("block_means");
+        let block_thresholds = pipeline("block_thresholds");
+        let binarize = pipeline("binarize");
+        let scan_rows = pipeline("scan_rows");
+
+        Some(Self { device, queue, layout, block_means, block_thresholds, binarize, scan_rows })
+    }
+
+    // Process wide backend, so options don't each open a device
+    pub fn shared() -> Option<Arc<Self>> {
+        static SHARED: OnceLock<Option<Arc<GpuBackend>>> = OnceLock::new();
+        SHARED.get_or_init(|| Self::new().map(Arc::new)).clone()
+    }
+
+    // Returns the binary image and the finder lines of each row. Lines are none if there were
+    // more than the readback buffer holds, in which case the cpu scans the image. Returns none if
+    // the image doesn't fit the device limits
+    pub(crate) fn binarize_and_scan(
+        &self,
+        img: &GrayImage,
+    ) -> Option<(BinaryImage, Option<Vec<FinderRun>>)> {
+        let (w, h) = img.dimensions();
+        if w < BLOCK_COUNT as u32 || h < BLOCK_COUNT as u32 {
+            return None;
+        }
+        let block_pow = (std::cmp::min(w, h) as f64 / BLOCK_COUNT).log2() as u32;
+        let mask = (1 << block_pow) - 1;
+        let (wsteps, hsteps) = ((w + mask) >> block_pow, (h + mask) >> block_pow);
+        let blocks = (wsteps * hsteps) as u64;
+        let words = (w as u64 * h as u64).div_ceil(32);
+        let run_cap = h * MAX_RUNS_PER_ROW;
+
+        let limits = self.device.limits();
+        let max_groups = limits.max_compute_workgroups_per_dimension as u64;
+        let max_binding = limits.max_storage_buffer_binding_size as u64;
+        let luma_len = (img.as_raw().len() as u64 + 3) & !3;
+        if words.div_ceil(WORKGROUP_SIZE) > max_groups
+            || blocks.div_ceil(WORKGROUP_SIZE) > max_groups
+            || luma_len > max_binding
+            || run_cap as u64 * 28 > max_binding
+        {
+            return None;
+        }
+
+        let params = [w, h, block_pow, wsteps, hsteps, run_cap]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut luma = img.as_raw().clone();
+        luma.resize(luma_len as usize, 0);
+
+        let init = |label, contents: &[u8], usage| {
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage,
+            })
+        };
+        let buffer = |label, size: u64, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let storage = wgpu::BufferUsages::STORAGE;
+        let readback = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
+        let params = init("params", &params, wgpu::BufferUsages::UNIFORM);
+        let luma = init("luma", &luma, storage);
+        let means = buffer("means", blocks * 4, storage);
+        let thresholds = buffer("thresholds", blocks * 4, storage);
+        let bits = buffer("bits", words * 4, storage | wgpu::BufferUsages::COPY_SRC);
+        let run_count = init("run_count", &[0; 4], storage | wgpu::BufferUsages::COPY_SRC);
+        let runs = buffer("runs", run_cap as u64 * 28, storage | wgpu::BufferUsages::COPY_SRC);
+        let bits_read = buffer("bits_read", words * 4, readback);
+        let runs_read = buffer("runs_read", 4 + run_cap as u64 * 28, readback);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("qrism"),
+            layout: &self.layout,
+            entries: &[&params, &luma, &means, &thresholds, &bits, &run_count, &runs]
+                .iter()
+                .enumerate()
+                .map(|(i, b)| wgpu::BindGroupEntry {
+                    binding: i as u32,
+                    resource: b.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_bind_group(0, &bind_group, &[]);
+            for (pipeline, n) in [
+                (&self.block_means, blocks),
+                (&self.block_thresholds, blocks),
+                (&self.binarize, words),
+                (&self.scan_rows, h as u64),
+            ] {
+                pass.set_pipeline(pipeline);
+                pass.dispatch_workgroups(n.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+            }
+        }
+        encoder.copy_buffer_to_buffer(&bits, 0, &bits_read, 0, words * 4);
+        encoder.copy_buffer_to_buffer(&run_count, 0, &runs_read, 0, 4);
+        encoder.copy_buffer_to_buffer(&runs, 0, &runs_read, 4, run_cap as u64 * 28);
+        self.queue.submit([encoder.finish()]);
+
+        let bits = self.read(&bits_read)?;
+        let runs = self.read(&runs_read)?;
+
+        let bin = BinaryImage::from_fn(w, h, |x, y| {
+            let i = (y * w + x) as usize;
+            Color::from((bits[i >> 5] >> (i & 31)) & 1 == 1)
+        });
+        let runs = match runs[0] {
+            n if n > run_cap => None,
+            n => Some(
+                runs[1..]
+                    .chunks_exact(7)
+                    .take(n as usize)
+                    .map(|r| FinderRun { pos: r[0], y: r[1], runs: [r[2], r[3], r[4], r[5], r[6]] })
+                    .collect(),
+            ),
+        };
+
+        Some((bin, runs))
+    }
+
+    // Blocks until the buffer is mapped and copies out its words
+    fn read(&self, buf: &wgpu::Buffer) -> Option<Vec<u32>> {
+        let (tx, rx) = mpsc::channel();
+        buf.map_async(wgpu::MapMode::Read, .., move |res| {
+            let _ = tx.send(res);
+        });
+        self.device.poll(wgpu::PollType::Wait).ok()?;
+        rx.recv().ok()?.ok()?;
+
+        let words = buf
+            .get_mapped_range(..)
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        buf.unmap();
+        Some(words)
+    }
+}
+
+#[cfg(test)]
+mod gpu_tests {
+    use image::{DynamicImage, GrayImage, Luma};
+
+    use super::GpuBackend;
+    use crate::{
+        builder::QRBuilder,
+        reader::{
+            binarize::BinaryImage,
+            detect_qr_with,
+            finder::{locate_finders, locate_finders_from},
+            DetectOptions,
+        },
+    };
+
+    #[test]
+    fn test_gpu_matches_cpu() {
+        // Skip on machines without an adapter
+        let Some(gpu) = GpuBackend::shared() else { return };
+
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let img = DynamicImage::ImageRgb8(qr.to_image(3)).to_luma8();
+        let img = GrayImage::from_fn(img.width() + 13, img.height() + 5, |x, y| {
+            *img.get_pixel_checked(x, y).unwrap_or(&Luma([255]))
+        });
+
+        let (mut gpu_bin, runs) = gpu.binarize_and_scan(&img).unwrap();
+        let mut cpu_bin = BinaryImage::prepare(&img);
+        assert_eq!(gpu_bin.buffer, cpu_bin.buffer);
+
+        let opts = DetectOptions::default();
+        let gpu_finders = locate_finders_from(&mut gpu_bin, &mut runs.unwrap(), &opts);
+        let cpu_finders = locate_finders(&mut cpu_bin, &opts);
+        assert_eq!(gpu_finders.len(), 3);
+        assert_eq!(gpu_finders, cpu_finders);
+    }
+
+    #[test]
+    fn test_reader_gpu() {
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        let img = DynamicImage::ImageRgb8(qr.to_image(3));
+
+        let mut opts = DetectOptions::new();
+        opts.gpu(true);
+        let (_, exp_msg) = detect_qr_with(&img, &opts).symbols()[0].decode().unwrap();
+        assert_eq!(msg, exp_msg);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const WORKGROUP_SIZE: u64 = 64;
+
+// Finder lines read back per row on average, beyond which the cpu rescans the image
+const MAX_RUNS_PER_ROW: u32 = 16;
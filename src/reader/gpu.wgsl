@@ -0,0 +1,163 @@
+// Mirrors BinaryImage::prepare for luma images and the finder LineScanner. Pixels are packed 4
+// per word on input and binarized 32 per word on output, with a set bit for white
+
+struct Params {
+    w: u32,
+    h: u32,
+    block_pow: u32,
+    wsteps: u32,
+    hsteps: u32,
+    run_cap: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> luma: array<u32>;
+@group(0) @binding(2) var<storage, read_write> means: array<u32>;
+@group(0) @binding(3) var<storage, read_write> thresholds: array<u32>;
+@group(0) @binding(4) var<storage, read_write> bits: array<u32>;
+@group(0) @binding(5) var<storage, read_write> run_count: atomic<u32>;
+@group(0) @binding(6) var<storage, read_write> runs: array<u32>;
+
+fn get_luma(x: u32, y: u32) -> u32 {
+    let i = y * params.w + x;
+    return (luma[i >> 2u] >> ((i & 3u) * 8u)) & 0xffu;
+}
+
+fn get_bit(x: u32, y: u32) -> u32 {
+    let i = y * params.w + x;
+    return (bits[i >> 5u] >> (i & 31u)) & 1u;
+}
+
+// Average of each block. Fractional blocks on the right & bottom edges are shifted inwards
+@compute @workgroup_size(64)
+fn block_means(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.wsteps * params.hsteps {
+        return;
+    }
+
+    let size = 1u << params.block_pow;
+    let x0 = min((i % params.wsteps) * size, params.w - size);
+    let y0 = min((i / params.wsteps) * size, params.h - size);
+    var sum = 0u;
+    for (var y = y0; y < y0 + size; y++) {
+        for (var x = x0; x < x0 + size; x++) {
+            sum += get_luma(x, y);
+        }
+    }
+    means[i] = sum >> (2u * params.block_pow);
+}
+
+// Average of the 5x5 blocks around each block. Blocks near the edges reuse the nearest inner
+// window, which is what copying the threshold above or to the left amounts to
+@compute @workgroup_size(64)
+fn block_thresholds(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.wsteps * params.hsteps {
+        return;
+    }
+
+    let cx = clamp(i % params.wsteps, 2u, params.wsteps - 3u);
+    let cy = clamp(i / params.wsteps, 2u, params.hsteps - 3u);
+    var sum = 0u;
+    for (var y = cy - 2u; y <= cy + 2u; y++) {
+        for (var x = cx - 2u; x <= cx + 2u; x++) {
+            sum += means[y * params.wsteps + x];
+        }
+    }
+    thresholds[i] = sum / 25u;
+}
+
+@compute @workgroup_size(64)
+fn binarize(@builtin(global_invocation_id) id: vec3<u32>) {
+    let len = params.w * params.h;
+    let start = id.x * 32u;
+    if start >= len {
+        return;
+    }
+
+    var word = 0u;
+    for (var i = start; i < min(start + 32u, len); i++) {
+        let x = i % params.w;
+        let y = i / params.w;
+        let t = thresholds[(y >> params.block_pow) * params.wsteps + (x >> params.block_pow)];
+        if get_luma(x, y) > t {
+            word |= 1u << (i - start);
+        }
+    }
+    bits[id.x] = word;
+}
+
+// 1:1:3:1:1 ratio with 95% tolerance in integers, loosened by a hair so rounding never drops a
+// line the cpu would accept. The cpu rechecks the exact ratio
+fn is_finder_ratio(r: array<u32, 5>) -> bool {
+    return is_exact_finder_ratio(r) || is_exact_finder_ratio(trim_outer_runs(r));
+}
+
+// Outer runs over 1.5 modules, as measured on the 5 inner modules, are taken as merged with
+// adjacent artwork & trimmed to a module, as on the cpu
+fn trim_outer_runs(r: array<u32, 5>) -> array<u32, 5> {
+    var t = r;
+    let inner = r[1] + r[2] + r[3];
+    let m = max((inner + 2u) / 5u, 1u);
+    if r[0] * 10u > inner * 3u {
+        t[0] = m;
+    }
+    if r[4] * 10u > inner * 3u {
+        t[4] = m;
+    }
+    return t;
+}
+
+fn is_exact_finder_ratio(r: array<u32, 5>) -> bool {
+    let sum = r[0] + r[1] + r[2] + r[3] + r[4];
+    let lo = array<u32, 5>(5u, 5u, 205u, 5u, 5u);
+    let hi = array<u32, 5>(195u, 195u, 395u, 195u, 195u);
+    for (var i = 0u; i < 5u; i++) {
+        if r[i] * 700u + 1u < sum * lo[i] || r[i] * 700u > sum * hi[i] + 1u {
+            return false;
+        }
+    }
+    return true;
+}
+
+// One row per invocation. A white pixel past the right edge flushes the last run
+@compute @workgroup_size(64)
+fn scan_rows(@builtin(global_invocation_id) id: vec3<u32>) {
+    let y = id.x;
+    if y >= params.h {
+        return;
+    }
+
+    var r = array<u32, 5>(0u, 0u, 0u, 0u, 0u);
+    var cur = 0u;
+    var prev = 2u;
+    var flips = 0u;
+    for (var x = 0u; x <= params.w; x++) {
+        var color = 1u;
+        if x < params.w {
+            color = get_bit(x, y);
+        }
+        if color == prev {
+            cur += 1u;
+            continue;
+        }
+
+        r = array<u32, 5>(r[1], r[2], r[3], r[4], cur);
+        cur = 1u;
+        prev = color;
+        flips += 1u;
+
+        if flips >= 5u && is_finder_ratio(r) {
+            let slot = atomicAdd(&run_count, 1u);
+            if slot < params.run_cap {
+                let o = slot * 7u;
+                runs[o] = x + 1u;
+                runs[o + 1u] = y;
+                for (var i = 0u; i < 5u; i++) {
+                    runs[o + 2u + i] = r[i];
+                }
+            }
+        }
+    }
+}
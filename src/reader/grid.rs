@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use super::{
+    binarize::BinaryImage,
+    symbol::{Symbol, SymbolLocation},
+};
+use crate::{
+    builder::{Module, QR},
+    metadata::{Color, Metadata},
+    utils::{QRError, QRResult},
+    ECLevel, Version,
+};
+
+// Grid validation
+//------------------------------------------------------------------------------
+
+// Function pattern a module belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FunctionPattern {
+    Finder,
+    Separator, // Light border around each finder
+    Timing,
+    Alignment,
+    DarkModule,
+}
+
+// Function pattern module whose color differs from the spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridMismatch {
+    pub x: usize,
+    pub y: usize,
+    pub pattern: FunctionPattern,
+    pub expected_dark: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridReport {
+    pub version: Version,
+    pub mismatches: Vec<GridMismatch>,
+}
+
+impl GridReport {
+    pub fn is_valid(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn count(&self, pattern: FunctionPattern) -> usize {
+        self.mismatches.iter().filter(|m| m.pattern == pattern).count()
+    }
+
+    // One line per mismatched module followed by a summary
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for m in &self.mismatches {
+            let exp = if m.expected_dark { "dark" } else { "light" };
+            out.push_str(&format!(
+                "{:?} module at ({}, {}) should be {exp}\n",
+                m.pattern, m.x, m.y
+            ));
+        }
+        out.push_str(&format!(
+            "{:?}: {} mismatched function pattern modules\n",
+            self.version,
+            self.mismatches.len()
+        ));
+        out
+    }
+
+    // Error decode_grid fails with, from the first mismatch
+    fn error(&self) -> Option<QRError> {
+        self.mismatches.first().map(|m| match m.pattern {
+            FunctionPattern::Finder | FunctionPattern::Separator => QRError::FinderMismatch,
+            FunctionPattern::Timing => QRError::TimingMismatch,
+            FunctionPattern::Alignment => QRError::AlignmentMismatch,
+            FunctionPattern::DarkModule => QRError::InvalidCapacityInfo,
+        })
+    }
+}
+
+// Checks the finders, separators, timing & alignment patterns & the dark module of a matrix of
+// modules against the spec. grid[y][x] is true for dark modules, without a quiet zone. Fails with
+// InvalidVersion if the grid isn't a square of a normal QR width
+pub fn validate_grid(grid: &[Vec<bool>]) -> QRResult<GridReport> {
+    let w = grid.len();
+    let ver = Version::from_grid_size(w)
+        .filter(|&v| v.width() == w && grid.iter().all(|row| row.len() == w))
+        .ok_or(QRError::InvalidVersion)?;
+
+    let tmpl = QR::from_template(ver, ECLevel::L, false);
+    let mut mismatches = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &dark) in row.iter().enumerate() {
+            let (xi, yi) = (x as i32, y as i32);
+            let pattern = match pattern_at(xi, yi, w as i32) {
+                Some(FunctionPattern::DarkModule) => FunctionPattern::DarkModule,
+                Some(p) if matches!(tmpl.get(xi, yi), Module::Func(_)) => p,
+                _ => continue,
+            };
+            let expected_dark = *tmpl.get(xi, yi) == Color::Black;
+            if dark != expected_dark {
+                mismatches.push(GridMismatch { x, y, pattern, expected_dark });
+            }
+        }
+    }
+    Ok(GridReport { version: ver, mismatches })
+}
+
+// Pattern of a function module, by position. Alignment patterns overlapping the timing lines
+// take precedence, as the template draws them last
+fn pattern_at(x: i32, y: i32, w: i32) -> Option<FunctionPattern> {
+    if (x, y) == (8, w - 8) {
+        return Some(FunctionPattern::DarkModule);
+    }
+    let near = |c: i32| c <= 7 || c >= w - 8;
+    let inner = |c: i32| c <= 6 || c >= w - 7;
+    let in_corner = near(x) && near(y) && !(x >= w - 8 && y >= w - 8);
+    if in_corner {
+        return match inner(x) && inner(y) {
+            true => Some(FunctionPattern::Finder),
+            false => Some(FunctionPattern::Separator),
+        };
+    }
+    let ver = Version::from_grid_size(w as usize)?;
+    let poses = ver.alignment_pattern();
+    if poses.iter().any(|&a| (x - a).abs() <= 2) && poses.iter().any(|&a| (y - a).abs() <= 2) {
+        return Some(FunctionPattern::Alignment);
+    }
+    (x == 6 || y == 6).then_some(FunctionPattern::Timing)
+}
+
+// Grid decoding
+//------------------------------------------------------------------------------
+
+// Decodes a matrix of modules, e.g. one built programmatically or read by another scanner. The
+// grid is validated first & decoding fails with the error of the first mismatched function
+// pattern. Run validate_grid for the full list of mismatches
+pub fn decode_grid(grid: &[Vec<bool>]) -> QRResult<(Metadata, String)> {
+    let report = validate_grid(grid)?;
+    if let Some(err) = report.error() {
+        return Err(err);
+    }
+
+    // Modules are rendered as 3x3 pixel squares so centre sampling never rounds into a neighbour
+    let (w, qz) = (grid.len() as u32, GRID_QUIET_ZONE);
+    let sz = (w + 2 * qz) * GRID_MODULE_PX;
+    let img = BinaryImage::from_fn(sz, sz, |x, y| {
+        let (x, y) = (x / GRID_MODULE_PX, y / GRID_MODULE_PX);
+        let dark = (qz..w + qz).contains(&x)
+            && (qz..w + qz).contains(&y)
+            && grid[(y - qz) as usize][(x - qz) as usize];
+        Color::from(!dark)
+    });
+    let (px, origin) = (GRID_MODULE_PX as f64, (qz * GRID_MODULE_PX) as f64);
+    let loc = SymbolLocation::axis_aligned((origin, origin), (px, px), report.version);
+    Symbol::new(Arc::new(img), loc).decode()
+}
+
+#[cfg(test)]
+mod grid_tests {
+    use super::{decode_grid, validate_grid, FunctionPattern, GridMismatch};
+    use crate::{
+        builder::QRBuilder,
+        metadata::{Color, MAX_VERSION},
+        utils::QRError,
+        Version,
+    };
+
+    fn to_grid(data: &str, ver: Version) -> Vec<Vec<bool>> {
+        let qr = QRBuilder::new(data.as_bytes()).version(ver).build().unwrap();
+        let w = qr.width() as i32;
+        (0..w).map(|y| (0..w).map(|x| *qr.get(x, y) == Color::Black).collect()).collect()
+    }
+
+    #[test]
+    fn test_decode_grid() {
+        for ver in [1, 7, 25].into_iter().filter(|&v| v <= MAX_VERSION) {
+            let grid = to_grid("Grid sanity", Version::Normal(ver));
+            assert!(validate_grid(&grid).unwrap().is_valid());
+            assert_eq!(decode_grid(&grid).unwrap().1, "Grid sanity");
+        }
+    }
+
+    #[test]
+    fn test_validate_grid() {
+        let mut grid = to_grid("Grid sanity", Version::Normal(5));
+        grid[3][3] = false;
+        grid[7][2] = true;
+        grid[6][11] = true;
+        grid[30][30] = false;
+        grid[29][8] = false;
+        grid[20][20] = !grid[20][20]; // Data module
+
+        let report = validate_grid(&grid).unwrap();
+        assert_eq!(report.version, Version::Normal(5));
+        assert_eq!(
+            report.mismatches,
+            [
+                GridMismatch { x: 3, y: 3, pattern: FunctionPattern::Finder, expected_dark: true },
+                GridMismatch {
+                    x: 11,
+                    y: 6,
+                    pattern: FunctionPattern::Timing,
+                    expected_dark: false
+                },
+                GridMismatch {
+                    x: 2,
+                    y: 7,
+                    pattern: FunctionPattern::Separator,
+                    expected_dark: false
+                },
+                GridMismatch {
+                    x: 8,
+                    y: 29,
+                    pattern: FunctionPattern::DarkModule,
+                    expected_dark: true
+                },
+                GridMismatch {
+                    x: 30,
+                    y: 30,
+                    pattern: FunctionPattern::Alignment,
+                    expected_dark: true
+                },
+            ]
+        );
+        assert!(report.summary().ends_with("Normal(5): 5 mismatched function pattern modules\n"));
+        assert_eq!(decode_grid(&grid).unwrap_err(), QRError::FinderMismatch);
+
+        let mut ragged = to_grid("Grid sanity", Version::Normal(1));
+        ragged[4].pop();
+        assert_eq!(validate_grid(&ragged), Err(QRError::InvalidVersion));
+        assert_eq!(validate_grid(&vec![vec![false; 22]; 22]), Err(QRError::InvalidVersion));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const GRID_MODULE_PX: u32 = 3;
+const GRID_QUIET_ZONE: u32 = 4;
@@ -0,0 +1,203 @@
+use encoding_rs::Encoding;
+
+use super::{symbol::Symbol, DecodeResult};
+use crate::{
+    metadata::Metadata,
+    utils::{QRError, QRResult},
+};
+
+// Module sampling
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sampling {
+    #[default]
+    Center, // Single pixel at the module centre
+    Area,     // Gaussian weighted vote over the module, robust to high ISO noise
+    Vote,     // Majority of the centre & 4 points around it, robust to dot gain & ink spread
+    Dithered, // Channel bits from the module quadrants, for Poly codes from to_dithered_image
+}
+
+// Decode hints
+//------------------------------------------------------------------------------
+
+// Expectations about the payload. Symbols whose payload doesn't meet them are rejected, which
+// guards against swapped or stickered codes and lets multi-symbol scenes stop at the relevant one
+#[derive(Debug, Clone, Default)]
+pub struct DecodeHints {
+    prefix: Option<String>,
+    #[cfg(feature = "regex")]
+    pattern: Option<regex::Regex>,
+    sampling: Sampling,
+    charset: Option<&'static Encoding>,
+}
+
+impl DecodeHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expect_prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn expect_regex(&mut self, pattern: regex::Regex) -> &mut Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    pub fn sampling(&mut self, sampling: Sampling) -> &mut Self {
+        self.sampling = sampling;
+        self
+    }
+
+    // Reads byte segments in this charset instead of detecting it from BOMs & byte patterns. For
+    // payloads in a known charset that is neither utf8 nor shift jis, e.g. windows 1252
+    pub fn charset_override(&mut self, charset: &'static Encoding) -> &mut Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    pub fn matches(&self, payload: &str) -> bool {
+        if let Some(p) = &self.prefix {
+            if !payload.starts_with(p.as_str()) {
+                return false;
+            }
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(re) = &self.pattern {
+            if !re.is_match(payload) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Symbol {
+    // Decodes the symbol and rejects payloads not matching the hints
+    pub fn decode_with_hints(&self, hints: &DecodeHints) -> QRResult<(Metadata, String)> {
+        let (meta, msg) = self.decode_with(hints.sampling, hints.charset)?;
+        if !hints.matches(&msg) {
+            return Err(QRError::UnexpectedPayload);
+        }
+        Ok((meta, msg))
+    }
+}
+
+impl DecodeResult {
+    // Decodes symbols in order and stops at the first one matching the hints
+    pub fn find_matching(&mut self, hints: &DecodeHints) -> Option<(Metadata, String)> {
+        self.symbols.iter_mut().find_map(|s| s.decode_with_hints(hints).ok())
+    }
+}
+
+#[cfg(test)]
+mod hints_tests {
+    use image::{DynamicImage, GenericImage, RgbImage};
+
+    use super::{DecodeHints, Sampling};
+    use crate::{builder::QRBuilder, metadata::Version, reader::detect_qr, utils::QRError};
+
+    #[test]
+    fn test_prefix() {
+        let mut hints = DecodeHints::new();
+        assert!(hints.matches("anything"));
+        hints.expect_prefix("https://example.com/");
+        assert!(hints.matches("https://example.com/ticket/42"));
+        assert!(!hints.matches("https://example.co/ticket/42"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let mut hints = DecodeHints::new();
+        hints.expect_regex(regex::Regex::new(r"^TICKET-\d{4}$").unwrap());
+        assert!(hints.matches("TICKET-1234"));
+        assert!(!hints.matches("TICKET-12345"));
+    }
+
+    // Version 2 symbol with specks of inverted pixels at the centres of 30% of the modules away
+    // from function patterns
+    fn specked_image(msg: &str) -> RgbImage {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let qr = QRBuilder::new(msg.as_bytes()).version(Version::Normal(2)).build().unwrap();
+        let mut img = qr.to_image(7);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let is_func = |x: u32, y: u32| {
+            let finder = |m: u32| !(9..=15).contains(&m);
+            (finder(x) && finder(y) && (x < 9 || y < 9))
+                || ((15..22).contains(&x) && (15..22).contains(&y))
+                || x == 6
+                || y == 6
+        };
+        for my in 0..25 {
+            for mx in 0..25 {
+                if is_func(mx, my) {
+                    continue;
+                }
+                if !rng.random_bool(0.3) {
+                    continue;
+                }
+                let (x0, y0) = ((mx + 4) * 7 + 3, (my + 4) * 7 + 3);
+                for (x, y) in [(x0, y0), (x0 + 1, y0), (x0, y0 + 1), (x0 + 1, y0 + 1)] {
+                    let p = img.get_pixel_mut(x, y);
+                    p.0 = p.0.map(|c| 255 - c);
+                }
+            }
+        }
+        img
+    }
+
+    #[test]
+    fn test_area_and_vote_sampling() {
+        let msg = "Hello, world!";
+        let res = detect_qr(&DynamicImage::ImageRgb8(specked_image(msg)));
+        assert!(res.symbols()[0].decode().is_err());
+
+        let mut hints = DecodeHints::new();
+        for sampling in [Sampling::Area, Sampling::Vote] {
+            hints.sampling(sampling);
+            let (_, decoded) = res.symbols()[0].decode_with_hints(&hints).unwrap();
+            assert_eq!(decoded, msg);
+        }
+    }
+
+    #[test]
+    fn test_charset_override() {
+        let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode("Café crème");
+        let qr = QRBuilder::new(&bytes).build().unwrap();
+        let res = detect_qr(&DynamicImage::ImageRgb8(qr.to_image(3)));
+        assert!(res.symbols()[0].decode().map_or(true, |(_, m)| m != "Café crème"));
+
+        let mut hints = DecodeHints::new();
+        hints.charset_override(encoding_rs::WINDOWS_1252);
+        let (_, msg) = res.symbols()[0].decode_with_hints(&hints).unwrap();
+        assert_eq!(msg, "Café crème");
+    }
+
+    #[test]
+    fn test_find_matching() {
+        let a = QRBuilder::new(b"https://evil.example/").build().unwrap().to_image(3);
+        let b = QRBuilder::new(b"https://example.com/pay").build().unwrap().to_image(3);
+        let mut img = RgbImage::from_pixel(a.width() + b.width(), a.height(), image::Rgb([255; 3]));
+        img.copy_from(&a, 0, 0).unwrap();
+        img.copy_from(&b, a.width(), 0).unwrap();
+
+        let mut hints = DecodeHints::new();
+        hints.expect_prefix("https://example.com/");
+
+        let mut res = detect_qr(&DynamicImage::ImageRgb8(img));
+        let (_, msg) = res.find_matching(&hints).unwrap();
+        assert_eq!(msg, "https://example.com/pay");
+
+        let rejected = res.symbols().iter().filter_map(|s| s.decode_with_hints(&hints).err());
+        assert_eq!(rejected.collect::<Vec<_>>(), [QRError::UnexpectedPayload]);
+    }
+}
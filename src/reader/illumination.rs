@@ -0,0 +1,122 @@
+use image::{imageops, GrayImage, Luma};
+
+// Illumination flattening
+//------------------------------------------------------------------------------
+
+// Divides out the background lighting & stretches the contrast, for images with strong
+// vignetting or a shadow cast across the symbol. Light modules in the shadow come out as bright
+// as those outside it, so the thresholds of blocks straddling the shadow edge aren't skewed
+pub(crate) fn flatten_illumination(img: &mut GrayImage) {
+    let Some(bg) = estimate_background(img) else { return };
+    for (p, b) in img.pixels_mut().zip(bg.pixels()) {
+        let v = p.0[0] as u32 * 255 / b.0[0].max(1) as u32;
+        p.0[0] = v.min(255) as u8;
+    }
+    stretch_contrast(img);
+}
+
+// Brightness of the paper under each pixel, like a rolling ball run from below. The image is
+// max pooled onto a coarse grid & closed, which removes dark details several modules wide, then
+// smoothed & scaled back up. None for empty images
+fn estimate_background(img: &GrayImage) -> Option<GrayImage> {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return None;
+    }
+
+    let cell = w.max(h).div_ceil(BG_GRID_SIZE);
+    let (gw, gh) = (w.div_ceil(cell), h.div_ceil(cell));
+    let mut grid = GrayImage::new(gw, gh);
+    for (x, y, p) in img.enumerate_pixels() {
+        let g = grid.get_pixel_mut(x / cell, y / cell);
+        g.0[0] = g.0[0].max(p.0[0]);
+    }
+
+    let dilated = min_max_filter(&grid, BG_CLOSING_RADIUS, u8::max);
+    let grid = min_max_filter(&dilated, BG_CLOSING_RADIUS, u8::min);
+    let grid = imageops::blur(&grid, BG_SMOOTHING_SIGMA);
+    Some(imageops::resize(&grid, w, h, imageops::FilterType::Triangle))
+}
+
+// Max or min of the square window of radius r around each pixel
+fn min_max_filter(img: &GrayImage, r: u32, f: fn(u8, u8) -> u8) -> GrayImage {
+    let (w, h) = img.dimensions();
+    GrayImage::from_fn(w, h, |x, y| {
+        let xs = x.saturating_sub(r)..=(x + r).min(w - 1);
+        let ys = y.saturating_sub(r)..=(y + r).min(h - 1);
+        let px = ys.flat_map(|ny| xs.clone().map(move |nx| (nx, ny)));
+        let v = px.map(|(nx, ny)| img.get_pixel(nx, ny).0[0]).reduce(f);
+        Luma([v.unwrap_or_default()])
+    })
+}
+
+// Maps the STRETCH_CLIP & 1 - STRETCH_CLIP quantiles of luma to 0 & 255
+fn stretch_contrast(img: &mut GrayImage) {
+    let mut hist = [0usize; 256];
+    img.pixels().for_each(|p| hist[p.0[0] as usize] += 1);
+    let clip = (img.len() as f64 * STRETCH_CLIP) as usize;
+    let lo = clip_level(&hist, 0..256, clip);
+    let hi = clip_level(&hist, (0..256).rev(), clip);
+    let (Some(lo), Some(hi)) = (lo, hi) else { return };
+    if hi <= lo {
+        return;
+    }
+
+    for p in img.pixels_mut() {
+        let v = (p.0[0] as usize).clamp(lo, hi);
+        p.0[0] = ((v - lo) * 255 / (hi - lo)) as u8;
+    }
+}
+
+// First level in the given order past the clipped pixel count
+fn clip_level(
+    hist: &[usize; 256],
+    mut levels: impl Iterator<Item = usize>,
+    clip: usize,
+) -> Option<usize> {
+    let mut acc = 0;
+    levels.find(|&v| {
+        acc += hist[v];
+        acc > clip
+    })
+}
+
+#[cfg(test)]
+mod illumination_tests {
+    use image::{GrayImage, Luma};
+
+    use super::flatten_illumination;
+
+    #[test]
+    fn test_flatten_illumination() {
+        // Stripes of paper & ink with the right half in shadow
+        let shade = |x: u32| if x < 60 { 1.0 } else { 0.35 };
+        let mut img = GrayImage::from_fn(120, 80, |x, _| {
+            let v = if x % 8 < 4 { 40.0 } else { 220.0 };
+            Luma([(v * shade(x)) as u8])
+        });
+        flatten_illumination(&mut img);
+
+        let (lit, shaded) = (img.get_pixel(20, 40).0[0], img.get_pixel(100, 40).0[0]);
+        assert!(lit > 200 && shaded > 200, "{lit} {shaded}");
+        assert!(img.get_pixel(16, 40).0[0] < 80);
+        assert!(img.get_pixel(96, 40).0[0] < 80);
+
+        let mut blank = GrayImage::new(0, 0);
+        flatten_illumination(&mut blank);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Cells along the longer side of the grid the background is estimated on
+const BG_GRID_SIZE: u32 = 64;
+
+// Radius in grid cells of the closing, which removes dark details up to twice as wide
+const BG_CLOSING_RADIUS: u32 = 4;
+
+const BG_SMOOTHING_SIGMA: f32 = 1.5;
+
+// Fraction of the darkest & brightest pixels saturated by the contrast stretch
+const STRETCH_CLIP: f64 = 0.01;
@@ -0,0 +1,430 @@
+use std::fmt::Write;
+
+use super::DecodeResult;
+use crate::{
+    codec::url_origin_len,
+    utils::{QRError, QRResult},
+};
+
+// Payload intents
+//------------------------------------------------------------------------------
+
+// Action a payload asks the scanning app to take. Payloads with a known scheme are validated &
+// unescaped, so fields can be handed to the platform as is
+#[derive(Debug, Clone, PartialEq)]
+pub enum Intent {
+    Url(String),
+    Phone(String), // Digits with an optional leading '+', visual separators removed
+    Email(Email),
+    Geo(Geo),
+    Wifi(WifiConfig),
+    Text(String), // Payload without a known scheme
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geo {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiAuth {
+    Open,
+    Wep,
+    Wpa, // WPA, WPA2 & WPA3 personal, which platforms negotiate from the same config
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiConfig {
+    pub ssid: String,
+    pub password: Option<String>,
+    pub auth: WifiAuth,
+    pub hidden: bool,
+}
+
+impl Intent {
+    // Recognizes http(s) urls, tel:, mailto: & MATMSG: emails, geo: & WIFI: payloads. Fails if
+    // the scheme is known but the rest of the payload is malformed
+    pub fn parse(payload: &str) -> QRResult<Self> {
+        let has_scheme =
+            |s: &str| payload.get(..s.len()).is_some_and(|p| p.eq_ignore_ascii_case(s));
+        let rest = |s: &str| &payload[s.len()..];
+        if url_origin_len(payload.as_bytes()).is_some() {
+            parse_url(payload).map(Self::Url)
+        } else if has_scheme(TEL_SCHEME) {
+            parse_phone(rest(TEL_SCHEME)).map(Self::Phone)
+        } else if has_scheme(MAILTO_SCHEME) {
+            parse_mailto(rest(MAILTO_SCHEME)).map(Self::Email)
+        } else if has_scheme(MATMSG_PREFIX) {
+            parse_matmsg(rest(MATMSG_PREFIX)).map(Self::Email)
+        } else if has_scheme(GEO_SCHEME) {
+            parse_geo(rest(GEO_SCHEME)).map(Self::Geo)
+        } else if has_scheme(WIFI_PREFIX) {
+            parse_wifi(rest(WIFI_PREFIX)).map(Self::Wifi)
+        } else {
+            Ok(Self::Text(payload.to_string()))
+        }
+    }
+
+    // Canonical uri of the intent, e.g. to hand to the platform's url opener. None for text
+    pub fn to_uri(&self) -> Option<String> {
+        let uri = match self {
+            Self::Url(url) => url.clone(),
+            Self::Phone(num) => format!("{TEL_SCHEME}{num}"),
+            Self::Email(email) => {
+                let mut uri = MAILTO_SCHEME.to_string();
+                let to = email.to.iter().map(|a| percent_encode(a, "@"));
+                uri.push_str(&to.collect::<Vec<_>>().join(","));
+                let query = [("subject", &email.subject), ("body", &email.body)];
+                let query = query.iter().filter_map(|(k, v)| Some((k, v.as_ref()?)));
+                for (i, (k, v)) in query.enumerate() {
+                    let sep = if i == 0 { '?' } else { '&' };
+                    let _ = write!(uri, "{sep}{k}={}", percent_encode(v, ""));
+                }
+                uri
+            }
+            Self::Geo(geo) => match geo.alt {
+                Some(alt) => format!("{GEO_SCHEME}{},{},{alt}", geo.lat, geo.lon),
+                None => format!("{GEO_SCHEME}{},{}", geo.lat, geo.lon),
+            },
+            Self::Wifi(wifi) => {
+                let auth = match wifi.auth {
+                    WifiAuth::Open => "nopass",
+                    WifiAuth::Wep => "WEP",
+                    WifiAuth::Wpa => "WPA",
+                };
+                let mut uri = format!("{WIFI_PREFIX}T:{auth};S:{};", wifi_escape(&wifi.ssid));
+                if let Some(pass) = &wifi.password {
+                    let _ = write!(uri, "P:{};", wifi_escape(pass));
+                }
+                if wifi.hidden {
+                    uri.push_str("H:true;");
+                }
+                uri.push(';');
+                uri
+            }
+            Self::Text(_) => return None,
+        };
+        Some(uri)
+    }
+}
+
+impl DecodeResult {
+    // Intent of every symbol, in symbol order. Symbols that fail to decode keep their error
+    pub fn intents(&self) -> Vec<QRResult<Intent>> {
+        let res = self.decode_all().into_iter();
+        res.map(|r| r.and_then(|(_, msg)| Intent::parse(&msg))).collect()
+    }
+}
+
+// Payload parsers
+//------------------------------------------------------------------------------
+
+fn parse_url(payload: &str) -> QRResult<String> {
+    let host_start = payload.find("//").map_or(0, |i| i + 2);
+    let host_end = url_origin_len(payload.as_bytes()).unwrap_or(payload.len());
+    let is_unsafe = |c: char| c.is_whitespace() || c.is_control();
+    if host_end == host_start || payload.contains(is_unsafe) {
+        return Err(QRError::MalformedIntent("url"));
+    }
+    Ok(payload.to_string())
+}
+
+// Strips the separators people type into numbers & checks for 3 to 15 digits, the longest an
+// E.164 number can be
+fn parse_phone(num: &str) -> QRResult<String> {
+    let err = QRError::MalformedIntent("tel");
+    let num = num.trim();
+    let (plus, digits) = match num.strip_prefix('+') {
+        Some(d) => ("+", d),
+        None => ("", num),
+    };
+    let mut res = plus.to_string();
+    for c in digits.chars() {
+        match c {
+            '0'..='9' => res.push(c),
+            ' ' | '-' | '.' | '(' | ')' => (),
+            _ => return Err(err),
+        }
+    }
+    if !(3..=15).contains(&(res.len() - plus.len())) {
+        return Err(err);
+    }
+    Ok(res)
+}
+
+// RFC 6068, i.e. comma separated addresses followed by an optional query of percent encoded
+// headers. Headers other than to, subject & body are ignored
+fn parse_mailto(rest: &str) -> QRResult<Email> {
+    let err = QRError::MalformedIntent("mailto");
+    let (addrs, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut email = Email { to: Vec::new(), subject: None, body: None };
+    for addr in addrs.split(',').filter(|a| !a.is_empty()) {
+        email.to.push(percent_decode(addr).ok_or(err)?);
+    }
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (k, v) = pair.split_once('=').ok_or(err)?;
+        let v = Some(percent_decode(v).ok_or(err)?);
+        match k.to_ascii_lowercase().as_str() {
+            "subject" => email.subject = v,
+            "body" => email.body = v,
+            "to" => email.to.extend(v),
+            _ => (),
+        }
+    }
+    validate_email(email, err)
+}
+
+// NTT docomo format, e.g. MATMSG:TO:a@b.com;SUB:Hi;BODY:Hello;;
+fn parse_matmsg(rest: &str) -> QRResult<Email> {
+    let err = QRError::MalformedIntent("mailto");
+    let mut email = Email { to: Vec::new(), subject: None, body: None };
+    for (k, v) in split_fields(rest).ok_or(err)? {
+        match k {
+            "TO" => email.to.push(v),
+            "SUB" => email.subject = Some(v),
+            "BODY" => email.body = Some(v),
+            _ => (),
+        }
+    }
+    validate_email(email, err)
+}
+
+// Every address needs a local part & a dotted domain. No address is fine, as the user picks one
+fn validate_email(email: Email, err: QRError) -> QRResult<Email> {
+    let is_valid = |a: &String| {
+        let Some((local, domain)) = a.rsplit_once('@') else { return false };
+        let is_unsafe = |c: char| c.is_whitespace() || c.is_control();
+        let is_dotted = domain.split('.').count() > 1 && domain.split('.').all(|l| !l.is_empty());
+        !local.is_empty() && is_dotted && !a.contains(is_unsafe)
+    };
+    if !email.to.iter().all(is_valid) {
+        return Err(err);
+    }
+    Ok(email)
+}
+
+// RFC 5870, i.e. lat,lon[,alt] followed by optional parameters & query, which are ignored
+fn parse_geo(rest: &str) -> QRResult<Geo> {
+    let err = QRError::MalformedIntent("geo");
+    let coords = rest.split([';', '?']).next().unwrap_or_default();
+    let coords = coords.split(',').map(|c| c.trim().parse::<f64>().ok().filter(|c| c.is_finite()));
+    let coords = coords.collect::<Option<Vec<_>>>().ok_or(err)?;
+    let (lat, lon, alt) = match coords[..] {
+        [lat, lon] => (lat, lon, None),
+        [lat, lon, alt] => (lat, lon, Some(alt)),
+        _ => return Err(err),
+    };
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(err);
+    }
+    Ok(Geo { lat, lon, alt })
+}
+
+// Fields as written by wifi share screens, e.g. WIFI:T:WPA;S:home;P:secret;H:true;;
+fn parse_wifi(rest: &str) -> QRResult<WifiConfig> {
+    let err = QRError::MalformedIntent("wifi");
+    let (mut ssid, mut password, mut auth, mut hidden) = (None, None, None, false);
+    for (k, v) in split_fields(rest).ok_or(err)? {
+        match k {
+            "S" => ssid = Some(v),
+            "P" if !v.is_empty() => password = Some(v),
+            "T" => {
+                auth = match v.to_ascii_uppercase().as_str() {
+                    "" | "NOPASS" => Some(WifiAuth::Open),
+                    "WEP" => Some(WifiAuth::Wep),
+                    "WPA" | "WPA2" | "WPA3" | "SAE" => Some(WifiAuth::Wpa),
+                    _ => return Err(err),
+                }
+            }
+            "H" => hidden = v.eq_ignore_ascii_case("true"),
+            _ => (),
+        }
+    }
+
+    let ssid = ssid.filter(|s| !s.is_empty()).ok_or(err)?;
+    let auth = auth.unwrap_or(if password.is_some() { WifiAuth::Wpa } else { WifiAuth::Open });
+    if (auth == WifiAuth::Open) != password.is_none() {
+        return Err(err);
+    }
+    Ok(WifiConfig { ssid, password, auth, hidden })
+}
+
+// Field helpers
+//------------------------------------------------------------------------------
+
+// Splits "KEY:value;" fields at unescaped ';' & unescapes the values. None if a field has no key
+fn split_fields(body: &str) -> Option<Vec<(&str, String)>> {
+    let mut fields = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix(';') {
+            rest = r;
+            continue;
+        }
+        let (key, val) = rest.split_once(':')?;
+        if key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        let mut value = String::new();
+        let mut chars = val.char_indices();
+        rest = "";
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                ';' => {
+                    rest = &val[i + 1..];
+                    break;
+                }
+                _ => value.push(c),
+            }
+        }
+        fields.push((key, value));
+    }
+    Some(fields)
+}
+
+fn wifi_escape(val: &str) -> String {
+    let mut res = String::with_capacity(val.len());
+    for c in val.chars() {
+        if WIFI_SPECIAL_CHARS.contains(c) {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+// Decodes %XX escapes. None for truncated escapes or bytes that aren't utf8
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            res.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            res.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(res).ok()
+}
+
+// Escapes every byte but unreserved chars & those in keep
+fn percent_encode(s: &str, keep: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || keep.as_bytes().contains(&b) {
+            res.push(b as char);
+        } else {
+            let _ = write!(res, "%{b:02X}");
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod intent_tests {
+    use super::{Email, Geo, Intent, WifiAuth, WifiConfig};
+    use crate::{builder::QRBuilder, reader::detect_qr, utils::QRError};
+
+    #[test]
+    fn test_intent_phone_url() {
+        assert_eq!(
+            Intent::parse("TEL:+1 (555) 010-9999"),
+            Ok(Intent::Phone("+15550109999".into()))
+        );
+        assert_eq!(Intent::parse("tel:12").err(), Some(QRError::MalformedIntent("tel")));
+        assert_eq!(Intent::parse("tel:555-CALL").err(), Some(QRError::MalformedIntent("tel")));
+        assert_eq!(Intent::Phone("+15550109999".into()).to_uri().unwrap(), "tel:+15550109999");
+
+        let url = "https://example.com/a?b=c";
+        assert_eq!(Intent::parse(url), Ok(Intent::Url(url.into())));
+        assert!(Intent::parse("https://example.com/a b").is_err());
+        assert!(Intent::parse("http:///path").is_err());
+        assert_eq!(Intent::parse("Hello"), Ok(Intent::Text("Hello".into())));
+        assert_eq!(Intent::Text("Hello".into()).to_uri(), None);
+    }
+
+    #[test]
+    fn test_intent_email() {
+        let exp = Email {
+            to: vec!["ops@example.com".into()],
+            subject: Some("Pallet 7".into()),
+            body: Some("Damaged; see photo".into()),
+        };
+        let uri = "mailto:ops@example.com?subject=Pallet%207&body=Damaged%3B%20see%20photo";
+        assert_eq!(Intent::parse(uri), Ok(Intent::Email(exp.clone())));
+        let matmsg = "MATMSG:TO:ops@example.com;SUB:Pallet 7;BODY:Damaged\\; see photo;;";
+        assert_eq!(Intent::parse(matmsg), Ok(Intent::Email(exp.clone())));
+        assert_eq!(Intent::Email(exp).to_uri().unwrap(), uri);
+
+        assert!(Intent::parse("mailto:ops@localhost").is_err());
+        assert!(Intent::parse("mailto:ops@example.com?subject=%E2%28").is_err());
+    }
+
+    #[test]
+    fn test_intent_geo() {
+        let geo = Geo { lat: 48.2010, lon: 16.3695, alt: Some(183.0) };
+        assert_eq!(Intent::parse("geo:48.2010,16.3695,183;u=40"), Ok(Intent::Geo(geo)));
+        assert_eq!(Intent::Geo(geo).to_uri().unwrap(), "geo:48.201,16.3695,183");
+        assert!(Intent::parse("geo:91,0").is_err());
+        assert!(Intent::parse("geo:0").is_err());
+        assert!(Intent::parse("geo:NaN,0").is_err());
+    }
+
+    #[test]
+    fn test_intent_wifi() {
+        let exp = WifiConfig {
+            ssid: "Cafe; 2F".into(),
+            password: Some("a:b,c".into()),
+            auth: WifiAuth::Wpa,
+            hidden: true,
+        };
+        let payload = "WIFI:T:WPA;S:Cafe\\; 2F;P:a\\:b\\,c;H:true;;";
+        assert_eq!(Intent::parse(payload), Ok(Intent::Wifi(exp.clone())));
+        assert_eq!(Intent::Wifi(exp).to_uri().unwrap(), payload);
+
+        let open = Intent::parse("WIFI:S:Lobby;T:nopass;;").unwrap();
+        assert!(matches!(open, Intent::Wifi(WifiConfig { auth: WifiAuth::Open, .. })));
+        assert!(Intent::parse("WIFI:T:WPA;P:secret;;").is_err());
+        assert!(Intent::parse("WIFI:T:WEP;S:Lobby;;").is_err());
+        assert!(Intent::parse("WIFI:T:WPA;S:home;oops;;").is_err());
+    }
+
+    #[test]
+    fn test_decode_result_intents() {
+        let qr = QRBuilder::new(b"geo:1.5,-2").build().unwrap();
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(qr.to_image(3)));
+        let geo = Geo { lat: 1.5, lon: -2.0, alt: None };
+        assert_eq!(res.intents(), [Ok(Intent::Geo(geo))]);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const TEL_SCHEME: &str = "tel:";
+
+const MAILTO_SCHEME: &str = "mailto:";
+
+const MATMSG_PREFIX: &str = "MATMSG:";
+
+const GEO_SCHEME: &str = "geo:";
+
+const WIFI_PREFIX: &str = "WIFI:";
+
+// Chars escaped with a backslash in WiFi & MATMSG field values
+const WIFI_SPECIAL_CHARS: &str = "\\;,:\"";
@@ -1,88 +1,508 @@
+pub mod barcode;
 pub mod binarize;
+pub mod debug;
+pub mod dedup;
 mod finder;
+mod glare;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod grid;
+pub mod hints;
+mod illumination;
+pub mod intent;
+#[cfg(feature = "opencv")]
+pub mod opencv;
+pub mod options;
+pub mod pages;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod report;
+pub mod robust;
+pub mod source;
+#[cfg(feature = "svg-in")]
+pub mod svg;
 pub mod symbol;
+pub mod threat;
 mod utils;
+pub mod view;
 
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::HashSet, sync::Arc, time::Instant};
 
-use finder::{group_finders, locate_finders, FinderGroup};
+use barcode::{locate_barcodes, Barcode};
+use debug::capture_stages;
+use finder::{group_finders, locate_finders, score_saliency, FinderGroup};
 
 use binarize::BinaryImage;
-use image::DynamicImage;
-use symbol::{Symbol, SymbolLocation};
+use dedup::{PayloadDeduper, UniquePayload};
+use glare::GlareMask;
+#[cfg(feature = "poly")]
+use hints::Sampling;
+use illumination::flatten_illumination;
+use image::{imageops, DynamicImage, GrayImage, Luma};
+pub use intent::{Email, Geo, Intent, WifiAuth, WifiConfig};
+pub use options::{DetectOptions, ScanAngles};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use report::DetectTimings;
+pub use robust::{DecodeStrategy, RobustDecoder, StrategyProfile, StrategyStats};
+use symbol::{sort_by_saliency, sort_reading_order, Symbol, SymbolLocation};
+pub use threat::{Threat, ThreatReport};
+use utils::geometry::Point;
+use view::{ImageView, LumaAdapter};
+
+use crate::{
+    metadata::{Color, Metadata, StructuredAppend, Version},
+    utils::{QRError, QRResult},
+};
 
 // Decode result
 //------------------------------------------------------------------------------
 
+#[derive(Debug, Clone)]
 pub struct DecodeResult {
-    img: Arc<BinaryImage>,
+    imgs: Vec<Arc<BinaryImage>>,
     symbols: Vec<Symbol>,
+    rejections: Vec<QRError>,
+    timings: DetectTimings,
 }
 
 impl DecodeResult {
-    pub fn symbols(&mut self) -> &mut [Symbol] {
+    // Symbols ordered by page, then top left to bottom right. Symbols decode through shared
+    // references, so the result can be shared across threads to decode them in parallel
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn symbols_mut(&mut self) -> &mut [Symbol] {
         &mut self.symbols
     }
+
+    // Reasons candidates with a full set of finders were dropped for degenerate geometry, such
+    // as collinear centres or an ill conditioned projection, or the image couldn't be searched
+    pub fn rejections(&self) -> &[QRError] {
+        &self.rejections
+    }
+
+    // Time spent detecting, summed over pages. Decoding happens on demand, so isn't included
+    pub fn timings(&self) -> DetectTimings {
+        self.timings
+    }
+
+    pub(crate) fn empty() -> Self {
+        let timings = DetectTimings::default();
+        Self { imgs: Vec::new(), symbols: Vec::new(), rejections: Vec::new(), timings }
+    }
+
+    // No symbols, with the reason the image couldn't be searched as the only rejection
+    fn failed(err: QRError, timings: DetectTimings) -> Self {
+        Self { rejections: vec![err], timings, ..Self::empty() }
+    }
+
+    // Appends the symbols of a page, tagging each with the page index
+    pub(crate) fn push_page(&mut self, page: usize, res: DecodeResult) {
+        self.imgs.extend(res.imgs);
+        self.rejections.extend(res.rejections);
+        self.timings += res.timings;
+        self.symbols.extend(res.symbols.into_iter().map(|s| s.with_page(page)));
+    }
+
+    // Decodes every symbol, in parallel with the rayon feature. Results are in symbol order
+    pub fn decode_all(&self) -> Vec<QRResult<(Metadata, String)>> {
+        #[cfg(feature = "rayon")]
+        let res = self.symbols.par_iter().map(Symbol::decode).collect();
+        #[cfg(not(feature = "rayon"))]
+        let res = self.symbols.iter().map(Symbol::decode).collect();
+        res
+    }
+
+    // Decodes all symbols and collapses repeated payloads into one entry with occurrence counts
+    pub fn unique_payloads(&self) -> Vec<UniquePayload> {
+        let mut dedup = PayloadDeduper::new();
+        dedup.add_frame(self);
+        dedup.into_payloads()
+    }
+
+    // Joins the symbols of a structured append sequence back into the message. Symbols without
+    // the header or that fail to decode are skipped, & repeats of an index are read once. Fails
+    // if symbols of several sequences are found, any is missing or the joined data doesn't match
+    // the parity in the header
+    pub fn assemble(&self) -> QRResult<String> {
+        let mut parts: Vec<Option<(Metadata, String)>> = Vec::new();
+        let mut head: Option<StructuredAppend> = None;
+        for (meta, msg) in self.decode_all().into_iter().flatten() {
+            let Some(sa) = meta.structured_append() else { continue };
+            let head = head.get_or_insert(sa);
+            if (sa.total, sa.parity) != (head.total, head.parity) {
+                return Err(QRError::MixedStructuredAppend);
+            }
+            parts.resize(sa.total as usize, None);
+            parts[sa.index as usize].get_or_insert((meta, msg));
+        }
+
+        let head = head.ok_or(QRError::SymbolNotFound)?;
+        let mut res = String::new();
+        let mut parity = 0;
+        for (i, part) in parts.into_iter().enumerate() {
+            let (meta, msg) =
+                part.ok_or(QRError::MissingStructuredAppendSymbol(i as u8, head.total))?;
+            parity ^= meta.parity().unwrap_or_default();
+            res.push_str(&msg);
+        }
+        if parity != head.parity {
+            return Err(QRError::StructuredAppendParityMismatch);
+        }
+        Ok(res)
+    }
 }
 
 // MAIN FUNCTION
 //------------------------------------------------------------------------------
 
 pub fn detect_qr(img: &DynamicImage) -> DecodeResult {
-    let img = img.to_luma8();
-    let mut img = BinaryImage::prepare(&img);
+    detect_qr_with(img, &DetectOptions::default())
+}
 
-    let finders = locate_finders(&mut img);
-    let groups = group_finders(&finders);
+pub fn detect_qr_with(img: &DynamicImage, opts: &DetectOptions) -> DecodeResult {
+    let start = Instant::now();
+    let mut gray = img.to_luma8();
+    let glare = match opts.glare_inpainting {
+        true => GlareMask::detect(&gray).map(Arc::new),
+        false => None,
+    };
+    if let Some(g) = &glare {
+        g.inpaint(&mut gray);
+    }
+    if opts.illumination_flattening {
+        flatten_illumination(&mut gray);
+    }
+    let mut timings = DetectTimings { prepare: start.elapsed(), ..Default::default() };
+
+    let start = Instant::now();
+    let located = if opts.color_clustering {
+        let mut img = BinaryImage::color_cluster(&img.to_rgb8());
+        let finders = locate_finders(&mut img, opts);
+        Ok((img, finders))
+    } else {
+        opts.locate(&gray)
+    };
+    timings.locate = start.elapsed();
+    let (img, finders) = match located {
+        Ok(l) => l,
+        Err(e) => return DecodeResult::failed(e, timings),
+    };
+
+    collect_symbols(img, &finders, glare, Some(&gray), opts, timings)
+}
 
-    let sym_locs = locate_symbols(&mut img, groups);
+// Detects QRs in borrowed luma buffers such as ndarray views or raw frames. The view is binarized
+// directly unless an option needs an owned image, in which case it is copied once
+pub fn detect_qr_view<V: ImageView + ?Sized>(img: &V) -> DecodeResult {
+    detect_qr_view_with(img, &DetectOptions::default())
+}
+
+pub fn detect_qr_view_with<V: ImageView + ?Sized>(img: &V, opts: &DetectOptions) -> DecodeResult {
+    if opts.needs_gray_image() {
+        let gray = GrayImage::from_fn(img.width(), img.height(), |x, y| Luma([img.get_luma(x, y)]));
+        return detect_qr_with(&DynamicImage::ImageLuma8(gray), opts);
+    }
+
+    let start = Instant::now();
+    let luma = LumaAdapter(img);
+    let mut bin = BinaryImage::prepare(&luma);
+    let finders = locate_finders(&mut bin, opts);
+    let timings = DetectTimings { locate: start.elapsed(), ..Default::default() };
+    collect_symbols(bin, &finders, None, Some(&luma), opts, timings)
+}
+
+// Detects QRs in an image binarized earlier, e.g. one loaded back with BinaryImage::from_bytes
+// in a later pass of a pipeline. Options that work on the gray image are skipped
+pub fn detect_qr_binary(img: BinaryImage) -> DecodeResult {
+    detect_qr_binary_with(img, &DetectOptions::default())
+}
+
+pub fn detect_qr_binary_with(img: BinaryImage, opts: &DetectOptions) -> DecodeResult {
+    detect_qr_in(img, None, opts)
+}
+
+// Locates symbols in an already binarized image
+pub(crate) fn detect_qr_in(
+    mut img: BinaryImage,
+    glare: Option<Arc<GlareMask>>,
+    opts: &DetectOptions,
+) -> DecodeResult {
+    let start = Instant::now();
+    let finders = locate_finders(&mut img, opts);
+    let timings = DetectTimings { locate: start.elapsed(), ..Default::default() };
+    collect_symbols(img, &finders, glare, None, opts, timings)
+}
+
+fn collect_symbols(
+    mut img: BinaryImage,
+    finders: &[Point],
+    glare: Option<Arc<GlareMask>>,
+    luma: Option<&dyn ImageView>,
+    opts: &DetectOptions,
+    mut timings: DetectTimings,
+) -> DecodeResult {
+    let start = Instant::now();
+    let (sym_locs, rejections) = locate_candidates(&mut img, finders, luma, opts);
 
     let img = Arc::new(img);
-    let symbols = sym_locs.into_iter().map(|sl| Symbol::new(img.clone(), sl)).collect::<_>();
+    let mut symbols = sym_locs
+        .into_iter()
+        .map(|sl| {
+            let sym = Symbol::new(img.clone(), sl);
+            let sym = match luma {
+                Some(luma) if !opts.color_clustering => upscale_tiny_symbol(sym, luma, opts),
+                _ => sym,
+            };
+            match &glare {
+                Some(g) => sym.with_glare(g.clone()),
+                None => sym,
+            }
+        })
+        .collect::<Vec<_>>();
+    sort_symbols(&mut symbols, opts);
+    timings.group = start.elapsed();
+
+    if let Some(sink) = &opts.debug_sink {
+        capture_stages(sink.as_ref(), &img, finders, &symbols);
+    }
 
-    DecodeResult { img, symbols }
+    DecodeResult { imgs: vec![img], symbols, rejections, timings }
+}
+
+// Symbols with modules under MIN_MODULE_PX pixels, e.g. in screenshots, are resampled from a
+// bicubic upscale of their region of the image, as their modules blur into each other once
+// binarized at the original size
+fn upscale_tiny_symbol(sym: Symbol, luma: &dyn ImageView, opts: &DetectOptions) -> Symbol {
+    let (Ok(corners), Ok(extent)) = (sym.corners(), sym.extent()) else { return sym };
+    let mod_px = extent / sym.ver.width() as f64;
+    if mod_px <= 0.0 || mod_px >= MIN_MODULE_PX {
+        return sym;
+    }
+    let k = (UPSCALE_MODULE_PX / mod_px).ceil().min(MAX_UPSCALE);
+
+    // Bounding box of the symbol & its quiet zone, within the image
+    let margin = (mod_px * 4.0).ceil() as i32;
+    let (w, h) = (luma.width() as i32, luma.height() as i32);
+    let xs = corners.iter().map(|p| p.x);
+    let ys = corners.iter().map(|p| p.y);
+    let x0 = (xs.clone().min().unwrap_or(0) - margin).clamp(0, w) as u32;
+    let x1 = (xs.max().unwrap_or(0) + margin + 1).clamp(0, w) as u32;
+    let y0 = (ys.clone().min().unwrap_or(0) - margin).clamp(0, h) as u32;
+    let y1 = (ys.max().unwrap_or(0) + margin + 1).clamp(0, h) as u32;
+    if x1 <= x0 || y1 <= y0 {
+        return sym;
+    }
+
+    let crop = GrayImage::from_fn(x1 - x0, y1 - y0, |x, y| Luma([luma.get_luma(x0 + x, y0 + y)]));
+    let (cw, ch) = ((crop.width() as f64 * k) as u32, (crop.height() as f64 * k) as u32);
+    let up = imageops::resize(&crop, cw, ch, imageops::FilterType::CatmullRom);
+
+    // Locating the symbol again at the higher resolution gives a finer homography. The one closest
+    // to the crop centre is taken, as neighbouring symbols may reach into the crop
+    let Ok(mut bin) = opts.binarize(&up) else {
+        return sym;
+    };
+    let finders = locate_finders(&mut bin, opts);
+    let (locs, _) = locate_candidates(&mut bin, &finders, None, opts);
+    let mid = Point { x: cw as i32 / 2, y: ch as i32 / 2 };
+    let centre = |c: [Point; 4]| Point { x: (c[0].x + c[2].x) / 2, y: (c[0].y + c[2].y) / 2 };
+    let loc = locs
+        .into_iter()
+        .filter_map(|l| Some((centre(l.corners().ok()?).dist_sq(&mid), l)))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, l)| l);
+    sym.with_upscaled(Arc::new(bin), loc, (x0, y0), k)
 }
 
 // Detect high capacity QR
+#[cfg(feature = "poly")]
 pub fn detect_hc_qr(img: &DynamicImage) -> DecodeResult {
+    detect_hc_qr_with(img, &DetectOptions::default())
+}
+
+#[cfg(feature = "poly")]
+pub fn detect_hc_qr_with(img: &DynamicImage, opts: &DetectOptions) -> DecodeResult {
+    let start = Instant::now();
     let gray_img = img.to_luma8();
-    let mut gray_bin = BinaryImage::prepare(&gray_img);
+    let mut timings = DetectTimings { prepare: start.elapsed(), ..Default::default() };
 
-    let finders = locate_finders(&mut gray_bin);
-    let groups = group_finders(&finders);
+    let start = Instant::now();
+    let located = opts.locate(&gray_img);
+    timings.locate = start.elapsed();
+    let (mut gray_bin, finders) = match located {
+        Ok(l) => l,
+        Err(e) => return DecodeResult::failed(e, timings),
+    };
 
-    let sym_locs = locate_symbols(&mut gray_bin, groups);
+    let start = Instant::now();
+    let (sym_locs, rejections) = locate_candidates(&mut gray_bin, &finders, Some(&gray_img), opts);
 
+    // Channels are thresholded within each symbol so gamma & white balance don't skew them
     let rgb_img = img.to_rgb8();
-    let rgb_bin = Arc::new(BinaryImage::prepare(&rgb_img));
-    let symbols = sym_locs.into_iter().map(|sl| Symbol::new(rgb_bin.clone(), sl)).collect::<_>();
+    let mut rgb_bin = BinaryImage::prepare(&rgb_img);
+    let quads = sym_locs.iter().filter_map(|sl| sl.corners().ok()).collect::<Vec<_>>();
+    rgb_bin.rethreshold_quads(&rgb_img, &quads);
+    let rgb_bin = Arc::new(rgb_bin);
+    let mut symbols =
+        sym_locs.into_iter().map(|sl| Symbol::new(rgb_bin.clone(), sl)).collect::<Vec<_>>();
+    sort_symbols(&mut symbols, opts);
+    timings.group = start.elapsed();
+
+    if let Some(sink) = &opts.debug_sink {
+        capture_stages(sink.as_ref(), &gray_bin, &finders, &symbols);
+    }
+
+    DecodeResult { imgs: vec![rgb_bin], symbols, rejections, timings }
+}
+
+// Detects Poly codes printed in mono with to_dithered_image. Symbols are located on the gray image
+// and the channels of each module are read off its quadrants
+#[cfg(feature = "poly")]
+pub fn detect_hc_qr_dithered(img: &DynamicImage) -> DecodeResult {
+    let start = Instant::now();
+    let gray_img = img.to_luma8();
+    let opts = DetectOptions::default();
+    let mut timings = DetectTimings { prepare: start.elapsed(), ..Default::default() };
+
+    let start = Instant::now();
+    let located = opts.locate(&gray_img);
+    timings.locate = start.elapsed();
+    let (mut gray_bin, finders) = match located {
+        Ok(l) => l,
+        Err(e) => return DecodeResult::failed(e, timings),
+    };
+
+    let start = Instant::now();
+    let (sym_locs, rejections) = locate_candidates(&mut gray_bin, &finders, Some(&gray_img), &opts);
 
-    DecodeResult { img: rgb_bin, symbols }
+    let gray_bin = Arc::new(gray_bin);
+    let mut symbols = sym_locs
+        .into_iter()
+        .map(|sl| Symbol::new(gray_bin.clone(), sl).with_sampling(Sampling::Dithered))
+        .collect::<Vec<_>>();
+    sort_reading_order(&mut symbols);
+    timings.group = start.elapsed();
+
+    DecodeResult { imgs: vec![gray_bin], symbols, rejections, timings }
+}
+
+// Detect Code 128 and EAN-13 barcodes
+pub fn detect_barcodes(img: &DynamicImage) -> Vec<Barcode> {
+    let img = img.to_luma8();
+    let img = BinaryImage::prepare(&img);
+
+    locate_barcodes(&img)
+}
+
+// Decodes a perfectly axis aligned rendering, such as the output of to_image in round trip tests.
+// Finder detection is skipped & the grid is read off the bounding box of dark pixels, which is
+// fast & deterministic but only holds for synthetic images. The module size is measured from the
+// top left finder if no hint is given
+pub fn decode_gray_matrix(
+    img: &GrayImage,
+    module_size_hint: Option<u32>,
+) -> QRResult<(Metadata, String)> {
+    let is_dark = |x, y| img.get_pixel(x, y).0[0] < GRAY_MATRIX_THRESHOLD;
+
+    // Bounding box of dark pixels
+    let (w, h) = img.dimensions();
+    let (mut x0, mut y0, mut x1, mut y1) = (w, h, 0, 0);
+    for (x, y, _) in img.enumerate_pixels().filter(|(x, y, _)| is_dark(*x, *y)) {
+        (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+    }
+    if x0 > x1 {
+        return Err(QRError::SymbolNotFound);
+    }
+    let (ext_w, ext_h) = ((x1 - x0 + 1) as f64, (y1 - y0 + 1) as f64);
+
+    // Top edge of the top left finder spans 7 modules
+    let mod_sz = match module_size_hint {
+        Some(sz) if sz > 0 => sz as f64,
+        _ => (x0..=x1).take_while(|&x| is_dark(x, y0)).count() as f64 / 7.0,
+    };
+    let size = (ext_w / mod_sz).round() as usize;
+    if size != (ext_h / mod_sz).round() as usize || size % 4 != 1 {
+        return Err(QRError::SymbolNotFound);
+    }
+    let ver = Version::from_grid_size(size).ok_or(QRError::SymbolNotFound)?;
+
+    let bin = BinaryImage::from_fn(w, h, |x, y| match is_dark(x, y) {
+        true => Color::Black,
+        false => Color::White,
+    });
+    let mod_sz = (ext_w / size as f64, ext_h / size as f64);
+    let loc = SymbolLocation::axis_aligned((x0 as f64, y0 as f64), mod_sz, ver);
+    Symbol::new(Arc::new(bin), loc).decode()
+}
+
+// Groups finders into candidate symbols, scores their saliency & locates them in the order set
+// by the options
+fn locate_candidates(
+    img: &mut BinaryImage,
+    finders: &[Point],
+    luma: Option<&dyn ImageView>,
+    opts: &DetectOptions,
+) -> (Vec<SymbolLocation>, Vec<QRError>) {
+    let mut groups = group_finders(img, finders);
+    score_saliency(&mut groups, img.w, img.h, luma);
+    if opts.saliency_order {
+        groups.sort_by(|a, b| b.saliency.total_cmp(&a.saliency));
+    }
+
+    locate_symbols(img, groups, opts.max_symbols)
 }
 
-fn locate_symbols(img: &mut BinaryImage, groups: Vec<FinderGroup>) -> Vec<SymbolLocation> {
+fn sort_symbols(symbols: &mut [Symbol], opts: &DetectOptions) {
+    match opts.saliency_order {
+        true => sort_by_saliency(symbols),
+        false => sort_reading_order(symbols),
+    }
+}
+
+// Returns the located symbols & the reasons degenerate candidates were rejected. Stops early
+// once max symbols were found
+fn locate_symbols(
+    img: &mut BinaryImage,
+    groups: Vec<FinderGroup>,
+    max: Option<usize>,
+) -> (Vec<SymbolLocation>, Vec<QRError>) {
     let mut is_grouped = HashSet::new();
     let mut sym_locs = Vec::with_capacity(100);
+    let mut rejections = Vec::new();
     for mut g in groups {
+        if max.is_some_and(|m| sym_locs.len() >= m) {
+            break;
+        }
         if g.finders.iter().any(|f| is_grouped.contains(f)) {
             continue;
         }
 
-        if let Some(sl) = SymbolLocation::locate(img, &mut g) {
-            sym_locs.push(sl);
-            is_grouped.extend(g.finders);
+        match SymbolLocation::locate(img, &mut g) {
+            Ok(sl) => {
+                sym_locs.push(sl);
+                is_grouped.extend(g.finders);
+            }
+            Err(QRError::SymbolNotFound) => (),
+            Err(e) => rejections.push(e),
         }
     }
-    sym_locs
+    (sym_locs, rejections)
 }
 
 #[cfg(test)]
 mod reader_tests {
 
     use crate::{
-        builder::QRBuilder,
-        metadata::{ECLevel, Version},
-        reader::{detect_hc_qr, detect_qr},
+        builder::{Module, QRBuilder},
+        metadata::{Color, ECLevel, Version, MAX_VERSION},
+        reader::{
+            binarize::BinaryImage, detect_qr, detect_qr_binary, detect_qr_view, detect_qr_with,
+            DetectOptions,
+        },
+        utils::QRError,
         MaskPattern,
     };
 
@@ -92,23 +512,46 @@ mod reader_tests {
         let ver = Version::Normal(1);
         let ecl = ECLevel::L;
         let mask = MaskPattern::new(1);
-        let hi_cap = false;
 
-        let qr = QRBuilder::new(msg.as_bytes())
-            .version(ver)
-            .ec_level(ecl)
-            .high_capacity(hi_cap)
-            .mask(mask)
-            .build()
-            .unwrap();
+        let qr =
+            QRBuilder::new(msg.as_bytes()).version(ver).ec_level(ecl).mask(mask).build().unwrap();
         let img = image::DynamicImage::ImageRgb8(qr.to_image(2));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
         let (_meta, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
 
         assert_eq!(msg, exp_msg, "Incorrect data read from qr image");
     }
 
+    #[test]
+    fn test_binary_image_round_trip() {
+        let msg = "Binarized once, decoded twice";
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        let img = qr.to_image(3);
+        let bin = BinaryImage::prepare(&image::DynamicImage::ImageRgb8(img).to_luma8());
+
+        let bytes = bin.to_bytes();
+        assert_eq!(bytes.len(), 13 + (bin.w * bin.h).div_ceil(8) as usize);
+        let loaded = BinaryImage::from_bytes(&bytes).unwrap();
+        assert_eq!((loaded.w, loaded.h, &loaded.buffer), (bin.w, bin.h, &bin.buffer));
+
+        let res = detect_qr_binary(loaded);
+        assert_eq!(res.symbols()[0].decode().unwrap().1, msg);
+
+        // Colours other than black & white take 3 bits each
+        let clrs = [Color::Red, Color::White, Color::Cyan, Color::Black, Color::Yellow];
+        let poly = BinaryImage::from_fn(5, 3, |x, y| clrs[((x + y) % 5) as usize]);
+        let loaded = BinaryImage::from_bytes(&poly.to_bytes()).unwrap();
+        assert_eq!(loaded.buffer, poly.buffer);
+
+        let truncated = &bytes[..bytes.len() - 1];
+        for data in [&b"QRBI"[..], truncated] {
+            let err = BinaryImage::from_bytes(data).err();
+            assert_eq!(err, Some(QRError::MalformedBinaryImage));
+        }
+    }
+
+    #[cfg(feature = "poly")]
     #[test]
     fn test_reader_1() {
         let msg = "Hello, world!🌎";
@@ -126,59 +569,627 @@ mod reader_tests {
             .unwrap();
         let img = image::DynamicImage::ImageRgb8(qr.to_image(2));
 
-        let mut res = detect_hc_qr(&img);
+        let res = super::detect_hc_qr(&img);
         let (_meta, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
 
         assert_eq!(msg, exp_msg, "Incorrect data read from qr image");
     }
 
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_reader_hc_dithered() {
+        let msg = "Poly code printed on a mono laser printer";
+        let qr = QRBuilder::new(msg.as_bytes()).high_capacity(true).build().unwrap();
+        let img = image::DynamicImage::ImageLuma8(qr.to_dithered_image(6));
+
+        let res = super::detect_hc_qr_dithered(&img);
+        let (meta, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
+        assert_eq!(meta.version(), Some(qr.version()));
+        assert_eq!(msg, exp_msg);
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_reader_hc_misregistered() {
+        let msg = "Poly code printed with misaligned colour plates".repeat(4);
+        let qr = QRBuilder::new(msg.as_bytes()).high_capacity(true).build().unwrap();
+        let src = qr.to_image(3);
+
+        // Red plate shifted right & down, blue plate shifted up
+        let shifts = [(2, 1), (0, 0), (0, -2)];
+        let (w, h) = src.dimensions();
+        let img = image::RgbImage::from_fn(w, h, |x, y| {
+            image::Rgb(std::array::from_fn(|c| {
+                let (sx, sy) = (x as i32 - shifts[c].0, y as i32 - shifts[c].1);
+                let inside = (0..w as i32).contains(&sx) && (0..h as i32).contains(&sy);
+                if inside {
+                    src.get_pixel(sx as u32, sy as u32)[c]
+                } else {
+                    255
+                }
+            }))
+        });
+
+        let res = super::detect_hc_qr(&image::DynamicImage::ImageRgb8(img));
+        let sym = &res.symbols()[0];
+        let (_, exp_msg) = sym.decode().expect("Failed to read QR");
+        assert_eq!(msg, exp_msg);
+
+        let offs = sym.channel_offsets();
+        let rel = |c: usize| (offs[c].0 - offs[1].0, offs[c].1 - offs[1].1);
+        assert_eq!([rel(0), rel(2)], [shifts[0], shifts[2]]);
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_reader_hc_gamma_and_white_balance() {
+        let msg = "Poly code shot under warm light with a gamma curve".repeat(3);
+        let qr = QRBuilder::new(msg.as_bytes()).high_capacity(true).build().unwrap();
+        let mut img = qr.to_image(4);
+
+        // Dark background around the symbol, gamma of 2.2 & a warm cast with crushed blues
+        let (w, h) = img.dimensions();
+        let bg = 4 * 2;
+        for (x, y, p) in img.enumerate_pixels_mut() {
+            if x < bg || y < bg || x >= w - bg || y >= h - bg {
+                p.0 = [40, 40, 40];
+                continue;
+            }
+            let [r, g, b] = p.0.map(|c| (c as f64 / 255.0).powf(2.2));
+            p.0 = [r * 0.9 + 0.1, g * 0.55 + 0.05, b * 0.25].map(|c| (c * 255.0) as u8);
+        }
+
+        let res = super::detect_hc_qr(&image::DynamicImage::ImageRgb8(img));
+        let (_, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
+        assert_eq!(msg, exp_msg);
+    }
+
+    #[test]
+    fn test_reader_partially_outside_frame() {
+        let msg = "EDGE CUT";
+        for (ver, cut) in [(1, 3.0), (3, 9.0)] {
+            let qr = QRBuilder::new(msg.as_bytes())
+                .version(Version::Normal(ver))
+                .ec_level(ECLevel::H)
+                .build()
+                .unwrap();
+            let src = qr.to_image(6);
+
+            // Rotates the symbol by 45 degrees so its bottom right corner points down, then crops
+            // the bottom of the frame through that corner
+            let s = std::f64::consts::FRAC_1_SQRT_2;
+            let c = src.width() as f64 / 2.0;
+            let rc = c / s; // Centre of the rotated frame
+            let half_diag = qr.width() as f64 * 6.0 * s;
+            let (w, h) = ((rc * 2.0) as u32, (rc + half_diag - cut * 6.0) as u32);
+            let img = image::RgbImage::from_fn(w, h, |x, y| {
+                let (dx, dy) = (x as f64 - rc, y as f64 - rc);
+                let (sx, sy) = ((dx + dy) * s + c, (dy - dx) * s + c);
+                let inside = (0.0..src.width() as f64).contains(&sx)
+                    && (0.0..src.height() as f64).contains(&sy);
+                if inside {
+                    *src.get_pixel(sx as u32, sy as u32)
+                } else {
+                    image::Rgb([255; 3])
+                }
+            });
+
+            let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+            let (meta, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
+            assert_eq!(meta.version(), Some(Version::Normal(ver)));
+            assert_eq!(msg, exp_msg);
+        }
+    }
+
+    // Some encoders set remainder bits randomly, which must not affect decoding
+    #[test]
+    fn test_reader_random_remainder_bits() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let msg = "Remainder bits";
+        let mut rng = StdRng::seed_from_u64(7);
+        for ver in [2, 14, 21, 28].into_iter().filter(|&v| v <= MAX_VERSION) {
+            let mut qr = QRBuilder::new(msg.as_bytes())
+                .version(Version::Normal(ver))
+                .ec_level(ECLevel::L)
+                .build()
+                .unwrap();
+            let rem = qr.remainder_modules();
+            assert!(!rem.is_empty());
+            for fill in [Some(Color::Black), None] {
+                for &(x, y) in rem.iter() {
+                    let clr = fill.unwrap_or_else(|| Color::from(rng.random_bool(0.5)));
+                    qr.set(x, y, Module::Data(clr));
+                }
+
+                let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+                let res = detect_qr(&img);
+                let (meta, exp_msg) = res.symbols()[0].decode().expect("Failed to read QR");
+                assert_eq!(meta.ec_usage().map(|u| u.errors), Some(0), "Version {ver}");
+                assert_eq!(msg, exp_msg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reader_ec_usage() {
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes())
+            .version(Version::Normal(2))
+            .ec_level(ECLevel::H)
+            .mask(MaskPattern::new(0))
+            .build()
+            .unwrap();
+        let mut img = qr.to_image(4);
+
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img.clone()));
+        let (meta, _) = res.symbols()[0].decode().unwrap();
+        let usage = meta.ec_usage().unwrap();
+        assert_eq!(usage.errors, 0);
+        assert_eq!(usage.headroom(), 1.0);
+
+        // Invert a patch of data modules in the bottom right corner
+        for y in (4 + 21) * 4..(4 + 25) * 4 {
+            for x in (4 + 21) * 4..(4 + 25) * 4 {
+                let p = img.get_pixel_mut(x, y);
+                p.0 = p.0.map(|c| 255 - c);
+            }
+        }
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+        let (meta, exp_msg) = res.symbols()[0].decode().unwrap();
+        let usage = meta.ec_usage().unwrap();
+        assert_eq!(msg, exp_msg);
+        assert!(usage.errors > 0 && usage.errors <= usage.budget, "{usage:?}");
+        assert!(usage.headroom() < 1.0);
+    }
+
+    #[test]
+    fn test_reader_color_clustering() {
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        let mut opts = DetectOptions::new();
+        opts.color_clustering(true);
+
+        // Red on yellow & dark blue on white
+        for (fg, bg) in [([200, 20, 30], [250, 220, 40]), ([20, 30, 110], [255, 255, 255])] {
+            let mut img = qr.to_image(3);
+            img.pixels_mut().for_each(|p| p.0 = if p.0[0] < 128 { fg } else { bg });
+
+            let img = image::DynamicImage::ImageRgb8(img);
+            let res = detect_qr_with(&img, &opts);
+            let (_, exp_msg) = res.symbols()[0].decode().unwrap();
+            assert_eq!(msg, exp_msg);
+        }
+    }
+
+    #[test]
+    fn test_reader_glare() {
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes())
+            .version(Version::Normal(2))
+            .ec_level(ECLevel::M)
+            .mask(MaskPattern::new(0))
+            .build()
+            .unwrap();
+        let mut img = qr.to_image(4);
+
+        // Matte print with a saturated highlight over the data region
+        let (cx, cy, rad) = ((4.0 + 12.0) * 4.0, (4.0 + 13.0) * 4.0, 4.0 * 4.0);
+        for (x, y, p) in img.enumerate_pixels_mut() {
+            let d = (x as f64 - cx).hypot(y as f64 - cy);
+            p.0 = if d < rad { [255; 3] } else { p.0.map(|c| if c < 128 { 40 } else { 190 }) };
+        }
+        let img = image::DynamicImage::ImageRgb8(img);
+
+        let res = detect_qr(&img);
+        assert!(res.symbols()[0].decode().is_err());
+
+        let mut opts = DetectOptions::new();
+        opts.glare_inpainting(true);
+        let res = detect_qr_with(&img, &opts);
+        let (meta, exp_msg) = res.symbols()[0].decode().unwrap();
+        assert_eq!(msg, exp_msg);
+        assert!(meta.ec_usage().unwrap().errors > meta.ec_usage().unwrap().budget);
+    }
+
+    #[test]
+    fn test_reader_custom_binarizer() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        use super::binarize::{Binarizer, BinaryImage};
+        use crate::metadata::Color;
+
+        struct FixedThreshold(u8, Arc<AtomicUsize>);
+
+        impl Binarizer for FixedThreshold {
+            fn binarize(&self, img: &image::GrayImage) -> BinaryImage {
+                self.1.fetch_add(1, Ordering::Relaxed);
+                let (w, h) = img.dimensions();
+                BinaryImage::from_fn(w, h, |x, y| Color::from(img.get_pixel(x, y).0[0] > self.0))
+            }
+        }
+
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut opts = DetectOptions::new();
+        opts.binarizer(FixedThreshold(127, calls.clone()));
+        let (_, exp_msg) = detect_qr_with(&img, &opts).symbols()[0].decode().unwrap();
+        assert_eq!(msg, exp_msg);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // Binarized images of another size are rejected rather than sampled out of bounds
+        struct Halving;
+
+        impl Binarizer for Halving {
+            fn binarize(&self, img: &image::GrayImage) -> BinaryImage {
+                BinaryImage::from_fn(img.width() / 2, img.height() / 2, |_, _| Color::White)
+            }
+        }
+
+        opts.binarizer(Halving);
+        let res = detect_qr_with(&img, &opts);
+        let (w, h) = (img.width() / 2, img.height() / 2);
+        assert!(res.symbols().is_empty());
+        assert_eq!(res.rejections(), [QRError::BinarizerSizeMismatch(w, h)]);
+    }
+
+    #[test]
+    fn test_reader_fingerprint() {
+        let msg = "Hello, world!";
+        let mut bldr = QRBuilder::new(msg.as_bytes());
+        bldr.version(Version::Normal(2)).ec_level(ECLevel::H).mask(MaskPattern::new(0));
+        let qr = bldr.build().unwrap();
+        let mut img = qr.to_image(4);
+
+        // Damaged copies of the symbol share its fingerprint
+        for y in (4 + 21) * 4..(4 + 25) * 4 {
+            for x in (4 + 21) * 4..(4 + 25) * 4 {
+                let p = img.get_pixel_mut(x, y);
+                p.0 = p.0.map(|c| 255 - c);
+            }
+        }
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+        let (meta, _) = res.symbols()[0].decode().unwrap();
+        assert_eq!(meta.fingerprint(), Some(qr.fingerprint()));
+
+        let remasked = bldr.mask(MaskPattern::new(1)).build().unwrap();
+        assert_ne!(remasked.fingerprint(), qr.fingerprint());
+
+        #[cfg(feature = "poly")]
+        {
+            let hc_qr = bldr.high_capacity(true).build().unwrap();
+            let img = image::DynamicImage::ImageRgb8(hc_qr.to_image(2));
+            let (meta, _) = super::detect_hc_qr(&img).symbols()[0].decode().unwrap();
+            assert_eq!(meta.fingerprint(), Some(hc_qr.fingerprint()));
+            assert_ne!(hc_qr.fingerprint(), remasked.fingerprint());
+        }
+    }
+
+    #[cfg(feature = "png")]
     #[test]
     #[ignore]
     fn debugger() {
-        #[allow(unused_imports)]
-        use super::{
-            binarize::BinaryImage, finder::locate_finders, locate_symbols, symbol::Symbol,
+        use std::sync::Arc;
+
+        use super::debug::DirSink;
+
+        let img = image::open("assets/example1.png").unwrap();
+
+        let mut opts = DetectOptions::new();
+        opts.debug_sink(Arc::new(DirSink::new("assets")));
+        let res = detect_qr_with(&img, &opts);
+        for s in res.symbols() {
+            let _ = dbg!(s.decode());
+        }
+    }
+
+    #[test]
+    fn test_reader_order_and_ids() {
+        use image::{GenericImage, RgbImage};
+
+        let qrs =
+            ["A", "B", "C", "D"].map(|m| QRBuilder::new(m.as_bytes()).build().unwrap().to_image(3));
+        let sz = qrs[0].width();
+        let mut img = RgbImage::from_pixel(sz * 2, sz * 2 + 20, image::Rgb([255, 255, 255]));
+        // Right column sits a few pixels higher, which must not put it ahead in its row
+        img.copy_from(&qrs[0], 0, 8).unwrap();
+        img.copy_from(&qrs[1], sz, 0).unwrap();
+        img.copy_from(&qrs[2], 0, sz + 20).unwrap();
+        img.copy_from(&qrs[3], sz, sz + 12).unwrap();
+        let img = image::DynamicImage::ImageRgb8(img);
+
+        let res = detect_qr(&img);
+        let msgs = res.symbols().iter().map(|s| s.decode().unwrap().1).collect::<Vec<_>>();
+        assert_eq!(msgs, ["A", "B", "C", "D"]);
+
+        let ids = res.symbols().iter().map(|s| s.id()).collect::<Vec<_>>();
+        let rescan = detect_qr(&img).symbols().iter().map(|s| s.id()).collect::<Vec<_>>();
+        assert_eq!(ids, rescan);
+        assert!((1..4).all(|i| !ids[..i].contains(&ids[i])));
+
+        // A pixel of shift between scans keeps the ids
+        let (w, h) = (img.width(), img.height());
+        let mut shifted = RgbImage::from_pixel(w + 1, h + 1, image::Rgb([255, 255, 255]));
+        shifted.copy_from(&img.to_rgb8(), 1, 1).unwrap();
+        let shifted = image::DynamicImage::ImageRgb8(shifted);
+        let rescan = detect_qr(&shifted).symbols().iter().map(|s| s.id()).collect::<Vec<_>>();
+        assert_eq!(ids, rescan);
+    }
+
+    #[test]
+    fn test_reader_assemble() {
+        use image::{GenericImage, RgbImage};
+
+        let msg = "Order 31415926535897932384, SHIP TO DOCK 4, café";
+        let qrs = QRBuilder::new(msg.as_bytes())
+            .version(Version::Normal(1))
+            .ec_level(ECLevel::M)
+            .structured_append(16)
+            .unwrap();
+        assert!(qrs.len() > 2);
+        let imgs = qrs.iter().map(|qr| qr.to_image(3)).collect::<Vec<_>>();
+        let sz = imgs[0].width();
+
+        // Symbols laid out in reverse, with the first one printed twice
+        let compose = |order: &[usize]| {
+            let mut img = RgbImage::from_pixel(sz * order.len() as u32, sz, image::Rgb([255; 3]));
+            for (x, &i) in order.iter().enumerate() {
+                img.copy_from(&imgs[i], sz * x as u32, 0).unwrap();
+            }
+            detect_qr(&image::DynamicImage::ImageRgb8(img))
         };
-        #[allow(unused_imports)]
-        use crate::reader::{
-            detect_qr,
-            finder::group_finders,
-            utils::geometry::{BresenhamLine, X, Y},
+        let mut order = (0..imgs.len()).rev().collect::<Vec<_>>();
+        order.insert(0, 0);
+        assert_eq!(compose(&order).assemble().unwrap(), msg);
+
+        order.retain(|&i| i != 1);
+        let err = QRError::MissingStructuredAppendSymbol(1, imgs.len() as u8);
+        assert_eq!(compose(&order).assemble(), Err(err));
+
+        let plain = QRBuilder::new(b"Plain").build().unwrap().to_image(3);
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(plain));
+        assert_eq!(res.assemble(), Err(QRError::SymbolNotFound));
+    }
+
+    #[test]
+    fn test_reader_assemble_mismatch() {
+        use image::{GenericImage, RgbImage};
+
+        let sequence = |msg: &str| {
+            let qrs = QRBuilder::new(msg.as_bytes())
+                .version(Version::Normal(1))
+                .ec_level(ECLevel::M)
+                .structured_append(16)
+                .unwrap();
+            qrs.iter().map(|qr| qr.to_image(3)).collect::<Vec<_>>()
         };
-        #[allow(unused_imports)]
-        use rayon::prelude::*;
-        #[allow(unused_imports)]
-        use std::sync::Arc;
+        let compose = |imgs: &[&RgbImage]| {
+            let sz = imgs[0].width();
+            let mut img = RgbImage::from_pixel(sz * imgs.len() as u32, sz, image::Rgb([255; 3]));
+            for (x, qr) in imgs.iter().enumerate() {
+                img.copy_from(*qr, sz * x as u32, 0).unwrap();
+            }
+            detect_qr(&image::DynamicImage::ImageRgb8(img))
+        };
+
+        // Swapping the first & last bytes keeps the length & parity, but not the parity of each
+        // symbol, so the first symbol of one with the rest of the other fails the parity check
+        let msg = "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz";
+        let swapped = format!("z{}a", &msg[1..msg.len() - 1]);
+        let (a, b) = (sequence(msg), sequence(&swapped));
+        assert!(a.len() > 1 && a.len() == b.len());
+        let mut imgs = vec![&b[0]];
+        imgs.extend(&a[1..]);
+        assert_eq!(compose(&imgs).assemble(), Err(QRError::StructuredAppendParityMismatch));
+
+        let other = sequence("A different message in its own sequence");
+        let mut imgs = a.iter().collect::<Vec<_>>();
+        imgs.push(&other[0]);
+        assert_eq!(compose(&imgs).assemble(), Err(QRError::MixedStructuredAppend));
+    }
 
-        let img_path = std::path::Path::new("assets/example1.png");
+    #[test]
+    fn test_reader_shared_decode() {
+        use std::thread;
 
-        let mut img = image::open(img_path).unwrap().to_rgb8();
+        use image::{GenericImage, RgbImage};
 
-        let prep_path = std::path::Path::new("assets/prep.png");
-        let mut bin_img = BinaryImage::prepare(&img);
-        // bin_img.save(prep_path).unwrap();
-        // let mut img = image::open(prep_path).unwrap().to_rgb8();
+        use super::{symbol::Symbol, DecodeResult};
+        use crate::{builder::QRBuilderOwned, builder::QR, metadata::Metadata};
 
-        let finders = locate_finders(&mut bin_img);
-        dbg!(finders.len());
-        finders.iter().for_each(|f| f.highlight(&mut img, image::Rgb([255, 0, 0])));
+        fn assert_shareable<T: Clone + Send + Sync>() {}
+        assert_shareable::<DecodeResult>();
+        assert_shareable::<Symbol>();
+        assert_shareable::<DetectOptions>();
+        assert_shareable::<Metadata>();
+        assert_shareable::<QR>();
+        assert_shareable::<QRBuilderOwned>();
 
-        let groups = group_finders(&finders);
-        dbg!(groups.len());
-        // groups.iter().for_each(|g| g.highlight(&mut img));
+        let msgs = ["North", "South", "East"];
+        let qrs = msgs.map(|m| QRBuilder::new(m.as_bytes()).build().unwrap().to_image(3));
+        let sz = qrs[0].width();
+        let mut img = RgbImage::from_pixel(sz * 3, sz, image::Rgb([255, 255, 255]));
+        for (i, qr) in qrs.iter().enumerate() {
+            img.copy_from(qr, sz * i as u32, 0).unwrap();
+        }
 
-        let sym_locs = locate_symbols(&mut bin_img, groups);
-        dbg!(sym_locs.len());
-        let bin_img = Arc::new(bin_img);
-        let mut symbols: Vec<Symbol> =
-            sym_locs.into_iter().map(|sl| Symbol::new(bin_img.clone(), sl)).collect::<_>();
-        symbols.iter().for_each(|s| s.highlight(&mut img));
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+        let all = res.decode_all().into_iter().map(|r| r.unwrap().1).collect::<Vec<_>>();
+        assert_eq!(all, msgs);
 
-        symbols.iter_mut().enumerate().for_each(|(i, s)| {
-            let _ = dbg!(s.decode());
+        // Symbols decode through shared references, from as many threads as needed
+        let syms = res.symbols();
+        let decoded = thread::scope(|s| {
+            let handles = syms.iter().map(|sym| s.spawn(|| sym.decode().unwrap().1));
+            handles.collect::<Vec<_>>().into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert_eq!(decoded, msgs);
+    }
+
+    #[test]
+    fn test_reader_saliency_order() {
+        use image::{imageops, GenericImage, Rgb, RgbImage};
+
+        let build = |m: &str, sz| QRBuilder::new(m.as_bytes()).build().unwrap().to_image(sz);
+        let (small, large) = (build("Small", 3), build("Large", 8));
+        let mut faint = build("Faint", 8);
+        faint.pixels_mut().filter(|p| p.0[0] == 0).for_each(|p| *p = Rgb([170, 170, 170]));
+
+        // Small symbol first in reading order. The faint one is larger but has a third the contrast
+        let mut img = RgbImage::from_pixel(900, 600, Rgb([255, 255, 255]));
+        img.copy_from(&small, 0, 0).unwrap();
+        imageops::overlay(&mut img, &faint, 640, 200);
+        img.copy_from(&large, 330, 160).unwrap();
+        let img = image::DynamicImage::ImageRgb8(img);
+
+        let res = detect_qr(&img);
+        let msgs = res.symbols().iter().map(|s| s.decode().unwrap().1).collect::<Vec<_>>();
+        assert_eq!(msgs, ["Small", "Large", "Faint"]);
+
+        let mut opts = DetectOptions::new();
+        opts.saliency_order(true);
+        let res = detect_qr_with(&img, &opts);
+        let msgs = res.symbols().iter().map(|s| s.decode().unwrap().1).collect::<Vec<_>>();
+        assert_eq!(msgs, ["Large", "Small", "Faint"]);
+        assert!(res.symbols().windows(2).all(|w| w[0].saliency() >= w[1].saliency()));
+
+        opts.max_symbols(1);
+        let res = detect_qr_with(&img, &opts);
+        assert_eq!(res.symbols().len(), 1);
+        assert_eq!(res.symbols()[0].decode().unwrap().1, "Large");
+    }
+
+    #[test]
+    fn test_reader_tiny_modules() {
+        let qr = QRBuilder::new(b"Tiny screenshot").version(Version::Normal(2)).build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(8)).to_luma8();
+
+        // Screenshot scaled down to 1.5 & 1.7 pixels per module
+        for sz in [50, 56] {
+            let small =
+                image::imageops::resize(&img, sz, sz, image::imageops::FilterType::Triangle);
+            let res = detect_qr(&image::DynamicImage::ImageLuma8(small));
+            let sym = &res.symbols()[0];
+            assert_eq!(sym.decode().unwrap().1, "Tiny screenshot");
+
+            // Symbol is still reported in image coordinates
+            let corners = sym.corners().unwrap();
+            assert!(corners.iter().all(|p| (0..sz as i32).contains(&p.x)), "{corners:?}");
+        }
+    }
+
+    #[test]
+    fn test_reader_reencode() {
+        let qr = QRBuilder::new(b"Label 0042, aisle 7")
+            .version(Version::Normal(3))
+            .ec_level(ECLevel::H)
+            .mask(MaskPattern::new(5))
+            .build()
+            .unwrap();
+
+        // Scuff across the data region
+        let mut img = qr.to_image(4);
+        for (x, y) in (60..84).flat_map(|x| (64..80).map(move |y| (x, y))) {
+            img.put_pixel(x, y, image::Rgb([255, 255, 255]));
+        }
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(img));
+        let sym = &res.symbols()[0];
+        assert!(sym.decode().unwrap().0.ec_usage().unwrap().errors > 0);
+
+        let reprint = sym.reencode().unwrap();
+        assert_eq!(reprint.grid(), qr.grid());
+        assert_eq!(reprint.mask(), Some(MaskPattern::new(5)));
+        assert_eq!(reprint.metadata().segments(), qr.metadata().segments());
+    }
+
+    #[test]
+    fn test_reader_illumination_flattening() {
+        let qr = QRBuilder::new(b"Shadowed label").version(Version::Normal(3)).build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(6)).to_luma8();
+
+        // Hard shadow over the bottom right two thirds, covering one finder
+        let (w, h) = img.dimensions();
+        let shaded = image::GrayImage::from_fn(w, h, |x, y| {
+            let v = img.get_pixel(x, y).0[0] as f64;
+            let shade = if x > w / 3 && y > h / 3 { 0.15 } else { 1.0 };
+            image::Luma([(v * shade) as u8])
         });
+        let shaded = image::DynamicImage::ImageLuma8(shaded);
 
-        let out_path = std::path::Path::new("assets/detect.png");
-        // img.save(out_path).unwrap();
+        let mut opts = DetectOptions::new();
+        assert!(detect_qr_with(&shaded, &opts).symbols().iter().all(|s| s.decode().is_err()));
+
+        opts.flatten_illumination(true);
+        let res = detect_qr_with(&shaded, &opts);
+        assert_eq!(res.symbols()[0].decode().unwrap().1, "Shadowed label");
+    }
+
+    #[test]
+    fn test_reader_view() {
+        use super::view::LumaSlice;
+
+        let msg = "Hello, world!";
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        let gray = image::DynamicImage::ImageRgb8(qr.to_image(3)).to_luma8();
+
+        let (_, exp_msg) = detect_qr_view(&gray).symbols()[0].decode().unwrap();
+        assert_eq!(msg, exp_msg);
+
+        // Pad each row as in a strided camera frame
+        let (w, h) = gray.dimensions();
+        let stride = w as usize + 7;
+        let mut buf = vec![0; stride * h as usize];
+        for (row, src) in buf.chunks_mut(stride).zip(gray.rows()) {
+            row.iter_mut().zip(src).for_each(|(d, p)| *d = p.0[0]);
+        }
+        let view = LumaSlice::with_stride(&buf, w, h, stride).unwrap();
+        let (_, exp_msg) = detect_qr_view(&view).symbols()[0].decode().unwrap();
+        assert_eq!(msg, exp_msg);
+
+        #[cfg(feature = "ndarray")]
+        {
+            let arr = ndarray::ArrayView2::from_shape((h as usize, w as usize), gray.as_raw());
+            let (_, exp_msg) = detect_qr_view(&arr.unwrap()).symbols()[0].decode().unwrap();
+            assert_eq!(msg, exp_msg);
+        }
+    }
+
+    #[test]
+    fn test_decode_gray_matrix() {
+        use super::decode_gray_matrix;
+
+        let long = "A".repeat(200);
+        let cases = [("Hello, world!", 1), (long.as_str(), 9)];
+        for (msg, ver) in cases.into_iter().filter(|&(_, v)| v <= MAX_VERSION) {
+            let qr = QRBuilder::new(msg.as_bytes()).version(Version::Normal(ver)).build().unwrap();
+            let gray = image::DynamicImage::ImageRgb8(qr.to_image(3)).to_luma8();
+            for hint in [None, Some(3)] {
+                let (meta, decoded) = decode_gray_matrix(&gray, hint).unwrap();
+                assert_eq!(meta.version(), Some(Version::Normal(ver)));
+                assert_eq!(decoded, msg);
+            }
+        }
+
+        let blank = image::GrayImage::from_pixel(50, 50, image::Luma([255]));
+        assert_eq!(decode_gray_matrix(&blank, None).err(), Some(QRError::SymbolNotFound));
     }
 }
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Luma below which pixels of a rendered matrix are dark
+const GRAY_MATRIX_THRESHOLD: u8 = 128;
+
+// Module size in pixels below which symbols are resampled from an upscaled crop
+const MIN_MODULE_PX: f64 = 2.0;
+
+// Module size in pixels the crop is upscaled to, & the largest upscale factor
+const UPSCALE_MODULE_PX: f64 = 4.0;
+const MAX_UPSCALE: f64 = 8.0;
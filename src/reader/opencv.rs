@@ -0,0 +1,114 @@
+use ::opencv::{
+    core::{self, Mat, Point2f, Point_},
+    imgproc,
+    prelude::*,
+};
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma, RgbImage};
+
+#[cfg(feature = "poly")]
+use super::detect_hc_qr_with;
+use super::{
+    binarize::BinaryImage, detect_qr_in, detect_qr_with, symbol::Symbol, utils::geometry::Point,
+    DecodeResult, DetectOptions,
+};
+use crate::utils::QRResult;
+
+// OpenCV interop
+//------------------------------------------------------------------------------
+
+pub fn detect_qr_mat(mat: &Mat) -> ::opencv::Result<DecodeResult> {
+    detect_qr_mat_with(mat, &DetectOptions::default())
+}
+
+// Detects QRs in an 8 bit gray, BGR or BGRA mat. Continuous gray mats are binarized straight from
+// the mat buffer without copying, unless an option needs an owned image
+pub fn detect_qr_mat_with(mat: &Mat, opts: &DetectOptions) -> ::opencv::Result<DecodeResult> {
+    if opts.needs_gray_image() {
+        return Ok(detect_qr_with(&mat_to_image(mat)?, opts));
+    }
+
+    let converted;
+    let gray = match mat.typ() == core::CV_8UC1 && mat.is_continuous() {
+        true => mat,
+        false => {
+            converted = to_continuous_gray(mat)?;
+            &converted
+        }
+    };
+    let (w, h) = (gray.cols() as u32, gray.rows() as u32);
+    let view = ImageBuffer::<Luma<u8>, &[u8]>::from_raw(w, h, gray.data_bytes()?)
+        .ok_or_else(|| unsupported("Mat buffer is smaller than its dimensions"))?;
+    let img = BinaryImage::prepare(&view);
+
+    Ok(detect_qr_in(img, None, opts))
+}
+
+#[cfg(feature = "poly")]
+pub fn detect_hc_qr_mat(mat: &Mat) -> ::opencv::Result<DecodeResult> {
+    detect_hc_qr_with(&mat_to_image(mat)?, &DetectOptions::default())
+}
+
+// Copies an 8 bit gray, BGR or BGRA mat into an image, swapping channels to RGB order
+pub fn mat_to_image(mat: &Mat) -> ::opencv::Result<DynamicImage> {
+    let (w, h) = (mat.cols() as u32, mat.rows() as u32);
+    let code = match mat.typ() {
+        core::CV_8UC1 => {
+            let gray = to_continuous_gray(mat)?;
+            let buf = gray.data_bytes()?.to_vec();
+            return GrayImage::from_raw(w, h, buf)
+                .map(DynamicImage::ImageLuma8)
+                .ok_or_else(|| unsupported("Mat buffer is smaller than its dimensions"));
+        }
+        core::CV_8UC3 => imgproc::COLOR_BGR2RGB,
+        core::CV_8UC4 => imgproc::COLOR_BGRA2RGB,
+        _ => return Err(unsupported("Expected an 8 bit mat with 1, 3 or 4 channels")),
+    };
+
+    let mut rgb = Mat::default();
+    imgproc::cvt_color_def(mat, &mut rgb, code)?;
+    let buf = rgb.data_bytes()?.to_vec();
+    RgbImage::from_raw(w, h, buf)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| unsupported("Mat buffer is smaller than its dimensions"))
+}
+
+// Copies the mat into a continuous single channel mat, converting colour mats and compacting
+// strided rois
+fn to_continuous_gray(mat: &Mat) -> ::opencv::Result<Mat> {
+    let code = match mat.typ() {
+        core::CV_8UC1 => return mat.try_clone(),
+        core::CV_8UC3 => imgproc::COLOR_BGR2GRAY,
+        core::CV_8UC4 => imgproc::COLOR_BGRA2GRAY,
+        _ => return Err(unsupported("Expected an 8 bit mat with 1, 3 or 4 channels")),
+    };
+    let mut gray = Mat::default();
+    imgproc::cvt_color_def(mat, &mut gray, code)?;
+    Ok(gray)
+}
+
+fn unsupported(msg: &str) -> ::opencv::Error {
+    ::opencv::Error::new(core::StsUnsupportedFormat, msg)
+}
+
+// Point conversions
+//------------------------------------------------------------------------------
+
+impl From<Point> for Point_<i32> {
+    fn from(pt: Point) -> Self {
+        Point_::new(pt.x, pt.y)
+    }
+}
+
+impl From<Point> for Point2f {
+    fn from(pt: Point) -> Self {
+        Point2f::new(pt.x as f32, pt.y as f32)
+    }
+}
+
+impl Symbol {
+    // Symbol corners as OpenCV points, clockwise from top left. Can be passed straight to
+    // polylines or get_perspective_transform
+    pub fn corners_cv(&self) -> QRResult<[Point2f; 4]> {
+        Ok(self.corners()?.map(Point2f::from))
+    }
+}
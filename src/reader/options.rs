@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use image::GrayImage;
+
+use super::{
+    binarize::{Binarizer, BinaryImage},
+    debug::DebugSink,
+    finder::locate_finders,
+    utils::geometry::Point,
+};
+use crate::utils::{QRError, QRResult};
+
+#[cfg(feature = "gpu")]
+use super::{finder::locate_finders_from, gpu::GpuBackend};
+
+// Scan angles
+//------------------------------------------------------------------------------
+
+// Lines along which finders are searched for. Every finder is crosschecked across the line it
+// was found on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanAngles {
+    #[default]
+    Rows,
+    // Rows & both diagonals. Diagonals cross the finders of symbols rotated near 45 degrees along
+    // their axes, which gives noisy images more lines to find them on, at the cost of 2 more
+    // passes over the image
+    RowsAndDiagonals,
+}
+
+// Detect options
+//------------------------------------------------------------------------------
+
+// Knobs for the detection pipeline. Defaults match detect_qr & detect_hc_qr
+#[derive(Debug, Clone, Default)]
+pub struct DetectOptions {
+    pub(crate) scan_angles: ScanAngles,
+    pub(crate) stylized_finders: bool,
+    pub(crate) color_clustering: bool,
+    pub(crate) glare_inpainting: bool,
+    pub(crate) illumination_flattening: bool,
+    pub(crate) saliency_order: bool,
+    pub(crate) max_symbols: Option<usize>,
+    pub(crate) binarizer: Option<Arc<dyn Binarizer>>,
+    pub(crate) debug_sink: Option<Arc<dyn DebugSink>>,
+    #[cfg(feature = "gpu")]
+    pub(crate) gpu: Option<Arc<GpuBackend>>,
+}
+
+impl DetectOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Directions of the lines scanned for finders
+    pub fn scan_angles(&mut self, angles: ScanAngles) -> &mut Self {
+        self.scan_angles = angles;
+        self
+    }
+
+    // Accepts finders with rounded or gapped eyes, which fail the strict ring connectivity and
+    // area checks, if the stone is compact and centred within the 1:1:3:1:1 crosschecks
+    pub fn stylized_finders(&mut self, enabled: bool) -> &mut Self {
+        self.stylized_finders = enabled;
+        self
+    }
+
+    // Binarizes by clustering pixels into 2 colors instead of thresholding luma. Only applies to
+    // standard QRs since high capacity QRs carry data in the colors
+    pub fn color_clustering(&mut self, enabled: bool) -> &mut Self {
+        self.color_clustering = enabled;
+        self
+    }
+
+    // Detects specular highlights and inpaints them before binarization. Codewords with modules
+    // under the highlights are passed as erasures to the ec decoder
+    pub fn glare_inpainting(&mut self, enabled: bool) -> &mut Self {
+        self.glare_inpainting = enabled;
+        self
+    }
+
+    // Divides out the background lighting & stretches the contrast before binarization, for
+    // images with strong vignetting or a shadow across the symbol. Only applies to standard QRs
+    pub fn flatten_illumination(&mut self, enabled: bool) -> &mut Self {
+        self.illumination_flattening = enabled;
+        self
+    }
+
+    // Tries candidate finder groups from the most to the least salient, judged by their size,
+    // contrast & closeness to the image centre, instead of by how square they are. Symbols are
+    // then returned in the same order rather than in reading order
+    pub fn saliency_order(&mut self, enabled: bool) -> &mut Self {
+        self.saliency_order = enabled;
+        self
+    }
+
+    // Stops locating symbols in an image once this many were found. Pair with saliency_order so
+    // the most prominent symbols are the ones kept
+    pub fn max_symbols(&mut self, max: usize) -> &mut Self {
+        self.max_symbols = Some(max);
+        self
+    }
+
+    pub fn unset_max_symbols(&mut self) -> &mut Self {
+        self.max_symbols = None;
+        self
+    }
+
+    // Replaces the built-in adaptive thresholding of grayscale images. Ignored if color
+    // clustering is enabled
+    pub fn binarizer(&mut self, binarizer: impl Binarizer + 'static) -> &mut Self {
+        self.binarizer = Some(Arc::new(binarizer));
+        self
+    }
+
+    pub fn unset_binarizer(&mut self) -> &mut Self {
+        self.binarizer = None;
+        self
+    }
+
+    // Sends the binarized image, found finders & located symbols of every pass to the sink. Takes
+    // an Arc so the caller can keep a handle to read back what was captured
+    pub fn debug_sink(&mut self, sink: Arc<dyn DebugSink>) -> &mut Self {
+        self.debug_sink = Some(sink);
+        self
+    }
+
+    pub fn unset_debug_sink(&mut self) -> &mut Self {
+        self.debug_sink = None;
+        self
+    }
+
+    // Runs adaptive thresholding and the finder row scan as compute shaders. Stays on the cpu if
+    // no adapter is present or a custom binarizer is set
+    #[cfg(feature = "gpu")]
+    pub fn gpu(&mut self, enabled: bool) -> &mut Self {
+        self.gpu = if enabled { GpuBackend::shared() } else { None };
+        self
+    }
+
+    // Whether borrowed inputs have to be copied into a GrayImage for these options
+    pub(crate) fn needs_gray_image(&self) -> bool {
+        #[cfg(feature = "gpu")]
+        if self.gpu.is_some() {
+            return true;
+        }
+        self.glare_inpainting
+            || self.illumination_flattening
+            || self.color_clustering
+            || self.binarizer.is_some()
+    }
+
+    // Binarizes the image & locates finder candidates in it
+    pub(crate) fn locate(&self, img: &GrayImage) -> QRResult<(BinaryImage, Vec<Point>)> {
+        #[cfg(feature = "gpu")]
+        if let (Some(gpu), None) = (&self.gpu, &self.binarizer) {
+            if let Some((mut bin, runs)) = gpu.binarize_and_scan(img) {
+                let finders = match runs {
+                    Some(mut runs) => locate_finders_from(&mut bin, &mut runs, self),
+                    None => locate_finders(&mut bin, self),
+                };
+                return Ok((bin, finders));
+            }
+        }
+
+        let mut bin = self.binarize(img)?;
+        let finders = locate_finders(&mut bin, self);
+        Ok((bin, finders))
+    }
+
+    // Custom binarizers must keep the size of the image, as symbols are mapped onto it by pixel
+    pub(crate) fn binarize(&self, img: &GrayImage) -> QRResult<BinaryImage> {
+        match &self.binarizer {
+            Some(b) => {
+                let bin = b.binarize(img);
+                if (bin.w, bin.h) != img.dimensions() {
+                    return Err(QRError::BinarizerSizeMismatch(bin.w, bin.h));
+                }
+                Ok(bin)
+            }
+            None => Ok(BinaryImage::prepare(img)),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+#[cfg(feature = "tiff")]
+use std::io::Read;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek},
+    path::Path,
+};
+
+use image::{
+    error::{ImageFormatHint, UnsupportedError, UnsupportedErrorKind},
+    DynamicImage, ImageError, ImageFormat, ImageReader, ImageResult,
+};
+#[cfg(any(feature = "gif", feature = "png", feature = "webp"))]
+use image::{AnimationDecoder, Frames};
+
+#[cfg(feature = "gif")]
+use image::codecs::gif::GifDecoder;
+#[cfg(feature = "png")]
+use image::codecs::png::PngDecoder;
+#[cfg(feature = "webp")]
+use image::codecs::webp::WebPDecoder;
+#[cfg(feature = "tiff")]
+use image::{error::DecodingError, GrayImage, ImageBuffer};
+#[cfg(feature = "tiff")]
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    ColorType, TiffError,
+};
+
+use super::{detect_qr_with, DecodeResult, DetectOptions};
+
+// Multi page reader
+//------------------------------------------------------------------------------
+
+// Detects QRs in every page of a document, tagging each symbol with the zero based page index
+pub fn detect_qr_pages<I>(pages: I, opts: &DetectOptions) -> DecodeResult
+where
+    I: IntoIterator<Item = DynamicImage>,
+{
+    let mut res = DecodeResult::empty();
+    for (i, img) in pages.into_iter().enumerate() {
+        res.push_page(i, detect_qr_with(&img, opts));
+    }
+    res
+}
+
+// Opens the file & detects QRs in each of its pages. Multi page tiffs and animated gif, png &
+// webp files are read frame by frame, other formats are read as a single page. Formats whose
+// codec feature is disabled fail as unsupported. Svg files are rasterized with the svg-in feature
+pub fn detect_qr_file(path: impl AsRef<Path>, opts: &DetectOptions) -> ImageResult<DecodeResult> {
+    #[cfg(feature = "svg-in")]
+    let mut reader = BufReader::new(File::open(path)?);
+    #[cfg(not(feature = "svg-in"))]
+    let reader = BufReader::new(File::open(path)?);
+
+    #[cfg(feature = "svg-in")]
+    if super::svg::is_svg(reader.fill_buf()?) {
+        let mut svg = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut svg)?;
+        return super::svg::detect_qr_svg_bytes(&svg, opts);
+    }
+
+    let reader = ImageReader::new(reader).with_guessed_format()?;
+    let Some(format) = reader.format() else {
+        return Err(ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+            ImageFormatHint::Unknown,
+            UnsupportedErrorKind::Format(ImageFormatHint::Unknown),
+        )));
+    };
+    detect_qr_pages_from(reader.into_inner(), format, opts)
+}
+
+pub fn detect_qr_pages_from<R: BufRead + Seek>(
+    r: R,
+    format: ImageFormat,
+    opts: &DetectOptions,
+) -> ImageResult<DecodeResult> {
+    match format {
+        #[cfg(feature = "tiff")]
+        ImageFormat::Tiff => detect_qr_tiff(r, opts),
+        #[cfg(feature = "gif")]
+        ImageFormat::Gif => detect_qr_frames(GifDecoder::new(r)?.into_frames(), opts),
+        #[cfg(feature = "png")]
+        ImageFormat::Png => {
+            let dec = PngDecoder::new(r)?;
+            match dec.is_apng()? {
+                true => detect_qr_frames(dec.apng()?.into_frames(), opts),
+                false => Ok(detect_qr_pages([DynamicImage::from_decoder(dec)?], opts)),
+            }
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => {
+            let dec = WebPDecoder::new(r)?;
+            match dec.has_animation() {
+                true => detect_qr_frames(dec.into_frames(), opts),
+                false => Ok(detect_qr_pages([DynamicImage::from_decoder(dec)?], opts)),
+            }
+        }
+        _ => {
+            let img = ImageReader::with_format(r, format).decode()?;
+            Ok(detect_qr_pages([img], opts))
+        }
+    }
+}
+
+// Reads every image file directory of the tiff as a page
+#[cfg(feature = "tiff")]
+pub fn detect_qr_tiff<R: Read + Seek>(r: R, opts: &DetectOptions) -> ImageResult<DecodeResult> {
+    let mut dec = Decoder::new(r).map_err(tiff_error)?;
+    let mut res = DecodeResult::empty();
+    for page in 0.. {
+        let img = read_tiff_page(&mut dec)?;
+        res.push_page(page, detect_qr_with(&img, opts));
+
+        if !dec.more_images() {
+            break;
+        }
+        dec.next_image().map_err(tiff_error)?;
+    }
+    Ok(res)
+}
+
+// Animation frames are composited onto the canvas by the decoder, so each frame is a full image
+#[cfg(any(feature = "gif", feature = "png", feature = "webp"))]
+fn detect_qr_frames(frames: Frames, opts: &DetectOptions) -> ImageResult<DecodeResult> {
+    let mut res = DecodeResult::empty();
+    for (page, frame) in frames.enumerate() {
+        let img = DynamicImage::ImageRgba8(frame?.into_buffer());
+        res.push_page(page, detect_qr_with(&img, opts));
+    }
+    Ok(res)
+}
+
+// Converts the current page to an image. Covers the bilevel, gray & rgb layouts scanners write
+#[cfg(feature = "tiff")]
+fn read_tiff_page<R: Read + Seek>(dec: &mut Decoder<R>) -> ImageResult<DynamicImage> {
+    let (w, h) = dec.dimensions().map_err(tiff_error)?;
+    let color = dec.colortype().map_err(tiff_error)?;
+    let img = match (color, dec.read_image().map_err(tiff_error)?) {
+        (ColorType::Gray(1), DecodingResult::U8(v)) => {
+            let stride = w.div_ceil(8);
+            Some(DynamicImage::ImageLuma8(GrayImage::from_fn(w, h, |x, y| {
+                let byte = v[(y * stride + x / 8) as usize];
+                image::Luma([((byte >> (7 - x % 8)) & 1) * 255])
+            })))
+        }
+        (ColorType::Gray(8), DecodingResult::U8(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(v)) => {
+            ImageBuffer::from_raw(w, h, v).map(DynamicImage::ImageRgba16)
+        }
+        _ => None,
+    };
+
+    img.ok_or_else(|| {
+        ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+            ImageFormat::Tiff.into(),
+            UnsupportedErrorKind::GenericFeature(format!("Tiff color type {color:?}")),
+        ))
+    })
+}
+
+#[cfg(feature = "tiff")]
+fn tiff_error(err: TiffError) -> ImageError {
+    match err {
+        TiffError::IoError(e) => ImageError::IoError(e),
+        e => ImageError::Decoding(DecodingError::new(ImageFormat::Tiff.into(), e)),
+    }
+}
+
+#[cfg(all(test, feature = "gif", feature = "tiff"))]
+mod pages_tests {
+    use std::io::Cursor;
+
+    use image::{
+        codecs::gif::GifEncoder, DynamicImage, Frame, GrayImage, ImageFormat, Luma, RgbaImage,
+    };
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    use super::{detect_qr_pages_from, detect_qr_tiff};
+    use crate::{builder::QRBuilder, reader::DetectOptions};
+
+    fn qr_page(msg: &str) -> GrayImage {
+        let qr = QRBuilder::new(msg.as_bytes()).build().unwrap();
+        DynamicImage::ImageRgb8(qr.to_image(3)).to_luma8()
+    }
+
+    fn decoded_pages(res: &mut super::DecodeResult) -> Vec<(usize, String)> {
+        res.symbols().iter().map(|s| (s.page(), s.decode().unwrap().1)).collect()
+    }
+
+    #[test]
+    fn test_multi_page_tiff() {
+        let pages = [qr_page("Page 0"), GrayImage::from_pixel(99, 99, Luma([255])), qr_page("2")];
+        let mut buf = Cursor::new(Vec::new());
+        let mut enc = TiffEncoder::new(&mut buf).unwrap();
+        for p in pages.iter() {
+            enc.write_image::<colortype::Gray8>(p.width(), p.height(), p.as_raw()).unwrap();
+        }
+        buf.set_position(0);
+
+        let mut res = detect_qr_tiff(buf, &DetectOptions::default()).unwrap();
+        assert_eq!(decoded_pages(&mut res), [(0, "Page 0".to_string()), (2, "2".to_string())]);
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        let frames = ["Frame 0", "Frame 1"].map(|m| {
+            let img = DynamicImage::ImageLuma8(qr_page(m)).to_rgba8();
+            Frame::new(RgbaImage::from_raw(img.width(), img.height(), img.into_raw()).unwrap())
+        });
+        let mut buf = Vec::new();
+        GifEncoder::new(&mut buf).encode_frames(frames).unwrap();
+
+        let opts = DetectOptions::default();
+        let mut res = detect_qr_pages_from(Cursor::new(buf), ImageFormat::Gif, &opts).unwrap();
+        let exp = [(0, "Frame 0".to_string()), (1, "Frame 1".to_string())];
+        assert_eq!(decoded_pages(&mut res), exp);
+    }
+}
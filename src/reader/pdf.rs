@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use pdfium_render::prelude::{PdfDocument, PdfRenderConfig, Pdfium, PdfiumError};
+
+use super::{detect_qr_with, DecodeResult, DetectOptions};
+
+// Pdf reader
+//------------------------------------------------------------------------------
+
+// Rasterizes each page of the pdf & detects QRs in it, tagging symbols with their page index. The
+// caller binds pdfium, so the library can be bundled or loaded from the system as it sees fit
+pub fn detect_qr_pdf(
+    pdfium: &Pdfium,
+    path: impl AsRef<Path>,
+    password: Option<&str>,
+    opts: &DetectOptions,
+) -> Result<DecodeResult, PdfiumError> {
+    let doc = pdfium.load_pdf_from_file(&path, password)?;
+    detect_qr_doc(&doc, opts)
+}
+
+pub fn detect_qr_pdf_bytes(
+    pdfium: &Pdfium,
+    bytes: &[u8],
+    password: Option<&str>,
+    opts: &DetectOptions,
+) -> Result<DecodeResult, PdfiumError> {
+    let doc = pdfium.load_pdf_from_byte_slice(bytes, password)?;
+    detect_qr_doc(&doc, opts)
+}
+
+fn detect_qr_doc(doc: &PdfDocument, opts: &DetectOptions) -> Result<DecodeResult, PdfiumError> {
+    let config = PdfRenderConfig::new()
+        .scale_page_by_factor(PDF_RENDER_DPI / PDF_POINTS_PER_INCH)
+        .set_maximum_width(PDF_MAX_RENDER_SIZE)
+        .set_maximum_height(PDF_MAX_RENDER_SIZE)
+        .render_form_data(true);
+
+    let mut res = DecodeResult::empty();
+    for (page, p) in doc.pages().iter().enumerate() {
+        let img = p.render_with_config(&config)?.as_image();
+        res.push_page(page, detect_qr_with(&img, opts));
+    }
+    Ok(res)
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Render resolution of pages. Fine enough for the modules of QRs printed on invoices & receipts
+const PDF_RENDER_DPI: f32 = 200.0;
+
+const PDF_POINTS_PER_INCH: f32 = 72.0;
+
+// Max width & height of rendered pages in pixels, to bound memory on oversized pages
+const PDF_MAX_RENDER_SIZE: i32 = 8000;
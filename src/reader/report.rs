@@ -0,0 +1,450 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter, Write},
+    ops::AddAssign,
+    time::{Duration, Instant},
+};
+
+use super::{source::SourceInfo, DecodeResult};
+use crate::metadata::{ECUsage, Metadata};
+
+// Symbol report
+//------------------------------------------------------------------------------
+
+// ISO/IEC 15415 grade, 4 being A and 0 being F
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Grade(pub u8);
+
+impl Grade {
+    pub fn letter(&self) -> char {
+        match self.0 {
+            4 => 'A',
+            3 => 'B',
+            2 => 'C',
+            1 => 'D',
+            _ => 'F',
+        }
+    }
+
+    // Unused error correction grade as per ISO/IEC 15415 thresholds
+    pub fn from_unused_ec(uec: f64) -> Self {
+        match uec {
+            u if u >= 0.62 => Self(4),
+            u if u >= 0.50 => Self(3),
+            u if u >= 0.37 => Self(2),
+            u if u >= 0.25 => Self(1),
+            _ => Self(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolReport {
+    pub index: usize,
+    pub decoded: bool,
+    pub text: Option<String>,
+    pub meta: Option<Metadata>,
+    pub error: Option<String>,
+    pub uec: f64, // Unused error correction of the worst block
+    pub uec_grade: Grade,
+}
+
+impl SymbolReport {
+    pub fn new(index: usize, res: Result<(Metadata, String), impl ToString>) -> Self {
+        match res {
+            Ok((meta, text)) => {
+                let uec = meta.ec_usage().map(|u| u.headroom().max(0.0)).unwrap_or(1.0);
+                let uec_grade = Grade::from_unused_ec(uec);
+                let (text, meta) = (Some(text), Some(meta));
+                Self { index, decoded: true, text, meta, error: None, uec, uec_grade }
+            }
+            Err(e) => {
+                let error = Some(e.to_string());
+                let uec_grade = Grade(0);
+                Self { index, decoded: false, text: None, meta: None, error, uec: 0.0, uec_grade }
+            }
+        }
+    }
+
+    // Overall grade is the lowest of the parameter grades. Only unused error correction is
+    // measured at the moment
+    pub fn grade(&self) -> Grade {
+        self.uec_grade
+    }
+
+    fn ec_usage(&self) -> Option<ECUsage> {
+        self.meta.as_ref().and_then(|m| m.ec_usage())
+    }
+}
+
+// Decodes every symbol of the result and reports its parameters & grades
+pub fn report(res: &mut DecodeResult) -> Vec<SymbolReport> {
+    res.symbols().iter().enumerate().map(|(i, s)| SymbolReport::new(i, s.decode())).collect()
+}
+
+// Decode summary
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetectTimings {
+    pub prepare: Duration, // Grayscale, glare inpainting & illumination flattening
+    pub locate: Duration,  // Binarization & finder location
+    pub group: Duration,   // Finder grouping & symbol location
+    pub decode: Duration,  // Sampling, error correction & segment decoding
+}
+
+impl DetectTimings {
+    pub fn total(&self) -> Duration {
+        self.prepare + self.locate + self.group + self.decode
+    }
+}
+
+impl AddAssign for DetectTimings {
+    fn add_assign(&mut self, rhs: Self) {
+        self.prepare += rhs.prepare;
+        self.locate += rhs.locate;
+        self.group += rhs.group;
+        self.decode += rhs.decode;
+    }
+}
+
+// Counts & timings of a DecodeResult, whose Display is a single line for batch logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeSummary {
+    pub located: usize,
+    pub decoded: usize,
+    pub rejected: usize, // Candidates dropped for degenerate geometry
+    pub timings: DetectTimings,
+    pub failures: BTreeMap<&'static str, usize>, // Code names of decode errors & rejections
+}
+
+impl DecodeSummary {
+    pub fn failed(&self) -> usize {
+        self.located - self.decoded
+    }
+}
+
+impl Display for DecodeSummary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let t = &self.timings;
+        write!(
+            f,
+            "located {}, decoded {}, rejected {} in {:.1}ms (prepare {:.1}, locate {:.1}, \
+             group {:.1}, decode {:.1})",
+            self.located,
+            self.decoded,
+            self.rejected,
+            ms(t.total()),
+            ms(t.prepare),
+            ms(t.locate),
+            ms(t.group),
+            ms(t.decode)
+        )?;
+        for (i, (name, n)) in self.failures.iter().enumerate() {
+            write!(f, "{}{name} x{n}", if i == 0 { ", failures: " } else { ", " })?;
+        }
+        Ok(())
+    }
+}
+
+impl DecodeResult {
+    // Decodes every symbol & sums up the outcome, with the decoding time added to the timings
+    pub fn summary(&self) -> DecodeSummary {
+        let start = Instant::now();
+        let res = self.decode_all();
+        let mut timings = self.timings();
+        timings.decode += start.elapsed();
+
+        let mut failures = BTreeMap::new();
+        let errs = res.iter().filter_map(|r| r.as_ref().err()).chain(self.rejections());
+        for e in errs {
+            *failures.entry(e.code_name()).or_default() += 1;
+        }
+
+        DecodeSummary {
+            located: res.len(),
+            decoded: res.iter().filter(|r| r.is_ok()).count(),
+            rejected: self.rejections().len(),
+            timings,
+            failures,
+        }
+    }
+}
+
+// JSON & CSV formatters
+//------------------------------------------------------------------------------
+
+pub fn to_csv(reports: &[SymbolReport]) -> String {
+    let mut out = String::with_capacity(128 * (reports.len() + 1));
+    out.push_str(CSV_HEADER);
+    out.push('\n');
+
+    for r in reports {
+        let meta = r.meta.as_ref();
+        let usage = r.ec_usage();
+        let fields = [
+            r.index.to_string(),
+            r.decoded.to_string(),
+            meta.and_then(|m| m.version()).map(|v| (*v).to_string()).unwrap_or_default(),
+            meta.and_then(|m| m.ec_level()).map(|e| format!("{e:?}")).unwrap_or_default(),
+            meta.and_then(|m| m.mask()).map(|m| (*m).to_string()).unwrap_or_default(),
+            usage.map(|u| u.errors.to_string()).unwrap_or_default(),
+            usage.map(|u| u.budget.to_string()).unwrap_or_default(),
+            format!("{:.3}", r.uec),
+            r.uec_grade.letter().to_string(),
+            r.grade().letter().to_string(),
+            csv_escape(r.text.as_deref().unwrap_or_default()),
+            csv_escape(r.error.as_deref().unwrap_or_default()),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(reports: &[SymbolReport]) -> String {
+    write_json(reports, None)
+}
+
+// Same as to_json, with the file name, EXIF timestamp & GPS position of the scanned image added
+// under "source", e.g. for audits of who scanned what where
+pub fn to_json_with_source(reports: &[SymbolReport], src: &SourceInfo) -> String {
+    write_json(reports, Some(src))
+}
+
+fn write_json(reports: &[SymbolReport], src: Option<&SourceInfo>) -> String {
+    let mut out = String::with_capacity(256 * (reports.len() + 1));
+    let _ = write!(out, "{{\"schema\":\"{JSON_SCHEMA_ID}\",");
+    if let Some(src) = src {
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "null".to_string());
+        let gps = src.gps.map(|g| {
+            let alt = opt(g.alt.map(|a| a.to_string()));
+            format!("{{\"lat\":{},\"lon\":{},\"alt\":{alt}}}", g.lat, g.lon)
+        });
+        let _ = write!(
+            out,
+            "\"source\":{{\"file\":{},\"timestamp\":{},\"gps\":{}}},",
+            opt(src.file.as_deref().map(json_escape)),
+            opt(src.timestamp.as_deref().map(json_escape)),
+            opt(gps),
+        );
+    }
+    out.push_str("\"symbols\":[");
+
+    for (i, r) in reports.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let meta = r.meta.as_ref();
+        let usage = r.ec_usage();
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "null".to_string());
+
+        let _ = write!(
+            out,
+            "{{\"index\":{},\"decoded\":{},\"version\":{},\"ec_level\":{},\"mask\":{},\
+             \"ec_errors\":{},\"ec_budget\":{},\"unused_ec\":{:.3},\"unused_ec_grade\":\"{}\",\
+             \"grade\":\"{}\",\"text\":{},\"error\":{}}}",
+            r.index,
+            r.decoded,
+            opt(meta.and_then(|m| m.version()).map(|v| (*v).to_string())),
+            opt(meta.and_then(|m| m.ec_level()).map(|e| format!("\"{e:?}\""))),
+            opt(meta.and_then(|m| m.mask()).map(|m| (*m).to_string())),
+            opt(usage.map(|u| u.errors.to_string())),
+            opt(usage.map(|u| u.budget.to_string())),
+            r.uec,
+            r.uec_grade.letter(),
+            r.grade().letter(),
+            opt(r.text.as_deref().map(json_escape)),
+            opt(r.error.as_deref().map(json_escape)),
+        );
+    }
+
+    out.push_str("]}");
+    out
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod report_tests {
+    use std::time::Duration;
+
+    use super::{
+        report, to_csv, to_json, to_json_with_source, DecodeSummary, DetectTimings, Grade,
+        SymbolReport, CSV_HEADER,
+    };
+    use crate::{
+        builder::QRBuilder,
+        metadata::{ECLevel, ECUsage, Metadata, Version},
+        reader::detect_qr,
+        reader::source::{GpsPosition, SourceInfo},
+        utils::QRError,
+        MaskPattern,
+    };
+
+    #[test]
+    fn test_unused_ec_grade() {
+        assert_eq!(Grade::from_unused_ec(1.0).letter(), 'A');
+        assert_eq!(Grade::from_unused_ec(0.5).letter(), 'B');
+        assert_eq!(Grade::from_unused_ec(0.4).letter(), 'C');
+        assert_eq!(Grade::from_unused_ec(0.25).letter(), 'D');
+        assert_eq!(Grade::from_unused_ec(0.1).letter(), 'F');
+    }
+
+    #[test]
+    fn test_csv_and_json() {
+        let meta =
+            Metadata::new(Some(Version::Normal(2)), Some(ECLevel::M), Some(MaskPattern::new(3)))
+                .with_ec_usage(ECUsage { errors: 3, budget: 8 });
+        let reports = [
+            SymbolReport::new(0, Ok::<_, QRError>((meta, "a,\"b\"".to_string()))),
+            SymbolReport::new(1, Err(QRError::TooManyError)),
+        ];
+
+        let csv = to_csv(&reports);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(lines.next(), Some("0,true,2,M,3,3,8,0.625,A,A,\"a,\"\"b\"\"\","));
+        assert_eq!(lines.next(), Some("1,false,,,,,,0.000,F,F,,Too many errors"));
+
+        let json = to_json(&reports);
+        assert!(json.starts_with("{\"schema\":\"qrism.symbol-report.v1\",\"symbols\":[{"));
+        assert!(json.contains("\"text\":\"a,\\\"b\\\"\""));
+        assert!(json.contains("\"index\":1,\"decoded\":false,\"version\":null"));
+        assert!(json.contains("\"error\":\"Too many errors\""));
+        assert!(!json.contains("\"source\""));
+
+        let src = SourceInfo {
+            file: Some("label\"1\".jpg".to_string()),
+            timestamp: Some("2024-05-17T14:03:59".to_string()),
+            gps: Some(GpsPosition { lat: -33.875, lon: 151.21, alt: None }),
+        };
+        let json = to_json_with_source(&reports, &src);
+        assert!(json.starts_with(
+            "{\"schema\":\"qrism.symbol-report.v1\",\"source\":{\"file\":\"label\\\"1\\\".jpg\",\
+             \"timestamp\":\"2024-05-17T14:03:59\",\"gps\":{\"lat\":-33.875,\"lon\":151.21,\
+             \"alt\":null}},\"symbols\":[{"
+        ));
+        let json = to_json_with_source(&reports, &SourceInfo::default());
+        assert!(json.contains("\"source\":{\"file\":null,\"timestamp\":null,\"gps\":null}"));
+    }
+
+    #[test]
+    fn test_decode_summary() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+
+        let res = detect_qr(&img);
+        let summary = res.summary();
+        assert_eq!((summary.located, summary.decoded, summary.failed()), (1, 1, 0));
+        assert!(summary.timings.locate > Duration::ZERO && summary.timings.decode > Duration::ZERO);
+        assert!(summary.failures.is_empty());
+        assert!(summary.to_string().starts_with("located 1, decoded 1, rejected 0 in "));
+
+        let summary = DecodeSummary {
+            located: 3,
+            decoded: 1,
+            rejected: 1,
+            timings: DetectTimings { locate: Duration::from_millis(4), ..Default::default() },
+            failures: [("DEGENERATE_QUAD", 1), ("TOO_MANY_ERROR", 2)].into_iter().collect(),
+        };
+        assert_eq!(
+            summary.to_string(),
+            "located 3, decoded 1, rejected 1 in 4.0ms (prepare 0.0, locate 4.0, group 0.0, \
+             decode 0.0), failures: DEGENERATE_QUAD x1, TOO_MANY_ERROR x2"
+        );
+    }
+
+    #[test]
+    fn test_report_from_image() {
+        let qr = QRBuilder::new(b"Hello, world!").build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+        let reports = report(&mut detect_qr(&img));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].grade().letter(), 'A');
+        assert_eq!(reports[0].text.as_deref(), Some("Hello, world!"));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+pub const JSON_SCHEMA_ID: &str = "qrism.symbol-report.v1";
+
+pub const CSV_HEADER: &str = "index,decoded,version,ec_level,mask,ec_errors,ec_budget,unused_ec,\
+                              unused_ec_grade,grade,text,error";
+
+// JSON schema of the report emitted by to_json
+pub const JSON_SCHEMA: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "qrism.symbol-report.v1",
+  "type": "object",
+  "required": ["schema", "symbols"],
+  "properties": {
+    "schema": { "const": "qrism.symbol-report.v1" },
+    "source": {
+      "type": "object",
+      "properties": {
+        "file": { "type": ["string", "null"] },
+        "timestamp": { "type": ["string", "null"] },
+        "gps": {
+          "type": ["object", "null"],
+          "required": ["lat", "lon"],
+          "properties": {
+            "lat": { "type": "number", "minimum": -90, "maximum": 90 },
+            "lon": { "type": "number", "minimum": -180, "maximum": 180 },
+            "alt": { "type": ["number", "null"] }
+          }
+        }
+      }
+    },
+    "symbols": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["index", "decoded", "unused_ec", "unused_ec_grade", "grade"],
+        "properties": {
+          "index": { "type": "integer", "minimum": 0 },
+          "decoded": { "type": "boolean" },
+          "version": { "type": ["integer", "null"], "minimum": 1, "maximum": 40 },
+          "ec_level": { "enum": ["L", "M", "Q", "H", null] },
+          "mask": { "type": ["integer", "null"], "minimum": 0, "maximum": 7 },
+          "ec_errors": { "type": ["integer", "null"], "minimum": 0 },
+          "ec_budget": { "type": ["integer", "null"], "minimum": 0 },
+          "unused_ec": { "type": "number", "minimum": 0, "maximum": 1 },
+          "unused_ec_grade": { "enum": ["A", "B", "C", "D", "F"] },
+          "grade": { "enum": ["A", "B", "C", "D", "F"] },
+          "text": { "type": ["string", "null"] },
+          "error": { "type": ["string", "null"] }
+        }
+      }
+    }
+  }
+}"#;
@@ -0,0 +1,334 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+use image::{imageops, DynamicImage};
+
+use super::{binarize::OtsuBinarizer, detect_qr_with, hints::Sampling, DetectOptions};
+use crate::metadata::Metadata;
+
+// Decode strategies
+//------------------------------------------------------------------------------
+
+// Pass of the robust decoder. Each retries the whole image with one change to the pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DecodeStrategy {
+    Default,         // Detect options as given
+    Otsu,            // Global threshold, for evenly lit images
+    Inverted,        // Light on dark symbols
+    Upscale,         // 2x, for symbols with modules of 1 or 2 pixels
+    VoteSampling,    // Robust to dot gain & ink spread
+    AreaSampling,    // Robust to high ISO noise
+    GlareInpainting, // Specular highlights on glossy labels & screens
+    StylizedFinders, // Rounded or gapped finder eyes
+    Flattening,      // Vignetting & shadows across the symbol
+}
+
+impl DecodeStrategy {
+    pub const ALL: [Self; 9] = [
+        Self::Default,
+        Self::Otsu,
+        Self::Inverted,
+        Self::Upscale,
+        Self::VoteSampling,
+        Self::AreaSampling,
+        Self::GlareInpainting,
+        Self::StylizedFinders,
+        Self::Flattening,
+    ];
+
+    // Name used in profiles
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Otsu => "otsu",
+            Self::Inverted => "inverted",
+            Self::Upscale => "upscale",
+            Self::VoteSampling => "vote_sampling",
+            Self::AreaSampling => "area_sampling",
+            Self::GlareInpainting => "glare_inpainting",
+            Self::StylizedFinders => "stylized_finders",
+            Self::Flattening => "flattening",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+// Strategy profile
+//------------------------------------------------------------------------------
+
+// Order the robust decoder tries strategies in. Strategies left out are never tried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyProfile {
+    order: Vec<DecodeStrategy>,
+}
+
+impl Default for StrategyProfile {
+    fn default() -> Self {
+        Self { order: DEFAULT_STRATEGY_ORDER.to_vec() }
+    }
+}
+
+impl StrategyProfile {
+    pub fn new(order: Vec<DecodeStrategy>) -> Self {
+        Self { order }
+    }
+
+    pub fn order(&self) -> &[DecodeStrategy] {
+        &self.order
+    }
+
+    // Parses one strategy name per line. Blank lines & text after a '#' are ignored
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut order = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let name = line.split('#').next().unwrap_or_default().trim();
+            if name.is_empty() {
+                continue;
+            }
+            let s = DecodeStrategy::from_name(name).ok_or_else(|| {
+                let msg = format!("line {}: unknown strategy {name:?}", i + 1);
+                io::Error::new(io::ErrorKind::InvalidData, msg)
+            })?;
+            if !order.contains(&s) {
+                order.push(s);
+            }
+        }
+        Ok(Self { order })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.order.iter().map(|s| format!("{}\n", s.name())).collect()
+    }
+}
+
+// Strategy statistics
+//------------------------------------------------------------------------------
+
+// Images each strategy decoded over a dataset, collected by the decoding benchmark to derive a
+// profile from
+#[derive(Debug, Clone, Default)]
+pub struct StrategyStats {
+    images: HashSet<String>,
+    decoded: HashMap<DecodeStrategy, HashSet<String>>,
+}
+
+impl StrategyStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, image: &str, strategy: DecodeStrategy, success: bool) {
+        self.images.insert(image.to_string());
+        if success {
+            self.decoded.entry(strategy).or_default().insert(image.to_string());
+        }
+    }
+
+    pub fn merge(&mut self, other: StrategyStats) {
+        self.images.extend(other.images);
+        for (s, imgs) in other.decoded {
+            self.decoded.entry(s).or_default().extend(imgs);
+        }
+    }
+
+    pub fn images(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn successes(&self, strategy: DecodeStrategy) -> usize {
+        self.decoded.get(&strategy).map_or(0, HashSet::len)
+    }
+
+    // Orders strategies by marginal gain, i.e. each next strategy is the one decoding the most
+    // images none of the strategies before it decoded. Strategies that add nothing follow by
+    // their total successes, so they stay available for images unlike the dataset
+    pub fn to_profile(&self) -> StrategyProfile {
+        let mut left = DecodeStrategy::ALL.to_vec();
+        let mut covered = HashSet::new();
+        let mut order = Vec::with_capacity(left.len());
+        while !left.is_empty() {
+            let gain = |s: &DecodeStrategy| {
+                let imgs = self.decoded.get(s);
+                let new = imgs.map_or(0, |i| i.difference(&covered).count());
+                (new, self.successes(*s), std::cmp::Reverse(*s))
+            };
+            let (i, _) = left.iter().enumerate().max_by_key(|(_, s)| gain(s)).unwrap();
+            let s = left.remove(i);
+            covered.extend(self.decoded.get(&s).into_iter().flatten().cloned());
+            order.push(s);
+        }
+        StrategyProfile { order }
+    }
+}
+
+// Robust decoder
+//------------------------------------------------------------------------------
+
+// Retries detection with fallback strategies until one decodes a symbol
+#[derive(Debug, Clone, Default)]
+pub struct RobustDecoder {
+    opts: DetectOptions,
+    profile: StrategyProfile,
+}
+
+impl RobustDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn options(&mut self, opts: DetectOptions) -> &mut Self {
+        self.opts = opts;
+        self
+    }
+
+    pub fn profile(&mut self, profile: StrategyProfile) -> &mut Self {
+        self.profile = profile;
+        self
+    }
+
+    // Payloads from the first strategy in the profile that decodes any symbol, with the strategy
+    pub fn decode(&self, img: &DynamicImage) -> Option<(DecodeStrategy, Vec<(Metadata, String)>)> {
+        self.profile.order.iter().find_map(|&s| {
+            let decoded = self.run(img, s);
+            (!decoded.is_empty()).then_some((s, decoded))
+        })
+    }
+
+    // Payloads of the symbols decoded by a single strategy
+    pub fn run(&self, img: &DynamicImage, strategy: DecodeStrategy) -> Vec<(Metadata, String)> {
+        let mut opts = self.opts.clone();
+        let mut sampling = Sampling::Center;
+        let alt = match strategy {
+            DecodeStrategy::Default => None,
+            DecodeStrategy::Otsu => {
+                opts.binarizer(OtsuBinarizer);
+                None
+            }
+            DecodeStrategy::Inverted => {
+                let mut inv = img.to_luma8();
+                imageops::invert(&mut inv);
+                Some(DynamicImage::ImageLuma8(inv))
+            }
+            DecodeStrategy::Upscale => {
+                let (w, h) = (img.width() * 2, img.height() * 2);
+                Some(img.resize_exact(w, h, imageops::FilterType::Triangle))
+            }
+            DecodeStrategy::VoteSampling => {
+                sampling = Sampling::Vote;
+                None
+            }
+            DecodeStrategy::AreaSampling => {
+                sampling = Sampling::Area;
+                None
+            }
+            DecodeStrategy::GlareInpainting => {
+                opts.glare_inpainting(true);
+                None
+            }
+            DecodeStrategy::StylizedFinders => {
+                opts.stylized_finders(true);
+                None
+            }
+            DecodeStrategy::Flattening => {
+                opts.flatten_illumination(true);
+                None
+            }
+        };
+
+        let res = detect_qr_with(alt.as_ref().unwrap_or(img), &opts);
+        res.symbols().iter().filter_map(|s| s.decode_with_sampling(sampling).ok()).collect()
+    }
+}
+
+#[cfg(test)]
+mod robust_tests {
+    use image::{imageops, DynamicImage};
+
+    use super::{DecodeStrategy, RobustDecoder, StrategyProfile, StrategyStats};
+    use crate::builder::QRBuilder;
+
+    #[test]
+    fn test_profile_text() {
+        let text = "# Tuned on receipts\ninverted\n\nupscale  # tiny codes\ndefault\ninverted\n";
+        let profile = StrategyProfile::parse(text).unwrap();
+        assert_eq!(
+            profile.order(),
+            [DecodeStrategy::Inverted, DecodeStrategy::Upscale, DecodeStrategy::Default]
+        );
+        assert_eq!(profile.to_text(), "inverted\nupscale\ndefault\n");
+        assert_eq!(
+            StrategyProfile::parse(&StrategyProfile::default().to_text()).unwrap(),
+            StrategyProfile::default()
+        );
+
+        let err = StrategyProfile::parse("default\nsharpen\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unknown strategy \"sharpen\"");
+    }
+
+    #[test]
+    fn test_stats_profile() {
+        let mut stats = StrategyStats::new();
+        for img in ["a", "b", "c", "d", "e"] {
+            stats.record(img, DecodeStrategy::Default, img <= "c");
+            stats.record(img, DecodeStrategy::Otsu, img <= "d");
+            stats.record(img, DecodeStrategy::Inverted, img == "e");
+        }
+        let mut other = StrategyStats::new();
+        other.record("f", DecodeStrategy::Upscale, true);
+        stats.merge(other);
+        assert_eq!(stats.images(), 6);
+        assert_eq!(stats.successes(DecodeStrategy::Otsu), 4);
+
+        let profile = stats.to_profile();
+        assert_eq!(
+            profile.order()[..4],
+            [
+                DecodeStrategy::Otsu,
+                DecodeStrategy::Inverted,
+                DecodeStrategy::Upscale,
+                DecodeStrategy::Default
+            ]
+        );
+        assert_eq!(profile.order().len(), DecodeStrategy::ALL.len());
+    }
+
+    #[test]
+    fn test_robust_decoder() {
+        let qr = QRBuilder::new(b"Robust").build().unwrap();
+        let mut img = DynamicImage::ImageRgb8(qr.to_image(4)).to_luma8();
+        imageops::invert(&mut img);
+        let img = DynamicImage::ImageLuma8(img);
+
+        let mut dec = RobustDecoder::new();
+        assert!(dec.run(&img, DecodeStrategy::Default).is_empty());
+        let (strategy, decoded) = dec.decode(&img).unwrap();
+        assert_eq!(strategy, DecodeStrategy::Inverted);
+        assert_eq!(decoded[0].1, "Robust");
+
+        dec.profile(StrategyProfile::new(vec![DecodeStrategy::Default, DecodeStrategy::Otsu]));
+        assert!(dec.decode(&img).is_none());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Shipped retry order, from StrategyStats::to_profile over the blackbox & decoding benchmark
+// datasets. Run the decoding benchmark to regenerate the profile into strategy_profile.txt under
+// the cargo target dir
+const DEFAULT_STRATEGY_ORDER: [DecodeStrategy; 9] = [
+    DecodeStrategy::Flattening,
+    DecodeStrategy::Upscale,
+    DecodeStrategy::Otsu,
+    DecodeStrategy::Default,
+    DecodeStrategy::VoteSampling,
+    DecodeStrategy::StylizedFinders,
+    DecodeStrategy::AreaSampling,
+    DecodeStrategy::GlareInpainting,
+    DecodeStrategy::Inverted,
+];
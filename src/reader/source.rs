@@ -0,0 +1,276 @@
+use std::path::Path;
+
+use image::{ImageDecoder, ImageReader, ImageResult};
+
+// Source metadata
+//------------------------------------------------------------------------------
+
+// Where & when the scanned image was taken, for audit trails alongside decoded payloads
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceInfo {
+    pub file: Option<String>,
+    pub timestamp: Option<String>, // ISO 8601 local time, as the camera has no zone in EXIF
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    pub lat: f64,         // Degrees, negative south of the equator
+    pub lon: f64,         // Degrees, negative west of Greenwich
+    pub alt: Option<f64>, // Metres, negative below sea level
+}
+
+impl SourceInfo {
+    // Reads the file name & the EXIF timestamp & position if the format carries EXIF, i.e.
+    // jpeg, png, tiff & webp. Missing or malformed EXIF only leaves the fields empty
+    pub fn from_file(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let file = path.file_name().map(|f| f.to_string_lossy().into_owned());
+        let exif = match ImageReader::open(path)?.with_guessed_format()?.into_decoder() {
+            Ok(mut dec) => dec.exif_metadata().ok().flatten(),
+            Err(_) => None,
+        };
+        let mut info = exif.as_deref().map(Self::from_exif).unwrap_or_default();
+        info.file = file;
+        Ok(info)
+    }
+
+    // Parses a raw EXIF block, with or without the "Exif\0\0" app segment prefix
+    pub fn from_exif(exif: &[u8]) -> Self {
+        let exif = exif.strip_prefix(b"Exif\0\0").unwrap_or(exif);
+        let Some(tiff) = Tiff::new(exif) else {
+            return Self::default();
+        };
+        let Some(ifd0) = tiff.u32_at(4) else {
+            return Self::default();
+        };
+
+        let sub_ifd = |tag| tiff.entry(ifd0, tag).and_then(|e| tiff.u32_at(e + 8));
+        let timestamp = sub_ifd(EXIF_IFD_TAG)
+            .and_then(|ifd| tiff.ascii(ifd, DATE_TIME_ORIGINAL_TAG))
+            .or_else(|| tiff.ascii(ifd0, DATE_TIME_TAG))
+            .and_then(|dt| iso_timestamp(&dt));
+        let gps = sub_ifd(GPS_IFD_TAG).and_then(|ifd| tiff.gps(ifd));
+
+        Self { file: None, timestamp, gps }
+    }
+}
+
+// Converts EXIF "YYYY:MM:DD HH:MM:SS" into "YYYY-MM-DDTHH:MM:SS"
+fn iso_timestamp(dt: &str) -> Option<String> {
+    let b = dt.as_bytes();
+    let digits = [0..4, 5..7, 8..10, 11..13, 14..16, 17..19];
+    let valid = b.len() >= 19
+        && digits.into_iter().all(|r| b[r].iter().all(u8::is_ascii_digit))
+        && [b[4], b[7], b[13], b[16]] == [b':'; 4];
+    valid.then(|| format!("{}-{}-{}T{}", &dt[..4], &dt[5..7], &dt[8..10], &dt[11..19]))
+}
+
+// Minimal TIFF structure reader
+//------------------------------------------------------------------------------
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    le: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let le = match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let tiff = Self { data, le };
+        (tiff.u16_at(2)? == 42).then_some(tiff)
+    }
+
+    fn u16_at(&self, off: usize) -> Option<u16> {
+        let b = self.data.get(off..off + 2)?.try_into().ok()?;
+        Some(if self.le { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    }
+
+    fn u32_at(&self, off: usize) -> Option<usize> {
+        let b = self.data.get(off..off + 4)?.try_into().ok()?;
+        Some(if self.le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) } as usize)
+    }
+
+    // Offset of the 12 byte entry for the tag in the IFD
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let n = self.u16_at(ifd)? as usize;
+        (0..n).map(|i| ifd + 2 + i * 12).find(|&e| self.u16_at(e) == Some(tag))
+    }
+
+    // Offset & count of the entry's values, which are inlined if they fit in 4 bytes
+    fn values(&self, ifd: usize, tag: u16, typ: u16, size: usize) -> Option<(usize, usize)> {
+        let e = self.entry(ifd, tag)?;
+        if self.u16_at(e + 2)? != typ {
+            return None;
+        }
+        let count = self.u32_at(e + 4)?;
+        let off = if count * size <= 4 { e + 8 } else { self.u32_at(e + 8)? };
+        self.data.get(off..off.checked_add(count.checked_mul(size)?)?)?;
+        Some((off, count))
+    }
+
+    fn ascii(&self, ifd: usize, tag: u16) -> Option<String> {
+        let (off, count) = self.values(ifd, tag, ASCII, 1)?;
+        let s = &self.data[off..off + count];
+        let s = s.split(|&c| c == 0).next()?;
+        Some(String::from_utf8_lossy(s).trim().to_string())
+    }
+
+    fn rationals(&self, ifd: usize, tag: u16) -> Option<Vec<f64>> {
+        let (off, count) = self.values(ifd, tag, RATIONAL, 8)?;
+        (0..count)
+            .map(|i| {
+                let (num, den) = (self.u32_at(off + i * 8)?, self.u32_at(off + i * 8 + 4)?);
+                (den != 0).then(|| num as f64 / den as f64)
+            })
+            .collect()
+    }
+
+    fn gps(&self, ifd: usize) -> Option<GpsPosition> {
+        // Bogus rationals past the max degrees are dropped, so reports stay within the schema
+        let degrees = |tag, ref_tag, neg: &str, max: f64| {
+            let dms = self.rationals(ifd, tag)?;
+            let deg = dms.first()?
+                + dms.get(1).unwrap_or(&0.0) / 60.0
+                + dms.get(2).unwrap_or(&0.0) / 3600.0;
+            if !deg.is_finite() || deg > max {
+                return None;
+            }
+            let sign = if self.ascii(ifd, ref_tag)?.eq_ignore_ascii_case(neg) { -1.0 } else { 1.0 };
+            Some(sign * deg)
+        };
+        let lat = degrees(GPS_LAT_TAG, GPS_LAT_REF_TAG, "S", MAX_LATITUDE)?;
+        let lon = degrees(GPS_LON_TAG, GPS_LON_REF_TAG, "W", MAX_LONGITUDE)?;
+
+        let below_sea =
+            self.values(ifd, GPS_ALT_REF_TAG, BYTE, 1).is_some_and(|(off, _)| self.data[off] == 1);
+        let alt = self.rationals(ifd, GPS_ALT_TAG).and_then(|a| a.first().copied()).map(|a| {
+            if below_sea {
+                -a
+            } else {
+                a
+            }
+        });
+
+        Some(GpsPosition { lat, lon, alt })
+    }
+}
+
+#[cfg(test)]
+mod source_tests {
+    use super::{GpsPosition, SourceInfo};
+
+    // Builds a little endian EXIF block with IFD0 pointing to an Exif & a GPS IFD
+    fn exif_block() -> Vec<u8> {
+        let mut b = b"Exif\0\0II*\0\x08\0\0\0".to_vec();
+        let entry = |b: &mut Vec<u8>, tag: u16, typ: u16, count: u32, val: u32| {
+            b.extend(tag.to_le_bytes());
+            b.extend(typ.to_le_bytes());
+            b.extend(count.to_le_bytes());
+            b.extend(val.to_le_bytes());
+        };
+
+        // IFD0 at 8: exif ptr -> 38, gps ptr -> 56
+        b.extend(2u16.to_le_bytes());
+        entry(&mut b, 0x8769, 4, 1, 38);
+        entry(&mut b, 0x8825, 4, 1, 56);
+        b.extend(0u32.to_le_bytes());
+
+        // Exif IFD at 38: DateTimeOriginal -> 134
+        b.extend(1u16.to_le_bytes());
+        entry(&mut b, 0x9003, 2, 20, 134);
+        b.extend(0u32.to_le_bytes());
+
+        // GPS IFD at 56: lat ref, lat -> 154, lon ref, lon -> 178, alt ref, alt -> 202
+        b.extend(6u16.to_le_bytes());
+        entry(&mut b, 1, 2, 2, u32::from_le_bytes(*b"S\0\0\0"));
+        entry(&mut b, 2, 5, 3, 154);
+        entry(&mut b, 3, 2, 2, u32::from_le_bytes(*b"E\0\0\0"));
+        entry(&mut b, 4, 5, 3, 178);
+        entry(&mut b, 5, 1, 1, 0);
+        entry(&mut b, 6, 5, 1, 202);
+        b.extend(0u32.to_le_bytes());
+
+        b.extend(b"2024:05:17 14:03:59\0");
+        for (num, den) in [(33, 1), (52, 1), (3000, 100), (151, 1), (12, 1), (36, 1), (58, 2)] {
+            b.extend((num as u32).to_le_bytes());
+            b.extend((den as u32).to_le_bytes());
+        }
+        b
+    }
+
+    #[test]
+    fn test_source_from_exif() {
+        let info = SourceInfo::from_exif(&exif_block());
+        assert_eq!(info.timestamp.as_deref(), Some("2024-05-17T14:03:59"));
+        let GpsPosition { lat, lon, alt } = info.gps.unwrap();
+        assert!((lat + 33.875).abs() < 1e-9);
+        assert!((lon - 151.21).abs() < 1e-9);
+        assert_eq!(alt, Some(29.0));
+
+        assert_eq!(SourceInfo::from_exif(b"garbage"), SourceInfo::default());
+        assert_eq!(SourceInfo::from_exif(&exif_block()[..60]).gps, None);
+    }
+
+    #[test]
+    fn test_source_gps_out_of_range() {
+        // Degrees of the latitude & longitude rationals, past the exif header
+        let with_degrees = |at: usize, deg: u32| {
+            let mut b = exif_block();
+            b[at..at + 4].copy_from_slice(&deg.to_le_bytes());
+            SourceInfo::from_exif(&b).gps
+        };
+        assert!(with_degrees(160, 89).is_some());
+        assert_eq!(with_degrees(160, 90), None); // Minutes & seconds take it past 90
+        assert_eq!(with_degrees(160, 4_000_000_000), None);
+        assert!(with_degrees(184, 179).is_some());
+        assert_eq!(with_degrees(184, 181), None);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_source_from_file() {
+        use image::{codecs::png::PngEncoder, ImageEncoder};
+
+        use crate::builder::QRBuilder;
+
+        let path = std::env::temp_dir().join(format!("qrism_source_{}.png", std::process::id()));
+        let img = QRBuilder::new(b"Source").build().unwrap().to_image(3);
+        let mut enc = PngEncoder::new(std::fs::File::create(&path).unwrap());
+        enc.set_exif_metadata(exif_block()[6..].to_vec()).unwrap();
+        enc.write_image(&img, img.width(), img.height(), image::ExtendedColorType::Rgb8).unwrap();
+
+        let info = SourceInfo::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(info.file, path.file_name().map(|f| f.to_string_lossy().into_owned()));
+        assert_eq!(info.timestamp.as_deref(), Some("2024-05-17T14:03:59"));
+        assert!(info.gps.is_some());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// TIFF field types
+const BYTE: u16 = 1;
+const ASCII: u16 = 2;
+const RATIONAL: u16 = 5;
+
+const DATE_TIME_TAG: u16 = 0x0132;
+const EXIF_IFD_TAG: u16 = 0x8769;
+const GPS_IFD_TAG: u16 = 0x8825;
+const DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+
+const GPS_LAT_REF_TAG: u16 = 1;
+const GPS_LAT_TAG: u16 = 2;
+const GPS_LON_REF_TAG: u16 = 3;
+const GPS_LON_TAG: u16 = 4;
+const GPS_ALT_REF_TAG: u16 = 5;
+const GPS_ALT_TAG: u16 = 6;
+
+const MAX_LATITUDE: f64 = 90.0;
+const MAX_LONGITUDE: f64 = 180.0;
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use image::{
+    error::{DecodingError, ImageFormatHint},
+    DynamicImage, ImageError, ImageResult, RgbaImage,
+};
+use resvg::{
+    tiny_skia::{Color, Pixmap, Transform},
+    usvg::{self, Tree},
+};
+
+use super::{detect_qr_with, DecodeResult, DetectOptions};
+
+// Svg reader
+//------------------------------------------------------------------------------
+
+// Rasterizes the svg & detects QRs in it. Text is not rendered
+pub fn detect_qr_svg(path: impl AsRef<Path>, opts: &DetectOptions) -> ImageResult<DecodeResult> {
+    detect_qr_svg_bytes(&std::fs::read(path)?, opts)
+}
+
+pub fn detect_qr_svg_bytes(svg: &[u8], opts: &DetectOptions) -> ImageResult<DecodeResult> {
+    Ok(detect_qr_with(&rasterize_svg(svg)?, opts))
+}
+
+// Renders the svg on white, scaled so its shorter side spans SVG_RENDER_SIZE pixels. Vector QRs
+// are often drawn one unit per module, which would leave a pixel per module at their own size
+pub fn rasterize_svg(svg: &[u8]) -> ImageResult<DynamicImage> {
+    let tree = Tree::from_data(svg, &usvg::Options::default()).map_err(svg_error)?;
+    let size = tree.size();
+    let scale = (SVG_RENDER_SIZE / size.width().min(size.height()))
+        .min(SVG_MAX_RENDER_SIZE / size.width().max(size.height()));
+    let (w, h) = ((size.width() * scale).ceil() as u32, (size.height() * scale).ceil() as u32);
+
+    let mut pixmap = Pixmap::new(w, h).ok_or_else(|| svg_error("Empty svg canvas"))?;
+    pixmap.fill(Color::WHITE);
+    resvg::render(&tree, Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // Opaque after filling with white, so premultiplied & straight alpha agree
+    let img = RgbaImage::from_raw(w, h, pixmap.take()).ok_or_else(|| svg_error("Bad pixmap"))?;
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+// Whether the bytes look like an svg document rather than a raster image
+pub(crate) fn is_svg(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&head[..head.len().min(SVG_SNIFF_LEN)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    (head.starts_with("<?xml") || head.starts_with("<!--") || head.starts_with("<svg"))
+        && head.contains("<svg")
+}
+
+fn svg_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name("svg".to_string()), err))
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use super::{detect_qr_svg_bytes, is_svg, rasterize_svg};
+    use crate::{builder::QRBuilder, metadata::Color, reader::DetectOptions, ECLevel, Version};
+
+    // Svg with a rect per dark module, one unit per module & a 4 unit quiet zone
+    fn qr_svg(msg: &str) -> String {
+        let qr = QRBuilder::new(msg.as_bytes())
+            .version(Version::Normal(3))
+            .ec_level(ECLevel::M)
+            .build()
+            .unwrap();
+        let w = qr.width() as i32;
+        let mut svg = format!(
+            "<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">",
+            w + 8
+        );
+        for (x, y) in (0..w).flat_map(|y| (0..w).map(move |x| (x, y))) {
+            if *qr.get(x, y) == Color::Black {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\"/>",
+                    x + 4,
+                    y + 4
+                ));
+            }
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    #[test]
+    fn test_detect_qr_svg() {
+        let svg = qr_svg("Vector asset");
+        assert!(is_svg(svg.as_bytes()));
+        assert!(!is_svg(b"\x89PNG\r\n\x1a\n"));
+
+        let img = rasterize_svg(svg.as_bytes()).unwrap();
+        assert_eq!((img.width(), img.height()), (800, 800));
+
+        let res = detect_qr_svg_bytes(svg.as_bytes(), &DetectOptions::default()).unwrap();
+        let decoded = res.decode_all().into_iter().flatten().map(|(_, m)| m).collect::<Vec<_>>();
+        assert_eq!(decoded, ["Vector asset"]);
+
+        assert!(rasterize_svg(b"<svg").is_err());
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Shorter side of the rendered svg in pixels, giving 4 or more pixels per module for version 40
+const SVG_RENDER_SIZE: f32 = 800.0;
+
+// Longer side cap in pixels, to bound memory for svgs with extreme aspect ratios
+const SVG_MAX_RENDER_SIZE: f32 = 8000.0;
+
+// Bytes read to tell svg documents apart from raster images
+const SVG_SNIFF_LEN: usize = 512;
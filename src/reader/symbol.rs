@@ -1,20 +1,26 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+
+use encoding_rs::Encoding;
 
 use super::{
     binarize::{BinaryImage, Pixel},
-    finder::FinderGroup,
+    finder::{refine_finder_centre, FinderGroup},
+    glare::GlareMask,
+    hints::Sampling,
     utils::{
         geometry::{Axis, BresenhamLine, Point, Slope},
         homography::Homography,
     },
 };
 use crate::{
-    codec::decode as codec_decode,
-    ec::{rectify_info, Block},
+    builder::QR,
+    codec::decode_segments,
+    ec::{add_ecc, rectify_info, Block, Interleaver, MAX_EC_SIZE},
     metadata::{
-        parse_format_info_qr, Color, Metadata, FORMAT_ERROR_CAPACITY, FORMAT_INFOS_QR,
-        FORMAT_INFO_COORDS_QR_MAIN, FORMAT_INFO_COORDS_QR_SIDE, FORMAT_MASK, VERSION_ERROR_BIT_LEN,
-        VERSION_ERROR_CAPACITY, VERSION_INFOS, VERSION_INFO_COORDS_BL, VERSION_INFO_COORDS_TR,
+        parse_format_info_qr, Color, ECUsage, Fingerprint, Metadata, FORMAT_ERROR_CAPACITY,
+        FORMAT_INFOS_QR, FORMAT_INFO_COORDS_QR_MAIN, FORMAT_INFO_COORDS_QR_SIDE, FORMAT_MASK,
+        MAX_BLOCK_COUNT, MAX_VERSION, VERSION_ERROR_BIT_LEN, VERSION_ERROR_CAPACITY, VERSION_INFOS,
+        VERSION_INFO_COORDS_BL, VERSION_INFO_COORDS_TR,
     },
     reader::utils::{
         geometry::{X, Y},
@@ -24,8 +30,7 @@ use crate::{
     ECLevel, MaskPattern, Version,
 };
 
-#[cfg(test)]
-use image::RgbImage;
+use image::{Rgb, RgbImage};
 
 // Locates symbol based on 3 finder centres, their edge points & provisional grid size
 //------------------------------------------------------------------------------
@@ -35,6 +40,7 @@ pub struct SymbolLocation {
     h: Homography,
     _anchors: [Point; 4],
     ver: Version,
+    saliency: f64,
 }
 
 impl SymbolLocation {
@@ -73,7 +79,9 @@ impl SymbolLocation {
     // ****************************
     // ****************************
     // ****************************
-    pub fn locate(img: &mut BinaryImage, group: &mut FinderGroup) -> Option<SymbolLocation> {
+    // Fails with symbol not found if the patterns don't line up, or with the reason the
+    // projection was degenerate
+    pub fn locate(img: &mut BinaryImage, group: &mut FinderGroup) -> QRResult<SymbolLocation> {
         let [mut c0, c1, mut c2] = group.finders;
 
         // Compute provisional location of alignment centre (c4)
@@ -81,59 +89,117 @@ impl SymbolLocation {
         let dy = c2.y - c1.y;
         let mut align = Point { x: c0.x + dx, y: c0.y + dy };
 
-        // Skip if intersection pt is outside the image
-        if align.x < 0 || align.x as u32 >= img.w || align.y < 0 || align.y as u32 >= img.h {
-            return None;
+        // Skip if intersection pt is well outside the image. Symbols whose bottom right corner is
+        // cut off by the frame are kept, and their missing modules are erased on decode
+        let margin = (c1.dist_sq(&c2) as f64).sqrt() as i32 / 4;
+        let (w, h) = (img.w as i32, img.h as i32);
+        if align.x < -margin || align.x >= w + margin || align.y < -margin || align.y >= h + margin
+        {
+            return Err(QRError::SymbolNotFound);
         }
 
         // Hypotenuse slope
-        let mut hm = Slope { dx: c2.x - c0.x, dy: c2.y - c0.y };
+        let hm = Slope { dx: c2.x - c0.x, dy: c2.y - c0.y };
 
         // Make sure the middle(datum) finder is top-left and not bottom-right
         if (c1.y - c0.y) * hm.dx - (c1.x - c0.x) * hm.dy > 0 {
             group.finders.swap(0, 2);
             std::mem::swap(&mut c0, &mut c2);
-            hm.dx *= -1;
-            hm.dy *= -1;
         }
 
         // Locating midpoints for finder edges which cross the lines connecting the centres. In
         // other words the edges which don't lie on the boundary. These will be used as endpoints
         // to measure timing patterns, and also to locate the provisional alignment centre for
         // versions above 1.
+        let mid = |from, to| find_edge_mid(img, from, to).ok_or(QRError::SymbolNotFound);
         let mids = [
-            find_edge_mid(img, &c0, &align)?,
-            find_edge_mid(img, &c0, &c1)?,
-            find_edge_mid(img, &c1, &c0)?,
-            find_edge_mid(img, &c1, &c2)?,
-            find_edge_mid(img, &c2, &c1)?,
-            find_edge_mid(img, &c2, &align)?,
+            mid(&c0, &align)?,
+            mid(&c0, &c1)?,
+            mid(&c1, &c0)?,
+            mid(&c1, &c2)?,
+            mid(&c2, &c1)?,
+            mid(&c2, &align)?,
         ];
 
-        let size = verify_symbol_size(img, &group.finders, &mids)?;
+        let ver = verify_symbol_size(img, &group.finders, &mids)
+            .and_then(|size| Version::from_grid_size(size as usize))
+            .ok_or(QRError::SymbolNotFound)?;
 
-        let ver = Version::from_grid_size(size as usize)?;
+        // Bottom right corner where the outer edges of the tr & bl finders meet. Unlike an
+        // extrapolation from the finder centres, it holds under perspective
+        let size = ver.width() as f64;
+        let corner = estimate_corner(img, &group.finders, &mids, size);
 
         // For versions greater than 1, a more robust algorithm to locate align centre.
-        // First, locate provisional centre from mid 1 with distance of c1 from mid 4.
-        // Spiral out of provisional align pt to identify potential pt. Then compare the area of
-        // black region with estimate module size to confirm alignment stone. Finally, locate the
-        // centre of the stone.
+        // First, locate provisional centre from the corner if found, else from mid 1 with
+        // distance of c1 from mid 4. Spiral out of provisional align pt to identify potential pt.
+        // Then compare the area of black region with estimate module size to confirm alignment
+        // stone. Finally, locate the centre of the stone.
+        // Without an alignment centre, both the extrapolated anchor & the corner map the symbol
+        // & the corner is kept unless it fits worse
+        let mut fourth = ((size - 3.5, size - 3.5), (align.x as f64, align.y as f64));
+        let mut fallback = corner.map(|c| ((size, size), c));
         if *ver != 1 {
-            align = locate_alignment_pattern(img, &group.finders, &mids, &ver)?;
+            let seed = corner.and_then(|c| project_alignment_seed(&group.finders, c, size));
+            match locate_alignment_pattern(img, &group.finders, &mids, &ver, seed) {
+                Some(pt) => {
+                    fourth = ((size - 6.5, size - 6.5), (pt.x as f64, pt.y as f64));
+                    fallback = None;
+                }
+                None => {
+                    let pt = cut_off_alignment_centre(img, &group.finders, ver)
+                        .ok_or(QRError::SymbolNotFound)?;
+                    fourth = ((size - 6.5, size - 6.5), (pt.x as f64, pt.y as f64));
+                }
+            }
         }
 
-        let h = setup_homography(img, group, align, ver)?;
+        let mut h = setup_homography(img, group, fourth, ver);
+        if let Some(alt) = fallback {
+            if let Ok(alt_h) = setup_homography(img, group, alt, ver) {
+                let better = match &h {
+                    Ok(h) => symbol_fitness(img, &alt_h, ver) >= symbol_fitness(img, h, ver),
+                    Err(_) => true,
+                };
+                if better {
+                    h = Ok(alt_h);
+                    fourth = alt;
+                }
+            }
+        }
+        let h = h?;
+        let (_, (ax, ay)) = fourth;
+        align = Point { x: ax as i32, y: ay as i32 };
 
         let _anchors = [c1, c2, align, c0];
 
-        Some(Self { h, _anchors, ver })
+        Ok(Self { h, _anchors, ver, saliency: group.saliency })
+    }
+
+    // Image coordinates of the symbol corners, clockwise from top left
+    pub(crate) fn corners(&self) -> QRResult<[Point; 4]> {
+        let w = self.ver.width() as f64;
+        let h = &self.h;
+        Ok([h.map(0.0, 0.0)?, h.map(w, 0.0)?, h.map(w, w)?, h.map(0.0, w)?])
+    }
+
+    // Location of an upright, unskewed symbol from its top left corner & module size in pixels
+    pub(crate) fn axis_aligned(origin: (f64, f64), mod_sz: (f64, f64), ver: Version) -> Self {
+        let ((ox, oy), (sx, sy)) = (origin, mod_sz);
+        let h = Homography([sx, 0.0, ox, 0.0, sy, oy, 0.0, 0.0]);
+        let w = ver.width() as f64;
+        let corner = |x: f64, y: f64| Point { x: (ox + x * sx) as i32, y: (oy + y * sy) as i32 };
+        let _anchors = [corner(0.0, 0.0), corner(w, 0.0), corner(w, w), corner(0.0, w)];
+        Self { h, _anchors, ver, saliency: 0.0 }
     }
 }
 
 // Validates the symbol and returns its size if valid. Validation involves:
 // 1. Ensuring the horizontal and vertical timing patterns are consistent.
-// 2. Verifying that the estimated number of modules along the center matches the timing patterns.
+// 2. Cross checking the size counted along the timing patterns against the size estimated from
+//    the distance between finders. The timing count is exact on a clean symbol, while the estimate
+//    drifts with perspective & blur, so the timing count wins when both timing patterns agree.
+//    Otherwise the estimate breaks the tie, and must roughly match the timing patterns.
 fn verify_symbol_size(img: &BinaryImage, finders: &[Point; 3], mids: &[Point; 6]) -> Option<u32> {
     let [c0, c1, c2] = finders;
     let [m03, m01, m10, m12, m21, m23] = mids;
@@ -150,10 +216,11 @@ fn verify_symbol_size(img: &BinaryImage, finders: &[Point; 3], mids: &[Point; 6]
         return None;
     }
 
-    // Estimate mod size along f1->f2 & f1->f0. This is used as threshold to filter noise in timing
-    // pattern
-    let ms12 = estimate_mod_size(c1, m12, c2, m21);
-    let ms10 = estimate_mod_size(c1, m10, c0, m01);
+    // Both timing patterns snap to the same version
+    let (s12, s10) = (snap_to_size(t12 as f64 + 13.0), snap_to_size(t10 as f64 + 13.0));
+    if s12 == s10 {
+        return Some(s12);
+    }
 
     // Estimate module count from c1 to c2
     let mc12 = estimate_mod_count(c1, m12, c2, m21);
@@ -173,12 +240,18 @@ fn verify_symbol_size(img: &BinaryImage, finders: &[Point; 3], mids: &[Point; 6]
         return None;
     }
 
-    // Provisional width and version
-    let size = (t12 + t10) / 2 + 13;
-    let ver = ((size as f64 - 15.0) / 4.0).floor() as u32;
-    let size = ver * 4 + 17;
+    // Timing pattern that agrees with the width estimate, else the average of both
+    let est = snap_to_size((mc12 + mc10) / 2.0 + 7.0);
+    match est {
+        e if e == s12 || e == s10 => Some(e),
+        _ => Some(snap_to_size((t12 + t10) as f64 / 2.0 + 13.0)),
+    }
+}
 
-    Some(size)
+// Rounds a measured module count across the symbol to the width of a version
+fn snap_to_size(size: f64) -> u32 {
+    let ver = ((size - 15.0) / 4.0).floor().max(0.0) as u32;
+    ver * 4 + 17
 }
 
 fn estimate_mod_size(c1: &Point, m1: &Point, c2: &Point, m2: &Point) -> f64 {
@@ -279,52 +352,395 @@ fn estimate_mod_count(c1: &Point, m1: &Point, c2: &Point, m2: &Point) -> f64 {
 pub struct Symbol {
     img: Arc<BinaryImage>,
     h: Homography,
+    img_h: Homography, // Projects onto img. Same as h unless img is an upscaled crop
     _anchors: [Point; 4],
-    pub ver: Version,
+    pub ver: Version, // Estimated from the finder spacing. Decoding leaves it as is
+    saliency: f64,
+    glare: Option<Arc<GlareMask>>,
+    sampling: Sampling,
+    charset: Option<&'static Encoding>,
+    page: usize,
+    offsets: OnceLock<[(i32, i32); 3]>, // Pixel offsets of the red, green & blue channels
 }
 
 impl Symbol {
     pub fn new(img: Arc<BinaryImage>, sym_loc: SymbolLocation) -> Self {
-        let SymbolLocation { h, _anchors, ver } = sym_loc;
-        Self { img, h, _anchors, ver }
+        let SymbolLocation { h, _anchors, ver, saliency } = sym_loc;
+        let sampling = Sampling::Center;
+        Self {
+            img,
+            img_h: h.clone(),
+            h,
+            _anchors,
+            ver,
+            saliency,
+            glare: None,
+            sampling,
+            charset: None,
+            page: 0,
+            offsets: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn with_glare(mut self, glare: Arc<GlareMask>) -> Self {
+        self.glare = Some(glare);
+        self
+    }
+
+    pub(crate) fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
     }
 
-    pub fn decode(&mut self) -> QRResult<(Metadata, String)> {
-        let (ecl, mask) = self.read_format_info()?;
+    // Samples modules from an upscaled crop of the image instead. The crop starts at origin in
+    // image pixels & is k times the size. The symbol is projected onto the crop with loc, if it
+    // was located again in the crop, else with the homography found in the image
+    pub(crate) fn with_upscaled(
+        mut self,
+        img: Arc<BinaryImage>,
+        loc: Option<SymbolLocation>,
+        origin: (u32, u32),
+        k: f64,
+    ) -> Self {
+        self.img_h = match loc {
+            Some(loc) if loc.ver == self.ver => loc.h,
+            _ => {
+                // Pixel centres of the crop sit at (i + 0.5) / k - 0.5 in image pixels
+                let shift = |o: u32| (k - 1.0) / 2.0 - k * o as f64;
+                self.h.then_scale(k, shift(origin.0), shift(origin.1))
+            }
+        };
+        self.img = img;
+        self
+    }
+
+    pub(crate) fn with_page(mut self, page: usize) -> Self {
+        self.page = page;
+        self
+    }
+
+    // Zero based index of the page or frame the symbol was found on. Always 0 for single images
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    // Score in 0..1 of how prominent the symbol is in the image, from its size, contrast &
+    // closeness to the centre. Used to order symbols when DetectOptions::saliency_order is set
+    pub fn saliency(&self) -> f64 {
+        self.saliency
+    }
+
+    // Pixel offsets of the red, green & blue channels relative to each other, estimated on the
+    // first decode of high capacity symbols. Non zero only for those whose plates were printed
+    // out of register
+    pub fn channel_offsets(&self) -> [(i32, i32); 3] {
+        self.offsets.get().copied().unwrap_or_default()
+    }
+
+    // Decodes byte segments in the given charset instead of detecting it
+    pub fn decode_with_charset(&self, charset: &'static Encoding) -> QRResult<(Metadata, String)> {
+        self.decode_with(self.sampling, Some(charset))
+    }
+
+    // Decodes with the given module sampling strategy
+    pub fn decode_with_sampling(&self, sampling: Sampling) -> QRResult<(Metadata, String)> {
+        self.decode_with(sampling, self.charset)
+    }
+
+    // Decoding works on a copy, which is cheap as the image is shared, so symbols can be decoded
+    // in parallel through shared references. Only the channel offsets are cached on the symbol
+    pub(crate) fn decode_with(
+        &self,
+        sampling: Sampling,
+        charset: Option<&'static Encoding>,
+    ) -> QRResult<(Metadata, String)> {
+        self.init_channel_offsets();
+        let mut sym = self.clone();
+        (sym.sampling, sym.charset) = (sampling, charset);
+        sym.decode_in_place()
+    }
+
+    // The version read off the version info is reported in the metadata, as ver isn't updated
+    pub fn decode(&self) -> QRResult<(Metadata, String)> {
+        self.decode_with(self.sampling, self.charset)
+    }
+
+    // Reconstructs a pristine QR with the version, ec level, mask & payload read off the symbol,
+    // so damaged labels can be reprinted identically from a partial scan. Errors are corrected &
+    // ecc recomputed, so the QR matches the original module for module if the symbol decodes
+    pub fn reencode(&self) -> QRResult<QR> {
+        self.init_channel_offsets();
+        let mut cwds = self.clone().rectify()?;
+        let (ver, ecl, hi_cap) = (cwds.ver, cwds.ecl, cwds.hi_cap);
+        let (_, _, segs, _) = decode_segments(&mut cwds.data, ver, ecl, hi_cap, self.charset)?;
+
+        let mut qr = QR::from_template(ver, ecl, hi_cap);
+        qr.draw_data_region(add_ecc(cwds.data.data(), ver, ecl, hi_cap));
+        qr.set_segments(segs);
+        qr.apply_mask(cwds.mask);
+        Ok(qr)
+    }
+
+    fn decode_in_place(&mut self) -> QRResult<(Metadata, String)> {
+        let Codewords { ver, ecl, mask, hi_cap, raw_format, mut data, interleaved, usage } =
+            self.rectify()?;
+        let (msg, sym, segs, parity) = decode_segments(&mut data, ver, ecl, hi_cap, self.charset)?;
+        let fp = Fingerprint::compute(ver, ecl, Some(mask), hi_cap, interleaved.data());
+        let mut meta = Metadata::new(Some(ver), Some(ecl), Some(mask))
+            .with_symbology(sym)
+            .with_fingerprint(fp)
+            .with_segments(segs)
+            .with_parity(parity)
+            .with_raw_format_bits(raw_format);
+        if let Some(u) = usage {
+            meta = meta.with_ec_usage(u);
+        }
+
+        Ok((meta, msg))
+    }
+
+    // Reads the format & version info and the codewords, correcting errors block by block
+    fn rectify(&mut self) -> QRResult<Codewords> {
+        let (ecl, mask, raw_format) = self.read_format()?;
         if matches!(self.ver, Version::Normal(7..=40)) {
             self.ver = self.read_version_info()?;
         }
         let ver = self.ver;
         let hi_cap = self.read_capacity_info()?;
+        if hi_cap && cfg!(not(feature = "poly")) {
+            return Err(QRError::InvalidCapacityInfo);
+        }
 
-        let pld = self.extract_payload(&mask)?;
+        let (pld, erased) = self.read_payload(&mask);
 
-        let blk_info = ver.data_codewords_per_block(ecl);
-        let ec_len = ver.ecc_per_block(ecl);
+        let layout = ver.block_layout(ecl);
+        let ilvr = Interleaver::new(layout);
         let mut enc = BitStream::new(pld.len() << 3);
         let chan_cap = ver.channel_codewords();
 
-        // Chunking channel data, deinterleaving & rectifying payload
-        for c in pld.data().chunks_exact(chan_cap) {
-            let mut blocks = deinterleave(c, blk_info, ec_len);
-            for b in blocks.iter_mut() {
-                let rectified = b.rectify()?;
-                enc.extend(rectified);
+        // Positions of erased codewords within each block. Same for all channels
+        let mut erasures = [[0; MAX_EC_SIZE]; MAX_BLOCK_COUNT];
+        let mut erasure_cnt = [0; MAX_BLOCK_COUNT];
+        if let Some(erased) = erased {
+            for (i, _) in erased.iter().enumerate().filter(|(_, &e)| e) {
+                let (b, pos) = ilvr.block_index(i);
+                if erasure_cnt[b] < MAX_EC_SIZE {
+                    erasures[b][erasure_cnt[b]] = pos;
+                }
+                erasure_cnt[b] += 1;
             }
         }
 
-        let msg = codec_decode(&mut enc, ver, ecl, hi_cap)?;
-        let meta = Metadata::new(Some(ver), Some(ecl), Some(mask));
+        // Chunking channel data, deinterleaving & rectifying payload
+        let chans = if hi_cap { 3 } else { 1 };
+        let mut usage = None;
+        let mut rectified = BitStream::new((chan_cap * chans) << 3);
+        let mut blk_buf = [Block::with_encoded(&[], 0); MAX_BLOCK_COUNT];
+        for c in pld.data().chunks_exact(chan_cap).take(chans) {
+            let blocks = ilvr.deinterleave_into(c, &mut blk_buf);
+            for (j, b) in blocks.iter_mut().enumerate() {
+                let errors = if erasure_cnt[j] <= layout.ec_len {
+                    b.rectify_and_count_with_erasures(&erasures[j][..erasure_cnt[j]])?
+                } else {
+                    b.rectify_and_count()?
+                };
+                let blk_usage = ECUsage { errors, budget: b.ec_len() / 2 };
+                usage = ECUsage::worst(usage.into_iter().chain([blk_usage]));
+                enc.extend(b.data());
+            }
+            ilvr.interleave_into(blocks, &mut rectified);
+        }
 
-        Ok((meta, msg))
+        Ok(Codewords {
+            ver,
+            ecl,
+            mask,
+            hi_cap,
+            raw_format,
+            data: enc,
+            interleaved: rectified,
+            usage,
+        })
     }
 
     pub fn get(&self, x: i32, y: i32) -> Option<&Pixel> {
         let (xp, yp) = self.wrap_coord(x, y);
-        let pt = self.map(xp as f64 + 0.5, yp as f64 + 0.5).ok()?;
+        let pt = self.img_h.map(xp as f64 + 0.5, yp as f64 + 0.5).ok()?;
         self.img.get_at_point(&pt)
     }
 
+    // Color of the module as per the sampling strategy
+    pub fn sample(&self, x: i32, y: i32) -> Option<Color> {
+        match self.sampling {
+            Sampling::Center => {
+                let (xp, yp) = self.wrap_coord(x, y);
+                self.sample_at(xp as f64 + 0.5, yp as f64 + 0.5)
+            }
+            Sampling::Area => self.sample_area(x, y),
+            Sampling::Vote => self.sample_vote(x, y),
+            Sampling::Dithered => self.sample_dithered(x, y),
+        }
+    }
+
+    // Poly codes printed in mono carry red, green & blue bits in the top left, top right & bottom
+    // left quadrants of each module, light for a set bit
+    fn sample_dithered(&self, x: i32, y: i32) -> Option<Color> {
+        let (xp, yp) = self.wrap_coord(x, y);
+        let mut byte = 0;
+        for (i, (dx, dy)) in DITHER_QUADRANT_CENTRES.iter().enumerate() {
+            let pt = self.img_h.map(xp as f64 + dx, yp as f64 + dy).ok()?;
+            if self.img.get_at_point(&pt)?.get_color() == Color::White {
+                byte |= 0b100 >> i;
+            }
+        }
+        Color::try_from(byte).ok()
+    }
+
+    // Gaussian weighted vote of each channel over a grid of points within the module. Isolated
+    // noisy pixels are outvoted by their neighbours
+    fn sample_area(&self, x: i32, y: i32) -> Option<Color> {
+        let (xp, yp) = self.wrap_coord(x, y);
+        let mut votes = [0.0; 3];
+        let mut total = 0.0;
+
+        for (dy, wy) in AREA_SAMPLE_OFFSETS.iter().zip(AREA_SAMPLE_WEIGHTS) {
+            for (dx, wx) in AREA_SAMPLE_OFFSETS.iter().zip(AREA_SAMPLE_WEIGHTS) {
+                let Some(clr) = self.sample_at(xp as f64 + dx, yp as f64 + dy) else { continue };
+
+                let wt = wx * wy;
+                let clr = clr as u8;
+                for (i, v) in votes.iter_mut().enumerate() {
+                    if (clr >> i) & 1 == 1 {
+                        *v += wt;
+                    }
+                }
+                total += wt;
+            }
+        }
+
+        if total == 0.0 {
+            return None;
+        }
+
+        let byte = votes
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v * 2.0 > total)
+            .fold(0, |b, (i, _)| b | (1 << i));
+        Color::try_from(byte).ok()
+    }
+
+    // Color at a point of the symbol grid, reading each channel at its own offset
+    fn sample_at(&self, x: f64, y: f64) -> Option<Color> {
+        let pt = self.img_h.map(x, y).ok()?;
+        let offsets = match self.offsets.get() {
+            Some(&offs) if offs != [(0, 0); 3] => offs,
+            _ => return self.img.get_at_point(&pt).map(|px| px.get_color()),
+        };
+
+        let mut byte = 0;
+        for (i, &(dx, dy)) in offsets.iter().enumerate() {
+            let px = self.img.get_at_point(&Point { x: pt.x + dx, y: pt.y + dy })?;
+            byte |= px.get_color() as u8 & (0b100 >> i);
+        }
+        Color::try_from(byte).ok()
+    }
+
+    // Only high capacity symbols carry data in separate channels, so mono ones skip the search
+    // over shifts. The capacity module is read unshifted, as offsets are a pixel or two
+    fn init_channel_offsets(&self) {
+        self.offsets.get_or_init(|| match self.read_capacity_info() {
+            Ok(true) => self.estimate_channel_offsets(),
+            _ => [(0, 0); 3],
+        });
+    }
+
+    // Cheap printers shift the colour plates by a pixel or two, misaligning the channels. Each
+    // channel is matched against the finder patterns & separators at every shift within
+    // MAX_CHANNEL_SHIFT, and the best fitting one is kept. Ties go to the smaller shift. The
+    // median shift is taken out, so the homography's own error isn't applied & mono symbols,
+    // whose channels agree, are left as is
+    fn estimate_channel_offsets(&self) -> [(i32, i32); 3] {
+        let r = MAX_CHANNEL_SHIFT;
+        let mut shifts =
+            (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy))).collect::<Vec<_>>();
+        shifts.sort_by_key(|&(dx, dy)| dx * dx + dy * dy);
+
+        let w = self.ver.width() as f64;
+        let centres = [(3.5, 3.5), (w - 3.5, 3.5), (3.5, w - 3.5)];
+        let steps = (9.0 / FINDER_SAMPLE_STEP) as usize;
+        let mut scores = vec![[0u32; 3]; shifts.len()];
+
+        for (cx, cy) in centres {
+            for i in 0..steps {
+                for j in 0..steps {
+                    let dx = -4.5 + (j as f64 + 0.5) * FINDER_SAMPLE_STEP;
+                    let dy = -4.5 + (i as f64 + 0.5) * FINDER_SAMPLE_STEP;
+                    let d = dx.abs().max(dy.abs());
+                    let dark = d < 1.5 || (2.5 < d && d < 3.5);
+
+                    let Ok(pt) = self.img_h.map(cx + dx, cy + dy) else { continue };
+                    for (&(ox, oy), score) in shifts.iter().zip(scores.iter_mut()) {
+                        let shifted = Point { x: pt.x + ox, y: pt.y + oy };
+                        let Some(px) = self.img.get_at_point(&shifted) else { continue };
+                        let clr = px.get_color() as u8;
+                        for (c, s) in score.iter_mut().enumerate() {
+                            if (clr & (0b100 >> c) == 0) == dark {
+                                *s += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut offsets = [(0, 0); 3];
+        for (c, off) in offsets.iter_mut().enumerate() {
+            let mut best = 0;
+            for (k, score) in scores.iter().enumerate() {
+                if score[c] > scores[best][c] {
+                    best = k;
+                }
+            }
+            *off = shifts[best];
+        }
+
+        let median = |mut v: [i32; 3]| {
+            v.sort_unstable();
+            v[1]
+        };
+        let mx = median(offsets.map(|o| o.0));
+        let my = median(offsets.map(|o| o.1));
+        offsets.map(|(x, y)| (x - mx, y - my))
+    }
+
+    // Majority of the centre & the points around it, for each channel. Dot gain & ink spread flip
+    // the centre of marginal modules, while most of the points still read true
+    fn sample_vote(&self, x: i32, y: i32) -> Option<Color> {
+        let (xp, yp) = self.wrap_coord(x, y);
+        let mut votes = [0; 3];
+        let mut total = 0;
+
+        for (dx, dy) in VOTE_SAMPLE_OFFSETS {
+            let Some(clr) = self.sample_at(xp as f64 + dx, yp as f64 + dy) else { continue };
+            for (i, v) in votes.iter_mut().enumerate() {
+                *v += (clr as u8 >> i) & 1;
+            }
+            total += 1;
+        }
+
+        if total == 0 {
+            return None;
+        }
+
+        let byte = votes
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v * 2 > total)
+            .fold(0, |b, (i, _)| b | (1 << i));
+        Color::try_from(byte).ok()
+    }
+
     fn wrap_coord(&self, x: i32, y: i32) -> (i32, i32) {
         let w = self.ver.width() as i32;
         debug_assert!(-w <= x && x < w, "x shouldn't be greater than or equal to w");
@@ -340,6 +756,37 @@ impl Symbol {
         self.h.map(x, y)
     }
 
+    // Image coordinates of the symbol centre
+    pub fn centre(&self) -> QRResult<Point> {
+        let half = self.ver.width() as f64 / 2.0;
+        self.map(half, half)
+    }
+
+    // Image coordinates of the symbol corners, clockwise from top left
+    pub fn corners(&self) -> QRResult<[Point; 4]> {
+        let w = self.ver.width() as f64;
+        Ok([self.map(0.0, 0.0)?, self.map(w, 0.0)?, self.map(w, w)?, self.map(0.0, w)?])
+    }
+
+    // Stable id hashed from the corners & page, so repeated scans of a sheet can be diffed. Corners
+    // are snapped to whole modules, so a pixel of jitter between scans keeps the id
+    pub fn id(&self) -> u64 {
+        let pts = self.corners().unwrap_or(self._anchors);
+        let mod_px = self.extent().map_or(1.0, |e| e / self.ver.width() as f64).round().max(1.0);
+        let snap = |v: i32| (v as f64 / mod_px).round() as i32;
+        let coords = pts.iter().flat_map(|p| [snap(p.x), snap(p.y)]).chain([self.page as i32]);
+        coords
+            .flat_map(i32::to_le_bytes)
+            .fold(FNV_OFFSET, |h, b| (h ^ b as u64).wrapping_mul(FNV_PRIME))
+    }
+
+    // Length of the top edge of the symbol in pixels
+    pub fn extent(&self) -> QRResult<f64> {
+        let tl = self.map(0.0, 0.0)?;
+        let tr = self.map(self.ver.width() as f64, 0.0)?;
+        Ok((tl.dist_sq(&tr) as f64).sqrt())
+    }
+
     #[cfg(feature = "benchmark")]
     #[inline]
     pub fn raw_map(&self, x: f64, y: f64) -> QRResult<(f64, f64)> {
@@ -348,22 +795,21 @@ impl Symbol {
 
     #[cfg(test)]
     pub fn highlight(&self, img: &mut RgbImage) {
-        use super::utils::geometry::{BresenhamLine, X, Y};
-        use crate::reader::utils::rnd_rgb;
+        self.draw_outline(img, crate::reader::utils::rnd_rgb());
+    }
 
-        let color = rnd_rgb();
+    // Marks the anchors & draws the edges of the symbol, clamped to the image
+    pub(crate) fn draw_outline(&self, img: &mut RgbImage, color: Rgb<u8>) {
+        use super::utils::geometry::{BresenhamLine, X, Y};
 
         for p in self._anchors.iter() {
             p.highlight(img, color);
         }
 
         let (w, h) = img.dimensions();
-        let sz = self.ver.width() as f64;
-        let tl = self.map(0.0, 0.0).unwrap();
-        let tr = self.map(sz, 0.0).unwrap();
-        let br = self.map(sz, sz).unwrap();
-        let bl = self.map(0.0, sz).unwrap();
-        let bounds = [tl, tr, br, bl];
+        let Ok(bounds) = self.corners() else {
+            return;
+        };
 
         for i in 0..4 {
             let mut a = bounds[i % 4];
@@ -396,15 +842,16 @@ fn locate_alignment_pattern(
     finders: &[Point; 3],
     mids: &[Point; 6],
     ver: &Version,
+    seed: Option<Point>,
 ) -> Option<Point> {
     let (w, h) = (img.w, img.h);
     let [c0, c1, c2] = finders;
     let pattern = [1.0, 1.0, 1.0];
 
-    // Locate provisional alignment centre
+    // Locate provisional alignment centre, unless given
     let dx = mids[4].x - c1.x;
     let dy = mids[4].y - c1.y;
-    let mut seed = Point { x: mids[1].x + dx, y: mids[1].y + dy };
+    let mut seed = seed.unwrap_or(Point { x: mids[1].x + dx, y: mids[1].y + dy });
 
     // Calculate estimate width of module
     let hor_w = c0.dist_sq(&mids[0]);
@@ -482,32 +929,194 @@ fn locate_alignment_pattern(
     None
 }
 
+// Extrapolates the alignment centre from the finders when it lies too close to or beyond the image
+// border to be found. None if it should have been within the image
+fn cut_off_alignment_centre(
+    img: &BinaryImage,
+    finders: &[Point; 3],
+    ver: Version,
+) -> Option<Point> {
+    let [c0, c1, c2] = finders;
+    let size = ver.width() as f64;
+    let t = (size - 10.0) / (size - 7.0);
+    let x = c1.x as f64 + (c2.x - c1.x) as f64 * t + (c0.x - c1.x) as f64 * t;
+    let y = c1.y as f64 + (c2.y - c1.y) as f64 * t + (c0.y - c1.y) as f64 * t;
+
+    let mod_w = (c1.dist_sq(c2) as f64).sqrt() / (size - 7.0);
+    let reach = mod_w * 3.0;
+    let inside = x - reach >= 0.0
+        && y - reach >= 0.0
+        && x + reach < img.w as f64
+        && y + reach < img.h as f64;
+    (!inside).then_some(Point { x: x as i32, y: y as i32 })
+}
+
+// Bottom right corner
+//------------------------------------------------------------------------------
+
+// Intersects lines fit along the outer right edge of the tr finder & the outer bottom edge of the
+// bl finder, which the symbol edges extend. Axes & module pitch near each finder are taken from
+// its edge mids. None if either edge can't be traced, e.g. with no quiet zone, the edges are
+// near parallel, or the corner strays too far from where the finder centres place it. Also None
+// if the corner is close to that placement, since the traced edges carry some binarization bias
+// & the extrapolation is then the steadier anchor
+fn estimate_corner(
+    img: &BinaryImage,
+    finders: &[Point; 3],
+    mids: &[Point; 6],
+    size: f64,
+) -> Option<(f64, f64)> {
+    let [c0, c1, c2] = finders.map(|p| (p.x as f64, p.y as f64));
+    let [m03, m01, _, _, m21, m23] = mids.map(|p| (p.x as f64, p.y as f64));
+
+    // Mids lie on the middle of the outer ring, 3 modules from the centre
+    let axis = |from: (f64, f64), to: (f64, f64)| {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let len = dx.hypot(dy);
+        (len > f64::EPSILON).then(|| ((dx / len, dy / len), len / 3.0))
+    };
+    let (right, right_p) = axis(m21, c2)?;
+    let (down, down_p) = axis(c2, m23)?;
+    let right_edge = trace_outer_edge(img, c2, (down, down_p), (right, right_p))?;
+
+    let (down, down_p) = axis(m01, c0)?;
+    let (right, right_p) = axis(c0, m03)?;
+    let bottom_edge = trace_outer_edge(img, c0, (right, right_p), (down, down_p))?;
+
+    let corner = intersect_lines(right_edge, bottom_edge)?;
+
+    // Parallelogram estimate of the corner, half a finder beyond the centres
+    let side = (c2.0 - c1.0).hypot(c2.1 - c1.1);
+    let mod_w = side / (size - 7.0);
+    let k = 3.5 / (size - 7.0);
+    let para = (
+        c0.0 + c2.0 - c1.0 + (c2.0 - c1.0 + c0.0 - c1.0) * k,
+        c0.1 + c2.1 - c1.1 + (c2.1 - c1.1 + c0.1 - c1.1) * k,
+    );
+    let dev = (corner.0 - para.0).hypot(corner.1 - para.1);
+    (CORNER_MIN_PERSPECTIVE * mod_w..=CORNER_MAX_DEVIATION * side).contains(&dev).then_some(corner)
+}
+
+// Fits a line to the outer edge of a finder, crossed by scanning out from each module row of the
+// finder along the scan axis. The edge of a row is past its last dark pixel within the finder
+// reach, which must lie on the outer ring. Rows that fail it are dropped, & most must be kept
+fn trace_outer_edge(
+    img: &BinaryImage,
+    centre: (f64, f64),
+    row_axis: ((f64, f64), f64),
+    scan_axis: ((f64, f64), f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    let ((rx, ry), row_p) = row_axis;
+    let ((sx, sy), scan_p) = scan_axis;
+    let is_dark = |x: f64, y: f64| {
+        (x >= 0.0 && y >= 0.0)
+            && img.get(x as u32, y as u32).is_some_and(|p| p.get_color() != Color::White)
+    };
+
+    let mut points = Vec::with_capacity(7);
+    for row in -3..=3 {
+        let (ox, oy) = (centre.0 + rx * row as f64 * row_p, centre.1 + ry * row as f64 * row_p);
+        let reach = (4.5 * scan_p) as usize * 2;
+        let last_dark = (0..=reach)
+            .rev()
+            .map(|i| i as f64 / 2.0)
+            .find(|&t| is_dark(ox + sx * t, oy + sy * t))?;
+        if !(2.5 * scan_p..=4.0 * scan_p).contains(&last_dark) {
+            continue;
+        }
+        let t = last_dark + 0.5;
+        points.push((ox + sx * t, oy + sy * t));
+    }
+    if points.len() < 5 {
+        return None;
+    }
+    fit_line(&points)
+}
+
+// Total least squares line through the points, as its centroid & unit direction
+fn fit_line(points: &[(f64, f64)]) -> Option<((f64, f64), (f64, f64))> {
+    let n = points.len() as f64;
+    let mx = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        let (dx, dy) = (x - mx, y - my);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    if sxx + syy <= f64::EPSILON {
+        return None;
+    }
+    let theta = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    Some(((mx, my), (theta.cos(), theta.sin())))
+}
+
+// Intersection of 2 lines given as a point & direction. None if they are near parallel
+fn intersect_lines(
+    l1: ((f64, f64), (f64, f64)),
+    l2: ((f64, f64), (f64, f64)),
+) -> Option<(f64, f64)> {
+    let (((x1, y1), (dx1, dy1)), ((x2, y2), (dx2, dy2))) = (l1, l2);
+    let cross = dx1 * dy2 - dy1 * dx2;
+    if cross.abs() < MIN_EDGE_SINE {
+        return None;
+    }
+    let t = ((x2 - x1) * dy2 - (y2 - y1) * dx2) / cross;
+    Some((x1 + dx1 * t, y1 + dy1 * t))
+}
+
+// Where the alignment centre falls on a projection from the finder centres & the corner
+fn project_alignment_seed(finders: &[Point; 3], corner: (f64, f64), size: f64) -> Option<Point> {
+    let [c0, c1, c2] = finders.map(|p| (p.x as f64, p.y as f64));
+    let src = [(3.5, 3.5), (size - 3.5, 3.5), (size, size), (3.5, size - 3.5)];
+    let h = Homography::compute(src, [c1, c2, corner, c0]).ok()?;
+    h.map(size - 6.5, size - 6.5).ok()
+}
+
+// Maps the finder centres & a fourth anchor towards the bottom right, given by its module &
+// image coordinates, i.e. the alignment centre or the corner
 fn setup_homography(
     img: &BinaryImage,
     group: &FinderGroup,
-    align_centre: Point,
+    fourth: ((f64, f64), (f64, f64)),
     ver: Version,
-) -> Option<Homography> {
+) -> QRResult<Homography> {
     let size = ver.width() as f64;
-    let br_off = if *ver == 1 { 3.5 } else { 6.5 };
-    let src = [(3.5, 3.5), (size - 3.5, 3.5), (size - br_off, size - br_off), (3.5, size - 3.5)];
+    let (br, ca) = fourth;
+    let src = [(3.5, 3.5), (size - 3.5, 3.5), br, (3.5, size - 3.5)];
 
-    let c0 = (group.finders[0].x as f64, group.finders[0].y as f64);
-    let c1 = (group.finders[1].x as f64, group.finders[1].y as f64);
-    let c2 = (group.finders[2].x as f64, group.finders[2].y as f64);
-    let ca = (align_centre.x as f64, align_centre.y as f64);
+    let [c0, c1, c2] = group.finders.map(|f| refine_finder_centre(img, &f));
     let dst = [c1, c2, ca, c0];
 
-    let initial_h = Homography::compute(src, dst).ok()?;
+    let mut initial_h = Homography::compute(src, dst)?;
+
+    // Flatbed scans & other near fronto-parallel views need no perspective, which a slightly
+    // misplaced alignment centre would add. An affine map from the finders alone is used instead
+    // if it lands close enough to the alignment centre
+    if let Ok(aff) = Homography::affine([src[0], src[1], src[3]], [c1, c2, c0]) {
+        let mod_w = ((c2.0 - c1.0).hypot(c2.1 - c1.1)) / (size - 7.0);
+        let (ax, ay) = (
+            aff[0] * src[2].0 + aff[1] * src[2].1 + aff[2],
+            aff[3] * src[2].0 + aff[4] * src[2].1 + aff[5],
+        );
+        if (ax - ca.0).hypot(ay - ca.1) <= AFFINE_MAX_DEVIATION * mod_w {
+            initial_h = aff;
+        }
+    }
 
-    jiggle_homography(img, initial_h, ver)
+    // Refinement only nudges the coefficients, but recheck so a fold can't slip through
+    let h = jiggle_homography(img, initial_h, ver).ok_or(QRError::SymbolNotFound)?;
+    h.check_conditioning(&src)?;
+    Ok(h)
 }
 
 // Adjust the homography slightly to refine projection of qr
 fn jiggle_homography(img: &BinaryImage, mut h: Homography, ver: Version) -> Option<Homography> {
     let mut best = symbol_fitness(img, &h, ver);
 
-    // Create an adjustment matrix by scaling the homography
+    // Create an adjustment matrix by scaling the homography. Coefficients at zero get no steps,
+    // so affine maps stay free of perspective
     let mut adjustments = h.0.map(|x| x * 0.04);
 
     for _pass in 0..6 {
@@ -639,14 +1248,43 @@ fn cell_fitness(img: &BinaryImage, hm: &Homography, x: i32, y: i32) -> i32 {
     score
 }
 
+// Most salient symbols first, keeping pages in order
+pub(crate) fn sort_by_saliency(symbols: &mut [Symbol]) {
+    symbols.sort_by(|a, b| a.page.cmp(&b.page).then(b.saliency.total_cmp(&a.saliency)));
+}
+
+// Orders symbols by page, then top left to bottom right. Symbols with centres within half a symbol
+// of the first symbol of a row are read as one row, left to right
+pub(crate) fn sort_reading_order(symbols: &mut [Symbol]) {
+    let centre = |s: &Symbol| s.centre().unwrap_or(s._anchors[0]);
+    symbols.sort_by_cached_key(|s| {
+        let c = centre(s);
+        (s.page, c.y, c.x)
+    });
+
+    let mut start = 0;
+    while start < symbols.len() {
+        let (page, top) = (symbols[start].page, centre(&symbols[start]).y);
+        let band = symbols[start].extent().unwrap_or(0.0) / 2.0;
+        let end = symbols[start..]
+            .iter()
+            .position(|s| s.page != page || (centre(s).y - top) as f64 > band)
+            .map_or(symbols.len(), |i| start + i);
+        symbols[start..end].sort_by_cached_key(|s| centre(s).x);
+        start = end;
+    }
+}
+
 #[cfg(test)]
 mod symbol_tests {
 
+    use super::snap_to_size;
     use crate::{
         reader::{
             binarize::BinaryImage,
             finder::{group_finders, locate_finders},
             locate_symbols,
+            utils::homography::Homography,
         },
         ECLevel, MaskPattern, QRBuilder, Version,
     };
@@ -657,26 +1295,63 @@ mod symbol_tests {
         let ver = Version::Normal(4);
         let ecl = ECLevel::L;
         let mask = MaskPattern::new(1);
-        let hi_cap = false;
 
-        let qr = QRBuilder::new(data.as_bytes())
-            .version(ver)
-            .ec_level(ecl)
-            .high_capacity(hi_cap)
-            .mask(mask)
-            .build()
-            .unwrap();
+        let qr =
+            QRBuilder::new(data.as_bytes()).version(ver).ec_level(ecl).mask(mask).build().unwrap();
 
         let img = qr.to_image(10);
         let exp_anchors = [(75, 75), (335, 75), (305, 305), (75, 335)];
 
         let mut img = BinaryImage::prepare(&img);
-        let finders = locate_finders(&mut img);
-        let groups = group_finders(&finders);
-        let symbols = locate_symbols(&mut img, groups);
+        let finders = locate_finders(&mut img, &Default::default());
+        let groups = group_finders(&img, &finders);
+        let (symbols, _) = locate_symbols(&mut img, groups, None);
         for b in symbols[0]._anchors {
             assert!(exp_anchors.contains(&(b.x, b.y)), "Symbol not within bounds");
         }
+        assert!(symbols[0].h.is_affine(), "Fronto-parallel symbol has perspective");
+    }
+
+    #[test]
+    fn test_locate_symbol_perspective() {
+        let data = "Hello, world!";
+        let ver = Version::Normal(1);
+        let qr = QRBuilder::new(data.as_bytes()).version(ver).ec_level(ECLevel::L).build().unwrap();
+        let flat = qr.to_image(10);
+
+        // Keystone the symbol, shrinking its bottom right corner towards the centre
+        let size = 21.0 * 10.0;
+        let quad = [(80.0, 60.0), (330.0, 80.0), (270.0, 270.0), (70.0, 320.0)];
+        let src =
+            [(40.0, 40.0), (40.0 + size, 40.0), (40.0 + size, 40.0 + size), (40.0, 40.0 + size)];
+        let warp = Homography::compute(quad, src).unwrap();
+        let img = image::RgbImage::from_fn(400, 400, |x, y| {
+            let pt = warp.map(x as f64 + 0.5, y as f64 + 0.5).unwrap();
+            match flat.get_pixel_checked(pt.x as u32, pt.y as u32) {
+                Some(px) if pt.x >= 0 && pt.y >= 0 => *px,
+                _ => image::Rgb([255, 255, 255]),
+            }
+        });
+
+        let mut img = BinaryImage::prepare(&img);
+        let finders = locate_finders(&mut img, &Default::default());
+        let groups = group_finders(&img, &finders);
+        let (symbols, _) = locate_symbols(&mut img, groups, None);
+        let corners = symbols[0].corners().unwrap();
+        for (c, exp) in corners.iter().zip(quad) {
+            let err = (c.x as f64 - exp.0).hypot(c.y as f64 - exp.1);
+            assert!(err < 6.0, "Corner {c:?} too far from {exp:?}");
+        }
+    }
+
+    #[test]
+    fn test_snap_to_size() {
+        assert_eq!(snap_to_size(8.0 + 13.0), 21);
+        assert_eq!(snap_to_size(27.0 + 13.0), 41);
+        assert_eq!(snap_to_size(28.0 + 13.0), 41);
+        assert_eq!(snap_to_size(29.0 + 13.0), 41);
+        assert_eq!(snap_to_size(30.0 + 13.0), 45);
+        assert_eq!(snap_to_size(0.0), 17);
     }
 }
 
@@ -685,21 +1360,24 @@ mod symbol_tests {
 
 impl Symbol {
     pub fn read_format_info(&self) -> QRResult<(ECLevel, MaskPattern)> {
-        // Parse main format area
-        if let Some(main) = self.get_number(&FORMAT_INFO_COORDS_QR_MAIN) {
-            if let Ok(format) = rectify_info(main, &FORMAT_INFOS_QR, FORMAT_ERROR_CAPACITY) {
-                let format = format ^ FORMAT_MASK;
-                let (ecl, mask) = parse_format_info_qr(format);
-                return Ok((ecl, mask));
-            }
-        }
+        self.read_format().map(|(ecl, mask, _)| (ecl, mask))
+    }
+
+    // Raw format bits of the main & side copies as sampled, before error correction & unmasking.
+    // Lets experimental symbols with format info outside the standard codes be inspected
+    pub fn raw_format_bits(&self) -> (Option<u16>, Option<u16>) {
+        let read = |coords: &[(i32, i32)]| self.get_number(coords).map(|n| n as u16);
+        (read(&FORMAT_INFO_COORDS_QR_MAIN), read(&FORMAT_INFO_COORDS_QR_SIDE))
+    }
 
-        // Parse side format area
-        if let Some(side) = self.get_number(&FORMAT_INFO_COORDS_QR_SIDE) {
-            if let Ok(format) = rectify_info(side, &FORMAT_INFOS_QR, FORMAT_ERROR_CAPACITY) {
-                let format = format ^ FORMAT_MASK;
-                let (ecl, mask) = parse_format_info_qr(format);
-                return Ok((ecl, mask));
+    // Ec level & mask, along with the raw bits of the copy they were read from. The main copy is
+    // tried first, then the side copy
+    fn read_format(&self) -> QRResult<(ECLevel, MaskPattern, u16)> {
+        let (main, side) = self.raw_format_bits();
+        for raw in [main, side].into_iter().flatten() {
+            if let Ok(format) = rectify_info(raw as u32, &FORMAT_INFOS_QR, FORMAT_ERROR_CAPACITY) {
+                let (ecl, mask) = parse_format_info_qr(format ^ FORMAT_MASK);
+                return Ok((ecl, mask, raw));
             }
         }
 
@@ -710,14 +1388,14 @@ impl Symbol {
         // Parse bottom left version area
         if let Some(bl) = self.get_number(&VERSION_INFO_COORDS_BL) {
             if let Ok(v) = rectify_info(bl, &VERSION_INFOS, VERSION_ERROR_CAPACITY) {
-                return Ok(Version::Normal(v as usize >> VERSION_ERROR_BIT_LEN));
+                return capped_version(v as usize >> VERSION_ERROR_BIT_LEN);
             }
         }
 
         // Parse top right version area
         if let Some(tr) = self.get_number(&VERSION_INFO_COORDS_TR) {
             if let Ok(v) = rectify_info(tr, &VERSION_INFOS, VERSION_ERROR_CAPACITY) {
-                return Ok(Version::Normal(v as usize >> VERSION_ERROR_BIT_LEN));
+                return capped_version(v as usize >> VERSION_ERROR_BIT_LEN);
             }
         }
 
@@ -725,9 +1403,7 @@ impl Symbol {
     }
 
     pub fn read_capacity_info(&self) -> QRResult<bool> {
-        if let Some(px) = self.get(8, -8) {
-            let color = px.get_color();
-
+        if let Some(color) = self.sample(8, -8) {
             if color == Color::Black {
                 return Ok(false); // Standard capacity
             } else {
@@ -741,7 +1417,7 @@ impl Symbol {
     pub fn get_number(&self, coords: &[(i32, i32)]) -> Option<u32> {
         let mut num = 0;
         for &(x, y) in coords {
-            let color = self.get(x, y)?.get_color();
+            let color = self.sample(x, y)?;
             let bit = (color != Color::White) as u32;
             num = (num << 1) | bit;
         }
@@ -753,7 +1429,8 @@ impl Symbol {
 mod symbol_infos_tests {
 
     use crate::{
-        metadata::Color, reader::detect_qr, ECLevel, MaskPattern, Module, QRBuilder, Version,
+        metadata::Color, reader::detect_qr, utils::EncRegionIter, ECLevel, MaskPattern, Module,
+        QRBuilder, Version,
     };
 
     #[test]
@@ -767,7 +1444,7 @@ mod symbol_infos_tests {
             QRBuilder::new(data.as_bytes()).version(ver).ec_level(ecl).mask(mask).build().unwrap();
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let fmt_info = res.symbols()[0].read_format_info().expect("Failed to read format info");
         assert_eq!(fmt_info, (ecl, mask));
@@ -787,7 +1464,7 @@ mod symbol_infos_tests {
         qr.set(4, 8, Module::Format(Color::Black));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let fmt_info = res.symbols()[0].read_format_info().expect("Failed to read format info");
         assert_eq!(fmt_info, (ecl, mask));
@@ -808,7 +1485,7 @@ mod symbol_infos_tests {
         qr.set(4, 8, Module::Format(Color::Black));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let fmt_info = res.symbols()[0].read_format_info().expect("Failed to read format info");
         assert_eq!(fmt_info, (ecl, mask));
@@ -834,11 +1511,12 @@ mod symbol_infos_tests {
         qr.set(8, -5, Module::Format(Color::Black));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let _ = res.symbols()[0].read_format_info().expect("Failed to read format info");
     }
 
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_read_version_info() {
         let data = "Hello, world! 🌎";
@@ -848,12 +1526,29 @@ mod symbol_infos_tests {
         let qr = QRBuilder::new(data.as_bytes()).version(ver).ec_level(ecl).build().unwrap();
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let scanned_ver = res.symbols()[0].read_version_info().expect("Failed to read format info");
         assert_eq!(scanned_ver, ver);
     }
 
+    #[cfg(not(feature = "max-version-5"))]
+    #[test]
+    fn test_decode_keeps_estimated_version() {
+        let data = "Hello, world! 🌎";
+        let ver = Version::Normal(9);
+        let qr = QRBuilder::new(data.as_bytes()).version(ver).ec_level(ECLevel::L).build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+
+        let res = detect_qr(&img);
+        let sym = &res.symbols()[0];
+        let est = sym.ver;
+        let (meta, msg) = sym.decode().expect("Failed to decode");
+        assert_eq!((meta.version(), msg.as_str()), (Some(ver), data));
+        assert_eq!(sym.ver, est);
+    }
+
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_read_version_info_one_corrupted() {
         let data = "Hello, world! 🌎";
@@ -866,12 +1561,13 @@ mod symbol_infos_tests {
         qr.set(5, -11, Module::Format(Color::Black));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let scanned_ver = res.symbols()[0].read_version_info().expect("Failed to read format info");
         assert_eq!(scanned_ver, ver);
     }
 
+    #[cfg(not(feature = "max-version-5"))]
     #[test]
     fn test_read_version_info_one_fully_corrupted() {
         let data = "Hello, world! 🌎";
@@ -885,7 +1581,7 @@ mod symbol_infos_tests {
         qr.set(4, -9, Module::Format(Color::White));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let scanned_ver = res.symbols()[0].read_version_info().expect("Failed to read format info");
         assert_eq!(scanned_ver, ver);
@@ -909,10 +1605,30 @@ mod symbol_infos_tests {
         qr.set(-9, 4, Module::Format(Color::White));
         let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
 
-        let mut res = detect_qr(&img);
+        let res = detect_qr(&img);
 
         let _ = res.symbols()[0].read_version_info().expect("Failed to read format info");
     }
+
+    #[test]
+    fn test_read_payload_erasures() {
+        let qr = QRBuilder::new(b"Hello, world!").version(Version::Normal(2)).build().unwrap();
+        let img = image::DynamicImage::ImageRgb8(qr.to_image(3));
+        let res = detect_qr(&img);
+        let sym = &res.symbols()[0];
+        let (_, mask, _) = sym.read_format().unwrap();
+        assert_eq!(sym.read_payload(&mask).1, None);
+
+        // Red channel shifted past the quiet zone, so only the rightmost column leaves the image
+        sym.offsets.set([(14, 0), (0, 0), (0, 0)]).unwrap();
+        let w = sym.ver.width() as i32;
+        let chan_bits = sym.ver.channel_codewords() << 3;
+        let mut exp = vec![false; chan_bits >> 3];
+        for (i, (x, _)) in EncRegionIter::new(sym.ver).take(chan_bits).enumerate() {
+            exp[i >> 3] |= x == w - 1;
+        }
+        assert_eq!(sym.read_payload(&mask).1, Some(exp));
+    }
 }
 
 // Extracts encoded data codewords and error correction codewords
@@ -920,17 +1636,30 @@ mod symbol_infos_tests {
 
 impl Symbol {
     pub fn extract_payload(&self, mask: &MaskPattern) -> QRResult<BitArray> {
+        Ok(self.read_payload(mask).0)
+    }
+
+    // Samples the payload & flags codewords of a channel with a module that couldn't be sampled,
+    // e.g. outside the image, or under glare. Flags are None if no codeword is erased
+    fn read_payload(&self, mask: &MaskPattern) -> (BitArray, Option<Vec<bool>>) {
         let ver = self.ver;
         let mask_fn = mask.mask_functions();
         let chan_bits = ver.channel_codewords() << 3;
         let offsets = [2 * chan_bits, chan_bits, 0]; // B, G, R offsets
         let mut payload = BitArray::new(chan_bits * 3);
+        let mut erased = vec![false; chan_bits >> 3];
         let mut rgn_iter = EncRegionIter::new(ver);
 
         for (i, (x, y)) in rgn_iter.by_ref().take(chan_bits).enumerate() {
-            let px = self.get(x, y).ok_or(QRError::PixelOutOfBounds)?;
-            let color = px.get_color();
-            let rgb = color as u8;
+            let (xp, yp) = self.wrap_coord(x, y);
+            let under_glare = self.glare.as_ref().is_some_and(|g| {
+                self.map(xp as f64 + 0.5, yp as f64 + 0.5).map_or(true, |pt| g.contains(&pt))
+            });
+
+            // Erased modules are corrected from ecc, so any color will do
+            let color = self.sample(x, y);
+            erased[i >> 3] |= color.is_none() || under_glare;
+            let rgb = color.unwrap_or(Color::White) as u8;
             for (j, off) in offsets.iter().enumerate() {
                 let mut bit = ((rgb >> j) & 1) == 1;
                 if !mask_fn(x, y) {
@@ -940,72 +1669,72 @@ impl Symbol {
             }
         }
 
+        // Remainder bits are never sampled, so their colour doesn't matter
         debug_assert_eq!(rgn_iter.count(), self.ver.remainder_bits(), "Remainder bits don't match");
 
-        Ok(payload)
+        (payload, erased.contains(&true).then_some(erased))
     }
 }
 
-fn deinterleave(data: &[u8], blk_info: (usize, usize, usize, usize), ec_len: usize) -> Vec<Block> {
-    // b1s = block1_size, b1c = block1_count
-    let (b1s, b1c, b2s, b2c) = blk_info;
+// Rejects versions the block buffers & bit streams weren't sized for
+fn capped_version(v: usize) -> QRResult<Version> {
+    match v {
+        1..=MAX_VERSION => Ok(Version::Normal(v)),
+        _ => Err(QRError::InvalidVersion),
+    }
+}
 
-    let total_blks = b1c + b2c;
-    let spl = b1s * total_blks;
-    let data_sz = b1s * b1c + b2s * b2c;
+// Codewords of a symbol after error correction, with the info needed to interpret them
+struct Codewords {
+    ver: Version,
+    ecl: ECLevel,
+    mask: MaskPattern,
+    hi_cap: bool,
+    raw_format: u16,
+    data: BitStream, // Data codewords of all blocks in order, i.e. the encoded data
+    interleaved: BitStream, // Data & ecc codewords of each channel as laid out in the symbol
+    usage: Option<ECUsage>,
+}
 
-    let mut dilvd = vec![Vec::with_capacity(b2s); total_blks];
+// Global constants
+//------------------------------------------------------------------------------
 
-    // Deinterleaving data
-    data[..spl]
-        .chunks(total_blks)
-        .for_each(|ch| ch.iter().enumerate().for_each(|(i, v)| dilvd[i].push(*v)));
-    if b2c > 0 {
-        data[spl..data_sz]
-            .chunks(b2c)
-            .for_each(|ch| ch.iter().enumerate().for_each(|(i, v)| dilvd[b1c + i].push(*v)));
-    }
+pub const SYMBOL_HEURICTIC_THRESHOLD: f64 = 0.5;
 
-    // Deinterleaving ecc
-    data[data_sz..]
-        .chunks(total_blks)
-        .for_each(|ch| ch.iter().enumerate().for_each(|(i, v)| dilvd[i].push(*v)));
+// Sample points within a module for area sampling, along each axis
+const AREA_SAMPLE_OFFSETS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
 
-    let mut blks: Vec<Block> = Vec::with_capacity(256);
-    dilvd.iter().for_each(|b| blks.push(Block::with_encoded(b, b.len() - ec_len)));
-    blks
-}
+// Gaussian weights of the sample points with sigma of 0.25 module
+const AREA_SAMPLE_WEIGHTS: [f64; 5] = [0.278, 0.726, 1.0, 0.726, 0.278];
 
-#[cfg(test)]
-mod reader_tests {
+// Max distance in modules between the alignment centre & where an affine map from the finders
+// places it, for the affine map to be used
+const AFFINE_MAX_DEVIATION: f64 = 0.25;
 
-    use crate::{
-        builder::QRBuilder,
-        metadata::{ECLevel, Version},
-        reader::symbol::deinterleave,
-        utils::BitStream,
-    };
+// Max distance of the corner from where the finder centres place it, relative to the distance
+// between the tl & tr finders
+const CORNER_MAX_DEVIATION: f64 = 0.5;
 
-    #[test]
-    fn test_deinterleave() {
-        // Data length has to match version capacity
-        let data = "Hello, world!!!🌍".as_bytes();
-        let ver = Version::Normal(1);
-        let ecl = ECLevel::L;
+// Min distance in modules of the corner from where the finder centres place it, for the
+// perspective it reveals to outweigh the bias of the traced edges
+const CORNER_MIN_PERSPECTIVE: f64 = 1.0;
 
-        let exp_blks = QRBuilder::blockify(data, ver, ecl);
+// Min sine of the angle between the outer edges for their intersection to be taken
+const MIN_EDGE_SINE: f64 = 0.2;
 
-        let mut bs = BitStream::new(ver.total_codewords(false) << 3);
-        QRBuilder::interleave_into(&exp_blks, &mut bs);
+// Centre & the points around it within a module for vote sampling
+const VOTE_SAMPLE_OFFSETS: [(f64, f64); 5] =
+    [(0.5, 0.5), (0.2, 0.5), (0.8, 0.5), (0.5, 0.2), (0.5, 0.8)];
 
-        let blk_info = ver.data_codewords_per_block(ecl);
-        let ec_len = ver.ecc_per_block(ecl);
-        let blks = deinterleave(bs.data(), blk_info, ec_len);
-        assert_eq!(blks, exp_blks);
-    }
-}
+// FNV-1a parameters for symbol ids, which must not change across runs or platforms
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
 
-// Global constants
-//------------------------------------------------------------------------------
+// Largest misregistration of a colour channel in pixels searched for in high capacity symbols
+const MAX_CHANNEL_SHIFT: i32 = 2;
 
-pub const SYMBOL_HEURICTIC_THRESHOLD: f64 = 0.5;
+// Spacing in modules of the points sampled over the finders when estimating channel offsets
+const FINDER_SAMPLE_STEP: f64 = 0.25;
+
+// Centres of the red, green & blue quadrants of a dithered module
+const DITHER_QUADRANT_CENTRES: [(f64, f64); 3] = [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75)];
@@ -0,0 +1,192 @@
+use super::DecodeResult;
+use crate::utils::QRResult;
+
+// Payload threats
+//------------------------------------------------------------------------------
+
+// Content a scanning app should warn about before acting on a payload. Flags are heuristics, so a
+// flagged payload isn't necessarily malicious, only worth a second look from the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Threat {
+    ScriptScheme,    // javascript: or vbscript: uri, which runs code when opened
+    DataScheme,      // data: uri, which can carry a whole page posing as another site
+    PunycodeHost,    // Host with an xn-- label, i.e. an encoded international domain
+    HomographHost,   // Host label spoofing a latin one with cyrillic or greek lookalikes
+    ExcessiveLength, // Longer than any legitimate link or text a user would check by eye
+    ControlChars,    // Control, bidi override or zero width chars that hide the real text
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatReport {
+    threats: Vec<Threat>,
+    host: Option<String>,
+}
+
+impl ThreatReport {
+    // Flags are in the order of Threat, each at most once
+    pub fn scan(payload: &str) -> Self {
+        let mut threats = Vec::new();
+        let host = url_host(payload);
+
+        match scheme(payload).as_deref() {
+            Some("javascript" | "vbscript") => threats.push(Threat::ScriptScheme),
+            Some("data") => threats.push(Threat::DataScheme),
+            _ => (),
+        }
+        if let Some(host) = &host {
+            let labels = || host.split('.');
+            if labels().any(|l| l.get(..4).is_some_and(|p| p.eq_ignore_ascii_case("xn--"))) {
+                threats.push(Threat::PunycodeHost);
+            }
+            if labels().any(is_homograph) {
+                threats.push(Threat::HomographHost);
+            }
+        }
+        if payload.chars().count() > MAX_PAYLOAD_CHARS {
+            threats.push(Threat::ExcessiveLength);
+        }
+        if payload.chars().any(is_hidden_char) {
+            threats.push(Threat::ControlChars);
+        }
+
+        Self { threats, host }
+    }
+
+    pub fn threats(&self) -> &[Threat] {
+        &self.threats
+    }
+
+    pub fn contains(&self, threat: Threat) -> bool {
+        self.threats.contains(&threat)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.threats.is_empty()
+    }
+
+    // Host of the url, without user info & port, for showing the user where a link really leads
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+}
+
+impl DecodeResult {
+    // Threat report of every symbol, in symbol order. Symbols that fail to decode keep their error
+    pub fn threats(&self) -> Vec<QRResult<ThreatReport>> {
+        let res = self.decode_all().into_iter();
+        res.map(|r| r.map(|(_, msg)| ThreatReport::scan(&msg))).collect()
+    }
+}
+
+// Payload helpers
+//------------------------------------------------------------------------------
+
+// Lowercase scheme the way browsers read it, i.e. ignoring leading whitespace & control chars,
+// and tabs & newlines within
+fn scheme(payload: &str) -> Option<String> {
+    let payload = payload.trim_start_matches(|c: char| c.is_whitespace() || c.is_control());
+    let (scheme, _) = payload.split_once(':')?;
+    let scheme: String = scheme.chars().filter(|c| !matches!(c, '\t' | '\n' | '\r')).collect();
+    let is_valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    is_valid.then(|| scheme.to_ascii_lowercase())
+}
+
+// Host of a scheme://authority url, without user info & port
+fn url_host(payload: &str) -> Option<String> {
+    scheme(payload)?;
+    let (_, rest) = payload.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+// Label with latin letters next to cyrillic or greek ones, or one spelled entirely in cyrillic
+// or greek letters that look latin, e.g. "аррӏе" for "apple"
+fn is_homograph(label: &str) -> bool {
+    let is_latin = |c: char| c.is_ascii_alphabetic() || ('\u{c0}'..='\u{24f}').contains(&c);
+    let is_lookalike_script = |c: char| ('\u{370}'..='\u{52f}').contains(&c);
+    if !label.chars().any(is_lookalike_script) {
+        return false;
+    }
+    label.chars().any(is_latin)
+        || label.chars().filter(|c| c.is_alphabetic()).all(|c| CONFUSABLES.contains(c))
+}
+
+// Control chars other than line breaks & tabs, bidi overrides that reorder the displayed text,
+// and zero width chars that split words without showing
+fn is_hidden_char(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\t' | '\n' | '\r')) || HIDDEN_CHARS.contains(c)
+}
+
+#[cfg(test)]
+mod threat_tests {
+    use super::{Threat, ThreatReport};
+    use crate::{builder::QRBuilder, reader::detect_qr};
+
+    #[test]
+    fn test_threat_schemes() {
+        let report = ThreatReport::scan(" Java\tScript:alert(1)");
+        assert_eq!(report.threats(), [Threat::ScriptScheme]);
+        let report = ThreatReport::scan("data:text/html;base64,PHNjcmlwdD4=");
+        assert_eq!(report.threats(), [Threat::DataScheme]);
+
+        let report = ThreatReport::scan("https://user@example.com:8443/path?q=javascript:");
+        assert!(report.is_clean());
+        assert_eq!(report.host(), Some("example.com"));
+        assert_eq!(ThreatReport::scan("[::1]").host(), None);
+        assert_eq!(ThreatReport::scan("http://[::1]:80/").host(), Some("::1"));
+    }
+
+    #[test]
+    fn test_threat_hosts() {
+        let report = ThreatReport::scan("https://xn--pple-43d.com/login");
+        assert_eq!(report.threats(), [Threat::PunycodeHost]);
+        assert!(ThreatReport::scan("https://\u{430}pple.com").contains(Threat::HomographHost));
+        let all_cyrillic = "https://\u{430}\u{440}\u{440}\u{4cf}\u{435}.com";
+        assert!(ThreatReport::scan(all_cyrillic).contains(Threat::HomographHost));
+
+        // Plain cyrillic & accented latin hosts are legitimate
+        assert!(
+            ThreatReport::scan("https://\u{43f}\u{440}\u{438}\u{43c}\u{435}\u{440}.ru").is_clean()
+        );
+        assert!(ThreatReport::scan("https://caf\u{e9}.fr").is_clean());
+    }
+
+    #[test]
+    fn test_threat_text() {
+        let report = ThreatReport::scan("Invoice \u{202e}fdp.exe");
+        assert_eq!(report.threats(), [Threat::ControlChars]);
+        assert!(ThreatReport::scan("Line 1\r\nLine 2\tend").is_clean());
+        assert!(ThreatReport::scan(&"a".repeat(2049)).contains(Threat::ExcessiveLength));
+        assert!(ThreatReport::scan(&"a".repeat(2048)).is_clean());
+    }
+
+    #[test]
+    fn test_decode_result_threats() {
+        let qr = QRBuilder::new(b"javascript:alert(1)").build().unwrap();
+        let res = detect_qr(&image::DynamicImage::ImageRgb8(qr.to_image(3)));
+        let reports = res.threats();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].as_ref().unwrap().threats(), [Threat::ScriptScheme]);
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Above the ~2000 chars browsers & link previews handle reliably
+const MAX_PAYLOAD_CHARS: usize = 2048;
+
+// Cyrillic & greek letters that render like latin ones in common fonts
+const CONFUSABLES: &str = "\u{430}\u{432}\u{435}\u{43a}\u{43c}\u{43d}\u{43e}\u{440}\u{441}\u{442}\
+    \u{443}\u{445}\u{455}\u{456}\u{458}\u{4bb}\u{4cf}\u{501}\u{51b}\u{51d}\u{3b1}\u{3b9}\u{3ba}\
+    \u{3bd}\u{3bf}\u{3c1}\u{3c4}\u{3c5}\u{3c7}";
+
+// Zero width chars, bidi embeddings, overrides & isolates, and the byte order mark
+const HIDDEN_CHARS: &str = "\u{200b}\u{200c}\u{200d}\u{200e}\u{200f}\u{202a}\u{202b}\u{202c}\
+    \u{202d}\u{202e}\u{2066}\u{2067}\u{2068}\u{2069}\u{feff}";
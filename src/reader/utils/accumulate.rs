@@ -71,3 +71,74 @@ impl Accumulator for AreaAndCentreLocator {
         self.area += width;
     }
 }
+
+// Chord midpoint line fit
+// Midpoints of parallel chords across a centrally symmetric shape lie on a line through its
+// centre, even when the shape is rotated or sheared. Each scanline votes with the midpoint it
+// measured, weighted by how well its runs fit the pattern. Like a Hough transform, votes far off
+// the consensus line are dropped before refitting, so lines clipped by noise don't drag it
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default)]
+pub struct MidpointLine {
+    votes: Vec<(f64, f64, f64)>, // Offset across the chords, midpoint along them & weight
+}
+
+impl MidpointLine {
+    pub fn new() -> Self {
+        Self { votes: Vec::with_capacity(32) }
+    }
+
+    pub fn vote(&mut self, offset: f64, mid: f64, weight: f64) {
+        if weight > 0.0 {
+            self.votes.push((offset, mid, weight));
+        }
+    }
+
+    // Returns (a, b) of the line mid = a + b * offset. Votes further than the radius from the
+    // first fit are dropped from the second
+    pub fn fit(&self, radius: f64) -> Option<(f64, f64)> {
+        let (a, b) = Self::fit_votes(self.votes.iter())?;
+        let inliers = self.votes.iter().filter(|(t, m, _)| (m - a - b * t).abs() <= radius);
+        Self::fit_votes(inliers).or(Some((a, b)))
+    }
+
+    // Weighted least squares. A single offset can't give the slope, so the line is taken as
+    // parallel to the offset axis
+    fn fit_votes<'a>(
+        votes: impl Iterator<Item = &'a (f64, f64, f64)> + Clone,
+    ) -> Option<(f64, f64)> {
+        let total = votes.clone().map(|v| v.2).sum::<f64>();
+        if total <= 0.0 {
+            return None;
+        }
+        let mean_t = votes.clone().map(|(t, _, w)| t * w).sum::<f64>() / total;
+        let mean_m = votes.clone().map(|(_, m, w)| m * w).sum::<f64>() / total;
+        let var_t = votes.clone().map(|(t, _, w)| w * (t - mean_t).powi(2)).sum::<f64>();
+        let cov = votes.map(|(t, m, w)| w * (t - mean_t) * (m - mean_m)).sum::<f64>();
+
+        let b = if var_t > f64::EPSILON { cov / var_t } else { 0.0 };
+        Some((mean_m - b * mean_t, b))
+    }
+}
+
+#[cfg(test)]
+mod accumulate_tests {
+    use super::MidpointLine;
+
+    #[test]
+    fn test_midpoint_line_fit() {
+        let mut line = MidpointLine::new();
+        assert_eq!(line.fit(1.0), None);
+
+        // Midpoints of a shape sheared by 0.5, centred at 10
+        for t in -3..=3 {
+            line.vote(t as f64, 10.0 + 0.5 * t as f64, 1.0);
+        }
+        line.vote(1.0, 25.0, 1.0); // Outlier from a clipped line
+        line.vote(2.0, 40.0, 0.0);
+
+        let (a, b) = line.fit(1.0).unwrap();
+        assert!((a - 10.0).abs() < 1e-9 && (b - 0.5).abs() < 1e-9, "{a} {b}");
+    }
+}
@@ -27,10 +27,17 @@ impl IndexMut<usize> for Homography {
 }
 
 impl Homography {
-    /// Compute homography matrix from 4 point pairs:
-    /// source[i] -> destination[i]
-    /// Returns homography matrix to project points from logical QR to image QR
+    // Compute homography matrix from 4 point pairs:
+    // source[i] -> destination[i]
+    // Returns homography matrix to project points from logical QR to image QR
+    // Rejects non finite points, collinear or folded destination quads & projections too
+    // skewed to sample, so degenerate finder groups fail before they reach the sampler
     pub fn compute(src: [(f64, f64); 4], dst: [(f64, f64); 4]) -> QRResult<Self> {
+        if src.iter().chain(dst.iter()).any(|(x, y)| !x.is_finite() || !y.is_finite()) {
+            return Err(QRError::NonFiniteGeometry);
+        }
+        check_quad(&dst)?;
+
         // Build matrix A (8x8) & B (8x1)
         // Rows: 2 per point, total 8 rows
         // Columns: 9 columns (h11..h33)
@@ -64,12 +71,81 @@ impl Homography {
             b[2 * i + 1] = -yp;
         }
 
-        let h = Self::solve_linear_system(a, b)?;
+        let h = Self(Self::solve_linear_system(a, b)?);
+        h.check_conditioning(&src)?;
+
+        Ok(h)
+    }
+
+    // Compute affine matrix, i.e. a homography without perspective, from 3 point pairs.
+    // Fails if either triangle is degenerate
+    pub fn affine(src: [(f64, f64); 3], dst: [(f64, f64); 3]) -> QRResult<Self> {
+        let (x0, y0) = src[0];
+        let (u1, u2) = ((src[1].0 - x0, src[1].1 - y0), (src[2].0 - x0, src[2].1 - y0));
+        let (v1, v2) = (
+            (dst[1].0 - dst[0].0, dst[1].1 - dst[0].1),
+            (dst[2].0 - dst[0].0, dst[2].1 - dst[0].1),
+        );
+
+        let det = u1.0 * u2.1 - u2.0 * u1.1;
+        if !det.is_finite() || det.abs() < f64::EPSILON {
+            return Err(QRError::SingularMatrix);
+        }
+
+        let a = (v1.0 * u2.1 - v2.0 * u1.1) / det;
+        let b = (v2.0 * u1.0 - v1.0 * u2.0) / det;
+        let d = (v1.1 * u2.1 - v2.1 * u1.1) / det;
+        let e = (v2.1 * u1.0 - v1.1 * u2.0) / det;
+        let (c, f) = (dst[0].0 - a * x0 - b * y0, dst[0].1 - d * x0 - e * y0);
+        if [a, b, c, d, e, f].iter().any(|v| !v.is_finite()) || a * e - b * d == 0.0 {
+            return Err(QRError::NonFiniteGeometry);
+        }
 
-        Ok(Self(h))
+        Ok(Self([a, b, c, d, e, f, 0.0, 0.0]))
     }
 
-    /// Solve 8x8 linear system Ax = b by Gaussian elimination
+    #[inline]
+    pub fn is_affine(&self) -> bool {
+        self[6] == 0.0 && self[7] == 0.0
+    }
+
+    // Checks the local 2x2 jacobian at each source point. The projection must not flip
+    // orientation between points, and the ratio of its singular values, i.e. its condition
+    // number, must stay below the limit
+    pub fn check_conditioning(&self, src: &[(f64, f64); 4]) -> QRResult<()> {
+        if self.0.iter().any(|v| !v.is_finite()) {
+            return Err(QRError::NonFiniteGeometry);
+        }
+
+        let mut orientation = 0.0;
+        for &(x, y) in src {
+            let w = self[6] * x + self[7] * y + 1.0;
+            if w.abs() <= f64::EPSILON {
+                return Err(QRError::PointAtInfinity);
+            }
+            let u = (self[0] * x + self[1] * y + self[2]) / w;
+            let v = (self[3] * x + self[4] * y + self[5]) / w;
+            let (a, b) = ((self[0] - u * self[6]) / w, (self[1] - u * self[7]) / w);
+            let (c, d) = ((self[3] - v * self[6]) / w, (self[4] - v * self[7]) / w);
+
+            let det = a * d - b * c;
+            if det == 0.0 || det * orientation < 0.0 {
+                return Err(QRError::DegenerateQuad);
+            }
+            orientation = det;
+
+            // Singular values of the jacobian from the eigenvalues of J^T J
+            let sq_sum = a * a + b * b + c * c + d * d;
+            let disc = (sq_sum * sq_sum - 4.0 * det * det).max(0.0).sqrt();
+            let (s_max, s_min) = (((sq_sum + disc) / 2.0).sqrt(), ((sq_sum - disc) / 2.0).sqrt());
+            if s_min <= 0.0 || s_max / s_min > MAX_HOMOGRAPHY_CONDITION {
+                return Err(QRError::IllConditionedHomography);
+            }
+        }
+        Ok(())
+    }
+
+    // Solve 8x8 linear system Ax = b by Gaussian elimination
     fn solve_linear_system(mut a: [[f64; 8]; 8], mut b: [f64; 8]) -> QRResult<[f64; 8]> {
         // Forward elimination
         for i in 0..8 {
@@ -95,6 +171,7 @@ impl Homography {
 
             // Normalize row
             let pivot = a[i][i];
+            #[allow(clippy::needless_range_loop)]
             for c in i..8 {
                 a[i][c] /= pivot;
             }
@@ -103,6 +180,7 @@ impl Homography {
             // Eliminate other rows
             for r in (i + 1)..8 {
                 let factor = a[r][i];
+                #[allow(clippy::needless_range_loop)]
                 for c in i..8 {
                     a[r][c] -= factor * a[i][c];
                 }
@@ -123,7 +201,7 @@ impl Homography {
         Ok(x)
     }
 
-    /// Map a point (x,y) using homography H (3x3)
+    // Map a point (x,y) using homography H (3x3)
     pub fn map(&self, x: f64, y: f64) -> QRResult<Point> {
         let xp = self[0] * x + self[1] * y + self[2];
         let yp = self[3] * x + self[4] * y + self[5];
@@ -142,6 +220,22 @@ impl Homography {
         Ok(Point { x, y })
     }
 
+    // Homography whose output is scaled by k & then shifted by (tx, ty), e.g. to project onto
+    // a resized crop of the image
+    pub fn then_scale(&self, k: f64, tx: f64, ty: f64) -> Self {
+        let h = &self.0;
+        Self([
+            k * h[0] + tx * h[6],
+            k * h[1] + tx * h[7],
+            k * h[2] + tx,
+            k * h[3] + ty * h[6],
+            k * h[4] + ty * h[7],
+            k * h[5] + ty,
+            h[6],
+            h[7],
+        ])
+    }
+
     #[cfg(feature = "benchmark")]
     pub fn raw_map(&self, x: f64, y: f64) -> QRResult<(f64, f64)> {
         let xp = self[0] * x + self[1] * y + self[2];
@@ -156,9 +250,31 @@ impl Homography {
     }
 }
 
+// Every triangle of the quad must enclose a fair area relative to the quad size, and all corners
+// must turn the same way
+fn check_quad(pts: &[(f64, f64); 4]) -> QRResult<()> {
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let scale = (0..4)
+        .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+        .map(|(i, j)| (pts[i].0 - pts[j].0).powi(2) + (pts[i].1 - pts[j].1).powi(2))
+        .fold(0.0, f64::max);
+
+    let turns = (0..4).map(|i| cross(pts[i], pts[(i + 1) & 3], pts[(i + 2) & 3]));
+    let mut sign = 0.0;
+    for t in turns {
+        if t.abs() < MIN_QUAD_AREA_RATIO * scale || t * sign < 0.0 {
+            return Err(QRError::DegenerateQuad);
+        }
+        sign = t;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod homography_tests {
-    use crate::reader::utils::geometry::Point;
+    use crate::{reader::utils::geometry::Point, utils::QRError};
 
     use super::Homography;
 
@@ -175,4 +291,58 @@ mod homography_tests {
             assert_eq!(proj_pt, exp_pt);
         }
     }
+
+    #[test]
+    fn test_then_scale() {
+        let src = [(3.5, 3.5), (21.5, 3.5), (18.5, 18.5), (3.5, 21.5)];
+        let dst = [(75.0, 75.0), (255.0, 75.0), (225.0, 225.0), (75.0, 255.0)];
+        let h = Homography::compute(src, dst).unwrap();
+        let scaled = h.then_scale(4.0, -200.0, 10.0);
+        for (x, y) in [(7.0, 7.0), (25.0, 0.0), (25.0, 25.0), (0.0, 25.0)] {
+            let (pt, sp) = (h.map(x, y).unwrap(), scaled.map(x, y).unwrap());
+            assert!((sp.x - (pt.x * 4 - 200)).abs() <= 2);
+            assert!((sp.y - (pt.y * 4 + 10)).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_affine() {
+        let src = [(3.5, 3.5), (21.5, 3.5), (3.5, 21.5)];
+        let dst = [(75.0, 85.0), (255.0, 95.0), (65.0, 265.0)];
+        let h = Homography::affine(src, dst).unwrap();
+        assert!(h.is_affine());
+        for (s, d) in src.iter().zip(dst) {
+            assert_eq!(h.map(s.0, s.1).unwrap(), Point { x: d.0 as i32, y: d.1 as i32 });
+        }
+        assert_eq!(h.map(21.5, 21.5).unwrap(), Point { x: 245, y: 275 });
+
+        let collinear = [(3.5, 3.5), (21.5, 3.5), (12.5, 3.5)];
+        assert_eq!(Homography::affine(collinear, dst), Err(QRError::SingularMatrix));
+    }
+
+    #[test]
+    fn test_degenerate_homography() {
+        let src = [(3.5, 3.5), (21.5, 3.5), (18.5, 18.5), (3.5, 21.5)];
+
+        let collinear = [(75.0, 75.0), (255.0, 75.0), (165.0, 75.0), (75.0, 75.5)];
+        assert_eq!(Homography::compute(src, collinear), Err(QRError::DegenerateQuad));
+
+        let folded = [(75.0, 75.0), (255.0, 75.0), (75.0, 255.0), (225.0, 225.0)];
+        assert_eq!(Homography::compute(src, folded), Err(QRError::DegenerateQuad));
+
+        let squashed = [(75.0, 75.0), (2075.0, 75.0), (1775.0, 80.0), (75.0, 85.0)];
+        assert_eq!(Homography::compute(src, squashed), Err(QRError::IllConditionedHomography));
+
+        let nan = [(f64::NAN, 75.0), (255.0, 75.0), (225.0, 225.0), (75.0, 255.0)];
+        assert_eq!(Homography::compute(src, nan), Err(QRError::NonFiniteGeometry));
+    }
 }
+
+// Global constants
+//------------------------------------------------------------------------------
+
+// Min area of each corner triangle of a quad relative to its longest side or diagonal squared
+const MIN_QUAD_AREA_RATIO: f64 = 1e-3;
+
+// Max ratio of the stretch along the most & least stretched directions of the projection
+const MAX_HOMOGRAPHY_CONDITION: f64 = 25.0;
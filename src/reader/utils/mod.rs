@@ -3,32 +3,38 @@ use geometry::{Axis, Point};
 use super::binarize::BinaryImage;
 
 pub mod accumulate;
-pub mod geometry;
+pub use crate::geometry;
 pub mod homography;
 
 // Util functions to verify a pattern along a line. This is used in 2 places; in finder locator
 // to verify 1:1:3:1:1 pattern, and in alignment locator to verify 1:1:1 pattern
 //------------------------------------------------------------------------------
 
+// Walks both ways from the seed across the pattern by the pixel step dir. Returns the steps from
+// the seed to either end of the pattern if its runs match
 pub fn verify_finder_pattern(
     img: &BinaryImage,
     seed: &Point,
+    dir: (i32, i32),
     pattern: &[f64],
     max_run: u32,
 ) -> Option<(u32, u32)> {
     let px = img.get_at_point(seed).unwrap();
     let pat_len = pattern.len();
+    let in_bounds = |p: &Point| 0 <= p.x && p.x < img.w as i32 && 0 <= p.y && p.y < img.h as i32;
 
     let mut run_len = vec![0; pat_len];
     run_len[pat_len / 2] = 1;
 
-    // Count upward
+    // Count backward
     let mut pos = *seed;
     let mut flips = pat_len / 2;
     let mut initial = px.get_color();
+    let mut up = 0u32;
     while run_len[flips] <= max_run {
-        pos.y -= 1;
-        if pos.y < 0 {
+        pos = Point { x: pos.x - dir.0, y: pos.y - dir.1 };
+        up += 1;
+        if !in_bounds(&pos) {
             break;
         }
 
@@ -42,15 +48,17 @@ pub fn verify_finder_pattern(
         }
         run_len[flips] += 1;
     }
-    let top = (pos.y + 1) as u32;
+    let mut up = up.saturating_sub(1);
 
-    // Count downward
+    // Count forward
     let mut pos = *seed;
     let mut flips = pat_len / 2;
     let mut initial = px.get_color();
+    let mut down = 0u32;
     while run_len[flips] <= max_run {
-        pos.y += 1;
-        if img.h == pos.y as u32 {
+        pos = Point { x: pos.x + dir.0, y: pos.y + dir.1 };
+        down += 1;
+        if !in_bounds(&pos) {
             break;
         }
 
@@ -64,7 +72,13 @@ pub fn verify_finder_pattern(
         }
         run_len[flips] += 1;
     }
-    let bottom = (pos.y - 1) as u32;
+    let mut down = down.saturating_sub(1);
+
+    // Ends of a pattern merged into adjacent artwork lie where its outer runs are trimmed to
+    let (first, last) = (run_len[0], run_len[pat_len - 1]);
+    trim_outer_runs(&mut run_len, pattern);
+    up -= first - run_len[0];
+    down -= last - run_len[pat_len - 1];
 
     // Verify pattern with 95% tolerance. This was tuned to pass maximum number of test images
     let avg = run_len.iter().sum::<u32>() as f64 / 7.0;
@@ -77,7 +91,22 @@ pub fn verify_finder_pattern(
         }
     }
 
-    Some((top, bottom))
+    Some((up, down))
+}
+
+// Dark artwork drawn right up to a pattern, violating the quiet zone, merges with its outer runs.
+// Outer runs far longer than the module size measured on the inner runs, which artwork can't
+// reach, are trimmed to their expected length
+pub fn trim_outer_runs(runs: &mut [u32], pattern: &[f64]) {
+    let n = runs.len();
+    let inner = runs[1..n - 1].iter().sum::<u32>() as f64;
+    let mod_sz = inner / pattern[1..n - 1].iter().sum::<f64>();
+    for i in [0, n - 1] {
+        let exp = pattern[i] * mod_sz;
+        if runs[i] as f64 > exp * MERGED_RUN_RATIO {
+            runs[i] = exp.round().max(1.0) as u32;
+        }
+    }
 }
 
 pub fn verify_alignment_pattern<A: Axis>(
@@ -100,7 +129,7 @@ pub fn verify_alignment_pattern<A: Axis>(
     let mut initial = px.get_color();
     while run_len[flips] <= max_run {
         A::shift(&mut pos, &dir);
-        if !A::bound_check(img, &pos) {
+        if !A::bound_check(img.w, img.h, &pos) {
             break;
         }
 
@@ -120,9 +149,9 @@ pub fn verify_alignment_pattern<A: Axis>(
     let dir = (1, 1);
     let mut flips = pat_len / 2;
     let mut initial = px.get_color();
-    while A::bound_check(img, &pos) && run_len[flips] <= max_run {
+    while A::bound_check(img.w, img.h, &pos) && run_len[flips] <= max_run {
         A::shift(&mut pos, &dir);
-        if !A::bound_check(img, &pos) {
+        if !A::bound_check(img.w, img.h, &pos) {
             break;
         }
 
@@ -188,4 +217,7 @@ pub fn rnd_rgb() -> image::Rgb<u8> {
 
 pub const FINDER_PATTERN_TOLERANCE: f64 = 0.95;
 
+// Outer runs longer than this many times their expected length are taken as merged with artwork
+pub const MERGED_RUN_RATIO: f64 = 1.5;
+
 pub const ALIGNMENT_PATTERN_TOLERANCE: f64 = 0.8;
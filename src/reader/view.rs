@@ -0,0 +1,141 @@
+use std::ops::Deref;
+
+use image::{GenericImageView, ImageBuffer, Luma};
+
+// Image view
+//------------------------------------------------------------------------------
+
+// Read only luma access to pixels in external buffers, so the reader can binarize them without
+// first copying into a GrayImage
+pub trait ImageView {
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+
+    fn get_luma(&self, x: u32, y: u32) -> u8;
+}
+
+impl<C: Deref<Target = [u8]>> ImageView for ImageBuffer<Luma<u8>, C> {
+    fn width(&self) -> u32 {
+        self.dimensions().0
+    }
+
+    fn height(&self) -> u32 {
+        self.dimensions().1
+    }
+
+    #[inline]
+    fn get_luma(&self, x: u32, y: u32) -> u8 {
+        self.get_pixel(x, y).0[0]
+    }
+}
+
+// Arrays are indexed by row, so the first axis is the height
+#[cfg(feature = "ndarray")]
+impl ImageView for ndarray::ArrayView2<'_, u8> {
+    fn width(&self) -> u32 {
+        self.ncols() as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.nrows() as u32
+    }
+
+    #[inline]
+    fn get_luma(&self, x: u32, y: u32) -> u8 {
+        self[[y as usize, x as usize]]
+    }
+}
+
+// Luma slice
+//------------------------------------------------------------------------------
+
+// Row major 8 bit luma buffer with an optional row stride for padded or cropped frames
+#[derive(Debug, Clone, Copy)]
+pub struct LumaSlice<'a> {
+    data: &'a [u8],
+    w: u32,
+    h: u32,
+    stride: usize,
+}
+
+impl<'a> LumaSlice<'a> {
+    pub fn new(data: &'a [u8], w: u32, h: u32) -> Option<Self> {
+        Self::with_stride(data, w, h, w as usize)
+    }
+
+    // Returns none if the stride is shorter than a row or the buffer can't hold all rows
+    pub fn with_stride(data: &'a [u8], w: u32, h: u32, stride: usize) -> Option<Self> {
+        if stride < w as usize {
+            return None;
+        }
+        let len = match h {
+            0 => 0,
+            _ => stride * (h as usize - 1) + w as usize,
+        };
+        (data.len() >= len).then_some(Self { data, w, h, stride })
+    }
+}
+
+impl ImageView for LumaSlice<'_> {
+    fn width(&self) -> u32 {
+        self.w
+    }
+
+    fn height(&self) -> u32 {
+        self.h
+    }
+
+    #[inline]
+    fn get_luma(&self, x: u32, y: u32) -> u8 {
+        self.data[y as usize * self.stride + x as usize]
+    }
+}
+
+// Adapter to feed views into the binarizers, which are generic over image-rs views
+pub(crate) struct LumaAdapter<'a, V: ?Sized>(pub &'a V);
+
+impl<V: ImageView + ?Sized> GenericImageView for LumaAdapter<'_, V> {
+    type Pixel = Luma<u8>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.0.width(), self.0.height())
+    }
+
+    #[inline]
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        Luma([self.0.get_luma(x, y)])
+    }
+}
+
+impl<V: ImageView + ?Sized> ImageView for LumaAdapter<'_, V> {
+    fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    #[inline]
+    fn get_luma(&self, x: u32, y: u32) -> u8 {
+        self.0.get_luma(x, y)
+    }
+}
+
+#[cfg(test)]
+mod view_tests {
+    use super::{ImageView, LumaSlice};
+
+    #[test]
+    fn test_luma_slice_stride() {
+        let data = (0..20).collect::<Vec<u8>>();
+        assert!(LumaSlice::with_stride(&data, 4, 4, 3).is_none());
+        assert!(LumaSlice::with_stride(&data, 4, 5, 5).is_none());
+
+        let view = LumaSlice::with_stride(&data, 4, 4, 5).unwrap();
+        assert_eq!((view.width(), view.height()), (4, 4));
+        assert_eq!(view.get_luma(0, 1), 5);
+        assert_eq!(view.get_luma(3, 3), 18);
+    }
+}
@@ -0,0 +1,372 @@
+use std::{env, fs, path::Path};
+
+use crate::{
+    builder::QR,
+    codec::{
+        decode_segments,
+        encoder::writer::{push_segment, push_terminator},
+        Mode, Segment,
+    },
+    metadata::{Color, ECLevel, Version},
+    utils::{BitStream, QRResult},
+};
+
+// Snapshot testing
+//------------------------------------------------------------------------------
+
+// Compact text export of a QR. A header with the encoding parameters is followed by one line per
+// row of modules, '#' for dark, '.' for light & the colour bits for high capacity modules
+pub fn snapshot(qr: &QR) -> String {
+    let w = qr.width();
+    let mut res = String::with_capacity((w + 1) * w + 64);
+    res.push_str(&format!(
+        "version: {:?}, ec level: {:?}, mask: {:?}, high capacity: {}\n",
+        *qr.version(),
+        qr.ec_level(),
+        qr.mask().map(|m| *m),
+        qr.high_capacity()
+    ));
+    for y in 0..w as i32 {
+        for x in 0..w as i32 {
+            let c = match *qr.get(x, y) {
+                Color::Black => '#',
+                Color::White => '.',
+                c => char::from(b'0' + c as u8),
+            };
+            res.push(c);
+        }
+        res.push('\n');
+    }
+    res
+}
+
+// Compares the QR against the snapshot stored at path. Missing snapshots are written, so the first
+// run records the baseline, and all snapshots are rewritten if QRISM_UPDATE_SNAPSHOTS is set
+pub fn assert_snapshot(qr: &QR, path: &Path) {
+    let update = env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|v| v != "0");
+    check_snapshot(qr, path, update);
+}
+
+fn check_snapshot(qr: &QR, path: &Path, update: bool) {
+    let actual = snapshot(qr);
+    if update || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("Failed to create snapshot directory");
+        }
+        fs::write(path, &actual).expect("Failed to write snapshot");
+        return;
+    }
+
+    let expected = fs::read_to_string(path).expect("Failed to read snapshot");
+    if let Some(diff) = first_diff(&expected, &actual) {
+        panic!(
+            "QR doesn't match snapshot {}: {diff}\nRerun with {UPDATE_SNAPSHOTS_VAR}=1 to accept \
+             the change\n\nExpected:\n{expected}\nActual:\n{actual}",
+            path.display()
+        );
+    }
+}
+
+// Locates the first mismatch. Line 1 is the header, module rows follow
+fn first_diff(expected: &str, actual: &str) -> Option<String> {
+    let (mut exp, mut act) = (expected.lines(), actual.lines());
+    for i in 0.. {
+        match (exp.next(), act.next()) {
+            (None, None) => return None,
+            (Some(e), Some(a)) if e == a => continue,
+            (Some(_), Some(_)) if i == 0 => return Some("header differs".to_string()),
+            (Some(e), Some(a)) => {
+                let x = e.chars().zip(a.chars()).position(|(e, a)| e != a).unwrap_or(e.len());
+                return Some(format!("first mismatch at module ({x}, {})", i - 1));
+            }
+            _ => return Some("size differs".to_string()),
+        }
+    }
+    unreachable!()
+}
+
+// Asserts the QR matches the snapshot of the given name under tests/snapshots of the calling
+// crate, recording it on the first run
+#[macro_export]
+macro_rules! assert_qr_snapshot {
+    ($qr:expr, $name:expr) => {
+        $crate::testing::assert_snapshot(
+            &$qr,
+            &::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("snapshots")
+                .join(format!("{}.qr", $name)),
+        )
+    };
+}
+
+pub use crate::assert_qr_snapshot;
+
+// Fuzz corpus
+//------------------------------------------------------------------------------
+
+// Seed input for fuzzing the segment reader. Inputs are serialized as the version, a byte with
+// the ec level in the low 2 bits & high capacity in bit 2, followed by the data codewords
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    pub name: &'static str,
+    pub ver: Version,
+    pub ecl: ECLevel,
+    pub hi_cap: bool,
+    pub data: Vec<u8>,
+}
+
+impl CorpusEntry {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(self.data.len() + 2);
+        res.push(*self.ver as u8);
+        res.push(self.ecl as u8 | (self.hi_cap as u8) << 2);
+        res.extend_from_slice(&self.data);
+        res
+    }
+}
+
+// Fuzz target for the segment reader, taking inputs serialized as in CorpusEntry. Data beyond the
+// capacity of the version is ignored. Returns None for headers that aren't a valid normal version
+pub fn decode_fuzz_input(inp: &[u8]) -> Option<QRResult<String>> {
+    let (&[v, flags], data) = inp.split_first_chunk::<2>()?;
+    if !(1..=40).contains(&v) {
+        return None;
+    }
+    let (ver, hi_cap) = (Version::Normal(v as usize), flags & 0b100 != 0);
+    let ecl = [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H][(flags & 0b11) as usize];
+
+    let data = &data[..data.len().min(ver.data_capacity(ecl, hi_cap))];
+    let mut bs = BitStream::from(data);
+    Some(decode_segments(&mut bs, ver, ecl, hi_cap, None).map(|(msg, ..)| msg))
+}
+
+// Minimized inputs covering the edges of the segment reader: mode switches & headers cut at the
+// capacity boundary, max char counts, count widths around version boundaries, invalid chunk
+// values & mode bits, ECI designators, FNC1 & terminator edge cases. The corpus is deterministic
+// so fuzzing runs are reproducible
+pub fn fuzz_corpus() -> Vec<CorpusEntry> {
+    use ECLevel::*;
+    use Mode::*;
+
+    let v = Version::Normal;
+    let mut corpus = Vec::new();
+    let mut add = |name, ver: Version, ecl, hi_cap, write: &dyn Fn(&mut BitStream, Version)| {
+        let mut bs = BitStream::new(ver.data_bit_capacity(ecl, hi_cap));
+        write(&mut bs, ver);
+        corpus.push(CorpusEntry { name, ver, ecl, hi_cap, data: bs.data().to_vec() });
+    };
+
+    // Terminator
+    add("no_data", v(1), L, false, &|_, _| ());
+    add("terminator_only", v(1), L, false, &|bs, _| push_terminator(bs));
+    add("numeric_exact_fill", v(1), M, false, &|bs, ver| seg(bs, ver, Numeric, &[b'7'; 34]));
+    add("alnum_one_bit_left", v(1), L, false, &|bs, ver| seg(bs, ver, Alphanumeric, &[b'A'; 25]));
+    add("byte_four_bits_left", v(1), M, false, &|bs, ver| seg(bs, ver, Byte, &[b'a'; 14]));
+    add("pad_without_terminator", v(1), L, false, &|bs, ver| {
+        seg(bs, ver, Byte, b"ab");
+        [0xEC, 0x11, 0xEC].into_iter().for_each(|p| bs.push_byte(p));
+    });
+
+    // Mode switches
+    let mixed = |bs: &mut BitStream, ver| {
+        seg(bs, ver, Numeric, b"123");
+        seg(bs, ver, Alphanumeric, b"AB");
+        seg(bs, ver, Byte, b"ab");
+        raw_seg(bs, ver, Kanji, 1, &[(KANJI_CHUNK, 13)]);
+    };
+    add("mode_switches", v(1), L, false, &|bs, ver| {
+        mixed(bs, ver);
+        push_terminator(bs);
+    });
+    add("header_cut_at_capacity", v(1), L, false, &|bs, ver| {
+        mixed(bs, ver);
+        seg(bs, ver, Byte, b"abcd");
+        bs.push_bits(Byte as u8, 4);
+        bs.push_bits(0b111u8, 3);
+    });
+
+    // Char counts, at their max & around the versions where their width changes
+    for (name, ver, mode) in [
+        ("numeric_max_count", 1, Numeric),
+        ("alnum_max_count", 27, Alphanumeric),
+        ("byte_max_count", 10, Byte),
+        ("kanji_max_count", 40, Kanji),
+    ] {
+        add(name, v(ver), L, false, &|bs, ver| {
+            let cnt_bits = ver.char_cnt_bits(mode);
+            bs.push_bits(mode as u8, 4);
+            bs.push_bits(u16::MAX >> (16 - cnt_bits), cnt_bits);
+        });
+    }
+    for (name, ver) in [
+        ("count_width_v9", 9),
+        ("count_width_v10", 10),
+        ("count_width_v26", 26),
+        ("count_width_v27", 27),
+    ] {
+        add(name, v(ver), H, false, &|bs, ver| {
+            seg(bs, ver, Numeric, b"9990");
+            seg(bs, ver, Alphanumeric, b"Z");
+            seg(bs, ver, Byte, b"xyz");
+            raw_seg(bs, ver, Kanji, 1, &[(KANJI_CHUNK, 13)]);
+            push_terminator(bs);
+        });
+    }
+
+    // Chunk values
+    add("numeric_remainders", v(1), L, false, &|bs, ver| {
+        seg(bs, ver, Numeric, b"1234");
+        seg(bs, ver, Numeric, b"12345");
+        push_terminator(bs);
+    });
+    add("numeric_invalid_groups", v(1), L, false, &|bs, ver| {
+        raw_seg(bs, ver, Numeric, 6, &[(1023, 10), (127, 7)]);
+        raw_seg(bs, ver, Numeric, 1, &[(15, 4)]);
+    });
+    add("alnum_invalid_values", v(1), L, false, &|bs, ver| {
+        raw_seg(bs, ver, Alphanumeric, 3, &[(2047, 11), (63, 6)]);
+    });
+    add("kanji_invalid_value", v(1), L, false, &|bs, ver| {
+        raw_seg(bs, ver, Kanji, 1, &[(0x1FFF, 13)]);
+    });
+    add("invalid_mode", v(1), L, false, &|bs, _| bs.push_bits(0b1011u8, 4));
+    add("invalid_mode_after_segment", v(1), L, false, &|bs, ver| {
+        seg(bs, ver, Byte, b"a");
+        bs.push_bits(0b1111u8, 4);
+    });
+
+    // ECI & FNC1
+    for (name, designator) in [
+        ("eci_one_byte", &[26u8][..]),
+        ("eci_two_bytes", &[0b1000_0000, 26]),
+        ("eci_three_bytes", &[0b1100_0000, 0, 26]),
+        ("eci_invalid_designator", &[0b1110_0000]),
+    ] {
+        add(name, v(1), L, false, &|bs, ver| {
+            bs.push_bits(Eci as u8, 4);
+            designator.iter().for_each(|&d| bs.push_byte(d));
+            seg(bs, ver, Byte, "é".as_bytes());
+        });
+    }
+    add("eci_cut_designator", v(1), L, false, &|bs, ver| {
+        seg(bs, ver, Byte, &[b'a'; 16]);
+        bs.push_bits(Eci as u8, 4);
+        bs.push_bits(0b1100u8, 4);
+    });
+    add("fnc1_first", v(1), L, false, &|bs, ver| {
+        bs.push_bits(Fnc1First as u8, 4);
+        seg(bs, ver, Alphanumeric, b"%%%1");
+    });
+    add("fnc1_second_invalid_ai", v(1), L, false, &|bs, ver| {
+        bs.push_bits(Fnc1Second as u8, 4);
+        bs.push_byte(150);
+        seg(bs, ver, Byte, b"a");
+    });
+
+    // High capacity channels
+    add("hi_cap_segments", v(1), L, true, &|bs, ver| {
+        seg(bs, ver, Byte, b"red");
+        push_terminator(bs);
+    });
+
+    corpus
+}
+
+fn seg(bs: &mut BitStream, ver: Version, mode: Mode, data: &[u8]) {
+    push_segment(Segment::with_bits(mode, ver.mode_bits(), ver.char_cnt_bits(mode), data), bs);
+}
+
+// Segment with a char count & chunks of (value, bit len) written as is, valid or not
+fn raw_seg(bs: &mut BitStream, ver: Version, mode: Mode, char_cnt: u16, chunks: &[(u16, usize)]) {
+    bs.push_bits(mode as u8, 4);
+    bs.push_bits(char_cnt, ver.char_cnt_bits(mode));
+    chunks.iter().for_each(|&(c, len)| bs.push_bits(c, len));
+}
+
+// Writes each entry of the corpus to dir as <name>.bin
+pub fn write_fuzz_corpus(dir: &Path) -> std::io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let corpus = fuzz_corpus();
+    for e in corpus.iter() {
+        fs::write(dir.join(format!("{}.bin", e.name)), e.to_bytes())?;
+    }
+    Ok(corpus.len())
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use std::{env, fs, panic};
+
+    use super::{check_snapshot, decode_fuzz_input, fuzz_corpus, snapshot, write_fuzz_corpus};
+    use crate::{utils::QRError, QRBuilder, Version};
+
+    #[test]
+    fn test_snapshot() {
+        let qr = QRBuilder::new(b"Snapshot").version(Version::Normal(1)).build().unwrap();
+        let snap = snapshot(&qr);
+        let lines: Vec<_> = snap.lines().collect();
+        assert_eq!(lines.len(), 22);
+        assert!(lines[0].starts_with("version: 1, ec level: M, mask: Some("));
+        assert_eq!(&lines[1][..8], "#######.");
+        assert_eq!(&lines[2][..8], "#.....#.");
+    }
+
+    #[test]
+    fn test_fuzz_corpus() {
+        let corpus = fuzz_corpus();
+        let decoded = |name| {
+            let e = corpus.iter().find(|e| e.name == name).unwrap();
+            decode_fuzz_input(&e.to_bytes()).unwrap()
+        };
+        assert_eq!(fuzz_corpus(), corpus);
+        assert!(corpus.iter().all(|e| e.data.len() <= e.ver.data_capacity(e.ecl, e.hi_cap)));
+
+        assert_eq!(decoded("mode_switches"), Ok("123ABab点".to_string()));
+        assert_eq!(decoded("count_width_v10"), Ok("9990Zxyz点".to_string()));
+        assert_eq!(decoded("numeric_exact_fill"), Ok("7".repeat(34)));
+        assert_eq!(decoded("numeric_max_count"), Err(QRError::CorruptDataSegment));
+        assert_eq!(decoded("header_cut_at_capacity"), Err(QRError::CorruptDataSegment));
+        assert_eq!(decoded("invalid_mode"), Err(QRError::InvalidMode(0b1011)));
+        for e in corpus.iter() {
+            let _ = decode_fuzz_input(&e.to_bytes()).unwrap();
+        }
+
+        assert_eq!(decode_fuzz_input(&[]), None);
+        assert_eq!(decode_fuzz_input(&[41, 0, 0x40]), None);
+        assert!(decode_fuzz_input(&[1, 7, 0x40, 0x16, 0x10]).is_some());
+
+        let dir = std::env::temp_dir().join(format!("qrism_corpus_{}", std::process::id()));
+        assert_eq!(write_fuzz_corpus(&dir).unwrap(), corpus.len());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), corpus.len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_assert_snapshot() {
+        let path = env::temp_dir().join(format!("qrism_snapshot_{}.qr", std::process::id()));
+        let qr = QRBuilder::new(b"Snapshot").build().unwrap();
+
+        // First run records, second compares. The update flag is passed explicitly, so the test
+        // holds whether QRISM_UPDATE_SNAPSHOTS is set or not
+        check_snapshot(&qr, &path, false);
+        check_snapshot(&qr, &path, false);
+
+        let other = QRBuilder::new(b"Snapshot!").build().unwrap();
+        let res = panic::catch_unwind(|| check_snapshot(&other, &path, false));
+        assert!(res.is_err());
+
+        // Updating rewrites the snapshot
+        check_snapshot(&other, &path, true);
+        check_snapshot(&other, &path, false);
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const UPDATE_SNAPSHOTS_VAR: &str = "QRISM_UPDATE_SNAPSHOTS";
+
+// "点", 0x935F in Shift JIS, as a kanji mode chunk. The encoder doesn't write kanji yet
+const KANJI_CHUNK: u16 = 0x12 * 0xC0 + 0x1F;
@@ -0,0 +1,168 @@
+// Fountain code
+//------------------------------------------------------------------------------
+
+// Systematic random linear fountain code. The file is split into k source blocks & frame seq
+// carries the xor of the blocks its row selects: source block seq for seq < k, a pseudo random
+// half of all blocks otherwise. Rows are derived from the seq & file crc, so only the seq is sent.
+// Any k frames with independent rows rebuild the file, which random rows reach within a couple of
+// extra frames
+
+// Blocks selected by the row of frame seq, as a bitset over the k source blocks
+pub(crate) fn fountain_row(seq: usize, k: usize, file_crc: u32) -> Vec<u64> {
+    let mut row = vec![0u64; k.div_ceil(64)];
+    if seq < k {
+        row[seq / 64] = 1 << (seq % 64);
+        return row;
+    }
+
+    let mut state = (file_crc as u64) << 32 ^ seq as u64;
+    row.iter_mut().for_each(|w| *w = splitmix64(&mut state));
+    if !k.is_multiple_of(64) {
+        row[k / 64] &= (1 << (k % 64)) - 1;
+    }
+    if row.iter().all(|&w| w == 0) {
+        row[(seq % k) / 64] = 1 << (seq % k % 64);
+    }
+    row
+}
+
+// Payload of frame seq from the source blocks, as long as the first. Shorter blocks count as
+// padded with zeros, so the file can be split without copying it
+pub(crate) fn fountain_encode(blocks: &[&[u8]], seq: usize, file_crc: u32) -> Vec<u8> {
+    let row = fountain_row(seq, blocks.len(), file_crc);
+    let mut out = vec![0; blocks.first().map_or(0, |b| b.len())];
+    for (w, &word) in row.iter().enumerate() {
+        let mut bits = word;
+        while bits != 0 {
+            xor_into(&mut out, blocks[w * 64 + bits.trailing_zeros() as usize]);
+            bits &= bits - 1;
+        }
+    }
+    out
+}
+
+// Gaussian elimination over gf(2), run as frames arrive. Each frame is reduced against the rows
+// kept so far & kept if anything remains, under the lowest block it still selects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FountainDecoder {
+    k: usize,
+    file_crc: u32,
+    blk_len: usize,
+    pivots: Vec<Option<(Vec<u64>, Vec<u8>)>>,
+    rank: usize,
+}
+
+impl FountainDecoder {
+    pub fn new(k: usize, file_crc: u32, blk_len: usize) -> Self {
+        Self { k, file_crc, blk_len, pivots: vec![None; k], rank: 0 }
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.k
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.blk_len
+    }
+
+    // Frames with independent rows received so far
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.rank == self.k
+    }
+
+    // Adds the frame, returning whether it told anything new about the blocks
+    pub fn push(&mut self, seq: usize, mut payload: Vec<u8>) -> bool {
+        debug_assert_eq!(payload.len(), self.blk_len, "Fountain payload length mismatch");
+
+        let mut row = fountain_row(seq, self.k, self.file_crc);
+        for col in 0..self.k {
+            if row[col / 64] >> (col % 64) & 1 == 0 {
+                continue;
+            }
+            match &self.pivots[col] {
+                Some((prow, pdata)) => {
+                    row.iter_mut().zip(prow).for_each(|(w, p)| *w ^= p);
+                    xor_into(&mut payload, pdata);
+                }
+                None => {
+                    self.pivots[col] = Some((row, payload));
+                    self.rank += 1;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // Source blocks back substituted from the last, once every block has a row
+    pub fn solve(&self) -> Option<Vec<Vec<u8>>> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); self.k];
+        for col in (0..self.k).rev() {
+            let (row, data) = self.pivots[col].as_ref()?;
+            let mut blk = data.clone();
+            for j in col + 1..self.k {
+                if row[j / 64] >> (j % 64) & 1 == 1 {
+                    xor_into(&mut blk, &blocks[j]);
+                }
+            }
+            blocks[col] = blk;
+        }
+        Some(blocks)
+    }
+}
+
+fn xor_into(out: &mut [u8], data: &[u8]) {
+    out.iter_mut().zip(data).for_each(|(o, d)| *o ^= d);
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod fountain_tests {
+    use super::{fountain_encode, fountain_row, FountainDecoder};
+
+    #[test]
+    fn test_fountain_rows() {
+        assert_eq!(fountain_row(3, 70, 7), [0b1000, 0]);
+        let row = fountain_row(70, 70, 7);
+        assert_eq!(row[1] >> 6, 0);
+        assert_eq!(row, fountain_row(70, 70, 7));
+        assert_ne!(row, fountain_row(70, 70, 8));
+    }
+
+    #[test]
+    fn test_fountain_decode_any_subset() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 13 % 256) as u8).collect();
+        let blocks: Vec<&[u8]> = data.chunks(10).collect();
+        let k = blocks.len();
+
+        // Every third source frame lost, made up for by repair frames
+        let mut dec = FountainDecoder::new(k, 42, 10);
+        let seqs = (0..k).filter(|s| s % 3 != 0).chain(k..);
+        for seq in seqs.take(k + 8) {
+            dec.push(seq, fountain_encode(&blocks, seq, 42));
+        }
+        assert!(dec.is_complete());
+        assert_eq!(dec.solve().unwrap().concat(), data);
+
+        // Repeated frames add nothing
+        let mut dec = FountainDecoder::new(k, 42, 10);
+        assert!(dec.push(k + 1, fountain_encode(&blocks, k + 1, 42)));
+        assert!(!dec.push(k + 1, fountain_encode(&blocks, k + 1, 42)));
+        assert_eq!((dec.rank(), dec.solve()), (1, None));
+    }
+}
@@ -0,0 +1,610 @@
+mod fountain;
+
+#[cfg(feature = "gif")]
+use std::{error::Error, fs::File, path::Path};
+
+#[cfg(feature = "gif")]
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame as GifFrame,
+};
+use image::{DynamicImage, RgbImage};
+
+use fountain::{fountain_encode, FountainDecoder};
+
+use crate::{
+    builder::{QRBuilder, RenderConfig, QR},
+    metadata::ECLevel,
+    reader::{detect_qr, DecodeResult},
+    utils::{QRError, QRResult},
+};
+
+// Transfer frames
+//------------------------------------------------------------------------------
+
+// A file is sent as a sequence of frames, one per symbol, each the text
+// "QRT:<seq>/<count>:<file crc>:<frame crc>:<chunk>". Crcs are crc32 in 8 hex digits, the frame
+// crc covering the rest of the frame. Chunks are base45, so whole frames fit alphanumeric mode.
+// Fountain frames are "QRF:<seq>/<blocks>/<file len>:..", see fountain.rs
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    seq: usize,
+    count: usize, // Frames of sequential transfers, source blocks of fountain ones
+    file_crc: u32,
+    file_len: Option<usize>, // Fountain frames only, as their blocks are padded
+    chunk: Vec<u8>,
+}
+
+impl Frame {
+    fn to_text(&self) -> String {
+        let header = match self.file_len {
+            Some(len) => format!("{FOUNTAIN_PREFIX}{}/{}/{len}:", self.seq, self.count),
+            None => format!("{FRAME_PREFIX}{}/{}:", self.seq, self.count),
+        };
+        let header = format!("{header}{:08X}:", self.file_crc);
+        let chunk = base45_encode(&self.chunk);
+        let crc = crc32(&[header.as_bytes(), chunk.as_bytes()].concat());
+        format!("{header}{crc:08X}:{chunk}")
+    }
+
+    fn parse(text: &str) -> QRResult<Self> {
+        let malformed = QRError::MalformedTransferFrame;
+        let (rest, is_fountain) = match text.strip_prefix(FRAME_PREFIX) {
+            Some(rest) => (rest, false),
+            None => (text.strip_prefix(FOUNTAIN_PREFIX).ok_or(malformed)?, true),
+        };
+        let mut fields = rest.splitn(4, ':');
+        let (Some(pos), Some(file_crc), Some(crc), Some(chunk)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(malformed);
+        };
+
+        let mut nums = pos.split('/').map(|n| n.parse::<usize>().map_err(|_| malformed));
+        let (seq, count) = (nums.next().ok_or(malformed)??, nums.next().ok_or(malformed)??);
+        let file_len = nums.next().transpose()?;
+        let (Some(file_crc), Some(crc)) = (parse_crc(file_crc), parse_crc(crc)) else {
+            return Err(malformed);
+        };
+        // Receivers allocate a slot per frame or block, so the count is bounded before it's trusted
+        let is_valid = match file_len {
+            Some(_) => is_fountain && count > 0,
+            None => !is_fountain && seq < count,
+        };
+        if !is_valid || count > MAX_TRANSFER_FRAMES {
+            return Err(malformed);
+        }
+        if nums.next().is_some() {
+            return Err(malformed);
+        }
+
+        let header = &text[..text.len() - chunk.len() - 9];
+        if crc32(&[header.as_bytes(), chunk.as_bytes()].concat()) != crc {
+            return Err(QRError::TransferChecksumMismatch);
+        }
+        let chunk = base45_decode(chunk).ok_or(malformed)?;
+        Ok(Self { seq, count, file_crc, file_len, chunk })
+    }
+}
+
+// Sender
+//------------------------------------------------------------------------------
+
+// Splits a file into frames for showing on a screen one after another, e.g. as an animation
+#[derive(Debug, Clone, Copy)]
+pub struct TransferSender<'a> {
+    data: &'a [u8],
+    file_crc: u32, // Of the whole file, which every frame carries
+    chunk_sz: usize,
+    ecl: ECLevel,
+    overhead: Option<f64>, // Share of repair frames on top of the blocks, for fountain transfers
+}
+
+impl<'a> TransferSender<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let file_crc = crc32(data);
+        Self { data, file_crc, chunk_sz: DEFAULT_CHUNK_SIZE, ecl: ECLevel::M, overhead: None }
+    }
+
+    // Bytes of the file per frame. Larger chunks need fewer frames but denser symbols
+    pub fn chunk_size(&mut self, chunk_sz: usize) -> &mut Self {
+        self.chunk_sz = chunk_sz.max(1);
+        self
+    }
+
+    pub fn ec_level(&mut self, ecl: ECLevel) -> &mut Self {
+        self.ecl = ecl;
+        self
+    }
+
+    // Sends fountain frames, with repair frames making up the overhead share on top of the blocks
+    // of the file, e.g. 0.25 for a quarter more frames. Receivers rebuild the file from any frames
+    // slightly more than its blocks, so higher overheads make up for more frames the camera misses
+    pub fn fountain(&mut self, overhead: f64) -> &mut Self {
+        self.overhead = Some(overhead.max(0.0));
+        self
+    }
+
+    // Chunks the file is split into. Empty files take a single empty chunk
+    pub fn block_count(&self) -> usize {
+        self.data.len().div_ceil(self.chunk_sz).max(1)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        let k = self.block_count();
+        match self.overhead {
+            Some(overhead) => k + (k as f64 * overhead).ceil() as usize,
+            None => k,
+        }
+    }
+
+    // Frame payloads in sequence. Fails if the file needs more blocks than receivers accept, in
+    // which case a larger chunk size fits it
+    pub fn frames(&self) -> QRResult<Vec<String>> {
+        self.check_block_count()?;
+        let blocks = self.blocks();
+        Ok((0..self.frame_count()).map(|seq| self.frame_of(&blocks, seq)).collect())
+    }
+
+    // Frame of an endless stream, for senders that keep showing frames until the receiver is
+    // done. Sequential transfers loop over their frames, fountain ones keep making repair frames
+    pub fn frame(&self, seq: usize) -> QRResult<String> {
+        self.check_block_count()?;
+        Ok(self.frame_of(&self.blocks(), seq))
+    }
+
+    fn check_block_count(&self) -> QRResult<()> {
+        match self.block_count() {
+            k if k > MAX_TRANSFER_FRAMES => Err(QRError::TooManyTransferFrames(k)),
+            _ => Ok(()),
+        }
+    }
+
+    // Fountain blocks of equal length over the file, the last one short & counted as zero padded.
+    // Sequential transfers slice their chunks directly, so take none
+    fn blocks(&self) -> Vec<&'a [u8]> {
+        if self.overhead.is_none() {
+            return Vec::new();
+        }
+        let k = self.block_count();
+        let blk_len = self.data.len().div_ceil(k).max(1);
+        let mut blocks: Vec<&[u8]> = self.data.chunks(blk_len).collect();
+        blocks.resize(k, &[]);
+        blocks
+    }
+
+    fn frame_of(&self, blocks: &[&[u8]], seq: usize) -> String {
+        let (k, file_crc) = (self.block_count(), self.file_crc);
+        if self.overhead.is_none() {
+            let seq = seq % k;
+            let start = (seq * self.chunk_sz).min(self.data.len());
+            let end = (start + self.chunk_sz).min(self.data.len());
+            let chunk = self.data[start..end].to_vec();
+            return Frame { seq, count: k, file_crc, file_len: None, chunk }.to_text();
+        }
+
+        let chunk = fountain_encode(blocks, seq, file_crc);
+        Frame { seq, count: k, file_crc, file_len: Some(self.data.len()), chunk }.to_text()
+    }
+
+    // Symbols of the frames, all in the version of the longest frame so an animation doesn't
+    // change size between frames
+    pub fn symbols(&self) -> QRResult<Vec<QR>> {
+        let frames = self.frames()?;
+        let longest = frames.iter().max_by_key(|f| f.len()).expect("Transfers have a frame");
+        let ver = QRBuilder::new(longest.as_bytes()).ec_level(self.ecl).build()?.version();
+        frames
+            .iter()
+            .map(|f| QRBuilder::new(f.as_bytes()).version(ver).ec_level(self.ecl).build())
+            .collect()
+    }
+
+    pub fn render(&self, config: &RenderConfig) -> QRResult<Vec<RgbImage>> {
+        Ok(self.symbols()?.iter().map(|qr| qr.to_image_with(config)).collect())
+    }
+
+    // Saves the frames as a looping gif, showing each frame for the given milliseconds
+    #[cfg(feature = "gif")]
+    pub fn save_gif(
+        &self,
+        path: impl AsRef<Path>,
+        config: &RenderConfig,
+        frame_ms: u32,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let delay = Delay::from_numer_denom_ms(frame_ms, 1);
+        let frames = self
+            .render(config)?
+            .into_iter()
+            .map(|img| GifFrame::from_parts(DynamicImage::ImageRgb8(img).to_rgba8(), 0, 0, delay));
+
+        let mut enc = GifEncoder::new(File::create(path)?);
+        enc.set_repeat(Repeat::Infinite)?;
+        enc.encode_frames(frames)?;
+        Ok(())
+    }
+}
+
+// Receiver
+//------------------------------------------------------------------------------
+
+// Collects frames in any order, e.g. from camera frames of a looping animation, until the file
+// is complete. The first frame pushed decides the transfer, frames of other transfers are rejected
+#[derive(Debug, Clone, Default)]
+pub struct TransferReceiver {
+    file_crc: Option<u32>,
+    chunks: Vec<Option<Vec<u8>>>,      // Chunks of sequential transfers
+    fountain: Option<FountainDecoder>, // Decoder of fountain transfers
+    file_len: usize,                   // Of fountain transfers, whose last block is padded
+}
+
+impl TransferReceiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds the frame, returning whether it wasn't received before. Fountain frames count as new if
+    // they tell anything the frames before didn't
+    pub fn push_payload(&mut self, payload: &str) -> QRResult<bool> {
+        let frame = Frame::parse(payload)?;
+
+        // Blocks of fountain frames must cover the file. Checked before any state changes, so a
+        // bad first frame doesn't lock the receiver to its transfer
+        if let Some(len) = frame.file_len {
+            let cap = frame.chunk.len().checked_mul(frame.count);
+            if cap.is_none_or(|cap| cap < len) {
+                return Err(QRError::MalformedTransferFrame);
+            }
+        }
+
+        match self.file_crc {
+            Some(crc) if crc != frame.file_crc || !self.is_same_layout(&frame) => {
+                return Err(QRError::MixedTransfer);
+            }
+            Some(_) => (),
+            None => {
+                self.file_crc = Some(frame.file_crc);
+                match frame.file_len {
+                    Some(len) => {
+                        let blk_len = frame.chunk.len();
+                        self.file_len = len;
+                        self.fountain =
+                            Some(FountainDecoder::new(frame.count, frame.file_crc, blk_len));
+                    }
+                    None => self.chunks = vec![None; frame.count],
+                }
+            }
+        }
+
+        if let Some(dec) = &mut self.fountain {
+            return Ok(dec.push(frame.seq, frame.chunk));
+        }
+        let slot = &mut self.chunks[frame.seq];
+        let is_new = slot.is_none();
+        slot.get_or_insert(frame.chunk);
+        Ok(is_new)
+    }
+
+    fn is_same_layout(&self, frame: &Frame) -> bool {
+        match (&self.fountain, frame.file_len) {
+            (Some(dec), Some(len)) => {
+                dec.block_count() == frame.count
+                    && dec.block_len() == frame.chunk.len()
+                    && self.file_len == len
+            }
+            (None, None) => self.chunks.len() == frame.count,
+            _ => false,
+        }
+    }
+
+    // Adds the frames among the decoded symbols, returning the number of new frames. Symbols that
+    // fail to decode or aren't frames of this transfer are skipped
+    pub fn push_result(&mut self, res: &DecodeResult) -> usize {
+        let msgs = res.decode_all().into_iter().flatten();
+        msgs.filter(|(_, msg)| self.push_payload(msg) == Ok(true)).count()
+    }
+
+    pub fn push_image(&mut self, img: &DynamicImage) -> usize {
+        self.push_result(&detect_qr(img))
+    }
+
+    // Frames received, only counting fountain frames that told anything new
+    pub fn received(&self) -> usize {
+        match &self.fountain {
+            Some(dec) => dec.rank(),
+            None => self.chunks.iter().flatten().count(),
+        }
+    }
+
+    // Frames needed to rebuild the file, none until the first frame is received
+    pub fn frame_count(&self) -> Option<usize> {
+        match &self.fountain {
+            Some(dec) => Some(dec.block_count()),
+            None => self.file_crc.map(|_| self.chunks.len()),
+        }
+    }
+
+    // Sequence numbers of the frames still to receive. Fountain transfers take any frames, so
+    // none are listed & only their number is known from frame_count & received
+    pub fn missing(&self) -> Vec<usize> {
+        (0..self.chunks.len()).filter(|&i| self.chunks[i].is_none()).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        match &self.fountain {
+            Some(dec) => dec.is_complete(),
+            None => self.file_crc.is_some() && self.chunks.iter().all(Option::is_some),
+        }
+    }
+
+    // Reassembled file, once all frames are received
+    pub fn finish(&self) -> QRResult<Vec<u8>> {
+        let file_crc = self.file_crc.ok_or(QRError::SymbolNotFound)?;
+        let count = self.frame_count().unwrap_or_default();
+        if !self.is_complete() {
+            return Err(QRError::IncompleteTransfer(self.received(), count));
+        }
+
+        let data = match &self.fountain {
+            Some(dec) => {
+                let mut data = dec.solve().expect("Complete decoders solve").concat();
+                data.truncate(self.file_len);
+                data
+            }
+            None => self.chunks.iter().flatten().flatten().copied().collect(),
+        };
+        if crc32(&data) != file_crc {
+            return Err(QRError::TransferChecksumMismatch);
+        }
+        Ok(data)
+    }
+}
+
+// Frame helpers
+//------------------------------------------------------------------------------
+
+// Crc32 of ieee 802.3, as in zip & png
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!0u32, |mut crc, &b| {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (CRC32_POLY & (crc & 1).wrapping_neg());
+        }
+        crc
+    });
+    !crc
+}
+
+fn parse_crc(hex: &str) -> Option<u32> {
+    let is_valid = hex.len() == 8 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+    is_valid.then(|| u32::from_str_radix(hex, 16).ok()).flatten()
+}
+
+// Base45 of rfc 9285, which packs 2 bytes into 3 chars of the alphanumeric charset
+fn base45_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(2) * 3);
+    for pair in data.chunks(2) {
+        let (mut n, digits) = match *pair {
+            [a, b] => ((a as usize) << 8 | b as usize, 3),
+            [a] => (a as usize, 2),
+            _ => unreachable!(),
+        };
+        for _ in 0..digits {
+            out.push(BASE45_CHARSET[n % 45] as char);
+            n /= 45;
+        }
+    }
+    out
+}
+
+fn base45_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 3 * 2 + 1);
+    for triple in text.as_bytes().chunks(3) {
+        let n = triple.iter().rev().try_fold(0, |n, c| {
+            let digit = BASE45_CHARSET.iter().position(|b| b == c)?;
+            Some(n * 45 + digit)
+        })?;
+        match triple.len() {
+            3 if n <= 0xFFFF => out.extend([(n >> 8) as u8, n as u8]),
+            2 if n <= 0xFF => out.push(n as u8),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod transfer_tests {
+    use image::DynamicImage;
+
+    use super::{
+        base45_decode, base45_encode, crc32, Frame, TransferReceiver, TransferSender,
+        MAX_TRANSFER_FRAMES,
+    };
+    use crate::{builder::RenderConfig, utils::QRError};
+
+    #[test]
+    fn test_frame_helpers() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(base45_encode(b"AB"), "BB8");
+        assert_eq!(base45_encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(base45_decode("QED8WEX0").unwrap(), b"ietf!");
+        assert_eq!(base45_decode("GGW"), None); // Above 0xFFFF
+        assert_eq!(base45_decode("A"), None);
+    }
+
+    #[test]
+    fn test_transfer_round_trip() {
+        let data: Vec<u8> = (0..350u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut sender = TransferSender::new(&data);
+        sender.chunk_size(50);
+        assert_eq!(sender.frame_count(), 7);
+
+        let mut config = RenderConfig::new();
+        config.module_size(3);
+        let imgs = sender.render(&config).unwrap();
+        assert!(imgs.iter().all(|img| img.dimensions() == imgs[0].dimensions()));
+
+        // Frames arrive out of order & repeat, as from a looping animation
+        let mut rx = TransferReceiver::new();
+        for i in [3, 0, 6, 3, 1, 5] {
+            rx.push_image(&DynamicImage::ImageRgb8(imgs[i].clone()));
+        }
+        assert_eq!(rx.received(), 5);
+        assert_eq!(rx.missing(), [2, 4]);
+        assert_eq!(rx.finish(), Err(QRError::IncompleteTransfer(5, 7)));
+
+        let frames = sender.frames().unwrap();
+        assert_eq!(rx.push_payload(&frames[2]), Ok(true));
+        assert_eq!(rx.push_payload(&frames[2]), Ok(false));
+        assert_eq!(rx.push_payload(&frames[4]), Ok(true));
+        assert!(rx.is_complete());
+        assert_eq!(rx.finish().unwrap(), data);
+    }
+
+    #[test]
+    fn test_transfer_rejects() {
+        let frames = TransferSender::new(b"Hello, world!").chunk_size(5).frames().unwrap();
+        let mut rx = TransferReceiver::new();
+        assert_eq!(rx.finish(), Err(QRError::SymbolNotFound));
+        assert_eq!(rx.push_payload("Hello"), Err(QRError::MalformedTransferFrame));
+
+        let corrupt = frames[0].replacen("QRT:0/3", "QRT:1/3", 1);
+        assert_eq!(rx.push_payload(&corrupt), Err(QRError::TransferChecksumMismatch));
+        assert_eq!(rx.frame_count(), None);
+
+        assert_eq!(rx.push_payload(&frames[0]), Ok(true));
+        let other = TransferSender::new(b"Other").frames().unwrap();
+        assert_eq!(rx.push_payload(&other[0]), Err(QRError::MixedTransfer));
+        assert_eq!(rx.frame_count(), Some(3));
+
+        // Counts past the cap are rejected before anything is allocated for them
+        let forged = |count| Frame { seq: 0, count, file_crc: 0, file_len: None, chunk: vec![] };
+        let mut rx = TransferReceiver::new();
+        let res = rx.push_payload(&forged(1_000_000_000_000).to_text());
+        assert_eq!(res, Err(QRError::MalformedTransferFrame));
+        assert_eq!(rx.push_payload(&forged(MAX_TRANSFER_FRAMES).to_text()), Ok(true));
+
+        // Fountain frames are bounded the same, & their blocks must cover the file. Rejected frames
+        // leave the receiver free for the real transfer
+        let fountain = |count, file_len, chunk: &[u8]| {
+            let chunk = chunk.to_vec();
+            Frame { seq: 0, count, file_crc: 1, file_len: Some(file_len), chunk }.to_text()
+        };
+        let mut rx = TransferReceiver::new();
+        let malformed = Err(QRError::MalformedTransferFrame);
+        assert_eq!(rx.push_payload(&fountain(MAX_TRANSFER_FRAMES + 1, 1, b"a")), malformed);
+        assert_eq!(rx.push_payload(&fountain(2, 5, b"ab")), malformed);
+        assert_eq!(rx.frame_count(), None);
+        assert_eq!(rx.push_payload(&frames[0]), Ok(true));
+
+        let empty = TransferSender::new(b"").frames().unwrap();
+        let mut rx = TransferReceiver::new();
+        assert_eq!(rx.push_payload(&empty[0]), Ok(true));
+        assert_eq!(rx.finish().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_transfer_frame_cap() {
+        // Senders refuse files that need more frames than receivers accept
+        let data = vec![0; MAX_TRANSFER_FRAMES + 1];
+        let mut sender = TransferSender::new(&data);
+        sender.chunk_size(1);
+        let err = QRError::TooManyTransferFrames(MAX_TRANSFER_FRAMES + 1);
+        assert_eq!(sender.frames(), Err(err));
+        assert_eq!(sender.frame(0), Err(err));
+        assert_eq!(sender.render(&RenderConfig::new()).err(), Some(err));
+        assert_eq!(sender.fountain(0.5).frames(), Err(err));
+
+        // The largest transfer receivers accept still goes through
+        let frame = TransferSender::new(&data[1..]).chunk_size(1).frame(0).unwrap();
+        assert_eq!(TransferReceiver::new().push_payload(&frame), Ok(true));
+    }
+
+    #[test]
+    fn test_fountain_transfer() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let mut sender = TransferSender::new(&data);
+        sender.chunk_size(90).fountain(0.5);
+        assert_eq!((sender.block_count(), sender.frame_count()), (12, 18));
+
+        // A third of the frames missed by the camera, in bursts
+        let mut rx = TransferReceiver::new();
+        let frames = sender.frames().unwrap();
+        for f in frames.iter().enumerate().filter(|(i, _)| i % 6 >= 2).map(|(_, f)| f) {
+            rx.push_payload(f).unwrap();
+        }
+        assert_eq!(rx.frame_count(), Some(12));
+        assert!(rx.received() < 12 && rx.missing().is_empty());
+        assert_eq!(rx.finish(), Err(QRError::IncompleteTransfer(rx.received(), 12)));
+
+        // Frames past the animation keep making up for missed ones
+        let mut seq = frames.len();
+        while !rx.is_complete() {
+            rx.push_payload(&sender.frame(seq).unwrap()).unwrap();
+            seq += 1;
+        }
+        assert!(seq < frames.len() + 6);
+        assert_eq!(rx.finish().unwrap(), data);
+
+        let seq_frames = TransferSender::new(&data).frames().unwrap();
+        assert_eq!(rx.push_payload(&seq_frames[0]), Err(QRError::MixedTransfer));
+    }
+
+    #[test]
+    fn test_fountain_transfer_large_file() {
+        // Blocks are sliced from the file once for all frames, so a file of a few hundred KiB
+        // takes moments rather than copying the file per frame
+        let data: Vec<u8> = (0..256 * 1024u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut sender = TransferSender::new(&data);
+        sender.fountain(0.25);
+        let frames = sender.frames().unwrap();
+        assert_eq!((sender.block_count(), frames.len()), (1024, 1280));
+
+        let mut rx = TransferReceiver::new();
+        for f in frames.iter().enumerate().filter(|(i, _)| i % 5 != 0).map(|(_, f)| f) {
+            rx.push_payload(f).unwrap();
+        }
+        let mut seq = frames.len();
+        while !rx.is_complete() && seq < frames.len() + 8 {
+            rx.push_payload(&sender.frame(seq).unwrap()).unwrap();
+            seq += 1;
+        }
+        assert_eq!(rx.finish().unwrap(), data);
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn test_transfer_gif() {
+        use crate::reader::{pages::detect_qr_file, DetectOptions};
+
+        let path = std::env::temp_dir().join(format!("qrism_transfer_{}.gif", std::process::id()));
+        let data = b"Data over screen, one frame at a time".repeat(4);
+        let mut sender = TransferSender::new(&data);
+        sender.chunk_size(40);
+        sender.save_gif(&path, RenderConfig::new().module_size(3), 200).unwrap();
+
+        let res = detect_qr_file(&path, &DetectOptions::default()).unwrap();
+        let mut rx = TransferReceiver::new();
+        assert_eq!(rx.push_result(&res), 4);
+        assert_eq!(rx.finish().unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+const FRAME_PREFIX: &str = "QRT:";
+
+const FOUNTAIN_PREFIX: &str = "QRF:";
+
+// Frames of 256 byte chunks fit version 12 at ec level M
+const DEFAULT_CHUNK_SIZE: usize = 256;
+
+// Frames a receiver accepts for a transfer, 16 MiB in the default chunks. Frame crcs are easily
+// forged, so a scanned count can't be trusted for allocation beyond this
+const MAX_TRANSFER_FRAMES: usize = 1 << 16;
+
+const CRC32_POLY: u32 = 0xEDB88320;
+
+const BASE45_CHARSET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
@@ -0,0 +1,228 @@
+use std::{fs, io, path::Path};
+
+use crate::reader::{pages::detect_qr_file, DetectOptions};
+
+// Label verification
+//------------------------------------------------------------------------------
+
+// Outcome of checking one expected label against its image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelStatus {
+    Match,
+    Mismatch { found: Vec<String> }, // Decoded, but none of the payloads is the expected one
+    Missing,                         // No image with the expected file name
+    Unreadable(String),              // Image could not be opened or no symbol decoded
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelCheck {
+    pub file: String,
+    pub expected: String,
+    pub status: LabelStatus,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub checks: Vec<LabelCheck>,
+}
+
+impl VerifyReport {
+    pub fn count(&self, f: impl Fn(&LabelStatus) -> bool) -> usize {
+        self.checks.iter().filter(|c| f(&c.status)).count()
+    }
+
+    // Exit code for CI: 0 if every label matched, otherwise the bits of the failures seen, i.e.
+    // 1 for mismatches, 2 for missing & 4 for unreadable labels
+    pub fn exit_code(&self) -> i32 {
+        self.checks.iter().fold(0, |code, c| match c.status {
+            LabelStatus::Match => code,
+            LabelStatus::Mismatch { .. } => code | EXIT_MISMATCH,
+            LabelStatus::Missing => code | EXIT_MISSING,
+            LabelStatus::Unreadable(_) => code | EXIT_UNREADABLE,
+        })
+    }
+
+    // One line per failed label followed by a summary
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for c in &self.checks {
+            let line = match &c.status {
+                LabelStatus::Match => continue,
+                LabelStatus::Mismatch { found } => {
+                    format!("MISMATCH {}: expected {:?}, found {:?}", c.file, c.expected, found)
+                }
+                LabelStatus::Missing => format!("MISSING {}: expected {:?}", c.file, c.expected),
+                LabelStatus::Unreadable(e) => format!("UNREADABLE {}: {e}", c.file),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{} labels: {} matched, {} mismatched, {} missing, {} unreadable\n",
+            self.checks.len(),
+            self.count(|s| *s == LabelStatus::Match),
+            self.count(|s| matches!(s, LabelStatus::Mismatch { .. })),
+            self.count(|s| *s == LabelStatus::Missing),
+            self.count(|s| matches!(s, LabelStatus::Unreadable(_))),
+        ));
+        out
+    }
+}
+
+// Decodes the image of every label listed in the csv & compares its payloads to the expected one.
+// The csv has a file & a payload column, with file names relative to the image directory. A
+// leading "file,payload" header is skipped
+pub fn verify_labels(
+    expected_csv: impl AsRef<Path>,
+    image_dir: impl AsRef<Path>,
+    opts: &DetectOptions,
+) -> io::Result<VerifyReport> {
+    let labels = parse_labels(&fs::read_to_string(expected_csv)?)?;
+    let dir = image_dir.as_ref();
+    if !dir.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "image directory not found"));
+    }
+
+    let checks = labels
+        .into_iter()
+        .map(|(file, expected)| {
+            let status = check_label(&dir.join(&file), &expected, opts);
+            LabelCheck { file, expected, status }
+        })
+        .collect();
+    Ok(VerifyReport { checks })
+}
+
+fn check_label(path: &Path, expected: &str, opts: &DetectOptions) -> LabelStatus {
+    if !path.is_file() {
+        return LabelStatus::Missing;
+    }
+    let res = match detect_qr_file(path, opts) {
+        Ok(res) => res,
+        Err(e) => return LabelStatus::Unreadable(e.to_string()),
+    };
+
+    let found = res.decode_all().into_iter().flatten().map(|(_, msg)| msg).collect::<Vec<_>>();
+    if found.is_empty() {
+        LabelStatus::Unreadable("no QR decoded".to_string())
+    } else if found.iter().any(|f| f == expected) {
+        LabelStatus::Match
+    } else {
+        LabelStatus::Mismatch { found }
+    }
+}
+
+// Parses (file, payload) rows. Fields may be quoted, with "" escaping a quote, so payloads can
+// hold commas & line breaks
+fn parse_labels(csv: &str) -> io::Result<Vec<(String, String)>> {
+    let invalid =
+        |line, msg| io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"));
+
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut line) = (false, 1);
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' | '\r' if !quoted => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                row.push(std::mem::take(&mut field));
+                rows.push((line, std::mem::take(&mut row)));
+                line += 1;
+            }
+            c => {
+                line += (c == '\n') as usize;
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(invalid(line, "unterminated quote"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((line, row));
+    }
+
+    let mut labels = Vec::with_capacity(rows.len());
+    for (i, (line, row)) in rows.into_iter().enumerate() {
+        match row.as_slice() {
+            [f] if f.is_empty() => continue,
+            [f, p] if i == 0 && f == "file" && p == "payload" => continue,
+            [f, p] if !f.is_empty() => labels.push((f.clone(), p.clone())),
+            _ => return Err(invalid(line, "expected file,payload")),
+        }
+    }
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod verify_tests {
+    use super::parse_labels;
+
+    #[test]
+    fn test_parse_labels() {
+        let csv = "file,payload\r\na.png,plain\n\nb.png,\"x, \"\"y\"\"\nz\"\n";
+        let labels = parse_labels(csv).unwrap();
+        assert_eq!(
+            labels,
+            [
+                ("a.png".to_string(), "plain".to_string()),
+                ("b.png".to_string(), "x, \"y\"\nz".to_string())
+            ]
+        );
+
+        assert!(parse_labels("a.png").is_err());
+        assert!(parse_labels("a.png,\"open").is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_verify_labels() {
+        use super::{verify_labels, LabelStatus};
+        use crate::{builder::QRBuilder, reader::DetectOptions};
+
+        let dir = std::env::temp_dir().join(format!("qrism_verify_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, msg) in [("1.png", "LBL-001"), ("2.png", "LBL-999")] {
+            let img = QRBuilder::new(msg.as_bytes()).build().unwrap().to_image(3);
+            img.save(dir.join(file)).unwrap();
+        }
+        image::RgbImage::new(60, 60).save(dir.join("3.png")).unwrap();
+
+        let csv = dir.join("labels.csv");
+        std::fs::write(&csv, "1.png,LBL-001\n2.png,LBL-002\n3.png,LBL-003\n4.png,LBL-004\n")
+            .unwrap();
+        let report = verify_labels(&csv, &dir, &DetectOptions::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let status = report.checks.iter().map(|c| c.status.clone()).collect::<Vec<_>>();
+        assert_eq!(status[0], LabelStatus::Match);
+        assert_eq!(status[1], LabelStatus::Mismatch { found: vec!["LBL-999".to_string()] });
+        assert!(matches!(status[2], LabelStatus::Unreadable(_)));
+        assert_eq!(status[3], LabelStatus::Missing);
+        assert_eq!(report.exit_code(), 7);
+        assert!(report
+            .summary()
+            .ends_with("4 labels: 1 matched, 1 mismatched, 1 missing, 1 unreadable\n"));
+    }
+}
+
+// Global constants
+//------------------------------------------------------------------------------
+
+pub const EXIT_MISMATCH: i32 = 1;
+pub const EXIT_MISSING: i32 = 2;
+pub const EXIT_UNREADABLE: i32 = 4;
+
+// Invalid arguments or an unreadable csv or image directory
+pub const EXIT_USAGE: i32 = 64;
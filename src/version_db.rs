@@ -0,0 +1,76 @@
+use crate::metadata::{BlockLayout, ECLevel, Version};
+
+// Palette
+//------------------------------------------------------------------------------
+
+// Colours a symbol is printed in. Poly symbols carry a channel each in red, green & blue, so
+// hold 3 times the codewords of mono ones
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Palette {
+    #[default]
+    Mono,
+    Poly,
+}
+
+impl Palette {
+    pub fn channels(self) -> usize {
+        match self {
+            Palette::Mono => 1,
+            Palette::Poly => 3,
+        }
+    }
+}
+
+// Version queries
+//------------------------------------------------------------------------------
+
+// Typed lookups into the version tables. The tables themselves stay private, so they can be
+// regenerated or packed differently without breaking callers
+
+// Row & column coordinates of the alignment pattern centres. Empty for version 1 & micro QR
+pub fn alignment_positions(ver: Version) -> &'static [i32] {
+    ver.alignment_pattern()
+}
+
+// Ec codewords of each Reed-Solomon block. Blocks of a channel all share the same ec length
+pub fn ecc_per_block(ver: Version, ecl: ECLevel) -> usize {
+    ver.block_layout(ecl).ec_len
+}
+
+// Sizes & counts of the Reed-Solomon blocks of a channel
+pub fn block_layout(ver: Version, ecl: ECLevel) -> BlockLayout {
+    ver.block_layout(ecl)
+}
+
+// Data & ec codewords of the symbol across all channels of the palette
+pub fn codewords(ver: Version, pal: Palette) -> usize {
+    ver.channel_codewords() * pal.channels()
+}
+
+// Data codewords of the symbol across all channels of the palette. The last data codeword of M1
+// & M3 is 4 bits & counted in full
+pub fn data_codewords(ver: Version, ecl: ECLevel, pal: Palette) -> usize {
+    ver.channel_data_capacity(ecl) * pal.channels()
+}
+
+#[cfg(test)]
+mod version_db_tests {
+    use super::{alignment_positions, codewords, data_codewords, ecc_per_block, Palette};
+    use crate::{ECLevel, Version};
+
+    #[test]
+    fn test_version_queries() {
+        assert_eq!(alignment_positions(Version::Normal(1)), [] as [i32; 0]);
+        assert_eq!(alignment_positions(Version::Normal(2)), [6, 18]);
+        assert_eq!(alignment_positions(Version::Micro(3)), [] as [i32; 0]);
+
+        assert_eq!(ecc_per_block(Version::Normal(1), ECLevel::L), 7);
+        assert_eq!(ecc_per_block(Version::Normal(5), ECLevel::Q), 18);
+
+        assert_eq!(codewords(Version::Normal(1), Palette::Mono), 26);
+        assert_eq!(codewords(Version::Normal(1), Palette::Poly), 78);
+        assert_eq!(codewords(Version::Micro(1), Palette::Mono), 5);
+        assert_eq!(data_codewords(Version::Normal(5), ECLevel::Q, Palette::Mono), 62);
+        assert_eq!(data_codewords(Version::Micro(1), ECLevel::L, Palette::Poly), 9);
+    }
+}